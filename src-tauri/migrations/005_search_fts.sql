@@ -0,0 +1,127 @@
+-- Migration 005: Full-text search (FTS5) over history, chat, snippets and transcripts
+-- Triggers keep each index in sync with its source table.
+--
+-- history and transcripts have INTEGER primary keys, so their indexes are external-content
+-- tables keyed on that id. chat_messages and snippets have TEXT primary keys and only an
+-- implicit rowid, which VACUUM may renumber; their indexes store their own copy of the text,
+-- keyed on a rowid from a `*_fts_keys` table that maps it to the row's `id`, so deletes go
+-- by rowid instead of scanning the index.
+
+-- ── Command history ──────────────────────────────────────────────────
+
+CREATE VIRTUAL TABLE history_fts USING fts5(
+    command,
+    content='command_history',
+    content_rowid='id',
+    tokenize='unicode61'
+);
+
+CREATE TRIGGER history_fts_ai AFTER INSERT ON command_history BEGIN
+    INSERT INTO history_fts(rowid, command) VALUES (new.id, new.command);
+END;
+
+CREATE TRIGGER history_fts_ad AFTER DELETE ON command_history BEGIN
+    INSERT INTO history_fts(history_fts, rowid, command) VALUES ('delete', old.id, old.command);
+END;
+
+CREATE TRIGGER history_fts_au AFTER UPDATE OF command ON command_history BEGIN
+    INSERT INTO history_fts(history_fts, rowid, command) VALUES ('delete', old.id, old.command);
+    INSERT INTO history_fts(rowid, command) VALUES (new.id, new.command);
+END;
+
+-- ── Chat messages ────────────────────────────────────────────────────
+
+CREATE TABLE chat_fts_keys (
+    rowid INTEGER PRIMARY KEY,
+    id TEXT NOT NULL UNIQUE
+);
+
+CREATE VIRTUAL TABLE chat_fts USING fts5(
+    content,
+    tokenize='unicode61'
+);
+
+CREATE TRIGGER chat_fts_ai AFTER INSERT ON chat_messages BEGIN
+    INSERT INTO chat_fts_keys(id) VALUES (new.id);
+    INSERT INTO chat_fts(rowid, content)
+    VALUES ((SELECT rowid FROM chat_fts_keys WHERE id = new.id), new.content);
+END;
+
+CREATE TRIGGER chat_fts_ad AFTER DELETE ON chat_messages BEGIN
+    DELETE FROM chat_fts WHERE rowid = (SELECT rowid FROM chat_fts_keys WHERE id = old.id);
+    DELETE FROM chat_fts_keys WHERE id = old.id;
+END;
+
+CREATE TRIGGER chat_fts_au AFTER UPDATE OF id, content ON chat_messages BEGIN
+    DELETE FROM chat_fts WHERE rowid = (SELECT rowid FROM chat_fts_keys WHERE id = old.id);
+    UPDATE chat_fts_keys SET id = new.id WHERE id = old.id;
+    INSERT INTO chat_fts(rowid, content)
+    VALUES ((SELECT rowid FROM chat_fts_keys WHERE id = new.id), new.content);
+END;
+
+-- ── Snippets ─────────────────────────────────────────────────────────
+
+CREATE TABLE snippets_fts_keys (
+    rowid INTEGER PRIMARY KEY,
+    id TEXT NOT NULL UNIQUE
+);
+
+CREATE VIRTUAL TABLE snippets_fts USING fts5(
+    title,
+    content,
+    tags,
+    tokenize='unicode61'
+);
+
+CREATE TRIGGER snippets_fts_ai AFTER INSERT ON snippets BEGIN
+    INSERT INTO snippets_fts_keys(id) VALUES (new.id);
+    INSERT INTO snippets_fts(rowid, title, content, tags)
+    VALUES ((SELECT rowid FROM snippets_fts_keys WHERE id = new.id),
+            new.title, new.content, new.tags);
+END;
+
+CREATE TRIGGER snippets_fts_ad AFTER DELETE ON snippets BEGIN
+    DELETE FROM snippets_fts WHERE rowid = (SELECT rowid FROM snippets_fts_keys WHERE id = old.id);
+    DELETE FROM snippets_fts_keys WHERE id = old.id;
+END;
+
+CREATE TRIGGER snippets_fts_au AFTER UPDATE OF id, title, content, tags ON snippets BEGIN
+    DELETE FROM snippets_fts WHERE rowid = (SELECT rowid FROM snippets_fts_keys WHERE id = old.id);
+    UPDATE snippets_fts_keys SET id = new.id WHERE id = old.id;
+    INSERT INTO snippets_fts(rowid, title, content, tags)
+    VALUES ((SELECT rowid FROM snippets_fts_keys WHERE id = new.id),
+            new.title, new.content, new.tags);
+END;
+
+-- ── AI transcripts ───────────────────────────────────────────────────
+
+CREATE VIRTUAL TABLE transcript_fts USING fts5(
+    content,
+    content='ai_messages',
+    content_rowid='id',
+    tokenize='unicode61'
+);
+
+CREATE TRIGGER transcript_fts_ai AFTER INSERT ON ai_messages BEGIN
+    INSERT INTO transcript_fts(rowid, content) VALUES (new.id, new.content);
+END;
+
+CREATE TRIGGER transcript_fts_ad AFTER DELETE ON ai_messages BEGIN
+    INSERT INTO transcript_fts(transcript_fts, rowid, content) VALUES ('delete', old.id, old.content);
+END;
+
+CREATE TRIGGER transcript_fts_au AFTER UPDATE OF content ON ai_messages BEGIN
+    INSERT INTO transcript_fts(transcript_fts, rowid, content) VALUES ('delete', old.id, old.content);
+    INSERT INTO transcript_fts(rowid, content) VALUES (new.id, new.content);
+END;
+
+-- ── Backfill existing rows ───────────────────────────────────────────
+
+INSERT INTO history_fts(history_fts) VALUES ('rebuild');
+INSERT INTO chat_fts_keys(id) SELECT id FROM chat_messages;
+INSERT INTO chat_fts(rowid, content)
+SELECT k.rowid, m.content FROM chat_messages m JOIN chat_fts_keys k ON k.id = m.id;
+INSERT INTO snippets_fts_keys(id) SELECT id FROM snippets;
+INSERT INTO snippets_fts(rowid, title, content, tags)
+SELECT k.rowid, s.title, s.content, s.tags FROM snippets s JOIN snippets_fts_keys k ON k.id = s.id;
+INSERT INTO transcript_fts(transcript_fts) VALUES ('rebuild');
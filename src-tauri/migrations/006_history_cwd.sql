@@ -0,0 +1,5 @@
+-- Migration 006: Record the working directory of each command (frecency cwd boost)
+
+ALTER TABLE command_history ADD COLUMN cwd TEXT;
+
+CREATE INDEX idx_history_timestamp ON command_history(timestamp DESC);
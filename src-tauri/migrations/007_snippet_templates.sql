@@ -0,0 +1,44 @@
+-- Migration 007: Snippet templates — project scoping and tags as a relation
+-- project_id = NULL means global snippet, project_id = UUID means project-specific
+
+ALTER TABLE snippets ADD COLUMN project_id TEXT REFERENCES projects(id) ON DELETE CASCADE;
+
+CREATE INDEX idx_snippets_project ON snippets(project_id);
+
+CREATE TABLE snippet_tags (
+    snippet_id   TEXT NOT NULL REFERENCES snippets(id) ON DELETE CASCADE,
+    tag          TEXT NOT NULL COLLATE NOCASE,
+    PRIMARY KEY (snippet_id, tag)
+);
+
+CREATE INDEX idx_snippet_tags_tag ON snippet_tags(tag);
+
+-- Split the old comma-separated tags column into rows
+WITH RECURSIVE split(snippet_id, tag, rest) AS (
+    SELECT id, '', tags || ',' FROM snippets WHERE tags IS NOT NULL AND tags != ''
+    UNION ALL
+    SELECT snippet_id,
+           trim(substr(rest, 1, instr(rest, ',') - 1)),
+           substr(rest, instr(rest, ',') + 1)
+    FROM split WHERE rest != ''
+)
+INSERT OR IGNORE INTO snippet_tags (snippet_id, tag)
+SELECT snippet_id, tag FROM split WHERE tag != '';
+
+-- snippets.tags is now a derived, comma-joined cache of snippet_tags.
+-- It stays because snippets_fts indexes it; never write it directly.
+UPDATE snippets SET tags = (
+    SELECT group_concat(tag, ',') FROM snippet_tags WHERE snippet_id = snippets.id
+);
+
+CREATE TRIGGER snippet_tags_ai AFTER INSERT ON snippet_tags BEGIN
+    UPDATE snippets SET tags = (
+        SELECT group_concat(tag, ',') FROM snippet_tags WHERE snippet_id = new.snippet_id
+    ) WHERE id = new.snippet_id;
+END;
+
+CREATE TRIGGER snippet_tags_ad AFTER DELETE ON snippet_tags BEGIN
+    UPDATE snippets SET tags = (
+        SELECT group_concat(tag, ',') FROM snippet_tags WHERE snippet_id = old.snippet_id
+    ) WHERE id = old.snippet_id;
+END;
@@ -0,0 +1,10 @@
+-- Migration 008: Last test run per project root and framework
+-- report = the serialized TestReport (tree, counts, output tail)
+
+CREATE TABLE test_results (
+    root         TEXT NOT NULL,
+    framework    TEXT NOT NULL,
+    report       TEXT NOT NULL,
+    finished_at  INTEGER NOT NULL,
+    PRIMARY KEY (root, framework)
+);
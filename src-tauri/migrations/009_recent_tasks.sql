@@ -0,0 +1,12 @@
+-- Migration 009: Recently run tasks per project root
+-- task_id = the discovered task's id, e.g. "npm:dev" or "make:test"
+
+CREATE TABLE recent_tasks (
+    root         TEXT NOT NULL,
+    task_id      TEXT NOT NULL,
+    name         TEXT NOT NULL,
+    source       TEXT NOT NULL,
+    run_count    INTEGER NOT NULL DEFAULT 1,
+    last_run_at  INTEGER NOT NULL,
+    PRIMARY KEY (root, task_id)
+);
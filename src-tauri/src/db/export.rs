@@ -0,0 +1,478 @@
+use crate::error::KodiqError;
+use crate::state::DbState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ── Types ────────────────────────────────────────────────────────────
+
+/// Identifies Kodiq chat exports; bump `EXPORT_VERSION` on breaking changes.
+const EXPORT_FORMAT: &str = "kodiq-chat";
+const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportSource {
+    /// Project chat thread (`chat_messages`).
+    Chat,
+    /// Captured CLI conversation (`ai_conversations` + `ai_messages`).
+    Transcript,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatExport {
+    pub format: String,
+    pub version: u32,
+    pub source: ExportSource,
+    pub exported_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    pub messages: Vec<ExportedMessage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedMessage {
+    pub id: String,
+    pub role: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_used: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub imported: usize,
+    /// Exported message id → newly assigned id.
+    pub id_map: HashMap<String, String>,
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────
+
+fn now() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+fn format_time(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+fn role_label(role: &str) -> String {
+    let mut chars = role.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "Message".to_string(),
+    }
+}
+
+/// A fence line's character and run length: up to three spaces of indent,
+/// then three or more backticks or tildes.
+fn fence(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let c = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|x| *x == c).count();
+    (len >= 3).then_some((c, len))
+}
+
+/// Close a dangling code fence so one truncated message can't swallow the
+/// headings of every message after it. A fence only closes with the same
+/// character, at least as many of them, and nothing else on the line.
+fn close_fences(content: &str) -> String {
+    let mut open: Option<(char, usize)> = None;
+    for line in content.lines() {
+        let Some((c, len)) = fence(line) else { continue };
+        let rest = &line.trim_start_matches(' ')[len..];
+        match open {
+            // Backtick info strings can't contain backticks
+            None if c == '`' && rest.contains('`') => {}
+            None => open = Some((c, len)),
+            Some((oc, olen)) if c == oc && len >= olen && rest.trim().is_empty() => open = None,
+            Some(_) => {}
+        }
+    }
+    let mut out = content.trim_end().to_string();
+    if let Some((c, len)) = open {
+        out.push('\n');
+        out.extend(std::iter::repeat(c).take(len));
+    }
+    out
+}
+
+// ── Pure functions ───────────────────────────────────────────────────
+
+pub fn load_chat(
+    conn: &rusqlite::Connection,
+    project_id: &str,
+) -> Result<ChatExport, rusqlite::Error> {
+    let project_name: String = conn.query_row(
+        "SELECT name FROM projects WHERE id = ?1",
+        rusqlite::params![project_id],
+        |r| r.get(0),
+    )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, role, content, provider, created_at
+         FROM chat_messages WHERE project_id = ?1
+         ORDER BY created_at ASC, rowid ASC",
+    )?;
+    let messages = stmt
+        .query_map(rusqlite::params![project_id], |row| {
+            Ok(ExportedMessage {
+                id: row.get(0)?,
+                role: row.get(1)?,
+                content: row.get(2)?,
+                provider: row.get(3)?,
+                created_at: row.get(4)?,
+                tokens_used: None,
+                cost_usd: None,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ChatExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        source: ExportSource::Chat,
+        exported_at: now(),
+        title: None,
+        project_name: Some(project_name),
+        provider: None,
+        messages,
+    })
+}
+
+pub fn load_transcript(
+    conn: &rusqlite::Connection,
+    conversation_id: &str,
+) -> Result<ChatExport, rusqlite::Error> {
+    let (title, cli_name, project_name): (Option<String>, String, Option<String>) = conn
+        .query_row(
+            "SELECT c.title, c.cli_name, p.name
+             FROM ai_conversations c LEFT JOIN projects p ON p.id = c.project_id
+             WHERE c.id = ?1",
+            rusqlite::params![conversation_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, role, content, timestamp, tokens_used, cost_usd
+         FROM ai_messages WHERE conversation_id = ?1
+         ORDER BY timestamp ASC, id ASC",
+    )?;
+    let messages = stmt
+        .query_map(rusqlite::params![conversation_id], |row| {
+            Ok(ExportedMessage {
+                id: row.get::<_, i64>(0)?.to_string(),
+                role: row.get(1)?,
+                content: row.get(2)?,
+                provider: None,
+                created_at: row.get(3)?,
+                tokens_used: row.get(4)?,
+                cost_usd: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ChatExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        source: ExportSource::Transcript,
+        exported_at: now(),
+        title,
+        project_name,
+        provider: Some(cli_name),
+        messages,
+    })
+}
+
+/// Render an export as Markdown suitable for PR descriptions.
+/// Message bodies are copied verbatim so code blocks survive.
+pub fn to_markdown(export: &ChatExport) -> String {
+    let heading = export
+        .title
+        .clone()
+        .or_else(|| export.project_name.as_ref().map(|name| format!("Chat — {}", name)))
+        .unwrap_or_else(|| "Chat".to_string());
+
+    let mut meta = vec![format!("Exported from Kodiq on {}", format_time(export.exported_at))];
+    if let Some(ref provider) = export.provider {
+        meta.push(format!("provider: {}", provider));
+    }
+    meta.push(format!("{} messages", export.messages.len()));
+
+    let mut out = format!("# {}\n\n_{}_\n", heading, meta.join(" · "));
+
+    for msg in &export.messages {
+        let mut header = role_label(&msg.role);
+        if let Some(provider) = msg.provider.as_ref().or(export.provider.as_ref()) {
+            if msg.role != "user" {
+                header.push_str(&format!(" ({})", provider));
+            }
+        }
+        out.push_str(&format!(
+            "\n---\n\n### {} · {}\n\n{}\n",
+            header,
+            format_time(msg.created_at),
+            close_fences(&msg.content)
+        ));
+    }
+
+    out
+}
+
+pub fn export(export: &ChatExport, format: ExportFormat) -> Result<String, KodiqError> {
+    match format {
+        ExportFormat::Markdown => Ok(to_markdown(export)),
+        ExportFormat::Json => serde_json::to_string_pretty(export)
+            .map_err(|e| KodiqError::Other(format!("Serialize export: {}", e))),
+    }
+}
+
+/// Restore a JSON export into `chat_messages` of `project_id`.
+/// Every message gets a fresh id; original timestamps are kept.
+pub fn import_chat(
+    conn: &mut rusqlite::Connection,
+    project_id: &str,
+    data: &str,
+) -> Result<ImportResult, KodiqError> {
+    let export: ChatExport = serde_json::from_str(data)
+        .map_err(|e| KodiqError::Other(format!("Invalid chat export: {}", e)))?;
+
+    if export.format != EXPORT_FORMAT {
+        return Err(KodiqError::Other(format!("Not a Kodiq chat export: {}", export.format)));
+    }
+    if export.version > EXPORT_VERSION {
+        return Err(KodiqError::Other(format!(
+            "Chat export version {} is newer than supported ({})",
+            export.version, EXPORT_VERSION
+        )));
+    }
+
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM projects WHERE id = ?1)",
+        rusqlite::params![project_id],
+        |r| r.get(0),
+    )?;
+    if !exists {
+        return Err(KodiqError::NotFound(format!("Project: {}", project_id)));
+    }
+
+    let tx = conn.transaction()?;
+    let mut id_map = HashMap::new();
+    for msg in &export.messages {
+        let new_id = uuid::Uuid::new_v4().to_string();
+        let provider =
+            msg.provider.as_deref().or(export.provider.as_deref()).unwrap_or("unknown").to_string();
+
+        tx.execute(
+            "INSERT INTO chat_messages (id, project_id, role, content, provider, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![new_id, project_id, msg.role, msg.content, provider, msg.created_at],
+        )?;
+        id_map.insert(msg.id.clone(), new_id);
+    }
+    tx.commit()?;
+
+    Ok(ImportResult { imported: export.messages.len(), id_map })
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn db_export_chat(
+    db: tauri::State<DbState>,
+    project_id: String,
+    format: ExportFormat,
+) -> Result<String, KodiqError> {
+    let conn = db.connection.lock()?;
+    let chat = load_chat(&conn, &project_id).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => {
+            KodiqError::NotFound(format!("Project: {}", project_id))
+        }
+        other => other.into(),
+    })?;
+    export(&chat, format)
+}
+
+#[tauri::command]
+pub fn db_export_transcript(
+    db: tauri::State<DbState>,
+    conversation_id: String,
+    format: ExportFormat,
+) -> Result<String, KodiqError> {
+    let conn = db.connection.lock()?;
+    let transcript = load_transcript(&conn, &conversation_id).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => {
+            KodiqError::NotFound(format!("Transcript: {}", conversation_id))
+        }
+        other => other.into(),
+    })?;
+    export(&transcript, format)
+}
+
+#[tauri::command]
+pub fn db_import_chat(
+    db: tauri::State<DbState>,
+    project_id: String,
+    data: String,
+) -> Result<ImportResult, KodiqError> {
+    let mut conn = db.connection.lock()?;
+    import_chat(&mut conn, &project_id, &data)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, chat, projects};
+
+    fn test_db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
+        db::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn save(conn: &rusqlite::Connection, project_id: &str, id: &str, role: &str, content: &str) {
+        chat::save(
+            conn,
+            &chat::NewChatMessage {
+                id: id.into(),
+                project_id: project_id.into(),
+                role: role.into(),
+                content: content.into(),
+                provider: "claude".into(),
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_markdown_export() {
+        let conn = test_db();
+        let p = projects::create(&conn, "kodiq", "/tmp/kodiq").unwrap();
+        save(&conn, &p.id, "m1", "user", "Fix the parser");
+        save(&conn, &p.id, "m2", "assistant", "Done:\n```rust\nfn parse() {}\n```");
+        save(&conn, &p.id, "m3", "assistant", "Truncated:\n```ts\nconst x = 1;");
+
+        let md = to_markdown(&load_chat(&conn, &p.id).unwrap());
+        assert!(md.starts_with("# Chat — kodiq\n"));
+        assert!(md.contains("### User · "));
+        assert!(md.contains("### Assistant (claude) · "));
+        assert!(md.contains("```rust\nfn parse() {}\n```"));
+        assert!(md.ends_with("const x = 1;\n```\n"));
+
+        assert!(matches!(load_chat(&conn, "missing"), Err(rusqlite::Error::QueryReturnedNoRows)));
+    }
+
+    #[test]
+    fn test_close_fences() {
+        // Tilde fences and longer backtick fences, closed or not
+        assert_eq!(close_fences("~~~\ncode\n~~~\n"), "~~~\ncode\n~~~");
+        assert_eq!(close_fences("~~~py\ncode"), "~~~py\ncode\n~~~");
+        assert_eq!(close_fences("````md\n```\ninner\n```\n````"), "````md\n```\ninner\n```\n````");
+        assert_eq!(close_fences("````md\n```\ninner"), "````md\n```\ninner\n````");
+        // A different fence character doesn't close the block
+        assert_eq!(close_fences("```\n~~~\n"), "```\n~~~\n```");
+        // Not fences: too deeply indented, or backticks in the info string
+        assert_eq!(close_fences("    ```\nx"), "    ```\nx");
+        assert_eq!(close_fences("``` a`b\nx"), "``` a`b\nx");
+        assert_eq!(
+            close_fences("```rust\nfn a() {}\n``` not a close"),
+            "```rust\nfn a() {}\n``` not a close\n```"
+        );
+    }
+
+    #[test]
+    fn test_json_roundtrip_remaps_ids() {
+        let mut conn = test_db();
+        let src = projects::create(&conn, "src", "/tmp/src").unwrap();
+        let dst = projects::create(&conn, "dst", "/tmp/dst").unwrap();
+        save(&conn, &src.id, "m1", "user", "hello");
+        save(&conn, &src.id, "m2", "assistant", "hi");
+
+        let json = export(&load_chat(&conn, &src.id).unwrap(), ExportFormat::Json).unwrap();
+        let result = import_chat(&mut conn, &dst.id, &json).unwrap();
+        assert_eq!(result.imported, 2);
+        assert_ne!(result.id_map["m1"], "m1");
+
+        let imported = chat::list(&conn, &dst.id, 10).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].id, result.id_map["m1"]);
+        assert_eq!(imported[1].content, "hi");
+        assert_eq!(imported[1].provider, "claude");
+
+        // Importing twice must not collide on primary keys
+        assert!(import_chat(&mut conn, &dst.id, &json).is_ok());
+    }
+
+    #[test]
+    fn test_import_rejects_bad_input() {
+        let mut conn = test_db();
+        let p = projects::create(&conn, "p", "/tmp/p").unwrap();
+
+        assert!(import_chat(&mut conn, &p.id, "not json").is_err());
+
+        let future = format!(
+            r#"{{"format":"{}","version":99,"source":"chat","exported_at":0,"messages":[]}}"#,
+            EXPORT_FORMAT
+        );
+        let err = import_chat(&mut conn, &p.id, &future).unwrap_err().to_string();
+        assert!(err.contains("newer than supported"));
+
+        let ok = format!(
+            r#"{{"format":"{}","version":1,"source":"chat","exported_at":0,"messages":[]}}"#,
+            EXPORT_FORMAT
+        );
+        assert!(matches!(import_chat(&mut conn, "missing", &ok), Err(KodiqError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_transcript_export() {
+        let conn = test_db();
+        let p = projects::create(&conn, "p", "/tmp/t").unwrap();
+        conn.execute(
+            "INSERT INTO ai_conversations (id, project_id, cli_name, title, started_at)
+             VALUES ('c1', ?1, 'gemini', 'Add tests', 0)",
+            rusqlite::params![p.id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO ai_messages (conversation_id, role, content, tokens_used, timestamp)
+             VALUES ('c1', 'assistant', 'Added 3 tests', 42, 10)",
+            [],
+        )
+        .unwrap();
+
+        let transcript = load_transcript(&conn, "c1").unwrap();
+        assert_eq!(transcript.source, ExportSource::Transcript);
+        assert_eq!(transcript.messages[0].tokens_used, Some(42));
+
+        let md = to_markdown(&transcript);
+        assert!(md.starts_with("# Add tests\n"));
+        assert!(md.contains("provider: gemini"));
+        assert!(md.contains("### Assistant (gemini) · 1970-01-01 00:00:10 UTC"));
+
+        assert!(load_transcript(&conn, "missing").is_err());
+    }
+}
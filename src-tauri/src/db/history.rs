@@ -1,6 +1,7 @@
 use crate::error::KodiqError;
 use crate::state::DbState;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HistoryEntry {
@@ -12,6 +13,7 @@ pub struct HistoryEntry {
     pub exit_code: Option<i32>,
     pub duration_ms: Option<i64>,
     pub timestamp: i64,
+    pub cwd: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -22,79 +24,208 @@ pub struct NewHistoryEntry {
     pub cli_name: Option<String>,
     pub exit_code: Option<i32>,
     pub duration_ms: Option<i64>,
+    pub cwd: Option<String>,
+}
+
+/// A deduplicated command ranked by frecency, for terminal autosuggestions.
+#[derive(Debug, Serialize)]
+pub struct CommandSuggestion {
+    pub command: String,
+    pub score: f64,
+    pub run_count: u32,
+    pub last_used: i64,
+    pub last_exit_code: Option<i32>,
+}
+
+/// Where the user is right now — runs from the same project/cwd rank higher.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FrecencyContext<'a> {
+    pub project_id: Option<&'a str>,
+    pub cwd: Option<&'a str>,
+}
+
+// ── Frecency ─────────────────────────────────────────────────────────
+
+/// A run loses half its weight every week.
+const HALF_LIFE_SECS: f64 = 7.0 * 24.0 * 3600.0;
+const PROJECT_BOOST: f64 = 2.0;
+const CWD_BOOST: f64 = 1.5;
+const FAILURE_PENALTY: f64 = 0.25;
+
+/// Upper bound on rows scored per query — older runs have decayed to ~0 anyway.
+const SCAN_LIMIT: u32 = 5000;
+
+const COLUMNS: &str =
+    "id, project_id, session_id, command, cli_name, exit_code, duration_ms, timestamp, cwd";
+
+/// Collapse whitespace so `git  status` and `git status ` count as one command.
+fn normalize(command: &str) -> String {
+    command.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Weight of a single run: exponential decay by age, boosted by context,
+/// penalized when the command failed.
+fn run_weight(entry: &HistoryEntry, ctx: &FrecencyContext, now: i64) -> f64 {
+    let age = (now - entry.timestamp).max(0) as f64;
+    let mut weight = 0.5_f64.powf(age / HALF_LIFE_SECS);
+
+    if ctx.project_id == Some(entry.project_id.as_str()) {
+        weight *= PROJECT_BOOST;
+    }
+    if ctx.cwd.is_some() && ctx.cwd == entry.cwd.as_deref() {
+        weight *= CWD_BOOST;
+    }
+    if entry.exit_code.is_some_and(|code| code != 0) {
+        weight *= FAILURE_PENALTY;
+    }
+    weight
+}
+
+/// Group runs by normalized command and sum their weights.
+/// Expects newest-first input; each group keeps its most recent run.
+fn rank(
+    entries: Vec<HistoryEntry>,
+    ctx: &FrecencyContext,
+    now: i64,
+) -> Vec<(HistoryEntry, f64, u32)> {
+    let mut ranked: Vec<(HistoryEntry, f64, u32)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for entry in entries {
+        let weight = run_weight(&entry, ctx, now);
+        let key = normalize(&entry.command);
+        match index.get(&key) {
+            Some(&i) => {
+                ranked[i].1 += weight;
+                ranked[i].2 += 1;
+            }
+            None => {
+                index.insert(key, ranked.len());
+                ranked.push((entry, weight, 1));
+            }
+        }
+    }
+
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.0.timestamp.cmp(&a.0.timestamp)));
+    ranked
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64
 }
 
 // ── Pure functions ───────────────────────────────────────────────────
 
+/// Token-prefix search over the `history_fts` index, newest first.
+/// An empty query returns the latest commands.
 pub fn search(
     conn: &rusqlite::Connection,
     query: &str,
     project_id: Option<&str>,
 ) -> Result<Vec<HistoryEntry>, rusqlite::Error> {
-    let pattern = format!("%{}%", query);
-
-    if let Some(pid) = project_id {
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, session_id, command, cli_name, exit_code, duration_ms, timestamp
-             FROM command_history WHERE project_id = ?1 AND command LIKE ?2
-             ORDER BY timestamp DESC LIMIT 100",
-        )?;
-        let rows = stmt.query_map(rusqlite::params![pid, pattern], map_row)?;
-        rows.collect()
-    } else {
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, session_id, command, cli_name, exit_code, duration_ms, timestamp
-             FROM command_history WHERE command LIKE ?1
-             ORDER BY timestamp DESC LIMIT 100",
-        )?;
-        let rows = stmt.query_map(rusqlite::params![pattern], map_row)?;
-        rows.collect()
+    let fts = super::search::fts_query(query);
+
+    let mut sql = format!("SELECT {} FROM command_history WHERE 1 = 1", COLUMNS);
+    let mut params: Vec<&dyn rusqlite::types::ToSql> = Vec::new();
+
+    if let Some(ref pid) = project_id {
+        params.push(pid);
+        sql.push_str(&format!(" AND project_id = ?{}", params.len()));
+    }
+    if let Some(ref q) = fts {
+        params.push(q);
+        sql.push_str(&format!(
+            " AND id IN (SELECT rowid FROM history_fts WHERE history_fts MATCH ?{})",
+            params.len()
+        ));
     }
+    sql.push_str(" ORDER BY timestamp DESC LIMIT 100");
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params.as_slice(), map_row)?;
+    rows.collect()
 }
 
 pub fn add(conn: &rusqlite::Connection, entry: &NewHistoryEntry) -> Result<(), rusqlite::Error> {
-    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
-        as i64;
-
     conn.execute(
         "INSERT INTO command_history
-         (project_id, session_id, command, cli_name, exit_code, duration_ms, timestamp)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+         (project_id, session_id, command, cli_name, exit_code, duration_ms, timestamp, cwd)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
         rusqlite::params![
             entry.project_id,
             entry.session_id,
-            entry.command,
+            entry.command.trim(),
             entry.cli_name,
             entry.exit_code,
             entry.duration_ms,
-            now,
+            now(),
+            entry.cwd,
         ],
     )?;
     Ok(())
 }
 
+/// Deduplicated commands ranked by frecency (frequency × recency decay),
+/// boosting runs from `cwd` when given. When `project_id` is set, only that
+/// project's runs are considered.
 pub fn recent(
     conn: &rusqlite::Connection,
     project_id: Option<&str>,
+    cwd: Option<&str>,
     limit: u32,
 ) -> Result<Vec<HistoryEntry>, rusqlite::Error> {
-    if let Some(pid) = project_id {
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, session_id, command, cli_name, exit_code, duration_ms, timestamp
-             FROM command_history WHERE project_id = ?1
-             GROUP BY command ORDER BY MAX(timestamp) DESC LIMIT ?2",
-        )?;
-        let rows = stmt.query_map(rusqlite::params![pid, limit], map_row)?;
-        rows.collect()
-    } else {
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, session_id, command, cli_name, exit_code, duration_ms, timestamp
-             FROM command_history
-             GROUP BY command ORDER BY MAX(timestamp) DESC LIMIT ?1",
-        )?;
-        let rows = stmt.query_map(rusqlite::params![limit], map_row)?;
-        rows.collect()
+    let scope = if project_id.is_some() { "WHERE project_id = ?1" } else { "WHERE ?1 IS NULL" };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM command_history {} ORDER BY timestamp DESC, id DESC LIMIT ?2",
+        COLUMNS, scope
+    ))?;
+    let rows = stmt.query_map(rusqlite::params![project_id, SCAN_LIMIT], map_row)?;
+    let entries: Vec<HistoryEntry> = rows.collect::<Result<_, _>>()?;
+
+    let ctx = FrecencyContext { project_id, cwd };
+    Ok(rank(entries, &ctx, now()).into_iter().take(limit as usize).map(|(e, _, _)| e).collect())
+}
+
+/// Shell-style autosuggestions: commands from any project starting with `prefix`,
+/// ranked by frecency relative to the current project and cwd.
+pub fn suggest(
+    conn: &rusqlite::Connection,
+    prefix: &str,
+    ctx: &FrecencyContext,
+    limit: u32,
+) -> Result<Vec<CommandSuggestion>, rusqlite::Error> {
+    let prefix = prefix.trim_start();
+    if prefix.is_empty() {
+        return Ok(Vec::new());
     }
+
+    let escaped = prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM command_history WHERE command LIKE ?1 ESCAPE '\\'
+         ORDER BY timestamp DESC LIMIT ?2",
+        COLUMNS
+    ))?;
+    let rows = stmt.query_map(rusqlite::params![format!("{}%", escaped), SCAN_LIMIT], map_row)?;
+
+    // LIKE is case-insensitive for ASCII — shells are not
+    let typed = normalize(prefix);
+    let entries: Vec<HistoryEntry> = rows
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|e| e.command.starts_with(prefix) && normalize(&e.command) != typed)
+        .collect();
+
+    Ok(rank(entries, ctx, now())
+        .into_iter()
+        .take(limit as usize)
+        .map(|(e, score, run_count)| CommandSuggestion {
+            command: e.command,
+            score,
+            run_count,
+            last_used: e.timestamp,
+            last_exit_code: e.exit_code,
+        })
+        .collect())
 }
 
 fn map_row(row: &rusqlite::Row) -> Result<HistoryEntry, rusqlite::Error> {
@@ -107,6 +238,7 @@ fn map_row(row: &rusqlite::Row) -> Result<HistoryEntry, rusqlite::Error> {
         exit_code: row.get(5)?,
         duration_ms: row.get(6)?,
         timestamp: row.get(7)?,
+        cwd: row.get(8)?,
     })
 }
 
@@ -126,10 +258,24 @@ pub fn db_search_history(
 pub fn db_recent_history(
     db: tauri::State<DbState>,
     project_id: Option<String>,
+    cwd: Option<String>,
     limit: Option<u32>,
 ) -> Result<Vec<HistoryEntry>, KodiqError> {
     let conn = db.connection.lock()?;
-    Ok(recent(&conn, project_id.as_deref(), limit.unwrap_or(20))?)
+    Ok(recent(&conn, project_id.as_deref(), cwd.as_deref(), limit.unwrap_or(20))?)
+}
+
+#[tauri::command]
+pub fn db_suggest_commands(
+    db: tauri::State<DbState>,
+    prefix: String,
+    project_id: Option<String>,
+    cwd: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<CommandSuggestion>, KodiqError> {
+    let conn = db.connection.lock()?;
+    let ctx = FrecencyContext { project_id: project_id.as_deref(), cwd: cwd.as_deref() };
+    Ok(suggest(&conn, &prefix, &ctx, limit.unwrap_or(5))?)
 }
 
 #[tauri::command]
@@ -137,3 +283,150 @@ pub fn db_add_history(db: tauri::State<DbState>, entry: NewHistoryEntry) -> Resu
     let conn = db.connection.lock()?;
     Ok(add(&conn, &entry)?)
 }
+
+// ── Tests ─────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn test_db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
+        db::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn entry(project_id: &str, command: &str) -> NewHistoryEntry {
+        NewHistoryEntry {
+            project_id: project_id.to_string(),
+            session_id: None,
+            command: command.to_string(),
+            cli_name: None,
+            exit_code: Some(0),
+            duration_ms: None,
+            cwd: None,
+        }
+    }
+
+    #[test]
+    fn test_search_prefix_and_project() {
+        let conn = test_db();
+        let a = db::projects::create(&conn, "a", "/tmp/a").unwrap();
+        let b = db::projects::create(&conn, "b", "/tmp/b").unwrap();
+        add(&conn, &entry(&a.id, "git status")).unwrap();
+        add(&conn, &entry(&a.id, "git stash pop")).unwrap();
+        add(&conn, &entry(&b.id, "git status -s")).unwrap();
+
+        assert_eq!(search(&conn, "git sta", None).unwrap().len(), 3);
+        assert_eq!(search(&conn, "stat", Some(&a.id)).unwrap().len(), 1);
+        assert_eq!(search(&conn, "", Some(&b.id)).unwrap().len(), 1);
+    }
+
+    fn run(project_id: &str, command: &str, exit_code: i32, age_days: i64) -> HistoryEntry {
+        HistoryEntry {
+            id: 0,
+            project_id: project_id.to_string(),
+            session_id: None,
+            command: command.to_string(),
+            cli_name: None,
+            exit_code: Some(exit_code),
+            duration_ms: None,
+            timestamp: 1_000_000_000 - age_days * 86_400,
+            cwd: None,
+        }
+    }
+
+    #[test]
+    fn test_rank_frequency_beats_single_recent_run() {
+        let now = 1_000_000_000;
+        let ctx = FrecencyContext::default();
+        let mut runs = vec![run("p", "vim notes.md", 0, 0)];
+        runs.extend((1..=5).map(|d| run("p", "cargo test", 0, d)));
+
+        let ranked = rank(runs, &ctx, now);
+        assert_eq!(ranked[0].0.command, "cargo test");
+        assert_eq!(ranked[0].2, 5);
+        assert_eq!(ranked.len(), 2);
+    }
+
+    #[test]
+    fn test_rank_context_and_failures() {
+        let now = 1_000_000_000;
+        let runs = || vec![run("a", "make", 0, 1), run("b", "make build", 0, 1)];
+
+        let ctx = FrecencyContext { project_id: Some("b"), cwd: None };
+        assert_eq!(rank(runs(), &ctx, now)[0].0.command, "make build");
+
+        let failing = vec![run("a", "npm tset", 1, 0), run("a", "npm test", 0, 1)];
+        let ranked = rank(failing, &FrecencyContext::default(), now);
+        assert_eq!(ranked[0].0.command, "npm test");
+    }
+
+    #[test]
+    fn test_rank_dedupes_whitespace() {
+        let runs = vec![run("p", "git  status", 0, 0), run("p", "git status", 0, 1)];
+        let ranked = rank(runs, &FrecencyContext::default(), 1_000_000_000);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].2, 2);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let conn = test_db();
+        let a = db::projects::create(&conn, "a", "/tmp/a").unwrap();
+        let b = db::projects::create(&conn, "b", "/tmp/b").unwrap();
+        add(&conn, &entry(&a.id, "git push origin main")).unwrap();
+        for _ in 0..3 {
+            add(&conn, &entry(&b.id, "git pull --rebase")).unwrap();
+        }
+        add(&conn, &entry(&a.id, "Git_pull")).unwrap();
+        add(&conn, &entry(&a.id, "git p")).unwrap();
+
+        let ctx = FrecencyContext { project_id: Some(&a.id), cwd: None };
+        let hits = suggest(&conn, "git p", &ctx, 5).unwrap();
+        let commands: Vec<&str> = hits.iter().map(|h| h.command.as_str()).collect();
+        assert_eq!(commands, vec!["git pull --rebase", "git push origin main"]);
+        assert_eq!(hits[0].run_count, 3);
+
+        assert!(suggest(&conn, "  ", &ctx, 5).unwrap().is_empty());
+        assert!(suggest(&conn, "git_", &ctx, 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recent_ranks_by_frecency() {
+        let conn = test_db();
+        let a = db::projects::create(&conn, "a", "/tmp/a").unwrap();
+        let b = db::projects::create(&conn, "b", "/tmp/b").unwrap();
+        for command in ["cargo check", "cargo  check", "cargo check "] {
+            add(&conn, &entry(&a.id, command)).unwrap();
+        }
+        add(&conn, &entry(&a.id, "ls")).unwrap();
+        add(&conn, &entry(&b.id, "make")).unwrap();
+
+        let commands = |entries: Vec<HistoryEntry>| -> Vec<String> {
+            entries.into_iter().map(|e| e.command).collect()
+        };
+        assert_eq!(commands(recent(&conn, Some(&a.id), None, 10).unwrap()), ["cargo check", "ls"]);
+        assert_eq!(recent(&conn, None, None, 10).unwrap().len(), 3);
+        assert_eq!(commands(recent(&conn, None, None, 1).unwrap()), ["cargo check"]);
+    }
+
+    #[test]
+    fn test_recent_boosts_cwd() {
+        let conn = test_db();
+        let p = db::projects::create(&conn, "p", "/tmp/p").unwrap();
+        let run_in = |cwd: &str, command: &str| NewHistoryEntry {
+            cwd: Some(cwd.to_string()),
+            ..entry(&p.id, command)
+        };
+        add(&conn, &run_in("/tmp/p", "npm test")).unwrap();
+        add(&conn, &run_in("/tmp/p/web", "npm run dev")).unwrap();
+
+        let entries = recent(&conn, Some(&p.id), Some("/tmp/p/web"), 10).unwrap();
+        assert_eq!(entries[0].command, "npm run dev");
+        assert_eq!(entries[0].cwd.as_deref(), Some("/tmp/p/web"));
+        assert_eq!(recent(&conn, Some(&p.id), Some("/tmp/p"), 10).unwrap()[0].command, "npm test");
+    }
+}
@@ -2,6 +2,7 @@ use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
 use crate::error::KodiqError;
+use crate::launch::schema;
 use crate::state::DbState;
 
 // ── Types ────────────────────────────────────────────────────────────────────
@@ -40,6 +41,19 @@ fn now() -> i64 {
     std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64
 }
 
+fn from_row(row: &rusqlite::Row) -> Result<LaunchConfig, rusqlite::Error> {
+    Ok(LaunchConfig {
+        id: row.get(0)?,
+        cli_name: row.get(1)?,
+        profile_name: row.get(2)?,
+        config: row.get(3)?,
+        is_default: row.get::<_, i32>(4)? != 0,
+        project_id: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
 // ── CRUD ─────────────────────────────────────────────────────────────────────
 
 /// List launch configs: global (project_id IS NULL) + project-specific.
@@ -54,22 +68,20 @@ pub fn list(
          ORDER BY is_default DESC, profile_name ASC",
     )?;
 
-    let rows = stmt.query_map(rusqlite::params![project_id], |row| {
-        Ok(LaunchConfig {
-            id: row.get(0)?,
-            cli_name: row.get(1)?,
-            profile_name: row.get(2)?,
-            config: row.get(3)?,
-            is_default: row.get::<_, i32>(4)? != 0,
-            project_id: row.get(5)?,
-            created_at: row.get(6)?,
-            updated_at: row.get(7)?,
-        })
-    })?;
+    let rows = stmt.query_map(rusqlite::params![project_id], from_row)?;
 
     rows.collect()
 }
 
+pub fn get(conn: &Connection, id: &str) -> Result<LaunchConfig, rusqlite::Error> {
+    conn.query_row(
+        "SELECT id, cli_name, profile_name, config, is_default, project_id, created_at, updated_at
+         FROM cli_profiles WHERE id = ?1",
+        rusqlite::params![id],
+        from_row,
+    )
+}
+
 pub fn create(conn: &Connection, cfg: NewLaunchConfig) -> Result<LaunchConfig, rusqlite::Error> {
     let id = uuid::Uuid::new_v4().to_string();
     let ts = now();
@@ -150,6 +162,9 @@ pub fn db_create_launch_config(
     config: NewLaunchConfig,
     db: tauri::State<'_, DbState>,
 ) -> Result<LaunchConfig, KodiqError> {
+    if config.cli_name == schema::COMPOUND {
+        schema::check(&config.config)?;
+    }
     let conn = db.connection.lock()?;
     Ok(create(&conn, config)?)
 }
@@ -161,6 +176,11 @@ pub fn db_update_launch_config(
     db: tauri::State<'_, DbState>,
 ) -> Result<(), KodiqError> {
     let conn = db.connection.lock()?;
+    if let Some(ref config) = patch.config {
+        if get(&conn, &id)?.cli_name == schema::COMPOUND {
+            schema::check(config)?;
+        }
+    }
     Ok(update(&conn, &id, patch)?)
 }
 
@@ -208,6 +228,9 @@ mod tests {
         let all = list(&conn, Some(pid)).unwrap();
         assert_eq!(all.len(), 1);
         assert_eq!(all[0].profile_name, "default");
+        assert_eq!(get(&conn, &cfg.id).unwrap().config, cfg.config);
+        assert!(get(&conn, "missing").is_err());
+        assert_eq!(crate::db::projects::path_of(&conn, pid).unwrap().as_deref(), Some("/tmp/test"));
     }
 
     #[test]
@@ -27,6 +27,31 @@ pub const MIGRATIONS: &[Migration] = &[
         name: "chat_messages",
         sql: include_str!("../../migrations/004_chat.sql"),
     },
+    Migration {
+        version: 5,
+        name: "search_fts",
+        sql: include_str!("../../migrations/005_search_fts.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "command_history_cwd",
+        sql: include_str!("../../migrations/006_history_cwd.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "snippet_templates",
+        sql: include_str!("../../migrations/007_snippet_templates.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "test_results",
+        sql: include_str!("../../migrations/008_test_results.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "recent_tasks",
+        sql: include_str!("../../migrations/009_recent_tasks.sql"),
+    },
 ];
 
 pub fn run_migrations(conn: &Connection) -> Result<(), String> {
@@ -74,7 +99,7 @@ mod tests {
 
         let version: u32 =
             conn.query_row("SELECT MAX(version) FROM _migrations", [], |r| r.get(0)).unwrap();
-        assert_eq!(version, 4);
+        assert_eq!(version, 9);
     }
 
     #[test]
@@ -85,6 +110,6 @@ mod tests {
 
         let count: u32 =
             conn.query_row("SELECT COUNT(*) FROM _migrations", [], |r| r.get(0)).unwrap();
-        assert_eq!(count, 4);
+        assert_eq!(count, 9);
     }
 }
@@ -1,11 +1,15 @@
 pub mod chat;
+pub mod export;
 pub mod history;
 pub mod launch_configs;
 pub mod migrations;
 pub mod projects;
+pub mod recent_tasks;
+pub mod search;
 pub mod sessions;
 pub mod settings;
 pub mod snippets;
+pub mod test_results;
 
 use crate::state::DbState;
 
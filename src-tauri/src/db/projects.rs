@@ -1,4 +1,5 @@
 use crate::error::KodiqError;
+use crate::filesystem::sandbox;
 use crate::state::DbState;
 use serde::{Deserialize, Serialize};
 
@@ -23,23 +24,25 @@ pub struct ProjectPatch {
 
 // ── Pure functions (testable without Tauri) ──────────────────────────
 
+fn from_row(row: &rusqlite::Row) -> Result<Project, rusqlite::Error> {
+    Ok(Project {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        path: row.get(2)?,
+        created_at: row.get(3)?,
+        last_opened: row.get(4)?,
+        open_count: row.get(5)?,
+        default_cli: row.get(6)?,
+        settings: row.get(7)?,
+    })
+}
+
 pub fn list(conn: &rusqlite::Connection) -> Result<Vec<Project>, rusqlite::Error> {
     let mut stmt = conn.prepare(
         "SELECT id, name, path, created_at, last_opened, open_count, default_cli, settings
          FROM projects ORDER BY last_opened DESC",
     )?;
-    let rows = stmt.query_map([], |row| {
-        Ok(Project {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            path: row.get(2)?,
-            created_at: row.get(3)?,
-            last_opened: row.get(4)?,
-            open_count: row.get(5)?,
-            default_cli: row.get(6)?,
-            settings: row.get(7)?,
-        })
-    })?;
+    let rows = stmt.query_map([], from_row)?;
     rows.collect()
 }
 
@@ -101,32 +104,44 @@ pub fn update(
     Ok(())
 }
 
+/// The path of the project with `id`, if there is one.
+pub fn path_of(conn: &rusqlite::Connection, id: &str) -> Result<Option<String>, rusqlite::Error> {
+    use rusqlite::OptionalExtension;
+    conn.query_row("SELECT path FROM projects WHERE id = ?1", rusqlite::params![id], |r| r.get(0))
+        .optional()
+}
+
+/// Whether the user trusts the project at `path` to run its own binaries.
+/// Read from the project's settings in the database only, never from
+/// `.kodiq/workspace.json`, so a repository cannot trust itself.
+pub fn is_trusted(conn: &rusqlite::Connection, path: &str) -> bool {
+    find(conn, path.trim_end_matches('/'))
+        .ok()
+        .flatten()
+        .and_then(|p| p.settings)
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| v.get("trusted").and_then(|t| t.as_bool()))
+        .unwrap_or(false)
+}
+
+/// The project opened at `path`, if there is one.
+pub fn find(conn: &rusqlite::Connection, path: &str) -> Result<Option<Project>, rusqlite::Error> {
+    use rusqlite::OptionalExtension;
+    conn.query_row(
+        "SELECT id, name, path, created_at, last_opened, open_count, default_cli, settings
+         FROM projects WHERE path = ?1",
+        rusqlite::params![path],
+        from_row,
+    )
+    .optional()
+}
+
 pub fn get_or_create(
     conn: &rusqlite::Connection,
     name: &str,
     path: &str,
 ) -> Result<Project, rusqlite::Error> {
-    use rusqlite::OptionalExtension;
-
-    let existing: Option<Project> = conn
-        .query_row(
-            "SELECT id, name, path, created_at, last_opened, open_count, default_cli, settings
-             FROM projects WHERE path = ?1",
-            rusqlite::params![path],
-            |row| {
-                Ok(Project {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    path: row.get(2)?,
-                    created_at: row.get(3)?,
-                    last_opened: row.get(4)?,
-                    open_count: row.get(5)?,
-                    default_cli: row.get(6)?,
-                    settings: row.get(7)?,
-                })
-            },
-        )
-        .optional()?;
+    let existing = find(conn, path)?;
 
     match existing {
         Some(p) => {
@@ -139,12 +154,16 @@ pub fn get_or_create(
 
 // ── Tauri Commands ───────────────────────────────────────────────────
 
+/// Registering a project makes its path a sandbox root, so only the main
+/// webview may do it.
 #[tauri::command]
 pub fn db_get_or_create_project(
+    webview: tauri::Webview,
     db: tauri::State<DbState>,
     name: String,
     path: String,
 ) -> Result<Project, KodiqError> {
+    sandbox::check_webview(&webview)?;
     let conn = db.connection.lock()?;
     Ok(get_or_create(&conn, &name, &path)?)
 }
@@ -157,10 +176,12 @@ pub fn db_list_projects(db: tauri::State<DbState>) -> Result<Vec<Project>, Kodiq
 
 #[tauri::command]
 pub fn db_create_project(
+    webview: tauri::Webview,
     db: tauri::State<DbState>,
     name: String,
     path: String,
 ) -> Result<Project, KodiqError> {
+    sandbox::check_webview(&webview)?;
     let conn = db.connection.lock()?;
     Ok(create(&conn, &name, &path)?)
 }
@@ -173,10 +194,12 @@ pub fn db_touch_project(db: tauri::State<DbState>, path: String) -> Result<(), K
 
 #[tauri::command]
 pub fn db_update_project(
+    webview: tauri::Webview,
     db: tauri::State<DbState>,
     id: String,
     patch: ProjectPatch,
 ) -> Result<(), KodiqError> {
+    sandbox::check_webview(&webview)?;
     let conn = db.connection.lock()?;
     Ok(update(&conn, &id, &patch)?)
 }
@@ -246,6 +269,23 @@ mod tests {
         assert_eq!(projects[0].default_cli, Some("claude".to_string()));
     }
 
+    #[test]
+    fn test_is_trusted() {
+        let conn = test_db();
+        let project = create(&conn, "proj", "/tmp/trust").unwrap();
+        assert!(!is_trusted(&conn, "/tmp/trust"));
+        let patch = |settings: &str| ProjectPatch {
+            name: None,
+            default_cli: None,
+            settings: Some(settings.to_string()),
+        };
+        update(&conn, &project.id, &patch(r#"{"trusted":true}"#)).unwrap();
+        assert!(is_trusted(&conn, "/tmp/trust/"));
+        update(&conn, &project.id, &patch("not json")).unwrap();
+        assert!(!is_trusted(&conn, "/tmp/trust"));
+        assert!(!is_trusted(&conn, "/tmp/other"));
+    }
+
     #[test]
     fn test_get_or_create_new() {
         let conn = test_db();
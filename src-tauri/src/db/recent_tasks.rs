@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentTask {
+    pub task_id: String,
+    pub name: String,
+    pub source: String,
+    pub run_count: u32,
+    pub last_run_at: i64,
+}
+
+// ── Pure functions ───────────────────────────────────────────────────
+
+/// Note a run of `task_id` in `root` at `at` (Unix seconds).
+pub fn record(
+    conn: &rusqlite::Connection,
+    root: &str,
+    task_id: &str,
+    name: &str,
+    source: &str,
+    at: i64,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO recent_tasks (root, task_id, name, source, last_run_at) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(root, task_id) DO UPDATE SET
+            name = ?3, source = ?4, run_count = run_count + 1, last_run_at = ?5",
+        rusqlite::params![root, task_id, name, source, at],
+    )?;
+    Ok(())
+}
+
+/// A project's tasks, most recently run first.
+pub fn list(
+    conn: &rusqlite::Connection,
+    root: &str,
+    limit: u32,
+) -> Result<Vec<RecentTask>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT task_id, name, source, run_count, last_run_at FROM recent_tasks
+         WHERE root = ?1 ORDER BY last_run_at DESC, task_id LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![root, limit], |r| {
+        Ok(RecentTask {
+            task_id: r.get(0)?,
+            name: r.get(1)?,
+            source: r.get(2)?,
+            run_count: r.get(3)?,
+            last_run_at: r.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn clear(conn: &rusqlite::Connection, root: &str) -> Result<u64, rusqlite::Error> {
+    let count =
+        conn.execute("DELETE FROM recent_tasks WHERE root = ?1", rusqlite::params![root])?;
+    Ok(count as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test;
+
+    #[test]
+    fn test_record_and_list() {
+        let db = init_test();
+        let conn = db.connection.lock().unwrap();
+        record(&conn, "/work/app", "npm:dev", "dev", "npm", 100).unwrap();
+        record(&conn, "/work/app", "make:test", "test", "make", 200).unwrap();
+        record(&conn, "/work/app", "npm:dev", "dev", "npm", 300).unwrap();
+        record(&conn, "/other", "just:fmt", "fmt", "just", 300).unwrap();
+
+        let recent = list(&conn, "/work/app", 10).unwrap();
+        let summary: Vec<_> = recent.iter().map(|t| (t.task_id.as_str(), t.run_count)).collect();
+        assert_eq!(summary, vec![("npm:dev", 2), ("make:test", 1)]);
+        assert_eq!(list(&conn, "/work/app", 1).unwrap().len(), 1);
+
+        assert_eq!(clear(&conn, "/work/app").unwrap(), 2);
+        assert_eq!(list(&conn, "/other", 10).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,526 @@
+use crate::error::KodiqError;
+use crate::state::DbState;
+use serde::{Deserialize, Serialize};
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    History,
+    Chat,
+    Snippet,
+    Transcript,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchFilters {
+    pub project_id: Option<String>,
+    /// Chat provider or CLI name (`claude`, `gemini`, ...).
+    pub provider: Option<String>,
+    /// Unix seconds, inclusive.
+    pub since: Option<i64>,
+    /// Unix seconds, inclusive.
+    pub until: Option<i64>,
+    /// Only meaningful for history — other kinds are skipped when set.
+    pub exit_code: Option<i32>,
+    /// Restrict to these sources. `None` searches everything.
+    pub kinds: Option<Vec<SearchKind>>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    /// Source row id (history/transcript ids are stringified integers).
+    pub id: String,
+    pub project_id: Option<String>,
+    pub provider: Option<String>,
+    pub title: Option<String>,
+    /// Matching excerpt, HTML-escaped, with matches wrapped in `<mark>`.
+    pub snippet: String,
+    pub exit_code: Option<i32>,
+    pub timestamp: i64,
+    /// bm25 score — lower is a better match.
+    pub rank: f64,
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────
+
+/// Markers used inside SQLite `snippet()`/`highlight()` — replaced by `<mark>`
+/// after HTML-escaping so user content can't inject markup.
+const MARK_OPEN: &str = "\u{2}";
+const MARK_CLOSE: &str = "\u{3}";
+
+const DEFAULT_LIMIT: u32 = 50;
+
+/// Turn free-form user input into a safe FTS5 MATCH expression.
+/// Every whitespace-separated term becomes a quoted prefix query, ANDed together,
+/// so `git sta` matches `git status` and FTS operators in input are inert.
+pub fn fts_query(raw: &str) -> Option<String> {
+    let terms: Vec<String> = raw
+        .split_whitespace()
+        .map(|t| t.replace('"', ""))
+        .filter(|t| t.chars().any(|c| c.is_alphanumeric()))
+        .map(|t| format!("\"{}\"*", t))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+fn render_snippet(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace(MARK_OPEN, "<mark>")
+        .replace(MARK_CLOSE, "</mark>")
+}
+
+fn wants(filters: &SearchFilters, kind: SearchKind) -> bool {
+    if filters.exit_code.is_some() && kind != SearchKind::History {
+        return false;
+    }
+    filters.kinds.as_ref().map_or(true, |k| k.contains(&kind))
+}
+
+/// Accumulates `AND ...` clauses with positional params.
+struct Clauses {
+    sql: String,
+    params: Vec<Box<dyn rusqlite::types::ToSql>>,
+}
+
+impl Clauses {
+    fn new(fts: &str) -> Self {
+        Self { sql: String::new(), params: vec![Box::new(fts.to_string())] }
+    }
+
+    /// `condition` uses `{}` where the bound parameter goes.
+    fn push(&mut self, condition: &str, value: Box<dyn rusqlite::types::ToSql>) {
+        self.params.push(value);
+        let placeholder = format!("?{}", self.params.len());
+        self.sql.push_str(" AND ");
+        self.sql.push_str(&condition.replace("{}", &placeholder));
+    }
+
+    fn push_range(&mut self, column: &str, filters: &SearchFilters) {
+        if let Some(since) = filters.since {
+            self.push(&format!("{} >= {{}}", column), Box::new(since));
+        }
+        if let Some(until) = filters.until {
+            self.push(&format!("{} <= {{}}", column), Box::new(until));
+        }
+    }
+
+    fn finish(mut self, limit: u32) -> (String, Vec<Box<dyn rusqlite::types::ToSql>>) {
+        self.params.push(Box::new(limit));
+        let sql = format!("{} ORDER BY rank LIMIT ?{}", self.sql, self.params.len());
+        (sql, self.params)
+    }
+}
+
+fn run(
+    conn: &rusqlite::Connection,
+    select: &str,
+    clauses: Clauses,
+    limit: u32,
+    map: impl FnMut(&rusqlite::Row) -> Result<SearchHit, rusqlite::Error>,
+) -> Result<Vec<SearchHit>, rusqlite::Error> {
+    let (tail, params) = clauses.finish(limit);
+    let mut stmt = conn.prepare(&format!("{}{}", select, tail))?;
+    let rows =
+        stmt.query_map(rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())), map)?;
+    rows.collect()
+}
+
+// ── Per-source queries ───────────────────────────────────────────────
+
+fn search_history(
+    conn: &rusqlite::Connection,
+    fts: &str,
+    filters: &SearchFilters,
+    limit: u32,
+) -> Result<Vec<SearchHit>, rusqlite::Error> {
+    let select = format!(
+        "SELECT h.id, h.project_id, h.cli_name, h.exit_code, h.timestamp,
+                highlight(history_fts, 0, '{MARK_OPEN}', '{MARK_CLOSE}'), bm25(history_fts) AS rank
+         FROM history_fts JOIN command_history h ON h.id = history_fts.rowid
+         WHERE history_fts MATCH ?1"
+    );
+    let mut c = Clauses::new(fts);
+    if let Some(ref pid) = filters.project_id {
+        c.push("h.project_id = {}", Box::new(pid.clone()));
+    }
+    if let Some(ref provider) = filters.provider {
+        c.push("h.cli_name = {}", Box::new(provider.clone()));
+    }
+    if let Some(code) = filters.exit_code {
+        c.push("h.exit_code = {}", Box::new(code));
+    }
+    c.push_range("h.timestamp", filters);
+
+    run(conn, &select, c, limit, |row| {
+        Ok(SearchHit {
+            kind: SearchKind::History,
+            id: row.get::<_, i64>(0)?.to_string(),
+            project_id: row.get(1)?,
+            provider: row.get(2)?,
+            title: None,
+            exit_code: row.get(3)?,
+            timestamp: row.get(4)?,
+            snippet: render_snippet(&row.get::<_, String>(5)?),
+            rank: row.get(6)?,
+        })
+    })
+}
+
+fn search_chat(
+    conn: &rusqlite::Connection,
+    fts: &str,
+    filters: &SearchFilters,
+    limit: u32,
+) -> Result<Vec<SearchHit>, rusqlite::Error> {
+    let select = format!(
+        "SELECT m.id, m.project_id, m.provider, m.created_at,
+                snippet(chat_fts, 0, '{MARK_OPEN}', '{MARK_CLOSE}', '…', 16), bm25(chat_fts) AS rank
+         FROM chat_fts
+         JOIN chat_fts_keys k ON k.rowid = chat_fts.rowid
+         JOIN chat_messages m ON m.id = k.id
+         WHERE chat_fts MATCH ?1"
+    );
+    let mut c = Clauses::new(fts);
+    if let Some(ref pid) = filters.project_id {
+        c.push("m.project_id = {}", Box::new(pid.clone()));
+    }
+    if let Some(ref provider) = filters.provider {
+        c.push("m.provider = {}", Box::new(provider.clone()));
+    }
+    c.push_range("m.created_at", filters);
+
+    run(conn, &select, c, limit, |row| {
+        Ok(SearchHit {
+            kind: SearchKind::Chat,
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            provider: row.get(2)?,
+            title: None,
+            exit_code: None,
+            timestamp: row.get(3)?,
+            snippet: render_snippet(&row.get::<_, String>(4)?),
+            rank: row.get(5)?,
+        })
+    })
+}
+
+/// Global snippets (no project) match every project filter.
+fn search_snippets(
+    conn: &rusqlite::Connection,
+    fts: &str,
+    filters: &SearchFilters,
+    limit: u32,
+) -> Result<Vec<SearchHit>, rusqlite::Error> {
+    // Title matches weigh more than body or tag matches
+    let select = format!(
+        "SELECT s.id, s.cli_name, s.title, s.updated_at,
+                snippet(snippets_fts, -1, '{MARK_OPEN}', '{MARK_CLOSE}', '…', 16),
+                bm25(snippets_fts, 10.0, 1.0, 5.0) AS rank, s.project_id
+         FROM snippets_fts
+         JOIN snippets_fts_keys k ON k.rowid = snippets_fts.rowid
+         JOIN snippets s ON s.id = k.id
+         WHERE snippets_fts MATCH ?1"
+    );
+    let mut c = Clauses::new(fts);
+    if let Some(ref pid) = filters.project_id {
+        c.push("(s.project_id = {} OR s.project_id IS NULL)", Box::new(pid.clone()));
+    }
+    if let Some(ref provider) = filters.provider {
+        c.push("(s.cli_name = {} OR s.cli_name IS NULL)", Box::new(provider.clone()));
+    }
+    c.push_range("s.updated_at", filters);
+
+    run(conn, &select, c, limit, |row| {
+        Ok(SearchHit {
+            kind: SearchKind::Snippet,
+            id: row.get(0)?,
+            project_id: row.get(6)?,
+            provider: row.get(1)?,
+            title: row.get(2)?,
+            exit_code: None,
+            timestamp: row.get(3)?,
+            snippet: render_snippet(&row.get::<_, String>(4)?),
+            rank: row.get(5)?,
+        })
+    })
+}
+
+fn search_transcripts(
+    conn: &rusqlite::Connection,
+    fts: &str,
+    filters: &SearchFilters,
+    limit: u32,
+) -> Result<Vec<SearchHit>, rusqlite::Error> {
+    let select = format!(
+        "SELECT m.id, c.project_id, c.cli_name, c.title, m.timestamp,
+                snippet(transcript_fts, 0, '{MARK_OPEN}', '{MARK_CLOSE}', '…', 16),
+                bm25(transcript_fts) AS rank
+         FROM transcript_fts
+         JOIN ai_messages m ON m.id = transcript_fts.rowid
+         JOIN ai_conversations c ON c.id = m.conversation_id
+         WHERE transcript_fts MATCH ?1"
+    );
+    let mut c = Clauses::new(fts);
+    if let Some(ref pid) = filters.project_id {
+        c.push("c.project_id = {}", Box::new(pid.clone()));
+    }
+    if let Some(ref provider) = filters.provider {
+        c.push("c.cli_name = {}", Box::new(provider.clone()));
+    }
+    c.push_range("m.timestamp", filters);
+
+    run(conn, &select, c, limit, |row| {
+        Ok(SearchHit {
+            kind: SearchKind::Transcript,
+            id: row.get::<_, i64>(0)?.to_string(),
+            project_id: row.get(1)?,
+            provider: row.get(2)?,
+            title: row.get(3)?,
+            exit_code: None,
+            timestamp: row.get(4)?,
+            snippet: render_snippet(&row.get::<_, String>(5)?),
+            rank: row.get(6)?,
+        })
+    })
+}
+
+// ── Pure functions ───────────────────────────────────────────────────
+
+/// Search every FTS index, merge results and order them by relevance.
+pub fn search(
+    conn: &rusqlite::Connection,
+    query: &str,
+    filters: &SearchFilters,
+) -> Result<Vec<SearchHit>, rusqlite::Error> {
+    let Some(fts) = fts_query(query) else {
+        return Ok(Vec::new());
+    };
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT);
+
+    let mut hits = Vec::new();
+    if wants(filters, SearchKind::History) {
+        hits.extend(search_history(conn, &fts, filters, limit)?);
+    }
+    if wants(filters, SearchKind::Chat) {
+        hits.extend(search_chat(conn, &fts, filters, limit)?);
+    }
+    if wants(filters, SearchKind::Snippet) {
+        hits.extend(search_snippets(conn, &fts, filters, limit)?);
+    }
+    if wants(filters, SearchKind::Transcript) {
+        hits.extend(search_transcripts(conn, &fts, filters, limit)?);
+    }
+
+    hits.sort_by(|a, b| a.rank.total_cmp(&b.rank).then(b.timestamp.cmp(&a.timestamp)));
+    hits.truncate(limit as usize);
+    Ok(hits)
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn db_search(
+    db: tauri::State<DbState>,
+    query: String,
+    filters: Option<SearchFilters>,
+) -> Result<Vec<SearchHit>, KodiqError> {
+    let conn = db.connection.lock()?;
+    Ok(search(&conn, &query, &filters.unwrap_or_default())?)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, chat, history, projects, snippets};
+
+    fn test_db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
+        db::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn add_history(conn: &rusqlite::Connection, pid: &str, command: &str, exit_code: i32) {
+        history::add(
+            conn,
+            &history::NewHistoryEntry {
+                project_id: pid.to_string(),
+                session_id: None,
+                command: command.to_string(),
+                cli_name: None,
+                exit_code: Some(exit_code),
+                duration_ms: None,
+                cwd: None,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query("git sta"), Some("\"git\"* \"sta\"*".to_string()));
+        assert_eq!(fts_query("say \"hi\" OR"), Some("\"say\"* \"hi\"* \"OR\"*".to_string()));
+        assert_eq!(fts_query("  -- \"\" "), None);
+    }
+
+    #[test]
+    fn test_render_snippet_escapes_html() {
+        let raw = format!("<b>{}cargo{}</b>", MARK_OPEN, MARK_CLOSE);
+        assert_eq!(render_snippet(&raw), "&lt;b&gt;<mark>cargo</mark>&lt;/b&gt;");
+    }
+
+    #[test]
+    fn test_search_across_sources() {
+        let conn = test_db();
+        let p = projects::create(&conn, "proj", "/tmp/proj").unwrap();
+        add_history(&conn, &p.id, "cargo build --release", 0);
+        chat::save(
+            &conn,
+            &chat::NewChatMessage {
+                id: "m1".into(),
+                project_id: p.id.clone(),
+                role: "user".into(),
+                content: "Why does cargo build fail?".into(),
+                provider: "claude".into(),
+            },
+        )
+        .unwrap();
+        snippets::create(
+            &conn,
+            &snippets::NewSnippet {
+                title: "Cargo release".into(),
+                content: "cargo build --release && strip target/release/app".into(),
+                cli_name: None,
+                tags: vec![],
+                project_id: None,
+            },
+        )
+        .unwrap();
+
+        let hits = search(&conn, "cargo bui", &SearchFilters::default()).unwrap();
+        assert_eq!(hits.len(), 3);
+        assert!(hits.iter().all(|h| h.snippet.contains("<mark>")));
+
+        let chat_only = SearchFilters { kinds: Some(vec![SearchKind::Chat]), ..Default::default() };
+        let hits = search(&conn, "cargo", &chat_only).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "m1");
+        assert_eq!(hits[0].provider.as_deref(), Some("claude"));
+    }
+
+    #[test]
+    fn test_filters() {
+        let conn = test_db();
+        let a = projects::create(&conn, "a", "/tmp/a").unwrap();
+        let b = projects::create(&conn, "b", "/tmp/b").unwrap();
+        add_history(&conn, &a.id, "npm test", 1);
+        add_history(&conn, &a.id, "npm run dev", 0);
+        add_history(&conn, &b.id, "npm install", 0);
+
+        let by_project = SearchFilters { project_id: Some(a.id.clone()), ..Default::default() };
+        assert_eq!(search(&conn, "npm", &by_project).unwrap().len(), 2);
+
+        let failing = SearchFilters { exit_code: Some(1), ..Default::default() };
+        let hits = search(&conn, "npm", &failing).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, SearchKind::History);
+
+        let future = SearchFilters { since: Some(i64::MAX), ..Default::default() };
+        assert!(search(&conn, "npm", &future).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_triggers_keep_index_in_sync() {
+        let conn = test_db();
+        let p = projects::create(&conn, "proj", "/tmp/sync").unwrap();
+        for (id, content) in [("m1", "first draft"), ("m2", "second draft")] {
+            chat::save(
+                &conn,
+                &chat::NewChatMessage {
+                    id: id.into(),
+                    project_id: p.id.clone(),
+                    role: "assistant".into(),
+                    content: content.into(),
+                    provider: "gemini".into(),
+                },
+            )
+            .unwrap();
+        }
+        assert_eq!(search(&conn, "draft", &SearchFilters::default()).unwrap().len(), 2);
+
+        conn.execute("UPDATE chat_messages SET content = 'final copy' WHERE id = 'm1'", [])
+            .unwrap();
+        assert_eq!(search(&conn, "draft", &SearchFilters::default()).unwrap().len(), 1);
+        assert_eq!(search(&conn, "final", &SearchFilters::default()).unwrap().len(), 1);
+
+        chat::clear(&conn, &p.id).unwrap();
+        assert!(search(&conn, "draft", &SearchFilters::default()).unwrap().is_empty());
+        let keys: i64 =
+            conn.query_row("SELECT COUNT(*) FROM chat_fts_keys", [], |r| r.get(0)).unwrap();
+        assert_eq!(keys, 0);
+    }
+
+    #[test]
+    fn test_hits_survive_vacuum() {
+        let conn = test_db();
+        let p = projects::create(&conn, "proj", "/tmp/vacuum").unwrap();
+        for (id, content) in [("m1", "alpha"), ("m2", "beta"), ("m3", "gamma")] {
+            chat::save(
+                &conn,
+                &chat::NewChatMessage {
+                    id: id.into(),
+                    project_id: p.id.clone(),
+                    role: "user".into(),
+                    content: content.into(),
+                    provider: "claude".into(),
+                },
+            )
+            .unwrap();
+        }
+        conn.execute("DELETE FROM chat_messages WHERE id IN ('m1', 'm2')", []).unwrap();
+        conn.execute_batch("VACUUM").unwrap();
+
+        let hits = search(&conn, "gamma", &SearchFilters::default()).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "m3");
+        assert!(search(&conn, "alpha", &SearchFilters::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_transcripts() {
+        let conn = test_db();
+        let p = projects::create(&conn, "proj", "/tmp/transcripts").unwrap();
+        conn.execute(
+            "INSERT INTO ai_conversations (id, project_id, cli_name, title, started_at)
+             VALUES ('c1', ?1, 'codex', 'Refactor', 100)",
+            rusqlite::params![p.id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO ai_messages (conversation_id, role, content, timestamp)
+             VALUES ('c1', 'assistant', 'Extracted the parser into its own module', 120)",
+            [],
+        )
+        .unwrap();
+
+        let hits = search(&conn, "parser", &SearchFilters::default()).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, SearchKind::Transcript);
+        assert_eq!(hits[0].title.as_deref(), Some("Refactor"));
+        assert_eq!(hits[0].project_id.as_deref(), Some(p.id.as_str()));
+    }
+}
@@ -2,13 +2,15 @@ use crate::error::KodiqError;
 use crate::state::DbState;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Snippet {
     pub id: String,
     pub title: String,
     pub content: String,
     pub cli_name: Option<String>,
-    pub tags: Option<String>,
+    pub tags: Vec<String>,
+    /// `None` = global snippet, visible in every project.
+    pub project_id: Option<String>,
     pub usage_count: i32,
     pub created_at: i64,
     pub updated_at: i64,
@@ -19,98 +21,328 @@ pub struct NewSnippet {
     pub title: String,
     pub content: String,
     pub cli_name: Option<String>,
-    pub tags: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateSnippet {
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub cli_name: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// Shareable bundle of snippets (no ids, usage or scope — those are local).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnippetPack {
+    pub format: String,
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub snippets: Vec<PackSnippet>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackSnippet {
+    pub title: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cli_name: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: u32,
+}
+
+const PACK_FORMAT: &str = "kodiq-snippets";
+const PACK_VERSION: u32 = 1;
+
+const COLUMNS: &str =
+    "id, title, content, cli_name, tags, project_id, usage_count, created_at, updated_at";
+
+// ── Helpers ──────────────────────────────────────────────────────────
+
+fn now() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+/// Trim, drop empties and split accidental comma lists; dedupe case-insensitively.
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags.iter().flat_map(|t| t.split(',')).map(str::trim).filter(|t| !t.is_empty()) {
+        if !out.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            out.push(tag.to_string());
+        }
+    }
+    out
+}
+
+fn set_tags(conn: &rusqlite::Connection, id: &str, tags: &[String]) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM snippet_tags WHERE snippet_id = ?1", rusqlite::params![id])?;
+    for tag in normalize_tags(tags) {
+        conn.execute(
+            "INSERT INTO snippet_tags (snippet_id, tag) VALUES (?1, ?2)",
+            rusqlite::params![id, tag],
+        )?;
+    }
+    Ok(())
 }
 
 // ── Pure functions ───────────────────────────────────────────────────
 
+/// List global snippets plus those scoped to `project_id`, most used first.
+/// Without a project every snippet is listed, whatever its scope.
 pub fn list(
     conn: &rusqlite::Connection,
     cli_name: Option<&str>,
+    project_id: Option<&str>,
+    tag: Option<&str>,
 ) -> Result<Vec<Snippet>, rusqlite::Error> {
-    if let Some(cli) = cli_name {
-        let mut stmt = conn.prepare(
-            "SELECT id, title, content, cli_name, tags, usage_count, created_at, updated_at
-             FROM snippets WHERE cli_name = ?1 OR cli_name IS NULL
-             ORDER BY usage_count DESC",
-        )?;
-        let rows = stmt.query_map(rusqlite::params![cli], map_row)?;
-        rows.collect()
-    } else {
-        let mut stmt = conn.prepare(
-            "SELECT id, title, content, cli_name, tags, usage_count, created_at, updated_at
-             FROM snippets ORDER BY usage_count DESC",
-        )?;
-        let rows = stmt.query_map([], map_row)?;
-        rows.collect()
-    }
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM snippets
+         WHERE (?1 IS NULL OR cli_name = ?1 OR cli_name IS NULL)
+           AND (?2 IS NULL OR project_id IS NULL OR project_id = ?2)
+           AND (?3 IS NULL OR id IN (SELECT snippet_id FROM snippet_tags WHERE tag = ?3))
+         ORDER BY usage_count DESC, title ASC",
+        COLUMNS
+    ))?;
+    let rows = stmt.query_map(rusqlite::params![cli_name, project_id, tag], map_row)?;
+    rows.collect()
+}
+
+pub fn get(conn: &rusqlite::Connection, id: &str) -> Result<Snippet, rusqlite::Error> {
+    conn.query_row(
+        &format!("SELECT {} FROM snippets WHERE id = ?1", COLUMNS),
+        rusqlite::params![id],
+        map_row,
+    )
+}
+
+/// Insert a snippet and its tags. Callers own the transaction.
+fn insert(conn: &rusqlite::Connection, snippet: &NewSnippet) -> Result<String, rusqlite::Error> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now();
+    conn.execute(
+        "INSERT INTO snippets (id, title, content, cli_name, project_id, usage_count, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7)",
+        rusqlite::params![
+            id,
+            snippet.title,
+            snippet.content,
+            snippet.cli_name,
+            snippet.project_id,
+            now,
+            now
+        ],
+    )?;
+    set_tags(conn, &id, &snippet.tags)?;
+    Ok(id)
 }
 
 pub fn create(
     conn: &rusqlite::Connection,
     snippet: &NewSnippet,
 ) -> Result<Snippet, rusqlite::Error> {
-    let id = uuid::Uuid::new_v4().to_string();
-    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
-        as i64;
+    let tx = conn.unchecked_transaction()?;
+    let id = insert(&tx, snippet)?;
+    tx.commit()?;
 
-    conn.execute(
-        "INSERT INTO snippets (id, title, content, cli_name, tags, usage_count, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7)",
-        rusqlite::params![id, snippet.title, snippet.content, snippet.cli_name, snippet.tags, now, now],
-    )?;
+    get(conn, &id)
+}
 
-    Ok(Snippet {
-        id,
-        title: snippet.title.clone(),
-        content: snippet.content.clone(),
-        cli_name: snippet.cli_name.clone(),
-        tags: snippet.tags.clone(),
-        usage_count: 0,
-        created_at: now,
-        updated_at: now,
-    })
+pub fn update(
+    conn: &rusqlite::Connection,
+    id: &str,
+    patch: &UpdateSnippet,
+) -> Result<Snippet, rusqlite::Error> {
+    let mut sets = vec!["updated_at = ?1".to_string()];
+    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(now())];
+
+    if let Some(ref title) = patch.title {
+        params.push(Box::new(title.clone()));
+        sets.push(format!("title = ?{}", params.len()));
+    }
+    if let Some(ref content) = patch.content {
+        params.push(Box::new(content.clone()));
+        sets.push(format!("content = ?{}", params.len()));
+    }
+    if let Some(ref cli_name) = patch.cli_name {
+        // Empty string clears the CLI restriction
+        let value = Some(cli_name.as_str()).filter(|s| !s.is_empty()).map(String::from);
+        params.push(Box::new(value));
+        sets.push(format!("cli_name = ?{}", params.len()));
+    }
+
+    let sql = format!("UPDATE snippets SET {} WHERE id = ?{}", sets.join(", "), params.len() + 1);
+    params.push(Box::new(id.to_string()));
+
+    let tx = conn.unchecked_transaction()?;
+    let changed =
+        tx.execute(&sql, rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())))?;
+    if changed == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    if let Some(ref tags) = patch.tags {
+        set_tags(&tx, id, tags)?;
+    }
+    tx.commit()?;
+
+    get(conn, id)
 }
 
-pub fn use_snippet(conn: &rusqlite::Connection, id: &str) -> Result<Snippet, rusqlite::Error> {
-    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
-        as i64;
+pub fn delete(conn: &rusqlite::Connection, id: &str) -> Result<(), rusqlite::Error> {
+    let deleted = conn.execute("DELETE FROM snippets WHERE id = ?1", rusqlite::params![id])?;
+    if deleted == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    Ok(())
+}
 
+pub fn use_snippet(conn: &rusqlite::Connection, id: &str) -> Result<Snippet, rusqlite::Error> {
     conn.execute(
         "UPDATE snippets SET usage_count = usage_count + 1, updated_at = ?1 WHERE id = ?2",
-        rusqlite::params![now, id],
+        rusqlite::params![now(), id],
     )?;
+    get(conn, id)
+}
 
-    conn.query_row(
-        "SELECT id, title, content, cli_name, tags, usage_count, created_at, updated_at
-         FROM snippets WHERE id = ?1",
-        rusqlite::params![id],
-        map_row,
-    )
+/// All tags with the number of snippets using them, visible from `project_id`
+/// (every snippet's without one).
+pub fn list_tags(
+    conn: &rusqlite::Connection,
+    project_id: Option<&str>,
+) -> Result<Vec<TagCount>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT t.tag, COUNT(*) FROM snippet_tags t JOIN snippets s ON s.id = t.snippet_id
+         WHERE ?1 IS NULL OR s.project_id IS NULL OR s.project_id = ?1
+         GROUP BY t.tag ORDER BY COUNT(*) DESC, t.tag ASC",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![project_id], |row| {
+        Ok(TagCount { tag: row.get(0)?, count: row.get(1)? })
+    })?;
+    rows.collect()
+}
+
+pub fn export_pack(
+    conn: &rusqlite::Connection,
+    ids: &[String],
+    name: Option<&str>,
+) -> Result<SnippetPack, rusqlite::Error> {
+    let snippets = ids
+        .iter()
+        .map(|id| {
+            get(conn, id).map(|s| PackSnippet {
+                title: s.title,
+                content: s.content,
+                cli_name: s.cli_name,
+                tags: s.tags,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(SnippetPack {
+        format: PACK_FORMAT.to_string(),
+        version: PACK_VERSION,
+        name: name.map(String::from),
+        snippets,
+    })
+}
+
+/// Import a pack into `project_id` (or globally). Snippets whose title and
+/// content already exist in that scope are skipped. Returns the number added;
+/// nothing is added if any snippet fails.
+pub fn import_pack(
+    conn: &mut rusqlite::Connection,
+    pack: &SnippetPack,
+    project_id: Option<&str>,
+) -> Result<usize, KodiqError> {
+    if pack.format != PACK_FORMAT {
+        return Err(KodiqError::Other(format!("Not a Kodiq snippet pack: {}", pack.format)));
+    }
+    if pack.version > PACK_VERSION {
+        return Err(KodiqError::Other(format!(
+            "Snippet pack version {} is newer than supported ({})",
+            pack.version, PACK_VERSION
+        )));
+    }
+
+    let tx = conn.transaction()?;
+    let mut imported = 0;
+    for item in &pack.snippets {
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM snippets
+             WHERE title = ?1 AND content = ?2 AND project_id IS ?3)",
+            rusqlite::params![item.title, item.content, project_id],
+            |r| r.get(0),
+        )?;
+        if exists {
+            continue;
+        }
+        insert(
+            &tx,
+            &NewSnippet {
+                title: item.title.clone(),
+                content: item.content.clone(),
+                cli_name: item.cli_name.clone(),
+                tags: item.tags.clone(),
+                project_id: project_id.map(String::from),
+            },
+        )?;
+        imported += 1;
+    }
+    tx.commit()?;
+    Ok(imported)
 }
 
 fn map_row(row: &rusqlite::Row) -> Result<Snippet, rusqlite::Error> {
+    let tags: Option<String> = row.get(4)?;
+    let mut tags: Vec<String> =
+        tags.map(|t| t.split(',').map(String::from).collect()).unwrap_or_default();
+    tags.sort_by_key(|t| t.to_lowercase());
+
     Ok(Snippet {
         id: row.get(0)?,
         title: row.get(1)?,
         content: row.get(2)?,
         cli_name: row.get(3)?,
-        tags: row.get(4)?,
-        usage_count: row.get(5)?,
-        created_at: row.get(6)?,
-        updated_at: row.get(7)?,
+        tags,
+        project_id: row.get(5)?,
+        usage_count: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
     })
 }
 
+fn not_found(id: &str) -> impl FnOnce(rusqlite::Error) -> KodiqError + '_ {
+    move |e| match e {
+        rusqlite::Error::QueryReturnedNoRows => KodiqError::NotFound(format!("Snippet: {}", id)),
+        other => other.into(),
+    }
+}
+
 // ── Tauri Commands ───────────────────────────────────────────────────
 
 #[tauri::command]
 pub fn db_list_snippets(
     db: tauri::State<DbState>,
     cli_name: Option<String>,
+    project_id: Option<String>,
+    tag: Option<String>,
 ) -> Result<Vec<Snippet>, KodiqError> {
     let conn = db.connection.lock()?;
-    Ok(list(&conn, cli_name.as_deref())?)
+    Ok(list(&conn, cli_name.as_deref(), project_id.as_deref(), tag.as_deref())?)
+}
+
+#[tauri::command]
+pub fn db_get_snippet(db: tauri::State<DbState>, id: String) -> Result<Snippet, KodiqError> {
+    let conn = db.connection.lock()?;
+    get(&conn, &id).map_err(not_found(&id))
 }
 
 #[tauri::command]
@@ -122,8 +354,225 @@ pub fn db_create_snippet(
     Ok(create(&conn, &snippet)?)
 }
 
+#[tauri::command]
+pub fn db_update_snippet(
+    db: tauri::State<DbState>,
+    id: String,
+    patch: UpdateSnippet,
+) -> Result<Snippet, KodiqError> {
+    let conn = db.connection.lock()?;
+    update(&conn, &id, &patch).map_err(not_found(&id))
+}
+
+#[tauri::command]
+pub fn db_delete_snippet(db: tauri::State<DbState>, id: String) -> Result<(), KodiqError> {
+    let conn = db.connection.lock()?;
+    delete(&conn, &id).map_err(not_found(&id))
+}
+
 #[tauri::command]
 pub fn db_use_snippet(db: tauri::State<DbState>, id: String) -> Result<Snippet, KodiqError> {
     let conn = db.connection.lock()?;
-    Ok(use_snippet(&conn, &id)?)
+    use_snippet(&conn, &id).map_err(not_found(&id))
+}
+
+#[tauri::command]
+pub fn db_list_snippet_tags(
+    db: tauri::State<DbState>,
+    project_id: Option<String>,
+) -> Result<Vec<TagCount>, KodiqError> {
+    let conn = db.connection.lock()?;
+    Ok(list_tags(&conn, project_id.as_deref())?)
+}
+
+#[tauri::command]
+pub fn db_export_snippet_pack(
+    db: tauri::State<DbState>,
+    ids: Vec<String>,
+    name: Option<String>,
+) -> Result<String, KodiqError> {
+    let conn = db.connection.lock()?;
+    let pack = export_pack(&conn, &ids, name.as_deref())?;
+    serde_json::to_string_pretty(&pack)
+        .map_err(|e| KodiqError::Other(format!("Serialize snippet pack: {}", e)))
+}
+
+#[tauri::command]
+pub fn db_import_snippet_pack(
+    db: tauri::State<DbState>,
+    data: String,
+    project_id: Option<String>,
+) -> Result<usize, KodiqError> {
+    let pack: SnippetPack = serde_json::from_str(&data)
+        .map_err(|e| KodiqError::Other(format!("Invalid snippet pack: {}", e)))?;
+    let mut conn = db.connection.lock()?;
+    import_pack(&mut conn, &pack, project_id.as_deref())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn test_db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
+        db::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn new_snippet(title: &str, tags: &[&str], project_id: Option<&str>) -> NewSnippet {
+        NewSnippet {
+            title: title.to_string(),
+            content: format!("{} body", title),
+            cli_name: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            project_id: project_id.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_crud() {
+        let conn = test_db();
+        let s =
+            create(&conn, &new_snippet("Review", &["review", " git ", "Review"], None)).unwrap();
+        assert_eq!(s.tags, vec!["git", "review"]);
+
+        let s = update(
+            &conn,
+            &s.id,
+            &UpdateSnippet {
+                title: Some("Code review".into()),
+                tags: Some(vec!["pr".into()]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(s.title, "Code review");
+        assert_eq!(s.tags, vec!["pr"]);
+        assert_eq!(s.content, "Review body");
+
+        assert_eq!(use_snippet(&conn, &s.id).unwrap().usage_count, 1);
+
+        delete(&conn, &s.id).unwrap();
+        assert!(list(&conn, None, None, None).unwrap().is_empty());
+        assert!(matches!(delete(&conn, &s.id), Err(rusqlite::Error::QueryReturnedNoRows)));
+        let orphans: u32 =
+            conn.query_row("SELECT COUNT(*) FROM snippet_tags", [], |r| r.get(0)).unwrap();
+        assert_eq!(orphans, 0);
+
+        assert!(matches!(
+            update(&conn, "missing", &UpdateSnippet::default()),
+            Err(rusqlite::Error::QueryReturnedNoRows)
+        ));
+    }
+
+    #[test]
+    fn test_project_scope_and_tag_filter() {
+        let conn = test_db();
+        let a = db::projects::create(&conn, "a", "/tmp/a").unwrap();
+        let b = db::projects::create(&conn, "b", "/tmp/b").unwrap();
+        create(&conn, &new_snippet("Global", &["docs"], None)).unwrap();
+        create(&conn, &new_snippet("Only A", &["docs", "test"], Some(&a.id))).unwrap();
+        create(&conn, &new_snippet("Only B", &["test"], Some(&b.id))).unwrap();
+
+        assert_eq!(list(&conn, None, Some(&a.id), None).unwrap().len(), 2);
+        // No project: every snippet, as before scoping existed
+        assert_eq!(list(&conn, None, None, None).unwrap().len(), 3);
+        assert_eq!(list(&conn, None, Some(&a.id), Some("TEST")).unwrap().len(), 1);
+
+        let tags = list_tags(&conn, Some(&a.id)).unwrap();
+        assert_eq!(tags[0].tag, "docs");
+        assert_eq!(tags[0].count, 2);
+        let all = list_tags(&conn, None).unwrap();
+        assert_eq!((all[0].tag.as_str(), all[0].count), ("docs", 2));
+        assert_eq!((all[1].tag.as_str(), all[1].count), ("test", 2));
+    }
+
+    #[test]
+    fn test_tags_stay_searchable() {
+        let conn = test_db();
+        create(&conn, &new_snippet("Deploy", &["kubernetes"], None)).unwrap();
+        let hits =
+            db::search::search(&conn, "kubernetes", &db::search::SearchFilters::default()).unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn test_legacy_tags_migrated() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE _migrations (version INTEGER PRIMARY KEY, name TEXT NOT NULL, applied_at INTEGER NOT NULL);",
+        )
+        .unwrap();
+        for m in db::migrations::MIGRATIONS.iter().filter(|m| m.version < 7) {
+            conn.execute_batch(m.sql).unwrap();
+            conn.execute(
+                "INSERT INTO _migrations (version, name, applied_at) VALUES (?1, ?2, 0)",
+                rusqlite::params![m.version, m.name],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO snippets (id, title, content, tags, created_at, updated_at)
+             VALUES ('s1', 't', 'c', 'rust, cli,,rust', 0, 0)",
+            [],
+        )
+        .unwrap();
+
+        db::migrations::run_migrations(&conn).unwrap();
+        assert_eq!(get(&conn, "s1").unwrap().tags, vec!["cli", "rust"]);
+    }
+
+    #[test]
+    fn test_pack_roundtrip() {
+        let mut conn = test_db();
+        let p = db::projects::create(&conn, "p", "/tmp/p").unwrap();
+        let s = create(&conn, &new_snippet("Explain", &["learn"], None)).unwrap();
+
+        let pack = export_pack(&conn, std::slice::from_ref(&s.id), Some("Team prompts")).unwrap();
+        let json = serde_json::to_string(&pack).unwrap();
+        let pack: SnippetPack = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(import_pack(&mut conn, &pack, Some(&p.id)).unwrap(), 1);
+        assert_eq!(import_pack(&mut conn, &pack, Some(&p.id)).unwrap(), 0); // duplicate skipped
+
+        let scoped: Vec<Snippet> = list(&conn, None, Some(&p.id), None)
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.project_id.is_some())
+            .collect();
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].tags, vec!["learn"]);
+    }
+
+    #[test]
+    fn test_failed_import_adds_nothing() {
+        let mut conn = test_db();
+        conn.execute_batch(
+            "CREATE TEMP TRIGGER reject BEFORE INSERT ON snippets WHEN new.title = 'Bad'
+             BEGIN SELECT RAISE(ABORT, 'rejected'); END;",
+        )
+        .unwrap();
+        let item = |title: &str| PackSnippet {
+            title: title.into(),
+            content: "body".into(),
+            cli_name: None,
+            tags: vec!["t".into()],
+        };
+        let pack = SnippetPack {
+            format: PACK_FORMAT.into(),
+            version: PACK_VERSION,
+            name: None,
+            snippets: vec![item("Good"), item("Bad")],
+        };
+
+        assert!(import_pack(&mut conn, &pack, None).is_err());
+        assert!(list(&conn, None, None, None).unwrap().is_empty());
+        let tags: u32 =
+            conn.query_row("SELECT COUNT(*) FROM snippet_tags", [], |r| r.get(0)).unwrap();
+        assert_eq!(tags, 0);
+    }
 }
@@ -0,0 +1,73 @@
+use crate::test_runner::runner::TestReport;
+
+// ── Pure functions ───────────────────────────────────────────────────
+
+/// Keep `report` as the last run of its framework in `report.root`.
+pub fn save(conn: &rusqlite::Connection, report: &TestReport) -> Result<(), rusqlite::Error> {
+    let json = serde_json::to_string(report)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    conn.execute(
+        "INSERT INTO test_results (root, framework, report, finished_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(root, framework) DO UPDATE SET report = ?3, finished_at = ?4",
+        rusqlite::params![report.root, report.framework.as_str(), json, report.finished_at],
+    )?;
+    Ok(())
+}
+
+/// Last runs for a project, newest first. Rows from an older report
+/// format are skipped.
+pub fn list(conn: &rusqlite::Connection, root: &str) -> Result<Vec<TestReport>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT report FROM test_results WHERE root = ?1 ORDER BY finished_at DESC, framework",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![root], |r| r.get::<_, String>(0))?;
+    Ok(rows.filter_map(|r| r.ok()).filter_map(|json| serde_json::from_str(&json).ok()).collect())
+}
+
+pub fn clear(conn: &rusqlite::Connection, root: &str) -> Result<u64, rusqlite::Error> {
+    let count =
+        conn.execute("DELETE FROM test_results WHERE root = ?1", rusqlite::params![root])?;
+    Ok(count as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_test;
+    use crate::test_runner::runner::{Framework, TestScope};
+
+    fn report(framework: Framework, finished_at: i64, failed: u32) -> TestReport {
+        TestReport {
+            root: "/work/app".into(),
+            framework,
+            scope: TestScope::All,
+            started_at: finished_at - 1000,
+            finished_at,
+            duration_ms: 1000,
+            exit_code: Some(if failed > 0 { 101 } else { 0 }),
+            cancelled: false,
+            passed: 3,
+            failed,
+            skipped: 0,
+            tree: Vec::new(),
+            error: None,
+            output: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_save_replaces_per_framework() {
+        let db = init_test();
+        let conn = db.connection.lock().unwrap();
+        save(&conn, &report(Framework::Cargo, 1_000, 1)).unwrap();
+        save(&conn, &report(Framework::Vitest, 2_000, 0)).unwrap();
+        save(&conn, &report(Framework::Cargo, 3_000, 0)).unwrap();
+
+        let runs = list(&conn, "/work/app").unwrap();
+        let summary: Vec<_> = runs.iter().map(|r| (r.framework, r.failed)).collect();
+        assert_eq!(summary, vec![(Framework::Cargo, 0), (Framework::Vitest, 0)]);
+        assert!(list(&conn, "/other").unwrap().is_empty());
+
+        assert_eq!(clear(&conn, "/work/app").unwrap(), 2);
+    }
+}
@@ -0,0 +1,651 @@
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Longest unterminated line kept between chunks (progress bars, binary junk).
+const MAX_PARTIAL: usize = 8192;
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.to_lowercase();
+        if raw.starts_with("err") || raw.starts_with("fatal") {
+            Some(Self::Error)
+        } else if raw.starts_with("warn") {
+            Some(Self::Warning)
+        } else if ["info", "note", "hint", "help"].iter().any(|p| raw.starts_with(p)) {
+            Some(Self::Info)
+        } else {
+            None
+        }
+    }
+}
+
+/// A problem found in command output. `line` and `column` are 1-based, as
+/// the tools print them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    /// Terminal or preview server id.
+    pub source: String,
+    pub matcher: String,
+    /// The path as printed.
+    pub file: String,
+    /// Absolute path, resolved against the command's working directory.
+    pub path: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub code: Option<String>,
+    pub message: String,
+}
+
+// ── Settings ─────────────────────────────────────────────────────────
+
+/// One line of a matcher. Named groups `file`, `line`, `column`,
+/// `severity`, `code` and `message` fill in the diagnostic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternSpec {
+    pub regexp: String,
+    /// Last pattern only: keep matching following lines against it, each
+    /// one a diagnostic sharing what the earlier lines captured.
+    #[serde(default, rename = "loop")]
+    pub repeat: bool,
+}
+
+/// A problem matcher: consecutive output lines matched by `patterns`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatcherSpec {
+    pub name: String,
+    pub patterns: Vec<PatternSpec>,
+    /// When the output doesn't say.
+    #[serde(default)]
+    pub severity: Option<Severity>,
+    /// A line that starts a new run (watch mode rebuilds); clears what the
+    /// matcher found so far in the same output.
+    #[serde(default)]
+    pub begins: Option<String>,
+}
+
+/// Per-project settings, stored under `"problems"` in the project's
+/// settings JSON. `matchers` replace built-ins of the same name or add
+/// new ones.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProblemSettings {
+    pub matchers: Vec<MatcherSpec>,
+    /// Matcher names to turn off.
+    pub disabled: Vec<String>,
+}
+
+// ── Built-ins ────────────────────────────────────────────────────────
+
+fn spec(name: &str, patterns: &[(&str, bool)], begins: Option<&str>) -> MatcherSpec {
+    MatcherSpec {
+        name: name.into(),
+        patterns: patterns
+            .iter()
+            .map(|(regexp, repeat)| PatternSpec { regexp: regexp.to_string(), repeat: *repeat })
+            .collect(),
+        severity: None,
+        begins: begins.map(String::from),
+    }
+}
+
+/// Built-in matchers. A tool with several output shapes has one matcher
+/// per shape, under the same name.
+fn builtins() -> Vec<MatcherSpec> {
+    vec![
+        // error[E0308]: mismatched types
+        //   --> src/main.rs:4:5
+        spec(
+            "rust",
+            &[
+                (r"^(?P<severity>error|warning)(?:\[(?P<code>[^\]]+)\])?: (?P<message>.+)$", false),
+                (r"^\s*--> (?P<file>.+?):(?P<line>\d+):(?P<column>\d+)$", false),
+            ],
+            Some(r"^\[Running '"),
+        ),
+        // src/a.ts(3,5): error TS2322: …   or, with --pretty,
+        // src/a.ts:3:5 - error TS2322: …
+        spec(
+            "tsc",
+            &[(
+                r"^(?P<file>[^\s(:][^(:]*?)(?:\((?P<line>\d+),(?P<column>\d+)\): |:(?P<line2>\d+):(?P<column2>\d+) - )(?P<severity>error|warning) (?P<code>TS\d+): (?P<message>.+)$",
+                false,
+            )],
+            Some(r"Starting (?:incremental )?compilation"),
+        ),
+        // /abs/src/App.tsx
+        //   3:5  error  'x' is defined but never used  no-unused-vars
+        spec(
+            "eslint",
+            &[
+                (r"^(?P<file>(?:/|[A-Za-z]:[\\/])\S.*)$", false),
+                (
+                    r"^\s+(?P<line>\d+):(?P<column>\d+)\s+(?P<severity>error|warning)\s+(?P<message>.+?)(?:\s{2,}(?P<code>[\w@/-]+))?$",
+                    true,
+                ),
+            ],
+            None,
+        ),
+        // src/App.tsx:3:5: Missing semicolon. [Error/semi]  (--format unix/compact)
+        spec(
+            "eslint",
+            &[(
+                r"^(?P<file>[^\s:][^:]*):(?P<line>\d+):(?P<column>\d+): (?P<message>.+?) \[(?P<severity>Error|Warning)(?:/(?P<code>[^\]]+))?\]$",
+                false,
+            )],
+            None,
+        ),
+        // [vite] Internal server error: /abs/src/App.tsx: Unexpected token (3:5)
+        spec(
+            "vite",
+            &[(
+                r"\[vite\] (?:Internal server )?(?P<severity>error): (?P<file>(?:/|[A-Za-z]:[\\/])[^:]+?): (?P<message>.+?) \((?P<line>\d+):(?P<column>\d+)\)$",
+                false,
+            )],
+            Some(r"\[vite\] (?:hmr update|page reload)"),
+        ),
+        // ✘ [ERROR] Expected ";" but found "x"   (esbuild, via vite)
+        //
+        //     src/main.ts:3:5:
+        // (blank lines never reach the patterns)
+        spec(
+            "vite",
+            &[
+                (r"^\s*(?:✘ )?\[(?P<severity>ERROR|WARNING)\] (?P<message>.+)$", false),
+                (r"^\s+(?P<file>[^\s:][^:]*):(?P<line>\d+):(?P<column>\d+):$", false),
+            ],
+            None,
+        ),
+        // tests/test_math.py:5: AssertionError
+        spec(
+            "pytest",
+            &[(
+                r"^(?P<file>[^\s:][^:]*\.py):(?P<line>\d+): (?P<message>(?:\w*(?P<severity>Warning)|\w+(?:Error|Exception)|Failed)\b.*)$",
+                false,
+            )],
+            Some(r"^=+ test session starts =+$"),
+        ),
+        // ./main.go:12:5: undefined: foo
+        spec(
+            "go",
+            &[(
+                r"^(?P<file>[^\s:][^:]*\.go):(?P<line>\d+)(?::(?P<column>\d+))?: (?P<message>.+)$",
+                false,
+            )],
+            None,
+        ),
+    ]
+}
+
+/// Built-ins merged with the project's matchers, minus disabled names.
+pub fn registry(settings: &ProblemSettings) -> Vec<MatcherSpec> {
+    let mut specs = builtins();
+    specs.retain(|s| !settings.matchers.iter().any(|m| m.name == s.name));
+    specs.extend(settings.matchers.iter().cloned());
+    specs.retain(|s| !settings.disabled.contains(&s.name));
+    specs
+}
+
+// ── Matching ─────────────────────────────────────────────────────────
+
+#[derive(Debug)]
+struct Pattern {
+    regex: Regex,
+    repeat: bool,
+}
+
+#[derive(Debug)]
+pub struct Matcher {
+    pub name: String,
+    patterns: Vec<Pattern>,
+    severity: Option<Severity>,
+    begins: Option<Regex>,
+}
+
+impl Matcher {
+    pub fn compile(spec: &MatcherSpec) -> Result<Self, String> {
+        let compile =
+            |re: &str| Regex::new(re).map_err(|e| format!("Problem matcher {}: {}", spec.name, e));
+        let patterns = spec
+            .patterns
+            .iter()
+            .map(|p| Ok(Pattern { regex: compile(&p.regexp)?, repeat: p.repeat }))
+            .collect::<Result<Vec<_>, String>>()?;
+        let has = |group: &str| {
+            patterns.iter().any(|p| p.regex.capture_names().flatten().any(|n| n == group))
+        };
+        if let Some(missing) = ["file", "line", "message"].into_iter().find(|g| !has(g)) {
+            return Err(format!("Problem matcher {} has no `{}` group", spec.name, missing));
+        }
+        Ok(Self {
+            name: spec.name.clone(),
+            patterns,
+            severity: spec.severity,
+            begins: spec.begins.as_deref().map(compile).transpose()?,
+        })
+    }
+}
+
+/// What the patterns before the current one captured.
+#[derive(Debug, Clone, Default)]
+struct Fields {
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    severity: Option<Severity>,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+impl Fields {
+    fn merge(&mut self, caps: &Captures) {
+        let text = |names: &[&str]| {
+            names.iter().find_map(|n| caps.name(n)).map(|m| m.as_str().trim().to_string())
+        };
+        let number = |names: &[&str]| text(names).and_then(|t| t.parse().ok());
+        if let Some(file) = text(&["file"]) {
+            self.file = Some(file);
+        }
+        if let Some(line) = number(&["line", "line2"]) {
+            self.line = Some(line);
+        }
+        if let Some(column) = number(&["column", "column2"]) {
+            self.column = Some(column);
+        }
+        if let Some(severity) = text(&["severity"]).and_then(|s| Severity::parse(&s)) {
+            self.severity = Some(severity);
+        }
+        if let Some(code) = text(&["code"]) {
+            self.code = Some(code);
+        }
+        if let Some(message) = text(&["message"]) {
+            self.message = Some(message);
+        }
+    }
+}
+
+/// Matching progress of one matcher: the next pattern to try and what
+/// the earlier ones captured.
+#[derive(Default)]
+struct Progress {
+    next: usize,
+    fields: Fields,
+}
+
+/// Found in one chunk of output.
+#[derive(Debug, Default)]
+pub struct Scan {
+    /// Matchers whose `begins` line appeared, in order.
+    pub begun: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Runs matchers over a stream of output, line by line, across chunks.
+pub struct Scanner {
+    source: String,
+    cwd: PathBuf,
+    matchers: Vec<Matcher>,
+    progress: Vec<Progress>,
+    partial: String,
+}
+
+impl Scanner {
+    pub fn new(source: String, cwd: PathBuf, matchers: Vec<Matcher>) -> Self {
+        let progress = matchers.iter().map(|_| Progress::default()).collect();
+        Self { source, cwd, matchers, progress, partial: String::new() }
+    }
+
+    /// Where relative paths are resolved from (a shell `cd`, via OSC 7).
+    pub fn set_cwd(&mut self, cwd: PathBuf) {
+        self.cwd = cwd;
+    }
+
+    /// Feed ANSI-free output. Lines end at `\n` or `\r`; an unterminated
+    /// tail waits for the next chunk.
+    pub fn feed(&mut self, text: &str) -> Scan {
+        let mut scan = Scan::default();
+        self.partial.push_str(text);
+        let Some(end) = self.partial.rfind(['\n', '\r']) else {
+            if self.partial.len() > MAX_PARTIAL {
+                self.partial.clear();
+            }
+            return scan;
+        };
+        let complete: String = self.partial.drain(..=end).collect();
+        for line in complete.split(['\n', '\r']).filter(|l| !l.is_empty()) {
+            self.line(line, &mut scan);
+        }
+        scan
+    }
+
+    fn line(&mut self, line: &str, scan: &mut Scan) {
+        for i in 0..self.matchers.len() {
+            let matcher = &self.matchers[i];
+            if matcher.begins.as_ref().is_some_and(|re| re.is_match(line)) {
+                if !scan.begun.contains(&matcher.name) {
+                    scan.begun.push(matcher.name.clone());
+                }
+                // Drop what an earlier begin in this chunk led to
+                scan.diagnostics.retain(|d| d.matcher != matcher.name);
+                self.progress[i] = Progress::default();
+                continue;
+            }
+            if let Some(diagnostic) = self.step(i, line) {
+                scan.diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    fn step(&mut self, i: usize, line: &str) -> Option<Diagnostic> {
+        if self.progress[i].next > 0 {
+            if let Some(found) = self.advance(i, self.progress[i].next, line) {
+                return found;
+            }
+            self.progress[i] = Progress::default();
+        }
+        self.advance(i, 0, line).flatten()
+    }
+
+    /// Try pattern `at`. `None` if it doesn't match; `Some(diagnostic)`
+    /// when it completes one.
+    fn advance(&mut self, i: usize, at: usize, line: &str) -> Option<Option<Diagnostic>> {
+        let matcher = &self.matchers[i];
+        let pattern = &matcher.patterns[at];
+        let caps = pattern.regex.captures(line)?;
+        let progress = &mut self.progress[i];
+        if at == 0 {
+            progress.fields = Fields::default();
+        }
+        if at + 1 < matcher.patterns.len() {
+            progress.fields.merge(&caps);
+            progress.next = at + 1;
+            return Some(None);
+        }
+
+        let mut fields = progress.fields.clone();
+        fields.merge(&caps);
+        if pattern.repeat {
+            progress.next = at;
+        } else {
+            *progress = Progress::default();
+        }
+        let file = fields.file?;
+        Some(Some(Diagnostic {
+            source: self.source.clone(),
+            matcher: matcher.name.clone(),
+            path: resolve(&self.cwd, &file).to_string_lossy().to_string(),
+            file,
+            line: fields.line?,
+            column: fields.column,
+            severity: fields.severity.or(matcher.severity).unwrap_or(Severity::Error),
+            code: fields.code,
+            message: fields.message?,
+        }))
+    }
+}
+
+/// Compile `specs`, skipping (and logging) broken user matchers.
+pub fn compile_all(specs: &[MatcherSpec]) -> Vec<Matcher> {
+    specs
+        .iter()
+        .filter_map(|spec| Matcher::compile(spec).map_err(|e| tracing::warn!("{}", e)).ok())
+        .collect()
+}
+
+// ── Paths ────────────────────────────────────────────────────────────
+
+fn resolve(cwd: &Path, file: &str) -> PathBuf {
+    let file = Path::new(file);
+    if file.is_absolute() {
+        crate::filesystem::ops::normalize(file)
+    } else {
+        crate::filesystem::ops::normalize(&cwd.join(file))
+    }
+}
+
+fn osc7_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\x1b\]7;file://[^/\x07\x1b]*(/[^\x07\x1b]*)(?:\x07|\x1b\\)").unwrap()
+    })
+}
+
+/// The last working directory a shell reported with OSC 7 in `raw`.
+pub fn osc7_cwd(raw: &str) -> Option<PathBuf> {
+    let path = osc7_regex().captures_iter(raw).last()?.get(1)?.as_str();
+    url::Url::parse(&format!("file://{}", path)).ok()?.to_file_path().ok()
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(output: &str, cwd: &str) -> Vec<Diagnostic> {
+        let matchers = compile_all(&registry(&ProblemSettings::default()));
+        let mut scanner = Scanner::new("term-0".into(), PathBuf::from(cwd), matchers);
+        scanner.feed(output).diagnostics
+    }
+
+    fn summary(d: &Diagnostic) -> (&str, &str, u32, Option<u32>, Severity, Option<&str>, &str) {
+        (
+            d.matcher.as_str(),
+            d.path.as_str(),
+            d.line,
+            d.column,
+            d.severity,
+            d.code.as_deref(),
+            d.message.as_str(),
+        )
+    }
+
+    #[test]
+    fn test_rust_and_go() {
+        let output = "   Compiling app v0.1.0 (/work/app)\n\
+            error[E0308]: mismatched types\n  --> src/main.rs:4:5\n   |\n\
+            warning: unused variable: `x`\n --> src/lib.rs:10:9\n\
+            warning: `app` (bin \"app\") generated 1 warning\n\
+            error: could not compile `app`\n\
+            ./cmd/main.go:12:5: undefined: foo\n";
+        let found = scan(output, "/work/app");
+        let found: Vec<_> = found.iter().map(summary).collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "rust",
+                    "/work/app/src/main.rs",
+                    4,
+                    Some(5),
+                    Severity::Error,
+                    Some("E0308"),
+                    "mismatched types"
+                ),
+                (
+                    "rust",
+                    "/work/app/src/lib.rs",
+                    10,
+                    Some(9),
+                    Severity::Warning,
+                    None,
+                    "unused variable: `x`"
+                ),
+                (
+                    "go",
+                    "/work/app/cmd/main.go",
+                    12,
+                    Some(5),
+                    Severity::Error,
+                    None,
+                    "undefined: foo"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typescript_tools() {
+        let output = "src/a.ts(3,5): error TS2322: Type 'string' is not assignable.\n\
+            src/b.ts:7:1 - warning TS6133: 'x' is declared but never read.\n\
+            /web/src/App.tsx\n  3:5   error    'x' is defined but never used  no-unused-vars\n  \
+            10:1  warning  Unexpected console statement   no-console\n\n\
+            src/util.js:2:9: Missing semicolon. [Error/semi]\n\
+            12:01:33 PM [vite] Internal server error: /web/src/main.tsx: Unexpected token (8:2)\n\
+            ✘ [ERROR] Expected \";\" but found \"x\"\n\n    src/entry.ts:1:4:\n";
+        let found = scan(output, "/web");
+        let found: Vec<_> = found.iter().map(summary).collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "tsc",
+                    "/web/src/a.ts",
+                    3,
+                    Some(5),
+                    Severity::Error,
+                    Some("TS2322"),
+                    "Type 'string' is not assignable."
+                ),
+                (
+                    "tsc",
+                    "/web/src/b.ts",
+                    7,
+                    Some(1),
+                    Severity::Warning,
+                    Some("TS6133"),
+                    "'x' is declared but never read."
+                ),
+                (
+                    "eslint",
+                    "/web/src/App.tsx",
+                    3,
+                    Some(5),
+                    Severity::Error,
+                    Some("no-unused-vars"),
+                    "'x' is defined but never used"
+                ),
+                (
+                    "eslint",
+                    "/web/src/App.tsx",
+                    10,
+                    Some(1),
+                    Severity::Warning,
+                    Some("no-console"),
+                    "Unexpected console statement"
+                ),
+                (
+                    "eslint",
+                    "/web/src/util.js",
+                    2,
+                    Some(9),
+                    Severity::Error,
+                    Some("semi"),
+                    "Missing semicolon."
+                ),
+                (
+                    "vite",
+                    "/web/src/main.tsx",
+                    8,
+                    Some(2),
+                    Severity::Error,
+                    None,
+                    "Unexpected token"
+                ),
+                (
+                    "vite",
+                    "/web/src/entry.ts",
+                    1,
+                    Some(4),
+                    Severity::Error,
+                    None,
+                    "Expected \";\" but found \"x\""
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pytest_and_chunking() {
+        let matchers = compile_all(&registry(&ProblemSettings::default()));
+        let mut scanner = Scanner::new("server-1".into(), PathBuf::from("/py"), matchers);
+        // A line split across reads, CRLF endings
+        assert!(scanner.feed("tests/test_math.py:5: Assert").diagnostics.is_empty());
+        let scan = scanner.feed("ionError\r\ntests/test_io.py:9: DeprecationWarning: old\r\n");
+        let found: Vec<_> = scan.diagnostics.iter().map(summary).collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "pytest",
+                    "/py/tests/test_math.py",
+                    5,
+                    None,
+                    Severity::Error,
+                    None,
+                    "AssertionError"
+                ),
+                (
+                    "pytest",
+                    "/py/tests/test_io.py",
+                    9,
+                    None,
+                    Severity::Warning,
+                    None,
+                    "DeprecationWarning: old"
+                ),
+            ]
+        );
+        assert_eq!(scan.diagnostics[0].source, "server-1");
+
+        let scan = scanner.feed("===== test session starts =====\n");
+        assert_eq!(scan.begun, vec!["pytest".to_string()]);
+    }
+
+    #[test]
+    fn test_user_matchers() {
+        let settings: ProblemSettings = serde_json::from_value(serde_json::json!({
+            "matchers": [
+                { "name": "go", "patterns": [{ "regexp": "^GO (?P<file>\\S+) (?P<line>\\d+) (?P<message>.+)$" }], "severity": "info" },
+                { "name": "broken", "patterns": [{ "regexp": "(?P<file>" }] },
+                { "name": "nolines", "patterns": [{ "regexp": "(?P<file>.+)" }] }
+            ],
+            "disabled": ["rust"]
+        }))
+        .unwrap();
+        let specs = registry(&settings);
+        assert!(!specs.iter().any(|s| s.name == "rust"));
+        assert_eq!(specs.iter().filter(|s| s.name == "go").count(), 1);
+        assert!(Matcher::compile(&specs[specs.len() - 1]).unwrap_err().contains("`line`"));
+
+        let matchers = compile_all(&specs);
+        assert_eq!(matchers.len(), specs.len() - 2);
+        let mut scanner = Scanner::new("t".into(), PathBuf::from("/p"), matchers);
+        let found =
+            scanner.feed("error[E1]: x\n --> a.rs:1:1\nGO ../q/x.go 4 odd thing\n").diagnostics;
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].path.as_str(), found[0].severity), ("/q/x.go", Severity::Info));
+    }
+
+    #[test]
+    fn test_osc7_cwd() {
+        let raw = "\x1b]7;file://host/old\x07prompt\x1b]7;file://my-mac/Users/me/My%20App\x1b\\$ ";
+        assert_eq!(osc7_cwd(raw), Some(PathBuf::from("/Users/me/My App")));
+        assert_eq!(osc7_cwd("plain"), None);
+    }
+}
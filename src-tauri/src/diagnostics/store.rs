@@ -0,0 +1,272 @@
+use super::matcher::{self, Diagnostic, ProblemSettings, Scan, Scanner};
+use crate::error::KodiqError;
+use crate::filesystem::{filter, sandbox};
+use crate::state::DbState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Per source; a runaway build log shouldn't grow the store forever.
+const MAX_PER_SOURCE: usize = 2000;
+
+// ── Store ────────────────────────────────────────────────────────────
+
+/// Diagnostics by project root, then by the terminal or server that
+/// printed them. Output outside any project is keyed by its cwd.
+#[derive(Default)]
+pub struct DiagnosticsStore {
+    projects: HashMap<String, HashMap<String, Vec<Diagnostic>>>,
+}
+
+pub type DiagnosticsState = Arc<Mutex<DiagnosticsStore>>;
+
+pub fn new_diagnostics_state() -> DiagnosticsState {
+    Arc::new(Mutex::new(DiagnosticsStore::default()))
+}
+
+/// Payload of the `diagnostics-changed` event: everything for one project.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsChanged {
+    pub root: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl DiagnosticsStore {
+    /// Apply one scan of `source`'s output: matchers that began a new run
+    /// drop their earlier findings, then new diagnostics are added.
+    /// Returns whether anything changed.
+    pub fn apply(&mut self, root: &str, source: &str, scan: Scan) -> bool {
+        let list = self
+            .projects
+            .entry(root.to_string())
+            .or_default()
+            .entry(source.to_string())
+            .or_default();
+        let before = list.len();
+        list.retain(|d| !scan.begun.contains(&d.matcher));
+        let mut changed = list.len() != before;
+        for diagnostic in scan.diagnostics {
+            if list.len() < MAX_PER_SOURCE && !list.contains(&diagnostic) {
+                list.push(diagnostic);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Drop `source`'s diagnostics, or all of the project's. Returns
+    /// whether anything was removed.
+    pub fn clear(&mut self, root: &str, source: Option<&str>) -> bool {
+        let Some(sources) = self.projects.get_mut(root) else { return false };
+        let removed = match source {
+            Some(source) => sources.remove(source).is_some_and(|l| !l.is_empty()),
+            None => sources.drain().any(|(_, l)| !l.is_empty()),
+        };
+        if sources.is_empty() {
+            self.projects.remove(root);
+        }
+        removed
+    }
+
+    /// Forget a closed terminal or stopped server everywhere. Returns the
+    /// projects that lost diagnostics.
+    pub fn forget(&mut self, source: &str) -> Vec<String> {
+        let roots: Vec<String> = self
+            .projects
+            .iter()
+            .filter(|(_, sources)| sources.get(source).is_some_and(|l| !l.is_empty()))
+            .map(|(root, _)| root.clone())
+            .collect();
+        for sources in self.projects.values_mut() {
+            sources.remove(source);
+        }
+        self.projects.retain(|_, sources| !sources.is_empty());
+        roots
+    }
+
+    /// A project's diagnostics, ordered by file and position.
+    pub fn project(&self, root: &str) -> Vec<Diagnostic> {
+        let mut all: Vec<Diagnostic> = self
+            .projects
+            .get(root)
+            .map(|sources| sources.values().flatten().cloned().collect())
+            .unwrap_or_default();
+        all.sort_by(|a, b| {
+            (&a.path, a.line, a.column, &a.source).cmp(&(&b.path, b.line, b.column, &b.source))
+        });
+        all
+    }
+}
+
+fn publish(app: &AppHandle, store: &DiagnosticsStore, root: &str) {
+    let payload = DiagnosticsChanged { root: root.to_string(), diagnostics: store.project(root) };
+    let _ = app.emit("diagnostics-changed", payload);
+}
+
+/// Drop a terminal's or server's diagnostics when it goes away.
+pub fn forget(app: &AppHandle, source: &str) {
+    let state = app.state::<DiagnosticsState>();
+    let Ok(mut store) = state.lock() else { return };
+    for root in store.forget(source) {
+        publish(app, &store, &root);
+    }
+}
+
+/// Drop a source's diagnostics (a new command was entered in its terminal).
+pub fn reset(app: &AppHandle, source: &str) {
+    let state = app.state::<DiagnosticsState>();
+    let Ok(mut store) = state.lock() else { return };
+    let roots: Vec<String> = store.projects.keys().cloned().collect();
+    for root in roots {
+        if store.clear(&root, Some(source)) {
+            publish(app, &store, &root);
+        }
+    }
+}
+
+// ── Streams ──────────────────────────────────────────────────────────
+
+/// Problem matching for one terminal's or server's output, feeding the
+/// store of the project its cwd belongs to.
+pub struct ProblemStream {
+    app: AppHandle,
+    source: String,
+    root: String,
+    scanner: Scanner,
+}
+
+impl ProblemStream {
+    /// Matchers come from the project's `problems` settings.
+    pub fn new(app: &AppHandle, source: &str, cwd: &Path) -> Self {
+        let (root, settings) = project_for(app, cwd);
+        let matchers = matcher::compile_all(&matcher::registry(&settings));
+        Self {
+            app: app.clone(),
+            source: source.to_string(),
+            root,
+            scanner: Scanner::new(source.to_string(), cwd.to_path_buf(), matchers),
+        }
+    }
+
+    pub fn set_cwd(&mut self, cwd: PathBuf) {
+        self.scanner.set_cwd(cwd);
+    }
+
+    /// Feed ANSI-free output; emits `diagnostics-changed` when it matters.
+    pub fn push(&mut self, text: &str) {
+        let scan = self.scanner.feed(text);
+        if scan.begun.is_empty() && scan.diagnostics.is_empty() {
+            return;
+        }
+        let state = self.app.state::<DiagnosticsState>();
+        let Ok(mut store) = state.lock() else { return };
+        if store.apply(&self.root, &self.source, scan) {
+            publish(&self.app, &store, &self.root);
+        }
+    }
+}
+
+fn project_for(app: &AppHandle, cwd: &Path) -> (String, ProblemSettings) {
+    let fallback = || (cwd.to_string_lossy().to_string(), ProblemSettings::default());
+    let db = app.state::<DbState>();
+    let Ok(conn) = db.connection.lock() else { return fallback() };
+    match sandbox::project_root_for(&conn, cwd) {
+        Ok(Some(root)) => {
+            let root = root.to_string_lossy().to_string();
+            let settings = filter::project_section(&conn, &root, "problems");
+            (root, settings)
+        }
+        _ => fallback(),
+    }
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn diagnostics_list(
+    webview: tauri::Webview,
+    root: String,
+    diagnostics: tauri::State<'_, DiagnosticsState>,
+) -> Result<Vec<Diagnostic>, KodiqError> {
+    sandbox::check_webview(&webview)?;
+    Ok(diagnostics.lock()?.project(&root))
+}
+
+/// Dismiss a project's diagnostics, or only those from `source`.
+#[tauri::command]
+pub fn diagnostics_clear(
+    app: AppHandle,
+    webview: tauri::Webview,
+    root: String,
+    source: Option<String>,
+    diagnostics: tauri::State<'_, DiagnosticsState>,
+) -> Result<(), KodiqError> {
+    sandbox::check_webview(&webview)?;
+    let mut store = diagnostics.lock()?;
+    if store.clear(&root, source.as_deref()) {
+        publish(&app, &store, &root);
+    }
+    Ok(())
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::matcher::Severity;
+
+    fn diagnostic(source: &str, matcher: &str, path: &str, line: u32) -> Diagnostic {
+        Diagnostic {
+            source: source.into(),
+            matcher: matcher.into(),
+            file: path.into(),
+            path: path.into(),
+            line,
+            column: None,
+            severity: Severity::Error,
+            code: None,
+            message: "boom".into(),
+        }
+    }
+
+    fn scan(begun: &[&str], diagnostics: Vec<Diagnostic>) -> Scan {
+        Scan { begun: begun.iter().map(|s| s.to_string()).collect(), diagnostics }
+    }
+
+    #[test]
+    fn test_apply_dedupes_and_begins_clear() {
+        let mut store = DiagnosticsStore::default();
+        let a = diagnostic("term-0", "tsc", "/p/b.ts", 3);
+        let b = diagnostic("term-0", "tsc", "/p/a.ts", 9);
+        let c = diagnostic("server-0", "vite", "/p/a.ts", 1);
+        assert!(store.apply("/p", "term-0", scan(&[], vec![a.clone(), b.clone()])));
+        assert!(!store.apply("/p", "term-0", scan(&[], vec![a.clone()])));
+        assert!(store.apply("/p", "server-0", scan(&[], vec![c.clone()])));
+        assert_eq!(store.project("/p"), vec![c.clone(), b.clone(), a.clone()]);
+
+        // tsc rebuilt: only its new findings remain
+        assert!(store.apply("/p", "term-0", scan(&["tsc"], vec![b.clone()])));
+        assert_eq!(store.project("/p"), vec![c.clone(), b.clone()]);
+        assert!(store.project("/other").is_empty());
+    }
+
+    #[test]
+    fn test_clear_and_forget() {
+        let mut store = DiagnosticsStore::default();
+        store.apply("/p", "term-0", scan(&[], vec![diagnostic("term-0", "go", "/p/x.go", 1)]));
+        store.apply("/q", "term-0", scan(&[], vec![diagnostic("term-0", "go", "/q/y.go", 2)]));
+        store.apply("/q", "term-1", scan(&[], vec![diagnostic("term-1", "go", "/q/z.go", 3)]));
+
+        assert!(store.clear("/q", Some("term-1")));
+        assert!(!store.clear("/q", Some("term-1")));
+        let mut roots = store.forget("term-0");
+        roots.sort();
+        assert_eq!(roots, vec!["/p".to_string(), "/q".to_string()]);
+        assert!(store.projects.is_empty());
+        assert!(!store.clear("/p", None));
+    }
+}
@@ -3,8 +3,46 @@
 // Typed errors using thiserror. All Tauri commands return Result<T, KodiqError>.
 // Tauri auto-serializes KodiqError into a string for the frontend via Display.
 
+use serde::ser::SerializeStruct;
+use serde::Serialize;
 use thiserror::Error;
 
+// ── Payloads ─────────────────────────────────────────────────────────
+// Plain data carried by structured errors, so this module depends on no
+// feature module.
+
+/// One side of a write conflict.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSnapshot {
+    pub content: String,
+    pub mtime: Option<u64>,
+    pub hash: String,
+}
+
+/// A save was rejected because the file changed on disk since it was read.
+/// Carries both versions so the editor can diff or merge them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteConflict {
+    pub path: String,
+    /// What is on disk now; `None` if the file was deleted.
+    pub disk: Option<FileSnapshot>,
+    /// What the caller tried to write.
+    pub ours: FileSnapshot,
+}
+
+/// One validation problem: its own fields (tagged with `kind`) plus a
+/// readable `message`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Issue {
+    #[serde(flatten)]
+    pub detail: serde_json::Map<String, serde_json::Value>,
+    pub message: String,
+}
+
+// ── Error ────────────────────────────────────────────────────────────
+
 #[derive(Error, Debug)]
 pub enum KodiqError {
     #[error("Database error: {0}")]
@@ -31,6 +69,31 @@ pub enum KodiqError {
     #[error("Connection not found: {0}")]
     ConnectionNotFound(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// A save lost the race against a change on disk. Serialized as an
+    /// object (with `message`) so the editor gets both versions.
+    #[error("Conflict: {} changed on disk since it was opened", .0.path)]
+    WriteConflict(Box<WriteConflict>),
+
+    /// Serialized as an object (`kind: "outsideRoot"`, `path`).
+    #[error("Path is outside the project root: {0}")]
+    OutsideRoot(String),
+
+    /// A webview other than the main one called a filesystem command.
+    /// Serialized as an object (`kind: "forbidden"`, `webview`).
+    #[error("Filesystem access is not allowed from webview '{0}'")]
+    Forbidden(String),
+
+    /// A compound launch config failed validation. Serialized as an object
+    /// (`kind: "invalidLaunchConfig"`, `issues`).
+    #[error("Invalid launch config: {}", .0.iter().map(|i| i.message.as_str()).collect::<Vec<_>>().join("; "))]
+    InvalidLaunchConfig(Vec<Issue>),
+
+    #[error("Template error: {0}")]
+    Template(String),
+
     #[error("{0}")]
     Other(String),
 }
@@ -41,7 +104,39 @@ impl serde::Serialize for KodiqError {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        match self {
+            KodiqError::WriteConflict(conflict) => {
+                let mut s = serializer.serialize_struct("WriteConflict", 5)?;
+                s.serialize_field("kind", "writeConflict")?;
+                s.serialize_field("message", &self.to_string())?;
+                s.serialize_field("path", &conflict.path)?;
+                s.serialize_field("disk", &conflict.disk)?;
+                s.serialize_field("ours", &conflict.ours)?;
+                s.end()
+            }
+            KodiqError::OutsideRoot(path) => {
+                let mut s = serializer.serialize_struct("OutsideRoot", 3)?;
+                s.serialize_field("kind", "outsideRoot")?;
+                s.serialize_field("message", &self.to_string())?;
+                s.serialize_field("path", path)?;
+                s.end()
+            }
+            KodiqError::Forbidden(label) => {
+                let mut s = serializer.serialize_struct("Forbidden", 3)?;
+                s.serialize_field("kind", "forbidden")?;
+                s.serialize_field("message", &self.to_string())?;
+                s.serialize_field("webview", label)?;
+                s.end()
+            }
+            KodiqError::InvalidLaunchConfig(issues) => {
+                let mut s = serializer.serialize_struct("InvalidLaunchConfig", 3)?;
+                s.serialize_field("kind", "invalidLaunchConfig")?;
+                s.serialize_field("message", &self.to_string())?;
+                s.serialize_field("issues", issues)?;
+                s.end()
+            }
+            _ => serializer.serialize_str(&self.to_string()),
+        }
     }
 }
 
@@ -0,0 +1,419 @@
+use crate::error::KodiqError;
+use serde::{Deserialize, Serialize};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// How much of a file `detect` needs to see.
+pub const SAMPLE_BYTES: usize = 8192;
+
+/// Share of control bytes above which undecodable content counts as binary.
+const MAX_CONTROL_RATIO: f64 = 0.1;
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Charset {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    /// Also used for Latin-1 files, which it is a superset of.
+    #[serde(rename = "windows-1252")]
+    Windows1252,
+}
+
+/// How a text file is stored on disk. Passed back to `write_file` so a
+/// save reproduces the original bytes (BOM included).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEncoding {
+    pub charset: Charset,
+    pub bom: bool,
+}
+
+impl TextEncoding {
+    pub const UTF8: TextEncoding = TextEncoding { charset: Charset::Utf8, bom: false };
+
+    fn bom_bytes(&self) -> &'static [u8] {
+        match (self.bom, self.charset) {
+            (false, _) | (_, Charset::Windows1252) => &[],
+            (true, Charset::Utf8) => UTF8_BOM,
+            (true, Charset::Utf16Le) => UTF16LE_BOM,
+            (true, Charset::Utf16Be) => UTF16BE_BOM,
+        }
+    }
+
+    /// Bytes per code unit — chunk boundaries must be multiples of this.
+    fn unit(&self) -> usize {
+        match self.charset {
+            Charset::Utf16Le | Charset::Utf16Be => 2,
+            _ => 1,
+        }
+    }
+
+    /// The encoded form of `\n`.
+    fn newline(&self) -> &'static [u8] {
+        match self.charset {
+            Charset::Utf16Le => &[0x0A, 0x00],
+            Charset::Utf16Be => &[0x00, 0x0A],
+            _ => b"\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Cr,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Detected {
+    Text(TextEncoding),
+    Binary,
+}
+
+// ── Detection ────────────────────────────────────────────────────────
+
+/// Guess the encoding from the first few KB of a file: BOM first, then
+/// BOM-less UTF-16 (NULs in every other byte), then UTF-8, then Latin-1.
+pub fn detect(sample: &[u8]) -> Detected {
+    for (bom, charset) in [
+        (UTF8_BOM, Charset::Utf8),
+        (UTF16LE_BOM, Charset::Utf16Le),
+        (UTF16BE_BOM, Charset::Utf16Be),
+    ] {
+        if sample.starts_with(bom) {
+            return Detected::Text(TextEncoding { charset, bom: true });
+        }
+    }
+
+    let pairs = sample.len() / 2;
+    if pairs >= 2 {
+        let even = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+        let odd = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+        let mostly = |n: usize| n * 10 >= pairs * 4;
+        let rarely = |n: usize| n * 20 < pairs;
+        if mostly(odd) && rarely(even) {
+            return Detected::Text(TextEncoding { charset: Charset::Utf16Le, bom: false });
+        }
+        if mostly(even) && rarely(odd) {
+            return Detected::Text(TextEncoding { charset: Charset::Utf16Be, bom: false });
+        }
+    }
+
+    if sample.contains(&0) {
+        return Detected::Binary;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => Detected::Text(TextEncoding::UTF8),
+        // Only the sample's last character was cut off
+        Err(e) if e.error_len().is_none() && sample.len() - e.valid_up_to() < 4 => {
+            Detected::Text(TextEncoding::UTF8)
+        }
+        Err(_) => {
+            let control = sample
+                .iter()
+                .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+                .count();
+            if control as f64 > sample.len() as f64 * MAX_CONTROL_RATIO {
+                Detected::Binary
+            } else {
+                Detected::Text(TextEncoding { charset: Charset::Windows1252, bom: false })
+            }
+        }
+    }
+}
+
+/// Dominant line ending of `text` and whether others appear too.
+/// `None` when the text has no line breaks at all.
+pub fn line_ending(text: &str) -> (Option<LineEnding>, bool) {
+    let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+    let mut bytes = text.bytes().peekable();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'\r' if bytes.peek() == Some(&b'\n') => {
+                bytes.next();
+                crlf += 1;
+            }
+            b'\r' => cr += 1,
+            b'\n' => lf += 1,
+            _ => {}
+        }
+    }
+    let kinds = [(lf, LineEnding::Lf), (crlf, LineEnding::Crlf), (cr, LineEnding::Cr)];
+    let used = kinds.iter().filter(|(n, _)| *n > 0).count();
+    let dominant = kinds.iter().filter(|(n, _)| *n > 0).max_by_key(|(n, _)| *n).map(|(_, e)| *e);
+    (dominant, used > 1)
+}
+
+// ── Decode / encode ──────────────────────────────────────────────────
+
+/// Decode `bytes`; a BOM is only stripped when they start the file.
+pub fn decode(bytes: &[u8], encoding: TextEncoding, at_start: bool) -> String {
+    let bom = encoding.bom_bytes();
+    let bytes = match at_start && bytes.starts_with(bom) {
+        true => &bytes[bom.len()..],
+        false => bytes,
+    };
+    let codec = match encoding.charset {
+        Charset::Utf8 => encoding_rs::UTF_8,
+        Charset::Utf16Le => encoding_rs::UTF_16LE,
+        Charset::Utf16Be => encoding_rs::UTF_16BE,
+        Charset::Windows1252 => encoding_rs::WINDOWS_1252,
+    };
+    codec.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+/// Encode `text` the way the file was stored, BOM included. Fails rather
+/// than mangle characters a legacy charset cannot represent.
+pub fn encode(text: &str, encoding: TextEncoding) -> Result<Vec<u8>, KodiqError> {
+    let mut out = encoding.bom_bytes().to_vec();
+    match encoding.charset {
+        Charset::Utf8 => out.extend_from_slice(text.as_bytes()),
+        Charset::Utf16Le => out.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+        Charset::Utf16Be => out.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+        Charset::Windows1252 => {
+            let (bytes, _, unmappable) = encoding_rs::WINDOWS_1252.encode(text);
+            if unmappable {
+                return Err(KodiqError::Other(
+                    "Text contains characters windows-1252 cannot store; save as UTF-8".into(),
+                ));
+            }
+            out.extend_from_slice(&bytes);
+        }
+    }
+    Ok(out)
+}
+
+/// Decode a whole file whose encoding is unknown (e.g. for conflict previews).
+pub fn decode_detected(bytes: &[u8]) -> String {
+    match detect(&bytes[..bytes.len().min(SAMPLE_BYTES)]) {
+        Detected::Text(encoding) => decode(bytes, encoding, true),
+        Detected::Binary => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+// ── Chunks ───────────────────────────────────────────────────────────
+
+/// Shrink a byte range read from the middle of a file so it does not start
+/// or end inside a character. Returns `(skip_front, keep_len)`.
+pub fn char_bounds(bytes: &[u8], encoding: TextEncoding, at_eof: bool) -> (usize, usize) {
+    match encoding.charset {
+        Charset::Windows1252 => (0, bytes.len()),
+        Charset::Utf8 => {
+            let front = bytes.iter().take(3).take_while(|&&b| b & 0xC0 == 0x80).count();
+            let mut end = bytes.len();
+            if !at_eof {
+                // Walk back to the last lead byte; drop it if its sequence is incomplete
+                for back in 1..=3.min(end - front) {
+                    let b = bytes[end - back];
+                    if b & 0xC0 == 0x80 {
+                        continue;
+                    }
+                    let needed = match b {
+                        0xF0..=0xFF => 4,
+                        0xE0..=0xEF => 3,
+                        0xC0..=0xDF => 2,
+                        _ => 1,
+                    };
+                    if needed > back {
+                        end -= back;
+                    }
+                    break;
+                }
+            }
+            (front, end.saturating_sub(front))
+        }
+        Charset::Utf16Le | Charset::Utf16Be => {
+            let unit = |i: usize| match encoding.charset {
+                Charset::Utf16Le => u16::from_le_bytes([bytes[i], bytes[i + 1]]),
+                _ => u16::from_be_bytes([bytes[i], bytes[i + 1]]),
+            };
+            let mut len = bytes.len() & !1;
+            let mut front = 0;
+            if len >= 2 && (0xDC00..=0xDFFF).contains(&unit(0)) {
+                front = 2;
+            }
+            if !at_eof && len >= front + 2 && (0xD800..=0xDBFF).contains(&unit(len - 2)) {
+                len -= 2;
+            }
+            (front, len.saturating_sub(front))
+        }
+    }
+}
+
+/// Streams a file from the start and keeps lines
+/// `[start_line, start_line + count)`. Feed it blocks of any size, in order.
+pub struct LineWindow {
+    newline: &'static [u8],
+    unit: usize,
+    start_line: usize,
+    end_line: usize,
+    max_bytes: usize,
+    line: usize,
+    pos: u64,
+    carry: Vec<u8>,
+    /// Offset of the window's first byte, once reached.
+    pub start: Option<u64>,
+    pub bytes: Vec<u8>,
+    /// Stopped at `max_bytes` before the last requested line ended.
+    pub truncated: bool,
+    done: bool,
+}
+
+impl LineWindow {
+    pub fn new(encoding: TextEncoding, start_line: usize, count: usize, max_bytes: usize) -> Self {
+        Self {
+            newline: encoding.newline(),
+            unit: encoding.unit(),
+            start_line,
+            end_line: start_line.saturating_add(count),
+            max_bytes,
+            line: 0,
+            pos: 0,
+            carry: Vec::new(),
+            start: None,
+            bytes: Vec::new(),
+            truncated: false,
+            done: false,
+        }
+    }
+
+    /// Returns `true` once the window is complete; stop reading then.
+    pub fn feed(&mut self, chunk: &[u8]) -> bool {
+        if self.done {
+            return true;
+        }
+        let mut data = std::mem::take(&mut self.carry);
+        data.extend_from_slice(chunk);
+        let whole = data.len() - data.len() % self.unit;
+
+        for unit in data[..whole].chunks(self.unit) {
+            if self.line >= self.start_line && self.start.is_none() {
+                self.start = Some(self.pos);
+            }
+            if self.start.is_some() {
+                if self.bytes.len() >= self.max_bytes {
+                    self.truncated = true;
+                    self.done = true;
+                    return true;
+                }
+                self.bytes.extend_from_slice(unit);
+            }
+            self.pos += self.unit as u64;
+            if unit == self.newline {
+                self.line += 1;
+                if self.line >= self.end_line {
+                    self.done = true;
+                    return true;
+                }
+            }
+        }
+        self.carry = data[whole..].to_vec();
+        false
+    }
+
+    /// Offset just past the window (the file length if it was never reached).
+    pub fn end(&self) -> u64 {
+        self.start.map_or(self.pos, |start| start + self.bytes.len() as u64)
+    }
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(charset: Charset, bom: bool) -> Detected {
+        Detected::Text(TextEncoding { charset, bom })
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect(b"fn main() {}\n"), text(Charset::Utf8, false));
+        assert_eq!(detect(b"\xEF\xBB\xBFhello"), text(Charset::Utf8, true));
+        let le = TextEncoding { charset: Charset::Utf16Le, bom: true };
+        assert_eq!(detect(&encode("hi there", le).unwrap()), text(Charset::Utf16Le, true));
+        let be: Vec<u8> = "hello world".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(detect(&be), text(Charset::Utf16Be, false));
+        assert_eq!(detect(b"caf\xE9 cr\xE8me\n"), text(Charset::Windows1252, false));
+        assert_eq!(detect(b"\x7fELF\x02\x01\x01\x00\x00\x00\x00"), Detected::Binary);
+        // A sample cut inside a multi-byte character is still UTF-8
+        assert_eq!(detect("naïve".as_bytes()[..3].as_ref()), text(Charset::Utf8, false));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for encoding in [
+            TextEncoding::UTF8,
+            TextEncoding { charset: Charset::Utf8, bom: true },
+            TextEncoding { charset: Charset::Utf16Le, bom: true },
+            TextEncoding { charset: Charset::Utf16Be, bom: false },
+            TextEncoding { charset: Charset::Windows1252, bom: false },
+        ] {
+            let original = encode("Grüße\r\nzwei €\r\n", encoding).unwrap();
+            let decoded = decode(&original, encoding, true);
+            assert_eq!(decoded, "Grüße\r\nzwei €\r\n", "{:?}", encoding);
+            assert_eq!(encode(&decoded, encoding).unwrap(), original, "{:?}", encoding);
+        }
+        let latin = TextEncoding { charset: Charset::Windows1252, bom: false };
+        assert!(encode("emoji 😀", latin).is_err());
+    }
+
+    #[test]
+    fn test_line_ending() {
+        assert_eq!(line_ending("a\nb\n"), (Some(LineEnding::Lf), false));
+        assert_eq!(line_ending("a\r\nb\r\nc\n"), (Some(LineEnding::Crlf), true));
+        assert_eq!(line_ending("a\rb"), (Some(LineEnding::Cr), false));
+        assert_eq!(line_ending("single line"), (None, false));
+    }
+
+    #[test]
+    fn test_char_bounds() {
+        let s = "aé€b".as_bytes(); // a, é (2 bytes), € (3 bytes), b
+                                   // Starts inside é, ends inside €
+        assert_eq!(char_bounds(&s[2..5], TextEncoding::UTF8, false), (1, 0));
+        assert_eq!(char_bounds(&s[1..6], TextEncoding::UTF8, false), (0, 5));
+        assert_eq!(char_bounds(&s[1..5], TextEncoding::UTF8, false), (0, 2));
+
+        let le = TextEncoding { charset: Charset::Utf16Le, bom: false };
+        let bytes: Vec<u8> = "a😀b".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        // Cut between the surrogates of 😀
+        assert_eq!(char_bounds(&bytes[..4], le, false), (0, 2));
+        assert_eq!(char_bounds(&bytes[4..], le, true), (2, 2));
+    }
+
+    #[test]
+    fn test_line_window() {
+        let content = b"zero\none\ntwo\nthree\n";
+        let mut window = LineWindow::new(TextEncoding::UTF8, 1, 2, usize::MAX);
+        for block in content.chunks(3) {
+            if window.feed(block) {
+                break;
+            }
+        }
+        assert_eq!(window.bytes, b"one\ntwo\n");
+        assert_eq!((window.start, window.end()), (Some(5), 13));
+
+        let le = TextEncoding { charset: Charset::Utf16Le, bom: false };
+        let encoded = encode("a\nb\nc", le).unwrap();
+        let mut window = LineWindow::new(le, 2, 5, usize::MAX);
+        for block in encoded.chunks(3) {
+            window.feed(block);
+        }
+        assert_eq!(decode(&window.bytes, le, false), "c");
+        assert_eq!(window.end(), encoded.len() as u64);
+
+        let mut window = LineWindow::new(TextEncoding::UTF8, 0, 10, 4);
+        window.feed(content);
+        assert_eq!((window.bytes.as_slice(), window.truncated), (&b"zero"[..], true));
+    }
+}
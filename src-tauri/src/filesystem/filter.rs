@@ -0,0 +1,320 @@
+use crate::{db, workspace};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Never listed — there is nothing useful to browse in git's object store.
+const ALWAYS_SKIPPED: &[&str] = &[".git"];
+
+// ── Settings ─────────────────────────────────────────────────────────
+
+/// File tree settings, stored under `"files"` in a project's `settings` JSON.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FileFilterSettings {
+    pub show_hidden: bool,
+    pub show_ignored: bool,
+    /// Extra gitignore-style patterns, relative to the project root.
+    pub exclude: Vec<String>,
+}
+
+impl Default for FileFilterSettings {
+    fn default() -> Self {
+        Self { show_hidden: true, show_ignored: true, exclude: Vec::new() }
+    }
+}
+
+/// Per-call overrides from the file tree (e.g. a "show ignored" toggle).
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ListOptions {
+    /// Project root the listed directory belongs to; enables nested gitignores.
+    pub root: Option<String>,
+    pub show_hidden: Option<bool>,
+    pub show_ignored: Option<bool>,
+    /// Decorate entries with `git status`; on unless set to `false`.
+    pub git: Option<bool>,
+}
+
+/// Settings for the project at `root`, falling back to defaults.
+pub fn project_settings(conn: &rusqlite::Connection, root: &str) -> FileFilterSettings {
+    project_section(conn, root, "files")
+}
+
+/// One section of a project's `settings` JSON in the database.
+fn local_value(conn: &rusqlite::Connection, root: &str, key: &str) -> Option<serde_json::Value> {
+    let raw: Option<String> = conn
+        .query_row("SELECT settings FROM projects WHERE path = ?1", rusqlite::params![root], |r| {
+            r.get(0)
+        })
+        .ok()
+        .flatten();
+    raw.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| v.get(key).cloned())
+}
+
+/// One section of a project's `settings` JSON over the same section of
+/// `.kodiq/workspace.json`, falling back to defaults.
+pub fn project_section<T: DeserializeOwned + Default>(
+    conn: &rusqlite::Connection,
+    root: &str,
+    key: &str,
+) -> T {
+    let root = root.trim_end_matches('/');
+    let local = local_value(conn, root, key);
+    let shared = workspace::config::settings_section(Path::new(root), key);
+    workspace::config::overlay(shared, local)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Like `project_section`, but skips `.kodiq/workspace.json` unless the user
+/// trusts the project. For settings that decide what programs run.
+pub fn trusted_section<T: DeserializeOwned + Default>(
+    conn: &rusqlite::Connection,
+    root: &str,
+    key: &str,
+) -> T {
+    let root = root.trim_end_matches('/');
+    if db::projects::is_trusted(conn, root) {
+        return project_section(conn, root, key);
+    }
+    local_value(conn, root, key).and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default()
+}
+
+// ── Policy ───────────────────────────────────────────────────────────
+
+/// Where to look for ignore rules when listing `dir` inside `root`:
+/// `(layer root, file)` pairs, shallowest first. Works for remote paths too.
+pub fn ignore_files(root: &Path, dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut files = vec![(root.to_path_buf(), root.join(".git/info/exclude"))];
+    let mut ancestors: Vec<&Path> = dir.ancestors().take_while(|a| a.starts_with(root)).collect();
+    ancestors.reverse();
+    for a in ancestors {
+        files.push((a.to_path_buf(), a.join(".gitignore")));
+        files.push((a.to_path_buf(), a.join(".ignore")));
+    }
+    files
+}
+
+/// Shared hidden/ignored policy for local and SFTP directory listings.
+pub struct FilterPolicy {
+    root: PathBuf,
+    show_hidden: bool,
+    show_ignored: bool,
+    /// Shallowest first; deeper layers take precedence.
+    layers: Vec<Gitignore>,
+}
+
+/// What a listing should do with one entry.
+#[derive(Debug, PartialEq)]
+pub struct Verdict {
+    pub hidden: bool,
+    pub ignored: bool,
+}
+
+impl FilterPolicy {
+    /// `sources` are `(layer root, file contents)` for files that exist.
+    /// `global` holds user-wide excludes (core.excludesFile), applied at the root.
+    pub fn new(
+        root: &Path,
+        settings: &FileFilterSettings,
+        opts: &ListOptions,
+        global: Option<&str>,
+        sources: Vec<(PathBuf, String)>,
+    ) -> Self {
+        let layer = |dir: &Path, lines: &mut dyn Iterator<Item = &str>| {
+            let mut builder = GitignoreBuilder::new(dir);
+            for line in lines {
+                let _ = builder.add_line(None, line);
+            }
+            builder.build().unwrap_or_else(|_| Gitignore::empty())
+        };
+
+        let mut layers = Vec::new();
+        if let Some(global) = global {
+            layers.push(layer(root, &mut global.lines()));
+        }
+        for (dir, content) in &sources {
+            layers.push(layer(dir, &mut content.lines()));
+        }
+        // Project settings win over everything in the repo
+        layers.push(layer(root, &mut settings.exclude.iter().map(String::as_str)));
+
+        Self {
+            root: root.to_path_buf(),
+            show_hidden: opts.show_hidden.unwrap_or(settings.show_hidden),
+            show_ignored: opts.show_ignored.unwrap_or(settings.show_ignored),
+            layers,
+        }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for layer in self.layers.iter().rev() {
+            if !path.starts_with(layer.path()) || path == layer.path() {
+                continue;
+            }
+            match layer.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+
+    /// `None` = leave the entry out of the listing entirely.
+    pub fn classify(&self, path: &Path, name: &str, is_dir: bool) -> Option<Verdict> {
+        if ALWAYS_SKIPPED.contains(&name) {
+            return None;
+        }
+        let hidden = name.starts_with('.');
+        let ignored = path.starts_with(&self.root) && self.is_ignored(path, is_dir);
+        if (hidden && !self.show_hidden) || (ignored && !self.show_ignored) {
+            return None;
+        }
+        Some(Verdict { hidden, ignored })
+    }
+}
+
+/// Resolve the project root for a listing: the given root if `dir` is inside it.
+pub fn listing_root(dir: &Path, opts: &ListOptions) -> PathBuf {
+    opts.root.as_deref().map(Path::new).filter(|r| dir.starts_with(r)).unwrap_or(dir).to_path_buf()
+}
+
+/// Build the policy for a local directory, reading ignore files from disk.
+pub fn local_policy(dir: &Path, settings: &FileFilterSettings, opts: &ListOptions) -> FilterPolicy {
+    let root = listing_root(dir, opts);
+    let sources = ignore_files(&root, dir)
+        .into_iter()
+        .filter_map(|(layer, file)| std::fs::read_to_string(file).ok().map(|c| (layer, c)))
+        .collect();
+    let global =
+        ignore::gitignore::gitconfig_excludes_path().and_then(|p| std::fs::read_to_string(p).ok());
+    FilterPolicy::new(&root, settings, opts, global.as_deref(), sources)
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(opts: ListOptions, settings: FileFilterSettings) -> FilterPolicy {
+        let root = Path::new("/p");
+        FilterPolicy::new(
+            root,
+            &settings,
+            &opts,
+            Some("*.swp\n"),
+            vec![
+                (root.to_path_buf(), "dist/\n*.log\n!keep.log\n".into()),
+                (root.join("web"), "generated/\n".into()),
+            ],
+        )
+    }
+
+    fn verdict(p: &FilterPolicy, path: &str, is_dir: bool) -> Option<Verdict> {
+        let path = Path::new(path);
+        p.classify(path, &path.file_name().unwrap().to_string_lossy(), is_dir)
+    }
+
+    #[test]
+    fn test_marks_instead_of_dropping() {
+        let p = policy(ListOptions::default(), FileFilterSettings::default());
+        assert_eq!(verdict(&p, "/p/dist", true), Some(Verdict { hidden: false, ignored: true }));
+        assert!(verdict(&p, "/p/dist/app.js", false).unwrap().ignored);
+        assert_eq!(verdict(&p, "/p/.github", true), Some(Verdict { hidden: true, ignored: false }));
+        assert!(!verdict(&p, "/p/keep.log", false).unwrap().ignored);
+        assert!(verdict(&p, "/p/a.swp", false).unwrap().ignored);
+        assert!(verdict(&p, "/p/web/generated", true).unwrap().ignored);
+        assert!(!verdict(&p, "/p/generated", true).unwrap().ignored);
+        assert_eq!(verdict(&p, "/p/.git", true), None);
+    }
+
+    #[test]
+    fn test_modes_and_settings() {
+        let hide_all = ListOptions {
+            show_hidden: Some(false),
+            show_ignored: Some(false),
+            ..Default::default()
+        };
+        let p = policy(hide_all, FileFilterSettings::default());
+        assert_eq!(verdict(&p, "/p/dist", true), None);
+        assert_eq!(verdict(&p, "/p/.env.example", false), None);
+        assert!(verdict(&p, "/p/src", true).is_some());
+
+        let settings = FileFilterSettings { exclude: vec!["*.lock".into()], ..Default::default() };
+        let p = policy(ListOptions::default(), settings);
+        assert!(verdict(&p, "/p/Cargo.lock", false).unwrap().ignored);
+    }
+
+    #[test]
+    fn test_ignore_files_and_root() {
+        let files = ignore_files(Path::new("/p"), Path::new("/p/web/src"));
+        let paths: Vec<_> = files.iter().map(|(_, f)| f.to_string_lossy().to_string()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/p/.git/info/exclude",
+                "/p/.gitignore",
+                "/p/.ignore",
+                "/p/web/.gitignore",
+                "/p/web/.ignore",
+                "/p/web/src/.gitignore",
+                "/p/web/src/.ignore",
+            ]
+        );
+
+        let opts = ListOptions { root: Some("/other".into()), ..Default::default() };
+        assert_eq!(listing_root(Path::new("/p/web"), &opts), PathBuf::from("/p/web"));
+    }
+
+    #[test]
+    fn test_project_settings_from_db() {
+        let db = crate::db::init_test();
+        let conn = db.connection.lock().unwrap();
+        let project = crate::db::projects::create(&conn, "p", "/p").unwrap();
+        assert_eq!(project_settings(&conn, "/p"), FileFilterSettings::default());
+
+        conn.execute(
+            "UPDATE projects SET settings = ?1 WHERE id = ?2",
+            rusqlite::params![
+                r#"{"files":{"showHidden":false,"exclude":["*.min.js"]}}"#,
+                project.id
+            ],
+        )
+        .unwrap();
+        let settings = project_settings(&conn, "/p/");
+        assert!(!settings.show_hidden);
+        assert!(settings.show_ignored);
+        assert_eq!(settings.exclude, vec!["*.min.js"]);
+    }
+
+    #[test]
+    fn test_trusted_section_needs_trust_for_the_workspace_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        std::fs::create_dir_all(dir.path().join(".kodiq")).unwrap();
+        std::fs::write(
+            dir.path().join(".kodiq/workspace.json"),
+            r#"{"settings":{"files":{"showHidden":false}}}"#,
+        )
+        .unwrap();
+        let db = crate::db::init_test();
+        let conn = db.connection.lock().unwrap();
+        let project = crate::db::projects::create(&conn, "p", &root).unwrap();
+
+        assert!(!project_section::<FileFilterSettings>(&conn, &root, "files").show_hidden);
+        assert!(trusted_section::<FileFilterSettings>(&conn, &root, "files").show_hidden);
+
+        conn.execute(
+            "UPDATE projects SET settings = ?1 WHERE id = ?2",
+            rusqlite::params![r#"{"trusted":true}"#, project.id],
+        )
+        .unwrap();
+        assert!(!trusted_section::<FileFilterSettings>(&conn, &root, "files").show_hidden);
+    }
+}
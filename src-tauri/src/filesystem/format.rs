@@ -0,0 +1,807 @@
+use super::encoding::{self, LineEnding};
+use super::{filter, sandbox};
+use crate::db::projects;
+use crate::error::KodiqError;
+use crate::state::DbState;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+// ── Settings ─────────────────────────────────────────────────────────
+
+/// A formatter command for the files matching `globs`. It reads the
+/// content on stdin and prints the formatted result on stdout.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatterSpec {
+    pub name: String,
+    /// Matched against the path relative to the project root.
+    pub globs: Vec<String>,
+    pub command: String,
+    /// `{path}` is replaced with the file's absolute path.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Per-project format settings, stored under `"format"` in the project's
+/// settings JSON.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FormatSettings {
+    /// Format in `write_file` unless the caller says otherwise.
+    pub on_save: bool,
+    /// Project formatters, tried before the built-in ones.
+    pub formatters: Vec<FormatterSpec>,
+    /// Built-in formatters to skip, by name.
+    pub disabled: Vec<String>,
+    pub timeout_ms: u64,
+    /// Whether binaries inside the project (`node_modules/.bin/prettier`,
+    /// relative formatter commands) may run. Set from the project's trust,
+    /// never from the settings JSON.
+    #[serde(skip)]
+    pub trusted: bool,
+}
+
+impl Default for FormatSettings {
+    fn default() -> Self {
+        Self {
+            on_save: false,
+            formatters: Vec::new(),
+            disabled: Vec::new(),
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            trusted: false,
+        }
+    }
+}
+
+// ── Results ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A formatter failure, located when its output names a line.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatDiagnostic {
+    pub formatter: String,
+    pub severity: Severity,
+    pub message: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatOutcome {
+    pub content: String,
+    /// Name of the formatter that ran, if any matched.
+    pub formatter: Option<String>,
+    pub changed: bool,
+    pub diagnostics: Vec<FormatDiagnostic>,
+}
+
+// ── Registry ─────────────────────────────────────────────────────────
+
+struct Builtin {
+    name: &'static str,
+    globs: &'static [&'static str],
+    command: &'static str,
+    args: &'static [&'static str],
+    /// Project-local binary, relative to the root, preferred over PATH.
+    local_bin: Option<&'static str>,
+    /// If set, only used when the project has a local binary or one of
+    /// these config files (so unrelated projects aren't reformatted).
+    config_files: &'static [&'static str],
+}
+
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "prettier",
+        globs: &[
+            "*.{js,jsx,mjs,cjs,ts,tsx,mts,cts}",
+            "*.{json,jsonc,css,scss,less,html,vue,svelte}",
+            "*.{md,mdx,yaml,yml,graphql}",
+        ],
+        command: "prettier",
+        args: &["--stdin-filepath", "{path}"],
+        local_bin: Some("node_modules/.bin/prettier"),
+        config_files: &[
+            ".prettierrc",
+            ".prettierrc.json",
+            ".prettierrc.yaml",
+            ".prettierrc.yml",
+            ".prettierrc.js",
+            ".prettierrc.cjs",
+            ".prettierrc.mjs",
+            ".prettierrc.toml",
+            "prettier.config.js",
+            "prettier.config.cjs",
+            "prettier.config.mjs",
+        ],
+    },
+    Builtin {
+        name: "rustfmt",
+        globs: &["*.rs"],
+        command: "rustfmt",
+        args: &["--edition", "{edition}"],
+        local_bin: None,
+        config_files: &[],
+    },
+    Builtin {
+        name: "black",
+        globs: &["*.{py,pyi}"],
+        command: "black",
+        args: &["--quiet", "--stdin-filename", "{path}", "-"],
+        local_bin: None,
+        config_files: &[],
+    },
+    Builtin {
+        name: "gofmt",
+        globs: &["*.go"],
+        command: "gofmt",
+        args: &[],
+        local_bin: None,
+        config_files: &[],
+    },
+];
+
+/// A formatter resolved for one file: what to run and where.
+#[derive(Debug, PartialEq)]
+struct Resolved {
+    name: String,
+    program: PathBuf,
+    args: Vec<String>,
+}
+
+fn glob_set(globs: &[impl AsRef<str>]) -> Option<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob.as_ref()).ok()?);
+    }
+    builder.build().ok()
+}
+
+fn matches(globs: &[impl AsRef<str>], relative: &Path) -> bool {
+    glob_set(globs).is_some_and(|set| set.is_match(relative))
+}
+
+/// First executable named `name` on PATH.
+fn which(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join(name)).find(|p| p.is_file())
+}
+
+/// Whether one of `names` exists between the file's directory and `root`.
+fn has_config(root: &Path, file: &Path, names: &[&str]) -> bool {
+    file.ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .any(|dir| names.iter().any(|n| dir.join(n).is_file()))
+}
+
+/// Edition from the nearest `Cargo.toml`, since rustfmt on stdin would
+/// otherwise parse as 2015.
+fn cargo_edition(root: &Path, file: &Path) -> String {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r#"(?m)^\s*edition\s*=\s*"(\d{4})""#).unwrap());
+    file.ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .find_map(|dir| std::fs::read_to_string(dir.join("Cargo.toml")).ok())
+        .and_then(|toml| re.captures(&toml).map(|c| c[1].to_string()))
+        .unwrap_or_else(|| "2021".into())
+}
+
+fn expand(args: &[impl AsRef<str>], root: &Path, file: &Path) -> Vec<String> {
+    args.iter()
+        .map(|arg| {
+            let arg = arg.as_ref();
+            let mut out = arg.replace("{path}", &file.to_string_lossy());
+            if arg.contains("{edition}") {
+                out = out.replace("{edition}", &cargo_edition(root, file));
+            }
+            out
+        })
+        .collect()
+}
+
+/// The formatter for `file`: project formatters first, then the built-in
+/// ones that are enabled, applicable and installed. Binaries inside the
+/// project are only used when it is trusted.
+fn resolve_formatter(root: &Path, file: &Path, settings: &FormatSettings) -> Option<Resolved> {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+
+    if let Some(spec) = settings.formatters.iter().find(|f| matches(&f.globs, relative)) {
+        let program = Path::new(&spec.command);
+        let program = if program.is_relative() && spec.command.contains('/') {
+            if !settings.trusted {
+                return None;
+            }
+            root.join(program)
+        } else {
+            program.to_path_buf()
+        };
+        return Some(Resolved {
+            name: spec.name.clone(),
+            program,
+            args: expand(&spec.args, root, file),
+        });
+    }
+
+    BUILTINS
+        .iter()
+        .filter(|b| !settings.disabled.iter().any(|d| d == b.name))
+        .filter(|b| matches(b.globs, relative))
+        .find_map(|b| {
+            let local = b
+                .local_bin
+                .filter(|_| settings.trusted)
+                .map(|bin| root.join(bin))
+                .filter(|p| p.is_file());
+            if local.is_none()
+                && !b.config_files.is_empty()
+                && !has_config(root, file, b.config_files)
+            {
+                return None;
+            }
+            let program = local.or_else(|| which(b.command))?;
+            Some(Resolved { name: b.name.into(), program, args: expand(b.args, root, file) })
+        })
+}
+
+// ── Running ──────────────────────────────────────────────────────────
+
+/// Why a formatter run produced no output.
+#[derive(Debug, PartialEq)]
+enum RunError {
+    /// Stderr on a non-zero exit, or why it couldn't start.
+    Failed(String),
+    TimedOut(Duration),
+}
+
+/// Pipe `input` through the formatter.
+fn run(
+    formatter: &Resolved,
+    cwd: &Path,
+    input: &str,
+    timeout: Duration,
+) -> Result<String, RunError> {
+    let mut child = Command::new(&formatter.program)
+        .args(&formatter.args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            RunError::Failed(format!("Failed to start {}: {}", formatter.program.display(), e))
+        })?;
+
+    // Feed stdin and drain the pipes on threads so a large file can't
+    // deadlock against a full pipe buffer.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_string();
+    let writer = std::thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = std::thread::spawn(move || {
+        let mut out = Vec::new();
+        let _ = stdout.read_to_end(&mut out);
+        out
+    });
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let err_reader = std::thread::spawn(move || {
+        let mut out = Vec::new();
+        let _ = stderr.read_to_end(&mut out);
+        out
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(RunError::TimedOut(timeout));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(e) => return Err(RunError::Failed(e.to_string())),
+        }
+    };
+
+    let _ = writer.join();
+    let stdout = reader.join().unwrap_or_default();
+    let stderr = err_reader.join().unwrap_or_default();
+    if !status.success() {
+        let message = String::from_utf8_lossy(&stderr).trim().to_string();
+        return Err(RunError::Failed(if message.is_empty() {
+            format!("Exited with {}", status)
+        } else {
+            message
+        }));
+    }
+    String::from_utf8(stdout)
+        .map_err(|_| RunError::Failed("Formatter output is not valid UTF-8".into()))
+}
+
+/// Turn formatter output into diagnostics, one per line that names a
+/// location (`file:12:5: …`, `(12:5)`), or a single unlocated one.
+fn parse_diagnostics(formatter: &str, output: &str) -> Vec<FormatDiagnostic> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"[(:]\s?(\d+):(\d+)\)?:?\s*(.*)").unwrap());
+
+    let located: Vec<FormatDiagnostic> = output
+        .lines()
+        .filter_map(|line| {
+            let caps = re.captures(line)?;
+            let message = match caps[3].trim() {
+                "" => line.trim(),
+                rest => rest,
+            };
+            Some(FormatDiagnostic {
+                formatter: formatter.to_string(),
+                severity: Severity::Error,
+                message: message.to_string(),
+                line: caps[1].parse().ok(),
+                column: caps[2].parse().ok(),
+            })
+        })
+        .collect();
+    if !located.is_empty() {
+        return located;
+    }
+    vec![FormatDiagnostic {
+        formatter: formatter.to_string(),
+        severity: Severity::Error,
+        message: output.trim().to_string(),
+        line: None,
+        column: None,
+    }]
+}
+
+// ── EditorConfig ─────────────────────────────────────────────────────
+
+/// The `.editorconfig` properties the save pipeline applies. Indentation is
+/// left alone: reindenting whole files rewrites untouched lines, heredocs and
+/// Makefile recipes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EditorConfig {
+    pub end_of_line: Option<LineEnding>,
+    pub insert_final_newline: Option<bool>,
+    pub trim_trailing_whitespace: Option<bool>,
+}
+
+impl EditorConfig {
+    fn set(&mut self, key: &str, value: &str) {
+        let flag = match value {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        };
+        match key {
+            "end_of_line" => {
+                self.end_of_line = match value {
+                    "lf" => Some(LineEnding::Lf),
+                    "crlf" => Some(LineEnding::Crlf),
+                    "cr" => Some(LineEnding::Cr),
+                    _ => None,
+                }
+            }
+            "insert_final_newline" => self.insert_final_newline = flag,
+            "trim_trailing_whitespace" => self.trim_trailing_whitespace = flag,
+            _ => {}
+        }
+    }
+}
+
+/// A `[glob]` section and its `key = value` pairs, lowercased.
+type Section = (String, Vec<(String, String)>);
+
+/// Sections of one `.editorconfig` file, plus whether it has `root = true`.
+fn parse_editorconfig(text: &str) -> (bool, Vec<Section>) {
+    let mut root = false;
+    let mut sections: Vec<Section> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(glob) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((glob.to_string(), Vec::new()));
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let (key, value) = (key.trim().to_lowercase(), value.trim().to_lowercase());
+        match sections.last_mut() {
+            Some((_, props)) => props.push((key, value)),
+            None if key == "root" => root = value == "true",
+            None => {}
+        }
+    }
+    (root, sections)
+}
+
+/// Whether a section glob applies to `relative` (relative to the
+/// `.editorconfig`'s directory). Globs without a `/` match at any depth.
+fn section_matches(glob: &str, relative: &Path) -> bool {
+    let glob = match glob.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if glob.contains('/') => glob.to_string(),
+        None => format!("**/{}", glob),
+    };
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    globset::GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()
+        .is_ok_and(|g| g.compile_matcher().is_match(relative.as_str()))
+}
+
+/// Properties for `file` from every `.editorconfig` between it and `root`
+/// (or the nearest one marked `root = true`), closer files winning.
+pub fn editorconfig_for(root: &Path, file: &Path) -> EditorConfig {
+    let mut files = Vec::new();
+    for dir in file.ancestors().skip(1) {
+        if let Ok(text) = std::fs::read_to_string(dir.join(".editorconfig")) {
+            let (is_root, sections) = parse_editorconfig(&text);
+            files.push((dir.to_path_buf(), sections));
+            if is_root {
+                break;
+            }
+        }
+        if dir == root || !dir.starts_with(root) {
+            break;
+        }
+    }
+
+    let mut config = EditorConfig::default();
+    for (dir, sections) in files.iter().rev() {
+        let relative = file.strip_prefix(dir).unwrap_or(file);
+        for (glob, props) in sections {
+            if section_matches(glob, relative) {
+                for (key, value) in props {
+                    config.set(key, value);
+                }
+            }
+        }
+    }
+    config
+}
+
+/// Split into `(line, terminator)` pairs; the last terminator may be empty.
+fn split_lines(text: &str) -> Vec<(&str, &str)> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let end = match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => i + 2,
+            b'\r' | b'\n' => i + 1,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        lines.push((&text[start..i], &text[i..end]));
+        start = end;
+        i = end;
+    }
+    if start < text.len() {
+        lines.push((&text[start..], ""));
+    }
+    lines
+}
+
+fn eol_str(ending: LineEnding) -> &'static str {
+    match ending {
+        LineEnding::Lf => "\n",
+        LineEnding::Crlf => "\r\n",
+        LineEnding::Cr => "\r",
+    }
+}
+
+/// Apply `config`'s lossless rules to `text`: line endings, trailing
+/// whitespace and the final newline.
+pub fn apply_editorconfig(text: &str, config: &EditorConfig) -> String {
+    let eol =
+        config.end_of_line.or_else(|| encoding::line_ending(text).0).map(eol_str).unwrap_or("\n");
+    let trim = config.trim_trailing_whitespace == Some(true);
+
+    let mut out = String::with_capacity(text.len());
+    for (line, terminator) in split_lines(text) {
+        out.push_str(if trim { line.trim_end_matches([' ', '\t']) } else { line });
+        if !terminator.is_empty() {
+            out.push_str(if config.end_of_line.is_some() { eol } else { terminator });
+        }
+    }
+
+    match config.insert_final_newline {
+        Some(true) if !out.is_empty() && !out.ends_with(['\n', '\r']) => out.push_str(eol),
+        Some(false) => out.truncate(out.trim_end_matches(['\n', '\r']).len()),
+        _ => {}
+    }
+    out
+}
+
+// ── Pipeline ─────────────────────────────────────────────────────────
+
+/// Run the matching formatter over `content`, then apply `.editorconfig`.
+/// A failing formatter leaves the content as it was and reports why.
+pub fn format_content(
+    root: &Path,
+    file: &Path,
+    content: &str,
+    settings: &FormatSettings,
+) -> FormatOutcome {
+    let mut formatted = content.to_string();
+    let mut diagnostics = Vec::new();
+
+    let formatter = resolve_formatter(root, file, settings);
+    if let Some(formatter) = &formatter {
+        let cwd = file.parent().filter(|p| p.is_dir()).unwrap_or(root);
+        let timeout = Duration::from_millis(settings.timeout_ms.max(1));
+        match run(formatter, cwd, content, timeout) {
+            Ok(out) => formatted = out,
+            Err(RunError::Failed(message)) => {
+                tracing::warn!("{} failed on {}: {}", formatter.name, file.display(), message);
+                diagnostics = parse_diagnostics(&formatter.name, &message);
+            }
+            Err(RunError::TimedOut(after)) => diagnostics.push(FormatDiagnostic {
+                formatter: formatter.name.clone(),
+                severity: Severity::Warning,
+                message: format!("Timed out after {} ms", after.as_millis()),
+                line: None,
+                column: None,
+            }),
+        }
+    }
+
+    let config = editorconfig_for(root, file);
+    let formatted = apply_editorconfig(&formatted, &config);
+    FormatOutcome {
+        changed: formatted != content,
+        content: formatted,
+        formatter: formatter.map(|f| f.name),
+        diagnostics,
+    }
+}
+
+/// The registered project containing `path` (the deepest one, for nested
+/// projects) and its format settings.
+pub fn settings_for(db: &DbState, path: &Path) -> Result<(PathBuf, FormatSettings), KodiqError> {
+    let conn = db.connection.lock()?;
+    Ok(match sandbox::project_root_for(&conn, path)? {
+        Some(root) => {
+            // Formatters and `onSave` from an untrusted repo's workspace file
+            // would let it run any command on save
+            let root_str = root.to_string_lossy();
+            let settings = FormatSettings {
+                trusted: projects::is_trusted(&conn, &root_str),
+                ..filter::trusted_section(&conn, &root_str, "format")
+            };
+            (root, settings)
+        }
+        None => (path.parent().unwrap_or(path).to_path_buf(), FormatSettings::default()),
+    })
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// Format a local file without saving it. Formats `content` (the editor
+/// buffer) when given, otherwise the file on disk.
+#[tracing::instrument(skip(content, db))]
+#[tauri::command(async)]
+pub fn fs_format(
+    webview: tauri::Webview,
+    path: String,
+    content: Option<String>,
+    db: tauri::State<'_, DbState>,
+) -> Result<FormatOutcome, KodiqError> {
+    sandbox::check(&webview, &path, None)?;
+    let file = Path::new(&path);
+    let content = match content {
+        Some(content) => content,
+        None => encoding::decode_detected(&std::fs::read(file)?),
+    };
+    let (root, settings) = settings_for(&db, file)?;
+    Ok(format_content(&root, file, &content, &settings))
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, glob: &str, command: &str, args: &[&str]) -> FormatterSpec {
+        FormatterSpec {
+            name: name.into(),
+            globs: vec![glob.into()],
+            command: command.into(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_project_formatter_wins_and_builtins_can_be_disabled() {
+        let root = Path::new("/work/app");
+        let file = root.join("src/main.rs");
+        let settings = FormatSettings {
+            formatters: vec![spec("mine", "src/**/*.rs", "scripts/fmt", &["{path}"])],
+            ..Default::default()
+        };
+        // A script inside the project only runs once the project is trusted
+        assert_eq!(resolve_formatter(root, &file, &settings), None);
+        let settings = FormatSettings { trusted: true, ..settings };
+        let resolved = resolve_formatter(root, &file, &settings).unwrap();
+        assert_eq!(resolved.name, "mine");
+        assert_eq!(resolved.program, root.join("scripts/fmt"));
+        assert_eq!(resolved.args, vec!["/work/app/src/main.rs"]);
+
+        let disabled = FormatSettings {
+            disabled: BUILTINS.iter().map(|b| b.name.to_string()).collect(),
+            ..Default::default()
+        };
+        assert_eq!(resolve_formatter(root, &file, &disabled), None);
+        assert_eq!(resolve_formatter(root, &root.join("notes.txt"), &Default::default()), None);
+    }
+
+    #[test]
+    fn test_prettier_needs_trusted_local_binary_or_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("src/index.ts");
+        assert_eq!(resolve_formatter(dir.path(), &file, &Default::default()), None);
+
+        let bin = dir.path().join("node_modules/.bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("prettier"), "").unwrap();
+        let untrusted = resolve_formatter(dir.path(), &file, &Default::default());
+        assert!(untrusted.map_or(true, |r| r.program != bin.join("prettier")));
+
+        let trusted = FormatSettings { trusted: true, ..Default::default() };
+        let resolved = resolve_formatter(dir.path(), &file, &trusted).unwrap();
+        assert_eq!(resolved.program, bin.join("prettier"));
+        assert_eq!(resolved.args, vec!["--stdin-filepath".to_string(), file.display().to_string()]);
+    }
+
+    #[test]
+    fn test_cargo_edition() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        let file = dir.path().join("src/lib.rs");
+        assert_eq!(cargo_edition(dir.path(), &file), "2021");
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nedition = \"2018\"\n").unwrap();
+        assert_eq!(cargo_edition(dir.path(), &file), "2018");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_format_through_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        let settings = FormatSettings {
+            formatters: vec![spec("upper", "*.txt", "sh", &["-c", "tr a-z A-Z"])],
+            ..Default::default()
+        };
+        let outcome = format_content(dir.path(), &file, "hello\n", &settings);
+        assert_eq!(outcome.content, "HELLO\n");
+        assert_eq!(outcome.formatter.as_deref(), Some("upper"));
+        assert!(outcome.changed);
+        assert!(outcome.diagnostics.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_formatter_errors_and_timeouts_become_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        let failing = FormatSettings {
+            formatters: vec![spec(
+                "bad",
+                "*.txt",
+                "sh",
+                &["-c", "cat >/dev/null; echo '<stdin>:3:7: expected `;`' >&2; exit 1"],
+            )],
+            ..Default::default()
+        };
+        let outcome = format_content(dir.path(), &file, "x = 1\n", &failing);
+        assert_eq!(outcome.content, "x = 1\n");
+        assert!(!outcome.changed);
+        assert_eq!(
+            outcome.diagnostics,
+            vec![FormatDiagnostic {
+                formatter: "bad".into(),
+                severity: Severity::Error,
+                message: "expected `;`".into(),
+                line: Some(3),
+                column: Some(7),
+            }]
+        );
+
+        let slow = FormatSettings {
+            formatters: vec![spec("slow", "*.txt", "sleep", &["5"])],
+            timeout_ms: 100,
+            ..Default::default()
+        };
+        let started = Instant::now();
+        let outcome = format_content(dir.path(), &file, "x\n", &slow);
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert_eq!(outcome.diagnostics[0].severity, Severity::Warning);
+        assert!(outcome.diagnostics[0].message.contains("Timed out"));
+        assert_eq!(outcome.diagnostics[0].line, None);
+    }
+
+    #[test]
+    fn test_parse_diagnostics_formats() {
+        let black = "error: cannot format -: Cannot parse: 2:4: def f(:";
+        let parsed = parse_diagnostics("black", black);
+        assert_eq!((parsed[0].line, parsed[0].column), (Some(2), Some(4)));
+        assert_eq!(parsed[0].message, "def f(:");
+
+        let prettier = "[error] src/a.ts: SyntaxError: ';' expected. (1:10)";
+        let parsed = parse_diagnostics("prettier", prettier);
+        assert_eq!((parsed[0].line, parsed[0].column), (Some(1), Some(10)));
+    }
+
+    #[test]
+    fn test_editorconfig_sections_and_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        // Outside the project and above a root = true file: ignored
+        std::fs::write(dir.path().join(".editorconfig"), "[*]\nend_of_line = cr\n").unwrap();
+        std::fs::write(
+            project.join(".editorconfig"),
+            "root = true\n\n[*]\ntrim_trailing_whitespace = true\n\
+             insert_final_newline = true\n\n[*.md]\ntrim_trailing_whitespace = false\n",
+        )
+        .unwrap();
+        std::fs::write(
+            project.join("src/.editorconfig"),
+            "[*.rs]\ninsert_final_newline = false\nend_of_line = lf\n",
+        )
+        .unwrap();
+
+        let rs = editorconfig_for(&project, &project.join("src/main.rs"));
+        assert_eq!(rs.end_of_line, Some(LineEnding::Lf));
+        assert_eq!(rs.insert_final_newline, Some(false));
+        assert_eq!(rs.trim_trailing_whitespace, Some(true));
+
+        let md = editorconfig_for(&project, &project.join("README.md"));
+        assert_eq!(md.end_of_line, None);
+        assert_eq!(md.insert_final_newline, Some(true));
+        assert_eq!(md.trim_trailing_whitespace, Some(false));
+    }
+
+    #[test]
+    fn test_apply_editorconfig() {
+        let config = EditorConfig {
+            end_of_line: None,
+            insert_final_newline: Some(true),
+            trim_trailing_whitespace: Some(true),
+        };
+        // Indentation is never touched
+        let text = "fn f() {  \r\n\tx();\t\r\n    y();\r\n}";
+        assert_eq!(apply_editorconfig(text, &config), "fn f() {\r\n\tx();\r\n    y();\r\n}\r\n");
+
+        let lf = EditorConfig {
+            end_of_line: Some(LineEnding::Lf),
+            insert_final_newline: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(apply_editorconfig("      a  \r\nb\n\n", &lf), "      a  \nb");
+        assert_eq!(apply_editorconfig("", &config), "");
+    }
+}
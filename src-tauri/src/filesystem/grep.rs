@@ -0,0 +1,895 @@
+use super::sandbox;
+use super::search::project_walker;
+use crate::error::KodiqError;
+use crate::ssh::{self, SshState};
+use regex::Regex;
+use russh::ChannelMsg;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+const DEFAULT_MAX_RESULTS: usize = 5000;
+const MAX_CONTEXT_LINES: usize = 10;
+/// Files larger than this are skipped — almost always generated or data files.
+const MAX_FILE_BYTES: u64 = 8 * 1024 * 1024;
+/// Same heuristic as git/ripgrep: a NUL in the first few KB means binary.
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+// ── State ────────────────────────────────────────────────────────────
+
+/// Running searches by id, so they can be cancelled from the frontend.
+pub type SearchState = Arc<Mutex<HashMap<String, CancellationToken>>>;
+
+pub fn new_search_state() -> SearchState {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression instead of a literal.
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// Globs a file must match (any of). Empty = all files.
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Also search files excluded by `.gitignore` / `.ignore`.
+    pub include_ignored: bool,
+    pub context_lines: usize,
+    pub max_results: Option<usize>,
+}
+
+/// Char offsets of a match inside `LineMatch::text`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Submatch {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextLine {
+    pub line: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineMatch {
+    /// 1-based line number.
+    pub line: u64,
+    /// 1-based char column of the first match.
+    pub column: usize,
+    pub text: String,
+    pub submatches: Vec<Submatch>,
+    pub before: Vec<ContextLine>,
+    pub after: Vec<ContextLine>,
+}
+
+/// All matches in one file — the payload of a `search-result` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileResult {
+    pub path: String,
+    pub relative_path: String,
+    pub matches: Vec<LineMatch>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSummary {
+    pub search_id: String,
+    pub files: usize,
+    pub matches: usize,
+    pub truncated: bool,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchEvent<'a> {
+    search_id: &'a str,
+    #[serde(flatten)]
+    file: &'a FileResult,
+}
+
+// ── Matching ─────────────────────────────────────────────────────────
+
+pub fn build_matcher(query: &str, opts: &SearchOptions) -> Result<Regex, KodiqError> {
+    if query.is_empty() {
+        return Err(KodiqError::Other("Search query is empty".into()));
+    }
+    let pattern = if opts.regex { query.to_string() } else { regex::escape(query) };
+    let pattern = if opts.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+    regex::RegexBuilder::new(&pattern)
+        .case_insensitive(!opts.case_sensitive)
+        .build()
+        .map_err(|e| KodiqError::Other(format!("Invalid search pattern: {}", e)))
+}
+
+fn submatches(re: &Regex, text: &str) -> Vec<Submatch> {
+    let char_at = |byte: usize| text[..byte].chars().count();
+    re.find_iter(text)
+        .filter(|m| !m.is_empty())
+        .map(|m| Submatch { start: char_at(m.start()), end: char_at(m.end()) })
+        .collect()
+}
+
+/// Build a match for line `line`, pulling context from `get`.
+/// `submatches` may be empty when a remote tool matched with different syntax.
+fn line_match(
+    re: &Regex,
+    line: u64,
+    text: &str,
+    context: usize,
+    get: impl Fn(u64) -> Option<String>,
+) -> LineMatch {
+    let subs = submatches(re, text);
+    let ctx = |range: std::ops::Range<u64>| {
+        range.filter_map(|n| get(n).map(|text| ContextLine { line: n, text })).collect()
+    };
+    LineMatch {
+        line,
+        column: subs.first().map_or(1, |s| s.start + 1),
+        text: text.to_string(),
+        submatches: subs,
+        before: ctx(line.saturating_sub(context as u64).max(1)..line),
+        after: ctx(line + 1..line + 1 + context as u64),
+    }
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+// ── Local search ─────────────────────────────────────────────────────
+
+/// Walk `root` and call `emit` once per file with matches.
+/// Stops early on cancellation or once `max_results` lines matched.
+pub fn search_local(
+    root: &Path,
+    re: &Regex,
+    opts: &SearchOptions,
+    cancel: &CancellationToken,
+    mut emit: impl FnMut(&FileResult),
+) -> Result<SearchSummary, KodiqError> {
+    let mut overrides = ignore::overrides::OverrideBuilder::new(root);
+    let globs = opts.include.iter().cloned().chain(opts.exclude.iter().map(|g| format!("!{}", g)));
+    for glob in globs {
+        overrides.add(&glob).map_err(|e| KodiqError::Other(format!("Invalid glob: {}", e)))?;
+    }
+    let overrides =
+        overrides.build().map_err(|e| KodiqError::Other(format!("Invalid glob: {}", e)))?;
+
+    let context = opts.context_lines.min(MAX_CONTEXT_LINES);
+    let mut budget = opts.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+    let mut summary = SearchSummary::default();
+
+    let walk = project_walker(root, !opts.include_ignored).overrides(overrides).build();
+    for entry in walk.flatten() {
+        if cancel.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if entry.metadata().map_or(true, |m| m.len() > MAX_FILE_BYTES) {
+            continue;
+        }
+
+        let mut bytes = Vec::new();
+        if std::fs::File::open(entry.path()).and_then(|mut f| f.read_to_end(&mut bytes)).is_err()
+            || is_binary(&bytes)
+        {
+            continue;
+        }
+        let text = String::from_utf8_lossy(&bytes);
+        let lines: Vec<&str> = text.lines().collect();
+        let get = |n: u64| lines.get(n as usize - 1).map(|s| s.to_string());
+
+        let mut matches = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            if !re.is_match(line) {
+                continue;
+            }
+            if budget == 0 {
+                summary.truncated = true;
+                break;
+            }
+            budget -= 1;
+            matches.push(line_match(re, i as u64 + 1, line, context, get));
+        }
+
+        if !matches.is_empty() {
+            summary.files += 1;
+            summary.matches += matches.len();
+            emit(&FileResult {
+                path: entry.path().to_string_lossy().to_string(),
+                relative_path: entry
+                    .path()
+                    .strip_prefix(root)
+                    .unwrap_or(entry.path())
+                    .to_string_lossy()
+                    .to_string(),
+                matches,
+            });
+        }
+        if summary.truncated {
+            break;
+        }
+    }
+
+    Ok(summary)
+}
+
+// ── Remote search ────────────────────────────────────────────────────
+
+/// Stderr kept from a remote search, for the error message.
+const MAX_REMOTE_STDERR: usize = 4096;
+
+fn unsupported(what: &str) -> String {
+    format!("grep on this host can't match {}; install ripgrep for full regex support", what)
+}
+
+/// Translate Rust regex syntax into POSIX ERE for `grep -E` / `git grep -E`.
+/// Returns the pattern and whether it started with `(?i)`. Constructs ERE
+/// can't express are rejected rather than silently matching something else.
+pub(super) fn to_ere(pattern: &str) -> Result<(String, bool), String> {
+    let (pattern, ignore_case) = match pattern.strip_prefix("(?i)") {
+        Some(rest) => (rest, true),
+        None => (pattern, false),
+    };
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut out = String::with_capacity(pattern.len() + 8);
+    let mut i = 0;
+    let mut in_repeat = false;
+
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        match c {
+            '\\' => {
+                let Some(&e) = chars.get(i) else { return Err(unsupported("a trailing `\\`")) };
+                i += 1;
+                match e {
+                    'd' => out.push_str("[0-9]"),
+                    'D' => out.push_str("[^0-9]"),
+                    'w' => out.push_str("[[:alnum:]_]"),
+                    'W' => out.push_str("[^[:alnum:]_]"),
+                    's' => out.push_str("[[:space:]]"),
+                    'S' => out.push_str("[^[:space:]]"),
+                    'b' | 'B' => {
+                        out.push('\\');
+                        out.push(e);
+                    }
+                    't' => out.push('\t'),
+                    '.' | '[' | ']' | '{' | '}' | '(' | ')' | '*' | '+' | '?' | '^' | '$' | '|'
+                    | '\\' => {
+                        out.push('\\');
+                        out.push(e);
+                    }
+                    e if !e.is_alphanumeric() => out.push(e),
+                    e => return Err(unsupported(&format!("`\\{}`", e))),
+                }
+            }
+            '(' if chars.get(i) == Some(&'?') => {
+                let rest: String = chars[i..].iter().take(3).collect();
+                if rest.starts_with("?:") {
+                    i += 2;
+                } else if rest.starts_with("?P<") || (rest.starts_with("?<") && rest != "?<=") {
+                    // Named group: ERE groups are unnamed
+                    let Some(close) = chars[i..].iter().position(|&c| c == '>') else {
+                        return Err(unsupported("an unterminated group name"));
+                    };
+                    i += close + 1;
+                } else {
+                    return Err(unsupported("inline flags or lookaround"));
+                }
+                out.push('(');
+            }
+            '*' | '+' | '?' | '}' => {
+                if c == '}' && !in_repeat {
+                    out.push(c);
+                    continue;
+                }
+                in_repeat = false;
+                if chars.get(i) == Some(&'?') {
+                    return Err(unsupported("lazy repetition"));
+                }
+                out.push(c);
+            }
+            '{' => {
+                in_repeat = true;
+                out.push(c);
+            }
+            '[' => i = bracket(&chars, i, &mut out)?,
+            c => out.push(c),
+        }
+    }
+    Ok((out, ignore_case))
+}
+
+/// Copy a bracket expression starting after its `[`. Returns the index past `]`.
+fn bracket(chars: &[char], mut i: usize, out: &mut String) -> Result<usize, String> {
+    out.push('[');
+    if chars.get(i) == Some(&'^') {
+        out.push('^');
+        i += 1;
+    }
+    // A leading `]` is a literal in both syntaxes
+    if chars.get(i) == Some(&']') {
+        out.push(']');
+        i += 1;
+    }
+    while let Some(&c) = chars.get(i) {
+        i += 1;
+        match c {
+            ']' => {
+                out.push(']');
+                return Ok(i);
+            }
+            '[' if chars.get(i) == Some(&':') => {
+                let Some(len) = chars[i..].windows(2).position(|w| w == [':', ']']) else {
+                    return Err(unsupported("an unterminated character class"));
+                };
+                out.push('[');
+                out.extend(&chars[i..i + len + 2]);
+                i += len + 2;
+            }
+            '[' => return Err(unsupported("nested character classes")),
+            '&' | '-' | '~' if chars.get(i) == Some(&c) => {
+                return Err(unsupported("character class set operations"))
+            }
+            '\\' => {
+                let Some(&e) = chars.get(i) else { break };
+                i += 1;
+                match e {
+                    'd' => out.push_str("0-9"),
+                    'w' => out.push_str("[:alnum:]_"),
+                    's' => out.push_str("[:space:]"),
+                    't' => out.push('\t'),
+                    // Backslash is literal inside POSIX brackets
+                    '\\' => out.push('\\'),
+                    ']' | '[' | '^' | '-' => {
+                        return Err(unsupported(&format!("`\\{}` inside a character class", e)))
+                    }
+                    e if !e.is_alphanumeric() => out.push(e),
+                    e => return Err(unsupported(&format!("`\\{}` inside a character class", e))),
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    Err(unsupported("an unterminated character class"))
+}
+
+/// Shell command for a remote search: ripgrep if installed, `git grep` inside
+/// a repository (so `.gitignore` applies), else GNU grep. All print
+/// `path\0line:text` for matches and `path\0line-text` for context, except
+/// `git grep`, which separates the line number with a NUL too.
+pub(super) fn remote_command(root: &str, query: &str, opts: &SearchOptions) -> String {
+    let q = ssh::git::shell_quote;
+    let context = opts.context_lines.min(MAX_CONTEXT_LINES);
+
+    let mut rg = vec![
+        "rg --no-heading --with-filename --line-number --null --color never --no-messages --hidden"
+            .to_string(),
+        format!("-C {}", context),
+        "-g '!.git'".to_string(),
+    ];
+    let mut git =
+        vec!["git grep -n -z -I --untracked --color=never".to_string(), format!("-C {}", context)];
+    let mut grep = vec![
+        "grep -rnsIHZ --color=never --exclude-dir=.git".to_string(),
+        format!("-C {}", context),
+    ];
+
+    // rg speaks Rust regex syntax; the others need it translated to ERE
+    let ere = if opts.regex { Some(to_ere(query)) } else { None };
+    let (posix_query, flag_ignore_case) = match &ere {
+        Some(Ok((pattern, ignore_case))) => (pattern.as_str(), *ignore_case),
+        _ => (query, false),
+    };
+
+    rg.push(if opts.regex { String::new() } else { "-F".into() });
+    for args in [&mut git, &mut grep] {
+        args.push(if opts.regex { "-E".into() } else { "-F".into() });
+        if !opts.case_sensitive || flag_ignore_case {
+            args.push("-i".into());
+        }
+    }
+    if !opts.case_sensitive {
+        rg.push("-i".into());
+    }
+    if opts.whole_word {
+        for args in [&mut rg, &mut git, &mut grep] {
+            args.push("-w".into());
+        }
+    }
+    if opts.include_ignored {
+        rg.push("--no-ignore".into());
+    }
+    for glob in &opts.include {
+        rg.push(format!("-g {}", q(glob)));
+        grep.push(format!("--include={}", q(glob)));
+    }
+    for glob in &opts.exclude {
+        rg.push(format!("-g {}", q(&format!("!{}", glob))));
+        grep.push(format!("--exclude={}", q(glob)));
+    }
+    rg.retain(|a| !a.is_empty());
+    rg.push(format!("-e {} .", q(query)));
+    grep.push(format!("-e {} .", q(posix_query)));
+
+    let pathspecs: Vec<String> = if opts.include.is_empty() {
+        vec![".".into()]
+    } else {
+        opts.include.iter().map(|g| q(g)).collect()
+    };
+    let excludes = opts.exclude.iter().map(|g| q(&format!(":(exclude){}", g)));
+    git.push(format!("-e {} --", q(posix_query)));
+    git.extend(pathspecs.into_iter().chain(excludes));
+
+    let (git, grep) = match ere {
+        Some(Err(message)) => {
+            let fail = format!("{{ echo {} >&2; exit 2; }}", q(&message));
+            (fail.clone(), fail)
+        }
+        _ => (git.join(" "), grep.join(" ")),
+    };
+    // Ignored files are wanted: git grep can't include them, plain grep can
+    let git_branch = if opts.include_ignored {
+        String::new()
+    } else {
+        format!("elif git rev-parse --is-inside-work-tree >/dev/null 2>&1; then {}; ", git)
+    };
+
+    format!(
+        "cd {} && if command -v rg >/dev/null 2>&1; then {}; {}else {}; fi",
+        q(root),
+        rg.join(" "),
+        git_branch,
+        grep
+    )
+}
+
+/// Line number → (is_match, text) for one file of remote output.
+type Lines = BTreeMap<u64, (bool, String)>;
+
+/// Incremental parser for `rg --null` / `grep -Z` / `git grep -z` output.
+/// Each tool prints a file's lines together, so a file is complete once the
+/// next one starts.
+struct RemoteParser<'a> {
+    root: &'a str,
+    re: &'a Regex,
+    context: usize,
+    budget: usize,
+    truncated: bool,
+    partial: Vec<u8>,
+    /// Path and lines of the file being read.
+    file: Option<(String, Lines)>,
+}
+
+impl<'a> RemoteParser<'a> {
+    fn new(root: &'a str, re: &'a Regex, opts: &SearchOptions) -> Self {
+        Self {
+            root,
+            re,
+            context: opts.context_lines.min(MAX_CONTEXT_LINES),
+            budget: opts.max_results.unwrap_or(DEFAULT_MAX_RESULTS),
+            truncated: false,
+            partial: Vec::new(),
+            file: None,
+        }
+    }
+
+    /// Feed raw output. Returns the files it completed; once `max_results`
+    /// lines matched the rest is dropped and `truncated` is set.
+    fn feed(&mut self, bytes: &[u8]) -> Vec<FileResult> {
+        self.partial.extend_from_slice(bytes);
+        let mut done = Vec::new();
+        while !self.truncated {
+            let Some(end) = self.partial.iter().position(|&b| b == b'\n') else { break };
+            let raw: Vec<u8> = self.partial.drain(..=end).collect();
+            self.line(&String::from_utf8_lossy(&raw[..end]), &mut done);
+        }
+        done
+    }
+
+    /// Flush the last file once the output ended (or was cut off).
+    fn finish(mut self) -> Vec<FileResult> {
+        let mut done = Vec::new();
+        if !self.truncated && !self.partial.is_empty() {
+            let raw = std::mem::take(&mut self.partial);
+            self.line(&String::from_utf8_lossy(&raw), &mut done);
+        }
+        done.extend(self.flush());
+        done
+    }
+
+    fn line(&mut self, raw: &str, done: &mut Vec<FileResult>) {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        let Some((path, rest)) = raw.split_once('\0') else { return };
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let (Ok(line), Some(sep)) = (rest[..digits].parse::<u64>(), rest[digits..].chars().next())
+        else {
+            return;
+        };
+        let text = rest[digits + 1..].to_string();
+        let path = path.strip_prefix("./").unwrap_or(path);
+        // `git grep -z` doesn't mark context lines; the matcher decides
+        let is_match = match sep {
+            ':' => true,
+            '\0' => self.re.is_match(&text),
+            _ => false,
+        };
+
+        if self.file.as_ref().map_or(true, |(p, _)| p != path) {
+            done.extend(self.flush());
+            self.file = Some((path.to_string(), BTreeMap::new()));
+        }
+        if is_match {
+            if self.budget == 0 {
+                self.truncated = true;
+                return;
+            }
+            self.budget -= 1;
+        }
+        if let Some((_, lines)) = self.file.as_mut() {
+            lines.insert(line, (is_match, text));
+        }
+    }
+
+    fn flush(&mut self) -> Option<FileResult> {
+        let (rel, lines) = self.file.take()?;
+        let get = |n: u64| lines.get(&n).map(|(_, t)| t.clone());
+        let matches: Vec<LineMatch> = lines
+            .iter()
+            .filter(|(_, (is_match, _))| *is_match)
+            .map(|(&line, (_, text))| line_match(self.re, line, text, self.context, get))
+            .collect();
+        (!matches.is_empty()).then(|| FileResult {
+            path: format!("{}/{}", self.root.trim_end_matches('/'), rel),
+            relative_path: rel,
+            matches,
+        })
+    }
+}
+
+/// Parse complete remote output into per-file results.
+pub fn parse_remote_output(
+    root: &str,
+    output: &str,
+    re: &Regex,
+    opts: &SearchOptions,
+) -> (Vec<FileResult>, bool) {
+    let mut parser = RemoteParser::new(root, re, opts);
+    let mut results = parser.feed(output.as_bytes());
+    let truncated = parser.truncated;
+    results.extend(parser.finish());
+    (results, truncated)
+}
+
+/// Run a search on the remote host over one SSH channel, emitting each file
+/// as its output completes. The channel is closed on cancellation or once
+/// `max_results` lines matched, which stops the remote process.
+async fn search_remote(
+    app: &tauri::AppHandle,
+    connection_id: &str,
+    command: &str,
+    mut parser: RemoteParser<'_>,
+    cancel: &CancellationToken,
+    mut emit: impl FnMut(&FileResult),
+) -> Result<SearchSummary, KodiqError> {
+    let mut channel = ssh::git::ssh_exec(&app.state::<SshState>(), connection_id, command).await?;
+
+    let mut summary = SearchSummary::default();
+    let mut stderr = Vec::new();
+    let mut exit_status = None;
+    let mut report = |files: Vec<FileResult>, summary: &mut SearchSummary| {
+        for file in &files {
+            summary.files += 1;
+            summary.matches += file.matches.len();
+            emit(file);
+        }
+    };
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                summary.cancelled = true;
+                break;
+            }
+            msg = channel.wait() => match msg {
+                Some(ChannelMsg::Data { data }) => {
+                    report(parser.feed(&data), &mut summary);
+                    if parser.truncated {
+                        break;
+                    }
+                }
+                Some(ChannelMsg::ExtendedData { data, .. }) => {
+                    let room = MAX_REMOTE_STDERR.saturating_sub(stderr.len());
+                    stderr.extend_from_slice(&data[..data.len().min(room)]);
+                }
+                Some(ChannelMsg::ExitStatus { exit_status: status }) => exit_status = Some(status),
+                Some(_) => {}
+                None => break,
+            },
+        }
+    }
+    let _ = channel.close().await;
+
+    if !summary.cancelled {
+        summary.truncated = parser.truncated;
+        report(parser.finish(), &mut summary);
+    }
+    // 1 means "no matches"; anything above is a failure
+    if let Some(status) = exit_status.filter(|&s| s > 1 && summary.files == 0) {
+        let stderr = String::from_utf8_lossy(&stderr).trim().to_string();
+        summary.error = Some(if stderr.is_empty() {
+            format!("Remote search exited with status {}", status)
+        } else {
+            stderr
+        });
+    }
+    Ok(summary)
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+fn finish(app: &tauri::AppHandle, mut summary: SearchSummary, search_id: &str) {
+    if let Ok(mut searches) = app.state::<SearchState>().lock() {
+        searches.remove(search_id);
+    }
+    summary.search_id = search_id.to_string();
+    let _ = app.emit("search-done", summary);
+}
+
+/// Start a project-wide content search. Returns a search id immediately;
+/// results stream as `search-result` events (one per file), then `search-done`.
+#[tracing::instrument(skip(app, search_state))]
+#[tauri::command(async)]
+pub async fn fs_search_content(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    search_state: tauri::State<'_, SearchState>,
+    root: String,
+    query: String,
+    options: Option<SearchOptions>,
+    connection_id: Option<String>,
+) -> Result<String, KodiqError> {
+    sandbox::check(&webview, &root, connection_id.as_deref())?;
+    let opts = options.unwrap_or_default();
+    let re = build_matcher(&query, &opts)?;
+
+    let search_id = uuid::Uuid::new_v4().to_string();
+    let cancel = CancellationToken::new();
+    search_state.lock()?.insert(search_id.clone(), cancel.clone());
+
+    let id = search_id.clone();
+    if let Some(conn_id) = connection_id {
+        tokio::spawn(async move {
+            let command = remote_command(&root, &query, &opts);
+            let parser = RemoteParser::new(&root, &re, &opts);
+            let summary = search_remote(&app, &conn_id, &command, parser, &cancel, |file| {
+                let _ = app.emit("search-result", SearchEvent { search_id: &id, file });
+            })
+            .await
+            .unwrap_or_else(|e| SearchSummary { error: Some(e.to_string()), ..Default::default() });
+            finish(&app, summary, &id);
+        });
+    } else {
+        std::thread::spawn(move || {
+            let summary = search_local(Path::new(&root), &re, &opts, &cancel, |file| {
+                let _ = app.emit("search-result", SearchEvent { search_id: &id, file });
+            })
+            .unwrap_or_else(|e| SearchSummary { error: Some(e.to_string()), ..Default::default() });
+            finish(&app, summary, &id);
+        });
+    }
+
+    Ok(search_id)
+}
+
+#[tauri::command]
+pub fn fs_cancel_search(
+    webview: tauri::Webview,
+    search_state: tauri::State<'_, SearchState>,
+    search_id: String,
+) -> Result<(), KodiqError> {
+    sandbox::check_webview(&webview)?;
+    if let Some(token) = search_state.lock()?.get(&search_id) {
+        token.cancel();
+    }
+    Ok(())
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn opts() -> SearchOptions {
+        SearchOptions { context_lines: 1, ..Default::default() }
+    }
+
+    fn run(root: &Path, query: &str, opts: &SearchOptions) -> (Vec<FileResult>, SearchSummary) {
+        let re = build_matcher(query, opts).unwrap();
+        let mut files = Vec::new();
+        let summary =
+            search_local(root, &re, opts, &CancellationToken::new(), |f| files.push(f.clone()))
+                .unwrap();
+        files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        (files, summary)
+    }
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join(".gitignore"), "gen/\n").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {\n    let todo = 1; // TODO\n}\n").unwrap();
+        fs::write(root.join("src/notes.md"), "todo list\nTodoList\n").unwrap();
+        fs::create_dir_all(root.join("gen")).unwrap();
+        fs::write(root.join("gen/out.rs"), "// TODO generated\n").unwrap();
+        fs::write(root.join("blob.bin"), b"TODO\0\x01\x02").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_matcher_options() {
+        let re = build_matcher("a.b", &opts()).unwrap();
+        assert!(!re.is_match("axb"));
+        let re = build_matcher("a.b", &SearchOptions { regex: true, ..opts() }).unwrap();
+        assert!(re.is_match("axb"));
+        let re = build_matcher("todo", &SearchOptions { whole_word: true, ..opts() }).unwrap();
+        assert!(!re.is_match("TodoList"));
+        let re = build_matcher("todo", &SearchOptions { case_sensitive: true, ..opts() }).unwrap();
+        assert!(!re.is_match("TODO"));
+        assert!(build_matcher("(", &SearchOptions { regex: true, ..opts() }).is_err());
+        assert!(build_matcher("", &opts()).is_err());
+    }
+
+    #[test]
+    fn test_local_search_with_context() {
+        let dir = project();
+        let (files, summary) = run(dir.path(), "todo", &opts());
+
+        // gen/ is gitignored, blob.bin is binary
+        assert_eq!(summary.files, 2);
+        assert_eq!(summary.matches, 3);
+        let main = &files[0];
+        assert_eq!(main.relative_path, Path::new("src").join("main.rs").to_string_lossy());
+        let m = &main.matches[0];
+        assert_eq!((m.line, m.column), (2, 9));
+        assert_eq!(
+            m.submatches,
+            vec![Submatch { start: 8, end: 12 }, Submatch { start: 21, end: 25 }]
+        );
+        assert_eq!(m.before, vec![ContextLine { line: 1, text: "fn main() {".into() }]);
+        assert_eq!(m.after, vec![ContextLine { line: 3, text: "}".into() }]);
+    }
+
+    #[test]
+    fn test_local_search_filters_and_limits() {
+        let dir = project();
+        let with_ignored = SearchOptions { include_ignored: true, ..opts() };
+        assert_eq!(run(dir.path(), "todo", &with_ignored).1.files, 3);
+
+        let md_only = SearchOptions { include: vec!["*.md".into()], ..opts() };
+        assert_eq!(run(dir.path(), "todo", &md_only).1.files, 1);
+
+        let no_md = SearchOptions { exclude: vec!["*.md".into()], ..opts() };
+        assert_eq!(run(dir.path(), "todo", &no_md).1.files, 1);
+
+        let limited = SearchOptions { max_results: Some(1), ..opts() };
+        let (_, summary) = run(dir.path(), "todo", &limited);
+        assert_eq!(summary.matches, 1);
+        assert!(summary.truncated);
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let re = build_matcher("todo", &opts()).unwrap();
+        let summary = search_local(dir.path(), &re, &opts(), &cancel, |_| {}).unwrap();
+        assert!(summary.cancelled);
+        assert_eq!(summary.matches, 0);
+    }
+
+    #[test]
+    fn test_parse_remote_output() {
+        let output = "./src/a.rs\x001-use std::io;\n./src/a.rs\x002:let x = todo!();\n./src/a.rs\x003-}\n--\n./b:c.md\x0010:TODO: ship\n";
+        let re = build_matcher("todo", &opts()).unwrap();
+        let (files, truncated) = parse_remote_output("/srv/app/", output, &re, &opts());
+
+        assert!(!truncated);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "/srv/app/src/a.rs");
+        let m = &files[0].matches[0];
+        assert_eq!((m.line, m.column), (2, 9));
+        assert_eq!(m.before[0].text, "use std::io;");
+        assert_eq!(m.after[0].text, "}");
+        assert_eq!(files[1].relative_path, "b:c.md");
+        assert_eq!(files[1].matches[0].line, 10);
+    }
+
+    #[test]
+    fn test_parse_streamed_and_capped_output() {
+        let re = build_matcher("todo", &opts()).unwrap();
+        let limited = SearchOptions { max_results: Some(2), ..opts() };
+        let mut parser = RemoteParser::new("/srv", &re, &limited);
+
+        // a.rs is only complete once b.rs starts, even across chunk boundaries
+        assert!(parser.feed(b"a.rs\x001:todo one\na.rs\x002-ctx\nb.r").is_empty());
+        let done = parser.feed(b"s\x005:TODO two\nb.rs\x006:todo three\nc.rs\x001:todo\n");
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].matches[0].after[0].text, "ctx");
+        assert!(parser.truncated);
+        let rest = parser.finish();
+        assert_eq!(rest[0].relative_path, "b.rs");
+        assert_eq!(rest[0].matches.len(), 1);
+
+        // git grep -z: NUL after the line number, matches told apart by the matcher
+        let (files, _) =
+            parse_remote_output("/srv", "x.md\x001\x00intro\nx.md\x002\x00Todo: x\n", &re, &opts());
+        assert_eq!(files[0].matches.len(), 1);
+        assert_eq!(files[0].matches[0].line, 2);
+        assert_eq!(files[0].matches[0].before[0].text, "intro");
+    }
+
+    #[test]
+    fn test_to_ere() {
+        let ok = |p: &str| to_ere(p).unwrap();
+        assert_eq!(ok(r"\d+\s\w\W"), (r"[0-9]+[[:space:]][[:alnum:]_][^[:alnum:]_]".into(), false));
+        assert_eq!(ok(r"(?i)foo(?:bar|baz)"), ("foo(bar|baz)".into(), true));
+        assert_eq!(ok(r"(?P<n>a)(?<m>b)"), ("(a)(b)".into(), false));
+        assert_eq!(ok(r"[\d_a-f][^]x][[:alpha:]]"), ("[0-9_a-f][^]x][[:alpha:]]".into(), false));
+        assert_eq!(ok(r"a\.b\-c\bx{2,3}"), (r"a\.b-c\bx{2,3}".into(), false));
+
+        for bad in
+            [r"a+?", r"x{2}?", r"(?i:x)", r"x(?i)y", r"\pL", r"\A", r"[\D]", r"[a&&b]", r"[[a]]"]
+        {
+            assert!(to_ere(bad).is_err(), "{} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_remote_command_quotes_input() {
+        let cmd = remote_command(
+            "/srv/it's",
+            "a'b",
+            &SearchOptions { include: vec!["*.rs".into()], ..opts() },
+        );
+        assert!(cmd.starts_with("cd '/srv/it'\\''s' && if command -v rg"));
+        assert!(cmd.contains("-F -i -g '*.rs' -e 'a'\\''b' ."));
+        assert!(cmd.contains("--include='*.rs' -e 'a'\\''b' ."));
+        assert!(cmd.contains("elif git rev-parse --is-inside-work-tree"));
+        assert!(cmd.contains("-F -i -e 'a'\\''b' -- '*.rs'"));
+    }
+
+    #[test]
+    fn test_remote_command_regex_fallbacks() {
+        let regex = SearchOptions { regex: true, case_sensitive: true, ..opts() };
+        let cmd = remote_command("/srv", r"(?i)\d+", &regex);
+        // rg gets the pattern as-is, grep and git grep the ERE translation
+        assert!(cmd.contains(r"-e '(?i)\d+' ."));
+        assert!(cmd.contains("-E -i -e '[0-9]+' --"));
+        assert!(cmd.contains("-E -i -e '[0-9]+' ."));
+
+        let cmd = remote_command("/srv", r"a+?", &regex);
+        assert!(cmd.contains("exit 2;"));
+        assert!(cmd.contains("lazy repetition"));
+
+        let cmd = remote_command("/srv", "x", &SearchOptions { include_ignored: true, ..opts() });
+        assert!(!cmd.contains("git grep"));
+    }
+}
@@ -0,0 +1,509 @@
+use super::encoding::{self, Detected};
+use super::sandbox;
+use super::write::{self, FileVersion};
+use crate::error::KodiqError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+// Layout under `<config>/kodiq/history`:
+//   objects/<2 hex>/<62 hex>   file contents, named by sha256 (shared by all files)
+//   index/<sha256 of path>.json   one file's timeline
+
+/// Files larger than this are not snapshotted.
+const MAX_SNAPSHOT_BYTES: usize = 1_048_576;
+/// Retention: versions kept per file, and how long they live.
+/// The newest version of a file is always kept.
+const MAX_VERSIONS: usize = 100;
+const MAX_AGE_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+/// Minimum gap between two background snapshots of one file.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30);
+
+// ── Types ────────────────────────────────────────────────────────────
+
+/// What produced a version.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistorySource {
+    /// On disk just before an editor save replaced it.
+    Save,
+    /// Changed by something else (an agent, a formatter, git).
+    External,
+    /// Written back by `fs_history_restore`.
+    Restore,
+}
+
+/// One version in a file's timeline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    /// sha256 of the contents; identifies the version in diff/restore.
+    pub hash: String,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub size: u64,
+    pub source: HistorySource,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Timeline {
+    path: String,
+    /// Oldest first.
+    entries: Vec<HistoryEntry>,
+}
+
+// ── Store ────────────────────────────────────────────────────────────
+
+pub struct HistoryStore {
+    root: PathBuf,
+    /// Serializes index read-modify-write between commands and the watcher.
+    lock: Mutex<()>,
+    /// Feeds the background snapshot worker, started on first use.
+    queue: Mutex<Option<mpsc::Sender<PathBuf>>>,
+}
+
+pub type HistoryState = Arc<HistoryStore>;
+
+pub fn new_history_state() -> HistoryState {
+    let root = dirs::config_dir().unwrap_or_else(std::env::temp_dir).join("kodiq").join("history");
+    Arc::new(HistoryStore::at(root))
+}
+
+fn now_ms() -> u64 {
+    write::millis(Ok(std::time::SystemTime::now())).unwrap_or(0)
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Timelines are keyed by the resolved path so symlinked and watcher-reported
+/// spellings of the same file share one history.
+fn key_path(path: &Path) -> String {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()).to_string_lossy().to_string()
+}
+
+impl HistoryStore {
+    pub fn at(root: PathBuf) -> Self {
+        Self { root, lock: Mutex::new(()), queue: Mutex::new(None) }
+    }
+
+    /// Snapshot `path` on the worker thread, at most once per file every
+    /// `SNAPSHOT_INTERVAL`; changes inside the window fold into one
+    /// snapshot at its end.
+    pub fn snapshot_later(self: &Arc<Self>, path: PathBuf) {
+        let Ok(mut queue) = self.queue.lock() else { return };
+        let tx = queue.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::channel();
+            let store = Arc::downgrade(self);
+            std::thread::spawn(move || run_worker(store, rx));
+            tx
+        });
+        let _ = tx.send(path);
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.root.join("objects").join(&hash[..2]).join(&hash[2..])
+    }
+
+    fn index_path(&self, key: &str) -> PathBuf {
+        self.root.join("index").join(format!("{}.json", hex_digest(key.as_bytes())))
+    }
+
+    fn load_timeline(&self, key: &str) -> Timeline {
+        std::fs::read(self.index_path(key))
+            .ok()
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .unwrap_or_else(|| Timeline { path: key.to_string(), entries: Vec::new() })
+    }
+
+    fn save_timeline(&self, key: &str, timeline: &Timeline) -> Result<(), KodiqError> {
+        let path = self.index_path(key);
+        std::fs::create_dir_all(self.root.join("index"))?;
+        let raw = serde_json::to_vec(timeline).map_err(|e| KodiqError::Other(e.to_string()))?;
+        write::write_atomic(&path, &raw)?;
+        Ok(())
+    }
+
+    /// Add `bytes` as the newest version of `path`. Skips binaries, large
+    /// files and contents identical to the newest version.
+    pub fn record(
+        &self,
+        path: &Path,
+        bytes: &[u8],
+        source: HistorySource,
+    ) -> Result<Option<HistoryEntry>, KodiqError> {
+        self.record_at(path, bytes, source, now_ms())
+    }
+
+    fn record_at(
+        &self,
+        path: &Path,
+        bytes: &[u8],
+        source: HistorySource,
+        now: u64,
+    ) -> Result<Option<HistoryEntry>, KodiqError> {
+        if bytes.len() > MAX_SNAPSHOT_BYTES {
+            return Ok(None);
+        }
+        if let Detected::Binary =
+            encoding::detect(&bytes[..bytes.len().min(encoding::SAMPLE_BYTES)])
+        {
+            return Ok(None);
+        }
+
+        let key = key_path(path);
+        let hash = hex_digest(bytes);
+        let _guard = self.lock.lock()?;
+        let mut timeline = self.load_timeline(&key);
+        if timeline.entries.last().is_some_and(|e| e.hash == hash) {
+            return Ok(None);
+        }
+
+        let object = self.object_path(&hash);
+        if !object.exists() {
+            if let Some(dir) = object.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            write::write_atomic(&object, bytes)?;
+        }
+
+        let entry = HistoryEntry { hash, timestamp: now, size: bytes.len() as u64, source };
+        timeline.entries.push(entry.clone());
+        let dropped = prune(&mut timeline.entries, now);
+        self.save_timeline(&key, &timeline)?;
+        self.collect_garbage(dropped);
+        Ok(Some(entry))
+    }
+
+    /// Snapshot whatever is on disk at `path` now. Missing files are skipped.
+    pub fn snapshot_file(
+        &self,
+        path: &Path,
+        source: HistorySource,
+    ) -> Result<Option<HistoryEntry>, KodiqError> {
+        match std::fs::metadata(path) {
+            Ok(meta) if meta.is_file() && meta.len() <= MAX_SNAPSHOT_BYTES as u64 => {
+                self.record(path, &std::fs::read(path)?, source)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// A file's versions, newest first.
+    pub fn timeline(&self, path: &Path) -> Result<Vec<HistoryEntry>, KodiqError> {
+        let _guard = self.lock.lock()?;
+        let mut entries = self.load_timeline(&key_path(path)).entries;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Contents of a version of `path`.
+    pub fn contents(&self, path: &Path, hash: &str) -> Result<Vec<u8>, KodiqError> {
+        let known = self.timeline(path)?.iter().any(|e| e.hash == hash);
+        if !known {
+            return Err(KodiqError::NotFound(format!("Version {} of {}", hash, path.display())));
+        }
+        Ok(std::fs::read(self.object_path(hash))?)
+    }
+
+    /// Unified diff from version `from` to version `to`, or to the file on
+    /// disk when `to` is `None`.
+    pub fn diff(&self, path: &Path, from: &str, to: Option<&str>) -> Result<String, KodiqError> {
+        let old = encoding::decode_detected(&self.contents(path, from)?);
+        let (new, new_label) = match to {
+            Some(hash) => (encoding::decode_detected(&self.contents(path, hash)?), short(hash)),
+            None => {
+                let disk = std::fs::read(path).unwrap_or_default();
+                (encoding::decode_detected(&disk), "disk".to_string())
+            }
+        };
+        let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        Ok(similar::TextDiff::from_lines(&old, &new)
+            .unified_diff()
+            .header(
+                &format!("a/{} ({})", name, short(from)),
+                &format!("b/{} ({})", name, new_label),
+            )
+            .to_string())
+    }
+
+    /// Write a version back to disk. The current contents are snapshotted
+    /// first, so a restore can itself be undone from the timeline.
+    pub fn restore(&self, path: &Path, hash: &str) -> Result<FileVersion, KodiqError> {
+        let bytes = self.contents(path, hash)?;
+        self.snapshot_file(path, HistorySource::External)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write::write_atomic(path, &bytes)?;
+        self.record(path, &bytes, HistorySource::Restore)?;
+        let mtime = write::millis(std::fs::metadata(path).and_then(|m| m.modified()));
+        Ok(write::version_of(&bytes, mtime))
+    }
+
+    /// Delete objects no timeline references any more. Only `candidates`
+    /// (hashes just pruned) are checked, so the common case scans nothing.
+    fn collect_garbage(&self, candidates: Vec<String>) {
+        if candidates.is_empty() {
+            return;
+        }
+        let mut live = std::collections::HashSet::new();
+        if let Ok(indexes) = std::fs::read_dir(self.root.join("index")) {
+            for index in indexes.filter_map(|e| e.ok()) {
+                let Ok(raw) = std::fs::read(index.path()) else { continue };
+                if let Ok(timeline) = serde_json::from_slice::<Timeline>(&raw) {
+                    live.extend(timeline.entries.into_iter().map(|e| e.hash));
+                }
+            }
+        }
+        for hash in candidates.iter().filter(|h| !live.contains(*h)) {
+            let _ = std::fs::remove_file(self.object_path(hash));
+        }
+    }
+}
+
+// ── Worker ───────────────────────────────────────────────────────────
+
+/// Per-file trailing rate limit for background snapshots.
+#[derive(Default)]
+struct RateLimiter {
+    last: HashMap<PathBuf, Instant>,
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl RateLimiter {
+    fn push(&mut self, path: PathBuf, now: Instant) {
+        let due = self.last.get(&path).map_or(now, |t| *t + SNAPSHOT_INTERVAL);
+        self.pending.entry(path).or_insert(due);
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        self.pending.values().min().copied()
+    }
+
+    /// Paths whose turn has come; they count as snapshotted at `now`.
+    fn take_due(&mut self, now: Instant) -> Vec<PathBuf> {
+        let due: Vec<PathBuf> =
+            self.pending.iter().filter(|(_, t)| **t <= now).map(|(p, _)| p.clone()).collect();
+        for path in &due {
+            self.pending.remove(path);
+            self.last.insert(path.clone(), now);
+        }
+        self.last.retain(|_, t| now.duration_since(*t) < SNAPSHOT_INTERVAL);
+        due
+    }
+}
+
+fn run_worker(store: Weak<HistoryStore>, rx: mpsc::Receiver<PathBuf>) {
+    let mut limiter = RateLimiter::default();
+    loop {
+        let next = match limiter.next_due() {
+            Some(due) => rx.recv_timeout(due.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match next {
+            Ok(path) => limiter.push(path, Instant::now()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        let due = limiter.take_due(Instant::now());
+        let Some(store) = store.upgrade() else { return };
+        for path in due {
+            if let Err(e) = store.snapshot_file(&path, HistorySource::External) {
+                tracing::warn!("History snapshot failed for {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn short(hash: &str) -> String {
+    hash.chars().take(8).collect()
+}
+
+/// Apply the retention policy to a timeline (oldest first). Returns the
+/// hashes of dropped versions.
+fn prune(entries: &mut Vec<HistoryEntry>, now: u64) -> Vec<String> {
+    let newest = entries.len().saturating_sub(1);
+    let too_many = entries.len().saturating_sub(MAX_VERSIONS);
+    let mut dropped = Vec::new();
+    let mut i = 0;
+    entries.retain(|e| {
+        let keep = i == newest || (i >= too_many && now.saturating_sub(e.timestamp) <= MAX_AGE_MS);
+        if !keep {
+            dropped.push(e.hash.clone());
+        }
+        i += 1;
+        keep
+    });
+    dropped
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// A local file's saved versions, newest first.
+#[tauri::command]
+pub fn fs_history_list(
+    webview: tauri::Webview,
+    path: String,
+    history: tauri::State<'_, HistoryState>,
+) -> Result<Vec<HistoryEntry>, KodiqError> {
+    sandbox::check(&webview, &path, None)?;
+    history.timeline(Path::new(&path))
+}
+
+/// Unified diff between two versions; omit `to` to compare against disk.
+#[tauri::command(async)]
+pub fn fs_history_diff(
+    webview: tauri::Webview,
+    path: String,
+    from: String,
+    to: Option<String>,
+    history: tauri::State<'_, HistoryState>,
+) -> Result<String, KodiqError> {
+    sandbox::check(&webview, &path, None)?;
+    history.diff(Path::new(&path), &from, to.as_deref())
+}
+
+/// Restore a version; returns the new on-disk version for open editors.
+#[tracing::instrument(skip(history))]
+#[tauri::command(async)]
+pub fn fs_history_restore(
+    webview: tauri::Webview,
+    path: String,
+    hash: String,
+    history: tauri::State<'_, HistoryState>,
+) -> Result<FileVersion, KodiqError> {
+    sandbox::check(&webview, &path, None)?;
+    history.restore(Path::new(&path), &hash)
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (tempfile::TempDir, HistoryStore, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::at(dir.path().join("history"));
+        let file = dir.path().join("main.rs");
+        (dir, store, file)
+    }
+
+    #[test]
+    fn test_record_dedups_and_shares_objects() {
+        let (dir, store, file) = setup();
+        let other = dir.path().join("lib.rs");
+
+        assert!(store.record(&file, b"one", HistorySource::Save).unwrap().is_some());
+        assert!(store.record(&file, b"one", HistorySource::External).unwrap().is_none());
+        store.record(&file, b"two", HistorySource::External).unwrap();
+        store.record(&file, b"one", HistorySource::Save).unwrap();
+        store.record(&other, b"one", HistorySource::Save).unwrap();
+        // Binary contents are not kept
+        assert!(store.record(&file, b"\0\x01\x02", HistorySource::Save).unwrap().is_none());
+
+        let timeline = store.timeline(&file).unwrap();
+        assert_eq!(timeline.len(), 3);
+        assert_eq!(timeline[0].source, HistorySource::Save);
+        assert_eq!(timeline[1].source, HistorySource::External);
+        assert_eq!(store.timeline(&other).unwrap().len(), 1);
+
+        let objects = walk_count(&dir.path().join("history/objects"));
+        assert_eq!(objects, 2);
+    }
+
+    fn walk_count(dir: &Path) -> usize {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| std::fs::read_dir(e.unwrap().path()).unwrap().count())
+            .sum()
+    }
+
+    #[test]
+    fn test_retention_prunes_and_collects_garbage() {
+        let (dir, store, file) = setup();
+        let day = 24 * 60 * 60 * 1000;
+        store.record_at(&file, b"ancient", HistorySource::Save, 0).unwrap();
+        store.record_at(&file, b"recent", HistorySource::Save, 40 * day).unwrap();
+
+        let timeline = store.timeline(&file).unwrap();
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(timeline[0].hash, hex_digest(b"recent"));
+        assert_eq!(walk_count(&dir.path().join("history/objects")), 1);
+
+        // A lone old version is still kept
+        let mut entries = timeline;
+        assert!(prune(&mut entries, 400 * day).is_empty());
+        assert_eq!(entries.len(), 1);
+
+        let mut many: Vec<_> = (0..MAX_VERSIONS + 5)
+            .map(|i| HistoryEntry {
+                hash: i.to_string(),
+                timestamp: 0,
+                size: 0,
+                source: HistorySource::Save,
+            })
+            .collect();
+        assert_eq!(prune(&mut many, 0).len(), 5);
+        assert_eq!(many[0].hash, "5");
+    }
+
+    #[test]
+    fn test_diff_and_restore() {
+        let (_dir, store, file) = setup();
+        std::fs::write(&file, "fn main() {}\n").unwrap();
+        let first = store.snapshot_file(&file, HistorySource::Save).unwrap().unwrap();
+        std::fs::write(&file, "fn main() {\n    todo!()\n}\n").unwrap();
+        let second = store.snapshot_file(&file, HistorySource::External).unwrap().unwrap();
+
+        let diff = store.diff(&file, &first.hash, Some(&second.hash)).unwrap();
+        assert!(diff.contains("-fn main() {}"));
+        assert!(diff.contains("+    todo!()"));
+        assert!(store.diff(&file, &first.hash, None).unwrap().contains("+}"));
+
+        // Someone overwrites the file without us seeing it
+        std::fs::write(&file, "broken").unwrap();
+        store.restore(&file, &first.hash).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "fn main() {}\n");
+
+        let sources: Vec<_> = store.timeline(&file).unwrap().iter().map(|e| e.source).collect();
+        assert_eq!(
+            sources,
+            vec![
+                HistorySource::Restore,
+                HistorySource::External,
+                HistorySource::External,
+                HistorySource::Save
+            ]
+        );
+
+        assert!(matches!(store.restore(&file, &"0".repeat(64)), Err(KodiqError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_rate_limiter_trails_bursts() {
+        let mut limiter = RateLimiter::default();
+        let (a, b) = (PathBuf::from("/a"), PathBuf::from("/b"));
+        let start = Instant::now();
+
+        limiter.push(a.clone(), start);
+        assert_eq!(limiter.take_due(start), vec![a.clone()]);
+
+        // A burst inside the window becomes one snapshot at its end
+        let later = start + Duration::from_secs(1);
+        limiter.push(a.clone(), later);
+        limiter.push(a.clone(), later);
+        limiter.push(b.clone(), later);
+        assert_eq!(limiter.take_due(later), vec![b]);
+        assert_eq!(limiter.next_due(), Some(start + SNAPSHOT_INTERVAL));
+        assert!(limiter.take_due(start + SNAPSHOT_INTERVAL / 2).is_empty());
+        assert_eq!(limiter.take_due(start + SNAPSHOT_INTERVAL), vec![a]);
+        assert_eq!(limiter.next_due(), None);
+    }
+}
@@ -0,0 +1,397 @@
+use super::filter::Verdict;
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+
+/// Page size used by `read_dir_page` when the caller does not pass one.
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// How many leading bytes are sniffed for NULs when the extension is unknown.
+const SNIFF_BYTES: usize = 1024;
+
+const IMAGE_EXTENSIONS: &[&str] =
+    &["png", "jpg", "jpeg", "gif", "webp", "bmp", "ico", "svg", "avif", "tif", "tiff"];
+
+const BINARY_EXTENSIONS: &[&str] = &[
+    "pdf", "zip", "gz", "tgz", "tar", "xz", "bz2", "7z", "rar", "exe", "dll", "so", "dylib", "a",
+    "o", "class", "jar", "wasm", "woff", "woff2", "ttf", "otf", "eot", "mp3", "mp4", "wav", "ogg",
+    "mov", "avi", "mkv", "webm", "db", "sqlite", "bin", "dmg", "iso", "pyc",
+];
+
+const TEXT_EXTENSIONS: &[&str] = &[
+    "rs", "ts", "tsx", "js", "jsx", "mjs", "cjs", "json", "md", "mdx", "txt", "toml", "yaml",
+    "yml", "html", "css", "scss", "less", "py", "go", "rb", "java", "kt", "c", "h", "cpp", "hpp",
+    "cs", "swift", "php", "sh", "bash", "zsh", "fish", "sql", "xml", "csv", "lock", "env", "ini",
+    "cfg", "conf", "vue", "svelte", "astro", "graphql", "proto",
+];
+
+// ── Types ────────────────────────────────────────────────────────────
+
+/// Rough content class, so the tree can pick an icon or viewer without reading the file.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    Text,
+    Binary,
+    Image,
+}
+
+/// One row of a directory listing.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirEntry {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    pub hidden: bool,
+    pub ignored: bool,
+    /// Bytes; `None` for directories.
+    pub size: Option<u64>,
+    /// Milliseconds since the Unix epoch.
+    pub modified: Option<u64>,
+    /// Permission bits (`0o755`), unix only.
+    pub mode: Option<u32>,
+    pub executable: bool,
+    pub is_symlink: bool,
+    pub symlink_target: Option<String>,
+    pub broken_link: bool,
+    /// Porcelain `XY` code for changed files.
+    pub git_status: Option<String>,
+    /// `modified`, `untracked`, … — for directories, a summary of their contents.
+    pub git_kind: Option<String>,
+    pub kind: Option<FileKind>,
+}
+
+/// A slice of a listing; pass `next_cursor` back to get the following page.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirPage {
+    pub entries: Vec<DirEntry>,
+    pub next_cursor: Option<String>,
+    /// Entries in the whole directory after filtering.
+    pub total: usize,
+}
+
+/// Which part of a listing to return.
+#[derive(Debug, Clone, Copy)]
+pub struct PageRequest<'a> {
+    pub cursor: Option<&'a str>,
+    pub limit: usize,
+}
+
+impl PageRequest<'_> {
+    pub const ALL: PageRequest<'static> = PageRequest { cursor: None, limit: usize::MAX };
+}
+
+/// A listed entry before it is stat'ed — cheap enough to build for every child.
+pub struct Listed<M> {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    pub verdict: Verdict,
+    /// Whatever the backend already knows (SFTP attributes), or `()`.
+    pub meta: M,
+}
+
+impl<M> Listed<M> {
+    /// Sort key that doubles as the page cursor: directories first, then by name.
+    pub fn key(&self) -> String {
+        format!("{}{}", if self.is_dir { '0' } else { '1' }, self.name)
+    }
+
+    /// Start a `DirEntry` with the listing fields filled in.
+    pub fn entry(&self) -> DirEntry {
+        DirEntry {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            is_dir: self.is_dir,
+            hidden: self.verdict.hidden,
+            ignored: self.verdict.ignored,
+            size: None,
+            modified: None,
+            mode: None,
+            executable: false,
+            is_symlink: false,
+            symlink_target: None,
+            broken_link: false,
+            git_status: None,
+            git_kind: None,
+            kind: None,
+        }
+    }
+}
+
+/// Sort `items` and cut out the requested page. Returns `(page, next_cursor, total)`.
+///
+/// The cursor is the key of the last returned entry, so pages stay stable
+/// when files are created or removed between requests.
+pub fn paginate<M>(
+    mut items: Vec<Listed<M>>,
+    page: PageRequest,
+) -> (Vec<Listed<M>>, Option<String>, usize) {
+    items.sort_by_key(|a| a.key());
+    let total = items.len();
+    let start = match page.cursor {
+        Some(cursor) => items.partition_point(|i| i.key().as_str() <= cursor),
+        None => 0,
+    };
+    let limit = page.limit.max(1);
+    let mut items: Vec<_> = items.into_iter().skip(start).collect();
+    let next_cursor = if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(|i| i.key())
+    } else {
+        None
+    };
+    (items, next_cursor, total)
+}
+
+// ── File kind ────────────────────────────────────────────────────────
+
+/// Classify by extension alone; `None` when the extension says nothing.
+pub fn kind_from_name(name: &str) -> Option<FileKind> {
+    let ext = name.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase())?;
+    if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        Some(FileKind::Image)
+    } else if BINARY_EXTENSIONS.contains(&ext.as_str()) {
+        Some(FileKind::Binary)
+    } else if TEXT_EXTENSIONS.contains(&ext.as_str()) {
+        Some(FileKind::Text)
+    } else {
+        None
+    }
+}
+
+/// Text unless the sample contains a NUL byte (same heuristic as git and grep).
+pub fn sniff_kind(sample: &[u8]) -> FileKind {
+    if sample.contains(&0) {
+        FileKind::Binary
+    } else {
+        FileKind::Text
+    }
+}
+
+/// Extension first, then a peek at the first bytes of the file.
+fn local_kind(path: &Path, name: &str) -> Option<FileKind> {
+    kind_from_name(name).or_else(|| {
+        let mut sample = Vec::with_capacity(SNIFF_BYTES);
+        let file = std::fs::File::open(path).ok()?;
+        file.take(SNIFF_BYTES as u64).read_to_end(&mut sample).ok()?;
+        Some(sniff_kind(&sample))
+    })
+}
+
+// ── Git decorations ──────────────────────────────────────────────────
+
+/// Changed paths for one listing, from `git status --porcelain` of the enclosing repo.
+pub struct GitDecorations {
+    /// Listed directory relative to the repo toplevel, `/`-terminated (empty at the top).
+    prefix: String,
+    changes: Vec<(String, String, String)>,
+}
+
+impl GitDecorations {
+    /// `None` if `dir` is not inside `toplevel`.
+    pub fn new(toplevel: &Path, dir: &Path, status_raw: &str) -> Option<Self> {
+        let rel = dir.strip_prefix(toplevel).ok()?;
+        let mut prefix = rel.to_string_lossy().replace('\\', "/");
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        Some(Self { prefix, changes: crate::git::info::status_entries(status_raw) })
+    }
+
+    /// `(XY, kind)` for a file; `(None, summary kind)` for a directory with changes inside.
+    pub fn lookup(&self, name: &str, is_dir: bool) -> (Option<String>, Option<String>) {
+        let rel = format!("{}{}", self.prefix, name);
+        let under = format!("{}/", rel);
+
+        // Exact hit, or inside a directory git reports as a whole (untracked `dir/`)
+        let own = self
+            .changes
+            .iter()
+            .find(|(p, ..)| *p == rel || *p == under || (p.ends_with('/') && rel.starts_with(p)));
+        if let Some((_, status, kind)) = own {
+            return ((!is_dir).then(|| status.clone()), Some(kind.clone()));
+        }
+        if !is_dir {
+            return (None, None);
+        }
+
+        let mut inside = self.changes.iter().filter(|(p, ..)| p.starts_with(&under)).peekable();
+        if inside.peek().is_none() {
+            return (None, None);
+        }
+        let all_untracked = inside.all(|(.., kind)| kind == "untracked");
+        (None, Some(if all_untracked { "untracked" } else { "modified" }.to_string()))
+    }
+
+    pub fn decorate(&self, entry: &mut DirEntry) {
+        (entry.git_status, entry.git_kind) = self.lookup(&entry.name, entry.is_dir);
+    }
+}
+
+// ── Local ────────────────────────────────────────────────────────────
+
+/// Stat a local entry and fill in its metadata. Symlinks report their
+/// target's size and mode; dangling ones are flagged as `broken_link`.
+pub fn local_entry(item: &Listed<()>, git: Option<&GitDecorations>) -> DirEntry {
+    let mut entry = item.entry();
+    let path = Path::new(&item.path);
+
+    if let Ok(link) = std::fs::symlink_metadata(path) {
+        if link.file_type().is_symlink() {
+            entry.is_symlink = true;
+            entry.symlink_target =
+                std::fs::read_link(path).ok().map(|t| t.to_string_lossy().to_string());
+        }
+        let meta = match std::fs::metadata(path) {
+            Ok(meta) => meta,
+            Err(_) => {
+                entry.broken_link = entry.is_symlink;
+                link
+            }
+        };
+        if !meta.is_dir() {
+            entry.size = Some(meta.len());
+        }
+        entry.modified = super::write::millis(meta.modified());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = meta.permissions().mode() & 0o7777;
+            entry.mode = Some(mode);
+            entry.executable = meta.is_file() && mode & 0o111 != 0;
+        }
+    }
+
+    if !entry.is_dir && !entry.broken_link {
+        entry.kind = local_kind(path, &entry.name);
+    }
+    if let Some(git) = git {
+        git.decorate(&mut entry);
+    }
+    entry
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listed(name: &str, is_dir: bool) -> Listed<()> {
+        Listed {
+            name: name.into(),
+            path: format!("/p/{}", name),
+            is_dir,
+            verdict: Verdict { hidden: false, ignored: false },
+            meta: (),
+        }
+    }
+
+    fn names(items: &[Listed<()>]) -> Vec<&str> {
+        items.iter().map(|i| i.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_paginate_with_cursor() {
+        let items = || {
+            vec![
+                listed("b.txt", false),
+                listed("src", true),
+                listed("a.txt", false),
+                listed("docs", true),
+            ]
+        };
+
+        let (all, next, total) = paginate(items(), PageRequest::ALL);
+        assert_eq!(names(&all), vec!["docs", "src", "a.txt", "b.txt"]);
+        assert_eq!((next, total), (None, 4));
+
+        let (first, next, _) = paginate(items(), PageRequest { cursor: None, limit: 3 });
+        assert_eq!(names(&first), vec!["docs", "src", "a.txt"]);
+        let cursor = next.unwrap();
+
+        // A file created before the cursor does not shift the next page
+        let mut grown = items();
+        grown.push(listed("0-new.txt", false));
+        let (rest, next, total) = paginate(grown, PageRequest { cursor: Some(&cursor), limit: 3 });
+        assert_eq!(names(&rest), vec!["b.txt"]);
+        assert_eq!((next, total), (None, 5));
+    }
+
+    #[test]
+    fn test_file_kinds() {
+        assert_eq!(kind_from_name("logo.PNG"), Some(FileKind::Image));
+        assert_eq!(kind_from_name("app.wasm"), Some(FileKind::Binary));
+        assert_eq!(kind_from_name("main.rs"), Some(FileKind::Text));
+        assert_eq!(kind_from_name("Makefile"), None);
+        assert_eq!(sniff_kind(b"all:\n\tcargo build\n"), FileKind::Text);
+        assert_eq!(sniff_kind(b"\x7fELF\x02\x01\x00"), FileKind::Binary);
+    }
+
+    #[test]
+    fn test_git_decorations() {
+        let raw = " M web/src/main.ts\n?? web/new/\n?? web/notes.md\nR  old.rs -> lib.rs\n";
+        let top = Path::new("/repo");
+
+        let web = GitDecorations::new(top, Path::new("/repo/web"), raw).unwrap();
+        assert_eq!(web.lookup("src", true), (None, Some("modified".into())));
+        assert_eq!(web.lookup("new", true), (None, Some("untracked".into())));
+        assert_eq!(web.lookup("notes.md", false), (Some("??".into()), Some("untracked".into())));
+        assert_eq!(web.lookup("index.html", false), (None, None));
+
+        let root = GitDecorations::new(top, top, raw).unwrap();
+        assert_eq!(root.lookup("lib.rs", false), (Some("R ".into()), Some("renamed".into())));
+        assert_eq!(root.lookup("web", true), (None, Some("modified".into())));
+
+        let nested = GitDecorations::new(top, Path::new("/repo/web/new/deep"), raw).unwrap();
+        assert_eq!(nested.lookup("x.ts", false), (Some("??".into()), Some("untracked".into())));
+        assert!(GitDecorations::new(top, Path::new("/elsewhere"), raw).is_none());
+    }
+
+    #[test]
+    fn test_local_entry_metadata() {
+        let dir = std::env::temp_dir().join(format!("kodiq-meta-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("run.sh"), "#!/bin/sh\n").unwrap();
+        std::fs::write(dir.join("blob"), b"ab\x00cd").unwrap();
+
+        let item = |name: &str| Listed {
+            name: name.into(),
+            path: dir.join(name).to_string_lossy().to_string(),
+            is_dir: false,
+            verdict: Verdict { hidden: false, ignored: false },
+            meta: (),
+        };
+
+        let blob = local_entry(&item("blob"), None);
+        assert_eq!((blob.size, blob.kind), (Some(5), Some(FileKind::Binary)));
+        assert!(blob.modified.is_some());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let script = dir.join("run.sh");
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+            std::os::unix::fs::symlink(&script, dir.join("link")).unwrap();
+            std::os::unix::fs::symlink(dir.join("missing"), dir.join("dangling")).unwrap();
+
+            let run = local_entry(&item("run.sh"), None);
+            assert_eq!((run.mode, run.executable), (Some(0o755), true));
+
+            let link = local_entry(&item("link"), None);
+            assert!(link.is_symlink && !link.broken_link);
+            assert_eq!(link.symlink_target, Some(script.to_string_lossy().to_string()));
+
+            let dangling = local_entry(&item("dangling"), None);
+            assert!(dangling.is_symlink && dangling.broken_link);
+            assert_eq!(dangling.kind, None);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,3 +1,15 @@
+pub mod encoding;
+pub mod filter;
+pub mod format;
+pub mod grep;
+pub mod local_history;
+pub mod metadata;
+pub mod ops;
 pub mod read;
+pub mod replace;
+pub mod sandbox;
+pub mod search;
+pub mod symbols;
+pub mod trash;
 pub mod watcher;
 pub mod write;
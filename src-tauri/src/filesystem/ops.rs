@@ -0,0 +1,524 @@
+use super::sandbox;
+use super::trash::{self, TrashedItem};
+use super::watcher::{emit_changes, FsChange, FsChangeKind};
+use crate::error::KodiqError;
+use crate::ssh::{self, SshState};
+use std::path::{Component, Path, PathBuf};
+
+/// How many "copy N" names to try before giving up on a duplicate.
+const MAX_DUPLICATES: usize = 100;
+
+// ── Path checks ──────────────────────────────────────────────────────
+
+/// Resolve `.` and `..` without touching the disk.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Check that `path` lies strictly inside `root` and return it normalized.
+/// Local paths also get their existing ancestors resolved, so a symlinked
+/// directory cannot carry an operation out of the project.
+pub(super) fn inside_root(root: &str, path: &str, local: bool) -> Result<PathBuf, KodiqError> {
+    let root = normalize(Path::new(root));
+    let target = normalize(Path::new(path));
+    let outside = || KodiqError::OutsideRoot(path.to_string());
+    if !target.is_absolute() || !target.starts_with(&root) || target == root {
+        return Err(outside());
+    }
+
+    if local {
+        let real_root = std::fs::canonicalize(&root)?;
+        let parent = target.parent().unwrap_or(&root);
+        let existing = parent.ancestors().find(|a| a.exists()).unwrap_or(parent);
+        if !std::fs::canonicalize(existing)?.starts_with(&real_root) {
+            return Err(outside());
+        }
+    }
+    Ok(target)
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn change(kind: FsChangeKind, path: &Path, from: Option<&Path>, is_dir: bool) -> FsChange {
+    FsChange { kind, path: display(path), from: from.map(display), is_dir }
+}
+
+// ── Local operations ─────────────────────────────────────────────────
+
+fn ensure_free(path: &Path) -> Result<(), KodiqError> {
+    match path.symlink_metadata() {
+        Ok(_) => Err(KodiqError::Conflict(format!("{} already exists", path.display()))),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Copy a file or directory tree; symlinks are recreated, not followed.
+pub(super) fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    let meta = from.symlink_metadata()?;
+    if meta.file_type().is_symlink() {
+        let target = std::fs::read_link(from)?;
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(target, to);
+        #[cfg(windows)]
+        return match from.is_dir() {
+            true => std::os::windows::fs::symlink_dir(target, to),
+            false => std::os::windows::fs::symlink_file(target, to),
+        };
+    }
+    if meta.is_dir() {
+        std::fs::create_dir(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        std::fs::set_permissions(to, meta.permissions())
+    } else {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
+/// Delete a file, link or directory tree for good.
+pub(super) fn remove_recursive(path: &Path) -> std::io::Result<()> {
+    match path.symlink_metadata()?.is_dir() {
+        true => std::fs::remove_dir_all(path),
+        false => std::fs::remove_file(path),
+    }
+}
+
+fn create_file_local(path: &Path) -> Result<(), KodiqError> {
+    ensure_free(path)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+    Ok(())
+}
+
+fn create_dir_local(path: &Path) -> Result<(), KodiqError> {
+    ensure_free(path)?;
+    std::fs::create_dir_all(path)?;
+    Ok(())
+}
+
+/// Returns whether the renamed entry is a directory.
+fn rename_local(from: &Path, to: &Path) -> Result<bool, KodiqError> {
+    let is_dir = from.symlink_metadata()?.is_dir();
+    ensure_free(to)?;
+    std::fs::rename(from, to)?;
+    Ok(is_dir)
+}
+
+/// Returns whether the copied entry is a directory.
+fn copy_local(from: &Path, to: &Path) -> Result<bool, KodiqError> {
+    let is_dir = from.symlink_metadata()?.is_dir();
+    ensure_free(to)?;
+    if let Err(e) = copy_recursive(from, to) {
+        // Don't leave a half-copied tree behind
+        let _ = remove_recursive(to);
+        return Err(e.into());
+    }
+    Ok(is_dir)
+}
+
+/// `name copy.ext`, `name copy 2.ext`, … — the extension stays last for files.
+fn duplicate_name(name: &str, is_dir: bool, n: usize) -> String {
+    let suffix = if n == 1 { " copy".to_string() } else { format!(" copy {}", n) };
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !is_dir && !stem.is_empty() => format!("{}{}.{}", stem, suffix, ext),
+        _ => format!("{}{}", name, suffix),
+    }
+}
+
+/// Refuse to move a directory into itself or one of its descendants.
+fn check_move(from: &Path, to: &Path) -> Result<(), KodiqError> {
+    if to.starts_with(from) {
+        return Err(KodiqError::Conflict(format!("Cannot move {} into itself", from.display())));
+    }
+    Ok(())
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// Create an empty file (and missing parent folders) inside the project.
+#[tracing::instrument(skip(app, ssh_state))]
+#[tauri::command(async)]
+pub async fn fs_create_file(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    root: String,
+    path: String,
+    connection_id: Option<String>,
+    ssh_state: tauri::State<'_, SshState>,
+) -> Result<String, KodiqError> {
+    sandbox::check(&webview, &root, connection_id.as_deref())?;
+    let target = inside_root(&root, &path, connection_id.is_none())?;
+    match connection_id {
+        Some(ref conn_id) => {
+            ssh::filesystem::sftp_create_file(&display(&target), &ssh_state, conn_id).await?
+        }
+        None => create_file_local(&target)?,
+    }
+    let changes = vec![change(FsChangeKind::Created, &target, None, false)];
+    emit_changes(&app, &root, connection_id.as_deref(), changes);
+    Ok(display(&target))
+}
+
+/// Create a folder (and missing parents) inside the project.
+#[tracing::instrument(skip(app, ssh_state))]
+#[tauri::command(async)]
+pub async fn fs_create_dir(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    root: String,
+    path: String,
+    connection_id: Option<String>,
+    ssh_state: tauri::State<'_, SshState>,
+) -> Result<String, KodiqError> {
+    sandbox::check(&webview, &root, connection_id.as_deref())?;
+    let target = inside_root(&root, &path, connection_id.is_none())?;
+    match connection_id {
+        Some(ref conn_id) => {
+            ssh::filesystem::sftp_create_dir(&display(&target), &ssh_state, conn_id).await?
+        }
+        None => create_dir_local(&target)?,
+    }
+    let changes = vec![change(FsChangeKind::Created, &target, None, true)];
+    emit_changes(&app, &root, connection_id.as_deref(), changes);
+    Ok(display(&target))
+}
+
+/// Rename (or move) one entry to a full new path. Never overwrites.
+#[tracing::instrument(skip(app, ssh_state))]
+#[tauri::command(async)]
+pub async fn fs_rename(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    root: String,
+    from: String,
+    to: String,
+    connection_id: Option<String>,
+    ssh_state: tauri::State<'_, SshState>,
+) -> Result<String, KodiqError> {
+    sandbox::check(&webview, &root, connection_id.as_deref())?;
+    let local = connection_id.is_none();
+    let source = inside_root(&root, &from, local)?;
+    let target = inside_root(&root, &to, local)?;
+    check_move(&source, &target)?;
+    let is_dir = match connection_id {
+        Some(ref conn_id) => {
+            ssh::filesystem::sftp_rename(&display(&source), &display(&target), &ssh_state, conn_id)
+                .await?
+        }
+        None => rename_local(&source, &target)?,
+    };
+    let changes = vec![change(FsChangeKind::Renamed, &target, Some(&source), is_dir)];
+    emit_changes(&app, &root, connection_id.as_deref(), changes);
+    Ok(display(&target))
+}
+
+/// Move entries into `target_dir`, keeping their names. Stops at the first
+/// failure; entries moved before it stay moved and are reported.
+#[tracing::instrument(skip(app, ssh_state))]
+#[tauri::command(async)]
+pub async fn fs_move(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    root: String,
+    paths: Vec<String>,
+    target_dir: String,
+    connection_id: Option<String>,
+    ssh_state: tauri::State<'_, SshState>,
+) -> Result<Vec<String>, KodiqError> {
+    sandbox::check(&webview, &root, connection_id.as_deref())?;
+    let local = connection_id.is_none();
+    let dir = normalize(Path::new(&target_dir));
+    if dir != normalize(Path::new(&root)) {
+        inside_root(&root, &target_dir, local)?;
+    }
+
+    let mut moved = Vec::new();
+    let mut changes = Vec::new();
+    let mut result = Ok(());
+    for path in &paths {
+        let step = async {
+            let source = inside_root(&root, path, local)?;
+            let name = source.file_name().ok_or_else(|| KodiqError::OutsideRoot(path.clone()))?;
+            let target = dir.join(name);
+            check_move(&source, &target)?;
+            let is_dir = match connection_id {
+                Some(ref conn_id) => {
+                    let (from, to) = (display(&source), display(&target));
+                    ssh::filesystem::sftp_rename(&from, &to, &ssh_state, conn_id).await?
+                }
+                None => rename_local(&source, &target)?,
+            };
+            Ok::<_, KodiqError>(change(FsChangeKind::Renamed, &target, Some(&source), is_dir))
+        };
+        match step.await {
+            Ok(c) => {
+                moved.push(c.path.clone());
+                changes.push(c);
+            }
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+
+    emit_changes(&app, &root, connection_id.as_deref(), changes);
+    result.map(|_| moved)
+}
+
+/// Copy an entry. Without `to`, duplicates it next to the original as
+/// `name copy.ext`. Returns the new path.
+#[tracing::instrument(skip(app, ssh_state))]
+#[tauri::command(async)]
+pub async fn fs_copy(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    root: String,
+    from: String,
+    to: Option<String>,
+    connection_id: Option<String>,
+    ssh_state: tauri::State<'_, SshState>,
+) -> Result<String, KodiqError> {
+    sandbox::check(&webview, &root, connection_id.as_deref())?;
+    let local = connection_id.is_none();
+    let source = inside_root(&root, &from, local)?;
+    let source_is_dir = match connection_id {
+        Some(ref conn_id) => {
+            ssh::filesystem::sftp_probe(&display(&source), &ssh_state, conn_id).await?
+        }
+        None => source.symlink_metadata().ok().map(|m| m.is_dir()),
+    }
+    .ok_or_else(|| KodiqError::NotFound(from.clone()))?;
+
+    let target = match to {
+        Some(ref to) => inside_root(&root, to, local)?,
+        None => {
+            let parent = source.parent().unwrap_or(&source);
+            let name = source.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+            let mut free = None;
+            for n in 1..=MAX_DUPLICATES {
+                let candidate = parent.join(duplicate_name(&name, source_is_dir, n));
+                let taken = match connection_id {
+                    Some(ref conn_id) => {
+                        ssh::filesystem::sftp_probe(&display(&candidate), &ssh_state, conn_id)
+                            .await?
+                            .is_some()
+                    }
+                    None => candidate.symlink_metadata().is_ok(),
+                };
+                if !taken {
+                    free = Some(candidate);
+                    break;
+                }
+            }
+            free.ok_or_else(|| KodiqError::Conflict(format!("Too many copies of {}", from)))?
+        }
+    };
+    check_move(&source, &target)?;
+
+    let is_dir = match connection_id {
+        Some(ref conn_id) => {
+            ssh::filesystem::sftp_copy(&display(&source), &display(&target), &ssh_state, conn_id)
+                .await?
+        }
+        None => copy_local(&source, &target)?,
+    };
+    let changes = vec![change(FsChangeKind::Created, &target, None, is_dir)];
+    emit_changes(&app, &root, connection_id.as_deref(), changes);
+    Ok(display(&target))
+}
+
+/// Delete entries. Local ones go to the freedesktop trash unless `permanent`
+/// is set; remote ones are always deleted for good (SFTP has no trash).
+/// Returns the trashed items so the UI can offer an undo.
+#[tracing::instrument(skip(app, ssh_state))]
+#[tauri::command(async)]
+pub async fn fs_delete(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    root: String,
+    paths: Vec<String>,
+    permanent: Option<bool>,
+    connection_id: Option<String>,
+    ssh_state: tauri::State<'_, SshState>,
+) -> Result<Vec<TrashedItem>, KodiqError> {
+    sandbox::check(&webview, &root, connection_id.as_deref())?;
+    let local = connection_id.is_none();
+    let targets =
+        paths.iter().map(|p| inside_root(&root, p, local)).collect::<Result<Vec<_>, _>>()?;
+    let trash_dir = match (local, permanent.unwrap_or(false)) {
+        (true, false) => Some(
+            trash::home_trash()
+                .ok_or_else(|| KodiqError::NotFound("Trash directory".to_string()))?,
+        ),
+        _ => None,
+    };
+
+    let mut trashed = Vec::new();
+    let mut changes = Vec::new();
+    let mut result = Ok(());
+    for target in &targets {
+        let step = match (&connection_id, &trash_dir) {
+            (Some(conn_id), _) => {
+                ssh::filesystem::sftp_remove(&display(target), &ssh_state, conn_id).await
+            }
+            (None, Some(trash_dir)) => trash::trash_in(trash_dir, target).map(|item| {
+                let is_dir = item.is_dir;
+                trashed.push(item);
+                is_dir
+            }),
+            (None, None) => target
+                .symlink_metadata()
+                .map(|m| m.is_dir())
+                .and_then(|is_dir| remove_recursive(target).map(|_| is_dir))
+                .map_err(KodiqError::from),
+        };
+        match step {
+            Ok(is_dir) => changes.push(change(FsChangeKind::Removed, target, None, is_dir)),
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+
+    emit_changes(&app, &root, connection_id.as_deref(), changes);
+    result.map(|_| trashed)
+}
+
+/// Items in the trash that were deleted from this project, newest first.
+#[tauri::command]
+pub fn fs_trash_list(
+    webview: tauri::Webview,
+    root: String,
+) -> Result<Vec<TrashedItem>, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    Ok(trash::home_trash()
+        .map(|dir| trash::list_in(&dir, &normalize(Path::new(&root))))
+        .unwrap_or_default())
+}
+
+/// Put trashed items back where they were deleted from.
+#[tracing::instrument(skip(app))]
+#[tauri::command(async)]
+pub async fn fs_trash_restore(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    root: String,
+    ids: Vec<String>,
+) -> Result<Vec<String>, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    let trash_dir =
+        trash::home_trash().ok_or_else(|| KodiqError::NotFound("Trash directory".to_string()))?;
+    let ours = trash::list_in(&trash_dir, &normalize(Path::new(&root)));
+
+    let mut restored = Vec::new();
+    let mut changes = Vec::new();
+    let mut result = Ok(());
+    for id in &ids {
+        // Only restore items that belong to this project
+        let step = match ours.iter().find(|item| &item.id == id) {
+            Some(_) => trash::restore_in(&trash_dir, id),
+            None => Err(KodiqError::NotFound(format!("Trash item {}", id))),
+        };
+        match step {
+            Ok(item) => {
+                let path = PathBuf::from(&item.original_path);
+                changes.push(change(FsChangeKind::Created, &path, None, item.is_dir));
+                restored.push(item.original_path);
+            }
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+
+    emit_changes(&app, &root, None, changes);
+    result.map(|_| restored)
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inside_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("p");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let root_s = display(&root);
+        let path = |rel: &str| format!("{}/{}", root_s, rel);
+
+        assert_eq!(
+            inside_root(&root_s, &path("src/new.rs"), true).unwrap(),
+            root.join("src/new.rs")
+        );
+        assert!(inside_root(&root_s, &path("a/b/c.txt"), true).is_ok());
+        for bad in [path("../escape"), path("src/../../x"), root_s.clone(), "relative".into()] {
+            assert!(matches!(inside_root(&root_s, &bad, true), Err(KodiqError::OutsideRoot(_))));
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path(), root.join("out")).unwrap();
+            assert!(matches!(
+                inside_root(&root_s, &path("out/x.txt"), true),
+                Err(KodiqError::OutsideRoot(_))
+            ));
+            // Remote paths are only checked lexically
+            assert!(inside_root(&root_s, &path("out/x.txt"), false).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_local_operations() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        create_dir_local(&root.join("a/b")).unwrap();
+        create_file_local(&root.join("a/b/f.txt")).unwrap();
+        assert!(matches!(create_file_local(&root.join("a/b/f.txt")), Err(KodiqError::Conflict(_))));
+        std::fs::write(root.join("a/b/f.txt"), "data").unwrap();
+
+        copy_local(&root.join("a"), &root.join("c")).unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("c/b/f.txt")).unwrap(), "data");
+
+        rename_local(&root.join("c/b/f.txt"), &root.join("c/g.txt")).unwrap();
+        assert!(root.join("c/g.txt").is_file());
+        assert!(matches!(
+            rename_local(&root.join("a"), &root.join("c")),
+            Err(KodiqError::Conflict(_))
+        ));
+        assert!(check_move(&root.join("a"), &root.join("a/b/a")).is_err());
+
+        remove_recursive(&root.join("c")).unwrap();
+        assert!(!root.join("c").exists());
+    }
+
+    #[test]
+    fn test_duplicate_name() {
+        assert_eq!(duplicate_name("main.rs", false, 1), "main copy.rs");
+        assert_eq!(duplicate_name("main.rs", false, 3), "main copy 3.rs");
+        assert_eq!(duplicate_name(".env", false, 1), ".env copy");
+        assert_eq!(duplicate_name("v1.2", true, 1), "v1.2 copy");
+    }
+}
@@ -1,93 +1,466 @@
+use super::encoding::{self, Detected, LineEnding, LineWindow, TextEncoding};
+use super::filter::{self, FileFilterSettings, ListOptions};
+use super::metadata::FileKind;
+use super::metadata::{self, DirEntry, DirPage, GitDecorations, Listed, PageRequest};
+use super::sandbox;
+use super::write::{self, FileVersion};
 use crate::error::KodiqError;
 use crate::ssh::{self, SshState};
+use crate::state::DbState;
+use std::path::Path;
 
 /// Read directory contents for file tree.
 /// If `connection_id` is provided, reads from remote via SFTP.
 /// Sorts directories first, then alphabetically by name.
-/// Skips hidden files and common noise directories.
-#[tracing::instrument(skip(ssh_state))]
+/// Hidden and gitignored entries are marked (`hidden`, `ignored`) and only
+/// left out when the project settings or `options` say so.
+#[tracing::instrument(skip(db, ssh_state))]
 #[tauri::command(async)]
 pub async fn read_dir(
+    webview: tauri::Webview,
     path: String,
     connection_id: Option<String>,
+    options: Option<ListOptions>,
+    db: tauri::State<'_, DbState>,
     ssh_state: tauri::State<'_, SshState>,
-) -> Result<Vec<serde_json::Value>, KodiqError> {
+) -> Result<Vec<DirEntry>, KodiqError> {
+    sandbox::check(&webview, &path, connection_id.as_deref())?;
+    let page = list_dir(&path, connection_id, options, PageRequest::ALL, &db, &ssh_state).await?;
+    Ok(page.entries)
+}
+
+/// Like `read_dir`, but one page at a time for large directories.
+/// Pass the returned `nextCursor` back as `cursor` until it is `null`.
+#[tracing::instrument(skip(db, ssh_state))]
+#[allow(clippy::too_many_arguments)]
+#[tauri::command(async)]
+pub async fn read_dir_page(
+    webview: tauri::Webview,
+    path: String,
+    connection_id: Option<String>,
+    options: Option<ListOptions>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    db: tauri::State<'_, DbState>,
+    ssh_state: tauri::State<'_, SshState>,
+) -> Result<DirPage, KodiqError> {
+    sandbox::check(&webview, &path, connection_id.as_deref())?;
+    let page = PageRequest {
+        cursor: cursor.as_deref(),
+        limit: limit.unwrap_or(metadata::DEFAULT_PAGE_SIZE),
+    };
+    list_dir(&path, connection_id, options, page, &db, &ssh_state).await
+}
+
+async fn list_dir(
+    path: &str,
+    connection_id: Option<String>,
+    options: Option<ListOptions>,
+    page: PageRequest<'_>,
+    db: &DbState,
+    ssh_state: &tauri::State<'_, SshState>,
+) -> Result<DirPage, KodiqError> {
+    let opts = options.unwrap_or_default();
+    let settings = match opts.root {
+        Some(ref root) => filter::project_settings(&*db.connection.lock()?, root),
+        None => FileFilterSettings::default(),
+    };
+    let with_git = opts.git.unwrap_or(true);
+
     // Remote: delegate to SFTP
     if let Some(ref conn_id) = connection_id {
-        return ssh::filesystem::sftp_read_dir(&path, &ssh_state, conn_id).await;
+        let git = match with_git {
+            true => crate::git::info::remote_status(ssh_state, conn_id, path).await,
+            false => None,
+        }
+        .and_then(|(top, raw)| GitDecorations::new(Path::new(&top), Path::new(path), &raw));
+        return ssh::filesystem::sftp_read_dir(
+            path,
+            ssh_state,
+            conn_id,
+            &settings,
+            &opts,
+            git.as_ref(),
+            page,
+        )
+        .await;
     }
 
-    // Local: original logic
-    let dir = std::path::Path::new(&path);
+    let git =
+        with_git.then(|| crate::git::info::local_status(path)).flatten().and_then(|(top, raw)| {
+            // git reports the resolved toplevel, so resolve the listed dir too
+            let dir = std::fs::canonicalize(path).ok()?;
+            GitDecorations::new(Path::new(&top), &dir, &raw)
+        });
+    read_dir_local(path, &settings, &opts, git.as_ref(), page)
+}
+
+fn read_dir_local(
+    path: &str,
+    settings: &FileFilterSettings,
+    opts: &ListOptions,
+    git: Option<&GitDecorations>,
+    page: PageRequest,
+) -> Result<DirPage, KodiqError> {
+    let dir = Path::new(path);
     if !dir.is_dir() {
         return Err(KodiqError::NotFound(format!("Not a directory: {}", path)));
     }
+    let policy = filter::local_policy(dir, settings, opts);
+
+    let mut items = Vec::new();
+    for entry in std::fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        let Ok(file_type) = entry.file_type() else {
+            continue; // skip inaccessible entries
+        };
+        // Symlinked directories expand like real ones
+        let is_dir = file_type.is_dir()
+            || (file_type.is_symlink() && entry.path().metadata().is_ok_and(|m| m.is_dir()));
+        let Some(verdict) = policy.classify(&entry.path(), &name, is_dir) else {
+            continue;
+        };
+        let path = entry.path().to_string_lossy().to_string();
+        items.push(Listed { name, path, is_dir, verdict, meta: () });
+    }
+
+    // Only the returned page pays for stat calls and sniffing
+    let (items, next_cursor, total) = metadata::paginate(items, page);
+    let entries = items.iter().map(|item| metadata::local_entry(item, git)).collect();
+    Ok(DirPage { entries, next_cursor, total })
+}
+
+/// Largest file `read_file` returns whole; bigger text needs a range.
+const MAX_TEXT_BYTES: u64 = 1_048_576;
+const MAX_IMAGE_BYTES: u64 = 10 * 1_048_576;
+/// Cap for a single byte range or line window.
+const MAX_CHUNK_BYTES: u64 = 4 * 1_048_576;
+const DEFAULT_LINE_COUNT: usize = 1000;
+const BLOCK_BYTES: u64 = 64 * 1024;
+
+/// Partial reads: a byte window (`offset`/`length`) or a line window
+/// (`startLine`/`lineCount`, 0-based). Omit both for the whole file.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReadOptions {
+    pub offset: Option<u64>,
+    pub length: Option<u64>,
+    pub start_line: Option<usize>,
+    pub line_count: Option<usize>,
+}
 
-    let mut entries: Vec<serde_json::Value> = Vec::new();
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    Text,
+    Binary,
+    Image,
+}
 
-    let mut items: Vec<_> = std::fs::read_dir(dir)?.filter_map(|e| e.ok()).collect();
+/// Which part of the file a partial read returned.
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentRange {
+    /// Byte offsets, adjusted to character boundaries.
+    pub start: u64,
+    pub end: u64,
+    pub first_line: Option<usize>,
+    pub has_more: bool,
+}
 
-    // Sort: directories first, then by name
-    items.sort_by(|a, b| {
-        let a_dir = a.file_type().map(|t| t.is_dir()).unwrap_or(false);
-        let b_dir = b.file_type().map(|t| t.is_dir()).unwrap_or(false);
-        match (a_dir, b_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.file_name().cmp(&b.file_name()),
+/// A file as the editor needs it, plus the version a later `write_file`
+/// can check against. Partial reads carry no hash — save whole files only.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileContent {
+    pub kind: ContentKind,
+    /// Decoded text; base64 for images; empty for other binaries.
+    pub content: String,
+    pub size: u64,
+    pub encoding: Option<TextEncoding>,
+    pub line_ending: Option<LineEnding>,
+    pub mixed_line_endings: bool,
+    pub mime: Option<&'static str>,
+    pub range: Option<ContentRange>,
+    #[serde(flatten)]
+    pub version: FileVersion,
+}
+
+impl FileContent {
+    fn binary(kind: ContentKind, content: String, size: u64, version: FileVersion) -> Self {
+        Self {
+            kind,
+            content,
+            size,
+            encoding: None,
+            line_ending: None,
+            mixed_line_endings: false,
+            mime: None,
+            range: None,
+            version,
         }
-    });
+    }
+}
 
-    for entry in items {
-        let name = entry.file_name().to_string_lossy().to_string();
+fn image_mime(name: &str) -> &'static str {
+    let ext = name.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase()).unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "svg" => "image/svg+xml",
+        "avif" => "image/avif",
+        "tif" | "tiff" => "image/tiff",
+        _ => "application/octet-stream",
+    }
+}
 
-        // Skip hidden files and common noise
-        if name.starts_with('.')
-            || name == "node_modules"
-            || name == "target"
-            || name == "__pycache__"
-            || name == ".git"
-        {
-            continue;
+/// An open file, local or over SFTP.
+enum Source {
+    Local(std::fs::File),
+    Remote(russh_sftp::client::fs::File),
+}
+
+impl Source {
+    /// Up to `len` bytes from `offset`; shorter only at EOF.
+    async fn read_at(&mut self, offset: u64, len: u64) -> Result<Vec<u8>, KodiqError> {
+        use std::io::{Read, Seek, SeekFrom};
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+        let mut buf = Vec::new();
+        match self {
+            Source::Local(file) => {
+                file.seek(SeekFrom::Start(offset))?;
+                file.take(len).read_to_end(&mut buf)?;
+            }
+            Source::Remote(file) => {
+                let sftp_err = |e: std::io::Error| KodiqError::Sftp(format!("Read: {}", e));
+                file.seek(SeekFrom::Start(offset)).await.map_err(sftp_err)?;
+                file.take(len).read_to_end(&mut buf).await.map_err(sftp_err)?;
+            }
         }
+        Ok(buf)
+    }
+}
 
-        let Ok(file_type) = entry.file_type() else {
-            continue; // skip broken symlinks / inaccessible entries
-        };
-        let full_path = entry.path().to_string_lossy().to_string();
+/// Decode `source` according to its type and the requested window.
+async fn read_content(
+    mut source: Source,
+    name: &str,
+    size: u64,
+    mtime: Option<u64>,
+    opts: &ReadOptions,
+) -> Result<FileContent, KodiqError> {
+    use base64::Engine;
 
-        entries.push(serde_json::json!({
-            "name": name,
-            "path": full_path,
-            "isDir": file_type.is_dir(),
-        }));
+    let unversioned = FileVersion { mtime, hash: None };
+    match metadata::kind_from_name(name) {
+        Some(FileKind::Image) => {
+            if size > MAX_IMAGE_BYTES {
+                return Err(KodiqError::Other("Image too large (>10MB)".to_string()));
+            }
+            let bytes = source.read_at(0, size).await?;
+            let content = base64::engine::general_purpose::STANDARD.encode(&bytes);
+            let version = write::version_of(&bytes, mtime);
+            let mut file = FileContent::binary(ContentKind::Image, content, size, version);
+            file.mime = Some(image_mime(name));
+            return Ok(file);
+        }
+        Some(FileKind::Binary) => {
+            return Ok(FileContent::binary(ContentKind::Binary, String::new(), size, unversioned))
+        }
+        _ => {}
     }
 
-    Ok(entries)
+    let sample = source.read_at(0, encoding::SAMPLE_BYTES as u64).await?;
+    let Detected::Text(text_encoding) = encoding::detect(&sample) else {
+        return Ok(FileContent::binary(ContentKind::Binary, String::new(), size, unversioned));
+    };
+
+    let (bytes, range, version) = if let Some(start_line) = opts.start_line {
+        let count = opts.line_count.unwrap_or(DEFAULT_LINE_COUNT);
+        let mut window =
+            LineWindow::new(text_encoding, start_line, count, MAX_CHUNK_BYTES as usize);
+        let mut offset = 0;
+        loop {
+            let block = source.read_at(offset, BLOCK_BYTES).await?;
+            offset += block.len() as u64;
+            if block.is_empty() || window.feed(&block) {
+                break;
+            }
+        }
+        let start = window.start.unwrap_or(size);
+        let range = ContentRange {
+            start,
+            end: window.end(),
+            first_line: Some(start_line),
+            has_more: window.end() < size,
+        };
+        (window.bytes, Some(range), unversioned)
+    } else if opts.offset.is_some() || opts.length.is_some() {
+        let offset = opts.offset.unwrap_or(0);
+        let length = opts.length.unwrap_or(MAX_CHUNK_BYTES).min(MAX_CHUNK_BYTES);
+        let mut bytes = source.read_at(offset, length).await?;
+        let at_eof = offset + bytes.len() as u64 >= size;
+        let (skip, keep) = encoding::char_bounds(&bytes, text_encoding, at_eof);
+        bytes = bytes[skip..skip + keep].to_vec();
+        let start = offset + skip as u64;
+        let end = start + keep as u64;
+        let range = ContentRange { start, end, first_line: None, has_more: end < size };
+        (bytes, Some(range), unversioned)
+    } else {
+        if size > MAX_TEXT_BYTES {
+            return Err(KodiqError::Other(
+                "File too large (>1MB); read it by byte range or line window".to_string(),
+            ));
+        }
+        let bytes = source.read_at(0, size).await?;
+        let version = write::version_of(&bytes, mtime);
+        (bytes, None, version)
+    };
+
+    let at_start = range.as_ref().map_or(true, |r| r.start == 0);
+    let content = encoding::decode(&bytes, text_encoding, at_start);
+    let (line_ending, mixed_line_endings) = encoding::line_ending(&content);
+    Ok(FileContent {
+        kind: ContentKind::Text,
+        content,
+        size,
+        encoding: Some(text_encoding),
+        line_ending,
+        mixed_line_endings,
+        mime: None,
+        range,
+        version,
+    })
 }
 
-/// Read a file's content as string (up to 1MB, for file viewer).
+/// Read a file for the editor or viewer.
 /// If `connection_id` is provided, reads from remote via SFTP.
+/// Text is decoded from its detected encoding (reported back for saving),
+/// images come back as base64 with a mime type, other binaries as a stub.
+/// Files over 1 MB must be read through `options` windows.
 #[tracing::instrument(skip(ssh_state))]
 #[tauri::command(async)]
 pub async fn read_file(
+    webview: tauri::Webview,
     path: String,
     connection_id: Option<String>,
+    options: Option<ReadOptions>,
     ssh_state: tauri::State<'_, SshState>,
-) -> Result<String, KodiqError> {
+) -> Result<FileContent, KodiqError> {
+    sandbox::check(&webview, &path, connection_id.as_deref())?;
+    let opts = options.unwrap_or_default();
+    let name = Path::new(&path).file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+
     // Remote: delegate to SFTP
     if let Some(ref conn_id) = connection_id {
-        return ssh::filesystem::sftp_read_file(&path, &ssh_state, conn_id).await;
+        let (file, size, mtime) = ssh::filesystem::sftp_open(&path, &ssh_state, conn_id).await?;
+        return read_content(Source::Remote(file), &name, size, mtime, &opts).await;
     }
 
-    // Local: original logic
-    let file_path = std::path::Path::new(&path);
+    let file_path = Path::new(&path);
     if !file_path.is_file() {
         return Err(KodiqError::NotFound(format!("Not a file: {}", path)));
     }
-    let metadata = std::fs::metadata(&path)?;
-    if metadata.len() > 1_048_576 {
-        return Err(KodiqError::Other("File too large (>1MB)".to_string()));
+    let file = std::fs::File::open(file_path)?;
+    let metadata = file.metadata()?;
+    let mtime = write::millis(metadata.modified());
+    read_content(Source::Local(file), &name, metadata.len(), mtime, &opts).await
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::encoding::Charset;
+    use super::*;
+
+    fn read(name: &str, bytes: &[u8], opts: ReadOptions) -> FileContent {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, bytes).unwrap();
+        let source = Source::Local(std::fs::File::open(&path).unwrap());
+        let size = bytes.len() as u64;
+        tauri::async_runtime::block_on(read_content(source, name, size, Some(7), &opts)).unwrap()
+    }
+
+    #[test]
+    fn test_image_is_base64_with_mime() {
+        let bytes = b"\x89PNG\r\n\x1a\n\0\0";
+        let file = read("logo.png", bytes, ReadOptions::default());
+        assert_eq!(file.kind, ContentKind::Image);
+        assert_eq!(file.mime, Some("image/png"));
+        assert_eq!(file.content, "iVBORw0KGgoAAA==");
+        assert_eq!(file.version, write::version_of(bytes, Some(7)));
+        assert!(file.encoding.is_none());
+    }
+
+    #[test]
+    fn test_binary_by_extension_and_by_content() {
+        let file = read("app.exe", b"MZ text-looking", ReadOptions::default());
+        assert_eq!(file.kind, ContentKind::Binary);
+        assert!(file.content.is_empty());
+        assert_eq!(file.version.hash, None);
+
+        let file = read("data.txt", b"abc\0\x01\x02def", ReadOptions::default());
+        assert_eq!(file.kind, ContentKind::Binary);
+        assert_eq!(file.size, 9);
+    }
+
+    #[test]
+    fn test_whole_text_is_versioned() {
+        let bytes = b"one\r\ntwo\r\n";
+        let file = read("a.txt", bytes, ReadOptions::default());
+        assert_eq!(file.kind, ContentKind::Text);
+        assert_eq!(file.content, "one\r\ntwo\r\n");
+        assert_eq!(file.encoding, Some(TextEncoding::UTF8));
+        assert_eq!(file.line_ending, Some(LineEnding::Crlf));
+        assert!(file.range.is_none());
+        assert_eq!(file.version, write::version_of(bytes, Some(7)));
+    }
+
+    #[test]
+    fn test_encoding_detection() {
+        let file = read("bom.txt", b"\xEF\xBB\xBFhi", ReadOptions::default());
+        assert_eq!(file.encoding, Some(TextEncoding { charset: Charset::Utf8, bom: true }));
+        assert_eq!(file.content, "hi");
+
+        let file = read("wide.txt", b"\xFF\xFEh\0i\0", ReadOptions::default());
+        assert_eq!(file.encoding, Some(TextEncoding { charset: Charset::Utf16Le, bom: true }));
+        assert_eq!(file.content, "hi");
+
+        let file = read("latin.txt", b"caf\xE9 cr\xE8me", ReadOptions::default());
+        assert_eq!(file.encoding.map(|e| e.charset), Some(Charset::Windows1252));
+        assert_eq!(file.content, "café crème");
+    }
+
+    #[test]
+    fn test_line_window() {
+        let opts = ReadOptions { start_line: Some(1), line_count: Some(2), ..Default::default() };
+        let file = read("a.txt", b"l0\nl1\nl2\nl3\n", opts);
+        assert_eq!(file.content, "l1\nl2\n");
+        let range = file.range.unwrap();
+        assert_eq!((range.start, range.end), (3, 9));
+        assert_eq!(range.first_line, Some(1));
+        assert!(range.has_more);
+        // Partial reads cannot be saved back whole
+        assert_eq!(file.version.hash, None);
+    }
+
+    #[test]
+    fn test_byte_range_snaps_to_characters() {
+        // "aéb" with a window starting inside the two-byte "é"
+        let opts = ReadOptions { offset: Some(2), length: Some(2), ..Default::default() };
+        let file = read("a.txt", "aéb".as_bytes(), opts);
+        assert_eq!(file.content, "b");
+        let range = file.range.unwrap();
+        assert_eq!((range.start, range.end), (3, 4));
+        assert!(!range.has_more);
+        assert_eq!(file.version.hash, None);
     }
-    Ok(std::fs::read_to_string(&path)?)
 }
@@ -0,0 +1,517 @@
+use super::encoding::{self, Detected, TextEncoding};
+use super::grep::{self, SearchOptions};
+use super::local_history::{HistorySource, HistoryState, HistoryStore};
+use super::sandbox;
+use crate::error::KodiqError;
+use crate::ssh::{self, SshState};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+/// How many applied batches are kept for undo.
+const MAX_UNDO_BATCHES: usize = 20;
+
+// ── State ────────────────────────────────────────────────────────────
+
+struct BatchFile {
+    path: String,
+    /// Bytes before the replace, restored as-is by undo.
+    original: Vec<u8>,
+    /// Hash of the bytes we wrote — undo refuses if the file moved on since.
+    replaced_hash: String,
+}
+
+struct ReplaceBatch {
+    id: String,
+    connection_id: Option<String>,
+    files: Vec<BatchFile>,
+}
+
+/// Applied replace batches, newest last.
+pub struct ReplaceHistory {
+    batches: Vec<ReplaceBatch>,
+}
+
+pub type ReplaceState = Arc<Mutex<ReplaceHistory>>;
+
+pub fn new_replace_state() -> ReplaceState {
+    Arc::new(Mutex::new(ReplaceHistory { batches: Vec::new() }))
+}
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineChange {
+    pub line: u64,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePreview {
+    pub path: String,
+    pub relative_path: String,
+    /// Hash of the bytes the preview was computed from; pass back to apply.
+    pub hash: String,
+    pub replacements: usize,
+    pub changes: Vec<LineChange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileSelection {
+    pub path: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceResult {
+    pub batch_id: String,
+    pub files: Vec<String>,
+    pub replacements: usize,
+}
+
+// ── Pure functions ───────────────────────────────────────────────────
+
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Decode a file with the encoding `read_file` would detect. `None` for binaries.
+fn decode(bytes: &[u8]) -> Option<(String, TextEncoding)> {
+    match encoding::detect(&bytes[..bytes.len().min(encoding::SAMPLE_BYTES)]) {
+        Detected::Text(enc) => Some((encoding::decode(bytes, enc, true), enc)),
+        Detected::Binary => None,
+    }
+}
+
+/// Replace matches line by line (so the preview is exactly what gets written).
+/// Line endings are preserved. `$1` / `${name}` expand only in regex mode.
+pub fn replace_lines(
+    content: &str,
+    re: &Regex,
+    replacement: &str,
+    expand: bool,
+) -> (String, Vec<LineChange>, usize) {
+    let mut out = String::with_capacity(content.len());
+    let mut changes = Vec::new();
+    let mut count = 0;
+
+    for (i, segment) in content.split_inclusive('\n').enumerate() {
+        let body = segment.trim_end_matches(['\n', '\r']);
+        let ending = &segment[body.len()..];
+
+        let hits = re.find_iter(body).count();
+        if hits == 0 {
+            out.push_str(segment);
+            continue;
+        }
+        let replaced = if expand {
+            re.replace_all(body, replacement)
+        } else {
+            re.replace_all(body, regex::NoExpand(replacement))
+        };
+        count += hits;
+        changes.push(LineChange {
+            line: i as u64 + 1,
+            before: body.to_string(),
+            after: replaced.to_string(),
+        });
+        out.push_str(&replaced);
+        out.push_str(ending);
+    }
+
+    (out, changes, count)
+}
+
+fn preview_file(
+    path: String,
+    relative_path: String,
+    bytes: &[u8],
+    re: &Regex,
+    replacement: &str,
+    expand: bool,
+) -> Option<FilePreview> {
+    let (content, _) = decode(bytes)?;
+    let (_, changes, replacements) = replace_lines(&content, re, replacement, expand);
+    (replacements > 0).then(|| FilePreview {
+        path,
+        relative_path,
+        hash: content_hash(bytes),
+        replacements,
+        changes,
+    })
+}
+
+// ── IO (local or SFTP) ───────────────────────────────────────────────
+
+/// Where a replace reads and writes. Local writes are snapshotted into the
+/// file's history first, like editor saves.
+enum Io<'a> {
+    Local(&'a HistoryStore),
+    Remote(&'a tauri::State<'a, SshState>, &'a str),
+}
+
+impl<'a> Io<'a> {
+    fn new(
+        connection_id: Option<&'a str>,
+        ssh_state: &'a tauri::State<'a, SshState>,
+        history: &'a HistoryStore,
+    ) -> Self {
+        match connection_id {
+            Some(conn_id) => Io::Remote(ssh_state, conn_id),
+            None => Io::Local(history),
+        }
+    }
+
+    async fn read(&self, path: &str) -> Result<Vec<u8>, KodiqError> {
+        match self {
+            Io::Local(_) => Ok(std::fs::read(path)?),
+            Io::Remote(ssh_state, conn_id) => {
+                use tokio::io::AsyncReadExt;
+                let (mut file, size, _) =
+                    ssh::filesystem::sftp_open(path, ssh_state, conn_id).await?;
+                let mut bytes = Vec::with_capacity(size as usize);
+                file.read_to_end(&mut bytes)
+                    .await
+                    .map_err(|e| KodiqError::Sftp(format!("Read {}: {}", path, e)))?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    async fn write(&self, path: &str, bytes: &[u8]) -> Result<(), KodiqError> {
+        match self {
+            Io::Local(history) => {
+                // History failures never block the write
+                if let Err(e) = history.snapshot_file(Path::new(path), HistorySource::External) {
+                    tracing::warn!("History snapshot failed for {}: {}", path, e);
+                }
+                Ok(super::write::write_atomic(Path::new(path), bytes)?)
+            }
+            Io::Remote(ssh_state, conn_id) => {
+                ssh::filesystem::sftp_write_bytes(path, bytes, ssh_state, conn_id).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Write every `(path, old, new)`; on failure restore the ones already written.
+    async fn write_all(&self, writes: &[(String, Vec<u8>, Vec<u8>)]) -> Result<(), KodiqError> {
+        for (done, (path, _, new)) in writes.iter().enumerate() {
+            if let Err(e) = self.write(path, new).await {
+                for (path, old, _) in &writes[..done] {
+                    let _ = self.write(path, old).await;
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn stale_error(paths: &[String]) -> KodiqError {
+    KodiqError::Conflict(format!("files changed since preview: {}", paths.join(", ")))
+}
+
+// ── Core logic ───────────────────────────────────────────────────────
+
+/// Replace in every selected file, keeping each file's encoding. Nothing is
+/// written if any file changed since its preview. Returns the batch files
+/// and the number of replacements.
+async fn apply(
+    io: &Io<'_>,
+    files: &[FileSelection],
+    re: &Regex,
+    replacement: &str,
+    expand: bool,
+) -> Result<(Vec<BatchFile>, usize), KodiqError> {
+    // Read, verify and encode everything before the first write
+    let mut writes = Vec::new();
+    let mut stale = Vec::new();
+    let mut replacements = 0;
+    for file in files {
+        let current = io.read(&file.path).await?;
+        if content_hash(&current) != file.hash {
+            stale.push(file.path.clone());
+            continue;
+        }
+        let Some((text, enc)) = decode(&current) else { continue };
+        let (new, _, count) = replace_lines(&text, re, replacement, expand);
+        if count > 0 {
+            replacements += count;
+            writes.push((file.path.clone(), current, encoding::encode(&new, enc)?));
+        }
+    }
+    if !stale.is_empty() {
+        return Err(stale_error(&stale));
+    }
+
+    io.write_all(&writes).await?;
+    let files = writes
+        .into_iter()
+        .map(|(path, original, new)| BatchFile {
+            path,
+            original,
+            replaced_hash: content_hash(&new),
+        })
+        .collect();
+    Ok((files, replacements))
+}
+
+/// Put back the original bytes of every file in `batch`.
+async fn restore(io: &Io<'_>, batch: &ReplaceBatch) -> Result<Vec<String>, KodiqError> {
+    let mut writes = Vec::new();
+    let mut stale = Vec::new();
+    for file in &batch.files {
+        let current = io.read(&file.path).await?;
+        if content_hash(&current) != file.replaced_hash {
+            stale.push(file.path.clone());
+        } else {
+            writes.push((file.path.clone(), current, file.original.clone()));
+        }
+    }
+    if !stale.is_empty() {
+        return Err(stale_error(&stale));
+    }
+
+    io.write_all(&writes).await?;
+    Ok(writes.into_iter().map(|(path, _, _)| path).collect())
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// Compute what a replace would change, per file, without writing anything.
+#[tracing::instrument(skip(ssh_state))]
+#[tauri::command(async)]
+pub async fn fs_replace_preview(
+    webview: tauri::Webview,
+    root: String,
+    query: String,
+    replacement: String,
+    options: Option<SearchOptions>,
+    connection_id: Option<String>,
+    ssh_state: tauri::State<'_, SshState>,
+) -> Result<Vec<FilePreview>, KodiqError> {
+    sandbox::check(&webview, &root, connection_id.as_deref())?;
+    let opts = SearchOptions { context_lines: 0, ..options.unwrap_or_default() };
+    let re = grep::build_matcher(&query, &opts)?;
+    let expand = opts.regex;
+
+    let mut files = Vec::new();
+    if let Some(ref conn_id) = connection_id {
+        let command = grep::remote_command(&root, &query, &opts);
+        let output = ssh::git::ssh_run_command(&ssh_state, conn_id, &command).await?;
+        let (found, _) = grep::parse_remote_output(&root, &output, &re, &opts);
+        files.extend(found.into_iter().map(|f| (f.path, f.relative_path)));
+    } else {
+        grep::search_local(Path::new(&root), &re, &opts, &CancellationToken::new(), |f| {
+            files.push((f.path.clone(), f.relative_path.clone()))
+        })?;
+    }
+
+    let mut previews = Vec::new();
+    for (path, relative_path) in files {
+        let bytes = match connection_id {
+            Some(ref conn_id) => Io::Remote(&ssh_state, conn_id).read(&path).await?,
+            None => std::fs::read(&path)?,
+        };
+        previews.extend(preview_file(path, relative_path, &bytes, &re, &replacement, expand));
+    }
+    Ok(previews)
+}
+
+/// Apply a previewed replace to the selected files as one undoable batch.
+/// Nothing is written if any file changed since its preview.
+#[tracing::instrument(skip(ssh_state, replace_state, history))]
+#[allow(clippy::too_many_arguments)]
+#[tauri::command(async)]
+pub async fn fs_replace_apply(
+    webview: tauri::Webview,
+    query: String,
+    replacement: String,
+    options: Option<SearchOptions>,
+    files: Vec<FileSelection>,
+    connection_id: Option<String>,
+    ssh_state: tauri::State<'_, SshState>,
+    replace_state: tauri::State<'_, ReplaceState>,
+    history: tauri::State<'_, HistoryState>,
+) -> Result<ReplaceResult, KodiqError> {
+    for file in &files {
+        sandbox::check(&webview, &file.path, connection_id.as_deref())?;
+    }
+    let opts = options.unwrap_or_default();
+    let re = grep::build_matcher(&query, &opts)?;
+
+    let io = Io::new(connection_id.as_deref(), &ssh_state, &history);
+    let (written, replacements) = apply(&io, &files, &re, &replacement, opts.regex).await?;
+
+    let batch =
+        ReplaceBatch { id: uuid::Uuid::new_v4().to_string(), connection_id, files: written };
+    let result = ReplaceResult {
+        batch_id: batch.id.clone(),
+        files: batch.files.iter().map(|f| f.path.clone()).collect(),
+        replacements,
+    };
+
+    let mut batches = replace_state.lock()?;
+    batches.batches.push(batch);
+    if batches.batches.len() > MAX_UNDO_BATCHES {
+        batches.batches.remove(0);
+    }
+
+    tracing::info!("Replaced {} matches in {} files", replacements, result.files.len());
+    Ok(result)
+}
+
+/// Undo a replace batch (the latest one if `batch_id` is omitted).
+/// Refuses if any file was edited after the replace. Returns restored paths.
+#[tracing::instrument(skip(ssh_state, replace_state, history))]
+#[tauri::command(async)]
+pub async fn fs_replace_undo(
+    webview: tauri::Webview,
+    batch_id: Option<String>,
+    ssh_state: tauri::State<'_, SshState>,
+    replace_state: tauri::State<'_, ReplaceState>,
+    history: tauri::State<'_, HistoryState>,
+) -> Result<Vec<String>, KodiqError> {
+    sandbox::check_webview(&webview)?;
+    let batch = {
+        let mut batches = replace_state.lock()?;
+        let idx = match batch_id {
+            Some(ref id) => batches.batches.iter().position(|b| &b.id == id),
+            None => batches.batches.len().checked_sub(1),
+        }
+        .ok_or_else(|| KodiqError::NotFound("Replace batch".into()))?;
+        batches.batches.remove(idx)
+    };
+    let io = Io::new(batch.connection_id.as_deref(), &ssh_state, &history);
+    match restore(&io, &batch).await {
+        Ok(paths) => Ok(paths),
+        Err(e) => {
+            // Keep the batch so the user can retry after resolving the conflict
+            replace_state.lock()?.batches.push(batch);
+            Err(e)
+        }
+    }
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn re(query: &str, regex: bool) -> Regex {
+        grep::build_matcher(
+            query,
+            &SearchOptions { regex, case_sensitive: true, ..Default::default() },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_replace_regex_with_captures() {
+        let content = "let a = foo(1);\r\nkeep\nlet b = foo(22);";
+        let (out, changes, count) =
+            replace_lines(content, &re(r"foo\((\d+)\)", true), "bar($1, ${1})", true);
+
+        assert_eq!(out, "let a = bar(1, 1);\r\nkeep\nlet b = bar(22, 22);");
+        assert_eq!(count, 2);
+        assert_eq!(
+            changes,
+            vec![
+                LineChange {
+                    line: 1,
+                    before: "let a = foo(1);".into(),
+                    after: "let a = bar(1, 1);".into()
+                },
+                LineChange {
+                    line: 3,
+                    before: "let b = foo(22);".into(),
+                    after: "let b = bar(22, 22);".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_literal_replacement_does_not_expand() {
+        let (out, _, count) = replace_lines("price: $5, $5\n", &re("$5", false), "$1", false);
+        assert_eq!(out, "price: $1, $1\n");
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_preview_hash_tracks_content() {
+        let p = preview_file("/a".into(), "a".into(), b"x\n", &re("x", false), "y", false).unwrap();
+        assert_eq!(p.hash, content_hash(b"x\n"));
+        assert_ne!(p.hash, content_hash(b"x"));
+        assert!(preview_file("/a".into(), "a".into(), b"z", &re("x", false), "y", false).is_none());
+        // Binaries are never previewed, even when they match
+        assert!(preview_file("/a".into(), "a".into(), b"x\0\x01", &re("x", false), "y", false)
+            .is_none());
+    }
+
+    fn selection(path: &Path) -> FileSelection {
+        let bytes = std::fs::read(path).unwrap();
+        FileSelection { path: path.to_string_lossy().to_string(), hash: content_hash(&bytes) }
+    }
+
+    #[test]
+    fn test_apply_then_undo_restores_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = HistoryStore::at(dir.path().join("history"));
+        let io = Io::Local(&history);
+        // Latin-1 with CRLF, and UTF-8 with a BOM: both must survive byte-for-byte
+        let latin = dir.path().join("latin.txt");
+        let original_latin = b"caf\xe9 foo\r\nfoo\r\n".to_vec();
+        std::fs::write(&latin, &original_latin).unwrap();
+        let bom = dir.path().join("bom.txt");
+        let original_bom = b"\xef\xbb\xbfna\xc3\xafve foo\n".to_vec();
+        std::fs::write(&bom, &original_bom).unwrap();
+
+        let files = [selection(&latin), selection(&bom)];
+        let (written, count) =
+            tauri::async_runtime::block_on(apply(&io, &files, &re("foo", false), "bar", false))
+                .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(std::fs::read(&latin).unwrap(), b"caf\xe9 bar\r\nbar\r\n");
+        assert_eq!(std::fs::read(&bom).unwrap(), b"\xef\xbb\xbfna\xc3\xafve bar\n");
+        // The pre-replace contents went into local history
+        assert_eq!(history.timeline(&latin).unwrap().len(), 1);
+
+        let batch = ReplaceBatch { id: "b".into(), connection_id: None, files: written };
+        let restored = tauri::async_runtime::block_on(restore(&io, &batch)).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(std::fs::read(&latin).unwrap(), original_latin);
+        assert_eq!(std::fs::read(&bom).unwrap(), original_bom);
+    }
+
+    #[test]
+    fn test_apply_refuses_files_changed_after_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = HistoryStore::at(dir.path().join("history"));
+        let io = Io::Local(&history);
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        std::fs::write(&a, "foo\n").unwrap();
+        std::fs::write(&b, "foo\n").unwrap();
+        let files = [selection(&a), selection(&b)];
+
+        std::fs::write(&b, "foo edited\n").unwrap();
+        let result =
+            tauri::async_runtime::block_on(apply(&io, &files, &re("foo", false), "bar", false));
+        match result {
+            Err(KodiqError::Conflict(message)) => assert!(message.contains("b.txt")),
+            other => panic!("expected conflict, got {:?}", other.map(|(_, n)| n)),
+        }
+        // Nothing was written, not even the unchanged file
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "foo\n");
+        assert_eq!(std::fs::read_to_string(&b).unwrap(), "foo edited\n");
+    }
+}
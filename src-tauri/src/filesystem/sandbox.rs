@@ -0,0 +1,212 @@
+use super::ops::normalize;
+use crate::error::KodiqError;
+use crate::state::DbState;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
+
+/// The only webview allowed to use filesystem commands. Preview and academy
+/// pages are child webviews of the same window and run third-party JS.
+pub const MAIN_WEBVIEW: &str = "main";
+
+// ── State ────────────────────────────────────────────────────────────
+
+/// Paths the user picked in a native dialog, on top of the registered
+/// project roots. Kept for the session only.
+pub struct Sandbox {
+    granted: Mutex<Vec<PathBuf>>,
+}
+
+pub type SandboxState = Arc<Sandbox>;
+
+pub fn new_sandbox_state() -> SandboxState {
+    Arc::new(Sandbox { granted: Mutex::new(Vec::new()) })
+}
+
+impl Sandbox {
+    /// Registered project roots plus granted paths.
+    fn roots(&self, db: &DbState) -> Result<Vec<PathBuf>, KodiqError> {
+        let mut roots = project_roots(&*db.connection.lock()?)?;
+        roots.extend(self.granted.lock()?.iter().cloned());
+        Ok(roots)
+    }
+
+    fn grant(&self, path: &Path) -> Result<(), KodiqError> {
+        let resolved = resolve(path);
+        let mut granted = self.granted.lock()?;
+        if !granted.contains(&resolved) {
+            granted.push(resolved);
+        }
+        Ok(())
+    }
+}
+
+fn project_roots(conn: &rusqlite::Connection) -> Result<Vec<PathBuf>, KodiqError> {
+    let mut stmt = conn.prepare("SELECT path FROM projects")?;
+    let rows = stmt.query_map([], |r| r.get::<_, String>(0))?;
+    Ok(rows.filter_map(|r| r.ok()).map(PathBuf::from).collect())
+}
+
+/// The registered project containing `path` (the deepest one, for nested
+/// projects).
+pub(crate) fn project_root_for(
+    conn: &rusqlite::Connection,
+    path: &Path,
+) -> Result<Option<PathBuf>, KodiqError> {
+    let resolved = resolve(path);
+    Ok(project_roots(conn)?
+        .into_iter()
+        .filter(|r| resolved.starts_with(resolve(r)))
+        .max_by_key(|r| r.as_os_str().len()))
+}
+
+// ── Pure functions ───────────────────────────────────────────────────
+
+/// Absolute, `..`-free and symlink-free form of `path`. Trailing parts that
+/// don't exist yet (a file about to be created) are appended to the nearest
+/// existing ancestor, after that ancestor's symlinks are resolved.
+pub fn resolve(path: &Path) -> PathBuf {
+    let lexical = normalize(path);
+    let mut existing = lexical.as_path();
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(real) = std::fs::canonicalize(existing) {
+            return missing.iter().rev().fold(real, |acc, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return lexical,
+        }
+    }
+}
+
+/// Whether `path` resolves inside one of `roots` (resolved the same way).
+pub fn is_allowed(path: &Path, roots: &[PathBuf]) -> bool {
+    if !path.is_absolute() {
+        return false;
+    }
+    let resolved = resolve(path);
+    roots.iter().filter(|r| r.is_absolute()).any(|r| resolved.starts_with(resolve(r)))
+}
+
+// ── Guards ───────────────────────────────────────────────────────────
+
+/// Refuse calls from any webview but the main one.
+pub fn check_webview(webview: &tauri::Webview) -> Result<(), KodiqError> {
+    match webview.label() {
+        MAIN_WEBVIEW => Ok(()),
+        label => Err(KodiqError::Forbidden(label.to_string())),
+    }
+}
+
+/// Gate for filesystem commands: the caller must be the main webview and a
+/// local `path` must resolve inside a project root or granted path. Remote
+/// paths are scoped by the SSH account instead.
+pub fn check(
+    webview: &tauri::Webview,
+    path: &str,
+    connection_id: Option<&str>,
+) -> Result<(), KodiqError> {
+    check_webview(webview)?;
+    if connection_id.is_some() {
+        return Ok(());
+    }
+    let roots = webview.state::<SandboxState>().roots(&webview.state::<DbState>())?;
+    if is_allowed(Path::new(path), &roots) {
+        Ok(())
+    } else {
+        Err(KodiqError::OutsideRoot(path.to_string()))
+    }
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// Let the user pick a file or folder in a native dialog and allow
+/// filesystem commands under it for this session. The path never comes from
+/// the webview, so a page cannot widen its own access. Returns the picked
+/// path, or `None` when the dialog was cancelled.
+#[tauri::command(async)]
+pub fn fs_grant_path(
+    webview: tauri::Webview,
+    directory: bool,
+    title: Option<String>,
+    sandbox: tauri::State<'_, SandboxState>,
+) -> Result<Option<String>, KodiqError> {
+    check_webview(&webview)?;
+    let mut dialog = webview.dialog().file();
+    if let Some(title) = title {
+        dialog = dialog.set_title(title);
+    }
+    let picked = match directory {
+        true => dialog.blocking_pick_folder(),
+        false => dialog.blocking_pick_file(),
+    };
+    let Some(path) = picked.and_then(|p| p.into_path().ok()) else {
+        return Ok(None);
+    };
+    sandbox.grant(&path)?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+#[tauri::command]
+pub fn fs_revoke_path(
+    webview: tauri::Webview,
+    path: String,
+    sandbox: tauri::State<'_, SandboxState>,
+) -> Result<(), KodiqError> {
+    check_webview(&webview)?;
+    let resolved = resolve(Path::new(&path));
+    sandbox.granted.lock()?.retain(|p| p != &resolved);
+    Ok(())
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_roots_from_db() {
+        let db = crate::db::init_test();
+        let conn = db.connection.lock().unwrap();
+        conn.execute(
+            "INSERT INTO projects (id, name, path, created_at, last_opened)
+             VALUES ('p1', 'app', '/work/app', 0, 0)",
+            [],
+        )
+        .unwrap();
+        assert_eq!(project_roots(&conn).unwrap(), vec![PathBuf::from("/work/app")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_allowed_resolves_dots_and_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        let secret = dir.path().join("secret");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::create_dir_all(&secret).unwrap();
+        std::fs::write(secret.join("key"), "x").unwrap();
+        std::os::unix::fs::symlink(&secret, project.join("escape")).unwrap();
+        let roots = vec![project.clone()];
+
+        assert!(is_allowed(&project.join("src"), &roots));
+        // Not created yet
+        assert!(is_allowed(&project.join("src/new/file.rs"), &roots));
+        assert!(!is_allowed(&project.join("src/../../secret/key"), &roots));
+        assert!(!is_allowed(&project.join("escape/key"), &roots));
+        assert!(!is_allowed(&project.join("escape/new.txt"), &roots));
+        assert!(!is_allowed(Path::new("src/main.rs"), &roots));
+        assert!(!is_allowed(&secret.join("key"), &roots));
+
+        // A root reached through a symlink still matches its real paths
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&project, &link).unwrap();
+        assert!(is_allowed(&project.join("src"), &[link]));
+    }
+}
@@ -0,0 +1,504 @@
+use super::sandbox;
+use crate::error::KodiqError;
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config, Matcher, Utf32Str};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+
+const DEFAULT_LIMIT: usize = 50;
+
+// ── State ────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct RootFiles {
+    files: BTreeSet<String>,
+    ready: bool,
+    generation: u64,
+    /// Watcher paths that arrived while the initial walk was running.
+    pending: Vec<PathBuf>,
+}
+
+/// Project files (relative paths) per watched root, respecting ignore files.
+#[derive(Default)]
+pub struct FileIndex {
+    roots: HashMap<PathBuf, RootFiles>,
+    /// Bumped on every rebuild so a stale background walk never wins.
+    generation: u64,
+}
+
+pub type FileIndexState = Arc<Mutex<FileIndex>>;
+
+pub fn new_file_index_state() -> FileIndexState {
+    Arc::new(Mutex::new(FileIndex::default()))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMatch {
+    pub path: String,
+    pub relative_path: String,
+    pub score: u32,
+    /// Char indices into `relative_path` to highlight.
+    pub positions: Vec<u32>,
+}
+
+// ── Walking ──────────────────────────────────────────────────────────
+
+/// Walker honouring `.gitignore`, `.ignore`, `.git/info/exclude` and the global
+/// excludes file. Dotfiles are included; the `.git` directory never is.
+fn walker(path: &Path) -> ignore::WalkBuilder {
+    project_walker(path, true)
+}
+
+/// Project walker shared by the file index and content search.
+/// `respect_ignores = false` still skips `.git` but ignores no other files.
+pub(super) fn project_walker(path: &Path, respect_ignores: bool) -> ignore::WalkBuilder {
+    let mut builder = ignore::WalkBuilder::new(path);
+    builder
+        .hidden(false)
+        .ignore(respect_ignores)
+        .git_ignore(respect_ignores)
+        .git_global(respect_ignores)
+        .git_exclude(respect_ignores)
+        .parents(respect_ignores)
+        .require_git(false)
+        .filter_entry(|e| e.file_name() != ".git");
+    builder
+}
+
+fn is_file(entry: &ignore::DirEntry) -> bool {
+    entry.file_type().is_some_and(|t| t.is_file() || (t.is_symlink() && entry.path().is_file()))
+}
+
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    path.strip_prefix(root).ok().map(|p| p.to_string_lossy().to_string())
+}
+
+/// All indexable files under `dir`, relative to `root`. Walks in parallel.
+fn walk_files(root: &Path, dir: &Path) -> Vec<String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    walker(dir).build_parallel().run(|| {
+        let tx = tx.clone();
+        Box::new(move |result| {
+            if let Ok(entry) = result {
+                if is_file(&entry) {
+                    if let Some(rel) = relative(root, entry.path()) {
+                        let _ = tx.send(rel);
+                    }
+                }
+            }
+            ignore::WalkState::Continue
+        })
+    });
+    drop(tx);
+    rx.into_iter().collect()
+}
+
+/// Answers "would a full walk include this path?" for single watcher paths,
+/// caching directory listings for the duration of one event batch.
+struct Visibility<'a> {
+    root: &'a Path,
+    listings: HashMap<PathBuf, HashSet<PathBuf>>,
+    dirs: HashMap<PathBuf, bool>,
+}
+
+impl<'a> Visibility<'a> {
+    fn new(root: &'a Path) -> Self {
+        Self { root, listings: HashMap::new(), dirs: HashMap::new() }
+    }
+
+    fn visible(&mut self, path: &Path) -> bool {
+        if path == self.root {
+            return true;
+        }
+        let Some(parent) = path.parent().filter(|p| p.starts_with(self.root)) else {
+            return false;
+        };
+        if !self.dir_visible(parent) {
+            return false;
+        }
+        self.listings
+            .entry(parent.to_path_buf())
+            .or_insert_with(|| {
+                walker(parent)
+                    .max_depth(Some(1))
+                    .build()
+                    .flatten()
+                    .filter(|e| e.depth() == 1)
+                    .map(|e| e.into_path())
+                    .collect()
+            })
+            .contains(path)
+    }
+
+    fn dir_visible(&mut self, dir: &Path) -> bool {
+        if let Some(&v) = self.dirs.get(dir) {
+            return v;
+        }
+        let v = self.visible(dir);
+        self.dirs.insert(dir.to_path_buf(), v);
+        v
+    }
+}
+
+fn is_ignore_file(path: &Path) -> bool {
+    path.file_name().is_some_and(|n| n == ".gitignore" || n == ".ignore")
+}
+
+// ── Core logic ───────────────────────────────────────────────────────
+
+impl FileIndex {
+    /// Forget `root`'s files and start a new walk. Returns its generation.
+    fn reset(&mut self, root: &Path) -> u64 {
+        self.generation += 1;
+        let generation = self.generation;
+        self.roots.insert(root.to_path_buf(), RootFiles { generation, ..Default::default() });
+        generation
+    }
+
+    /// Install the result of a full walk, then replay events seen meanwhile.
+    /// Returns whether an ignore file changed during the walk and the root
+    /// needs a rebuild, or None if the walk was superseded or the root closed.
+    fn install(&mut self, root: &Path, generation: u64, files: Vec<String>) -> Option<bool> {
+        let entry = self.roots.get_mut(root).filter(|r| r.generation == generation)?;
+        entry.files = files.into_iter().collect();
+        entry.ready = true;
+        let pending = std::mem::take(&mut entry.pending);
+        Some(entry.apply_changes(root, &pending))
+    }
+
+    fn forget(&mut self, root: Option<&Path>) {
+        match root {
+            Some(root) => {
+                self.roots.remove(root);
+            }
+            None => self.roots.clear(),
+        }
+    }
+
+    /// Reconcile each root with the watcher paths under it.
+    /// Returns the roots whose ignore files changed and need a full rebuild.
+    fn apply_changes(&mut self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        let mut rebuild = Vec::new();
+        for (root, entry) in self.roots.iter_mut() {
+            let under: Vec<PathBuf> =
+                paths.iter().filter(|p| p.starts_with(root)).cloned().collect();
+            if !under.is_empty() && entry.apply_changes(root, &under) {
+                rebuild.push(root.clone());
+            }
+        }
+        rebuild
+    }
+
+    fn find(&self, root: &Path, query: &str, limit: usize) -> Vec<FileMatch> {
+        self.roots.get(root).map_or_else(Vec::new, |entry| entry.find(root, query, limit))
+    }
+}
+
+impl RootFiles {
+    fn dir_prefix(rel: &str) -> String {
+        format!("{}{}", rel, std::path::MAIN_SEPARATOR)
+    }
+
+    fn has_files_under(&self, rel: &str) -> bool {
+        let prefix = Self::dir_prefix(rel);
+        self.files.range(prefix.clone()..).next().is_some_and(|p| p.starts_with(&prefix))
+    }
+
+    fn remove_under(&mut self, rel: &str) {
+        let prefix = Self::dir_prefix(rel);
+        let doomed: Vec<String> = self
+            .files
+            .range(prefix.clone()..)
+            .take_while(|p| p.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for p in doomed {
+            self.files.remove(&p);
+        }
+    }
+
+    /// Reconcile the index with paths reported by the watcher.
+    /// Returns true when an ignore file changed and a full rebuild is needed.
+    fn apply_changes(&mut self, root: &Path, paths: &[PathBuf]) -> bool {
+        if !self.ready {
+            self.pending.extend(paths.iter().cloned());
+            return false;
+        }
+        if paths.iter().any(|p| is_ignore_file(p)) {
+            return true;
+        }
+
+        let mut vis = Visibility::new(root);
+        for path in paths {
+            let Some(rel) = relative(root, path).filter(|r| !r.is_empty()) else { continue };
+            if path.is_dir() {
+                // Files inside a known dir get their own events; only walk new dirs
+                if !self.has_files_under(&rel) && vis.visible(path) {
+                    self.files.extend(walk_files(root, path));
+                }
+            } else if path.is_file() && vis.visible(path) {
+                self.files.insert(rel);
+            } else {
+                // Deleted (or now ignored) file, or a removed directory
+                self.files.remove(&rel);
+                self.remove_under(&rel);
+            }
+        }
+        false
+    }
+
+    /// Fuzzy-rank indexed paths against `query`. Empty query lists paths in order.
+    fn find(&self, root: &Path, query: &str, limit: usize) -> Vec<FileMatch> {
+        let to_match = |rel: &String, score: u32, positions: Vec<u32>| FileMatch {
+            path: root.join(rel).to_string_lossy().to_string(),
+            relative_path: rel.clone(),
+            score,
+            positions,
+        };
+
+        if query.trim().is_empty() {
+            return self.files.iter().take(limit).map(|p| to_match(p, 0, Vec::new())).collect();
+        }
+
+        let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+        let mut matcher = Matcher::new(Config::DEFAULT.match_paths());
+        let mut buf = Vec::new();
+
+        let mut scored: Vec<(u32, &String)> = self
+            .files
+            .iter()
+            .filter_map(|p| pattern.score(Utf32Str::new(p, &mut buf), &mut matcher).map(|s| (s, p)))
+            .collect();
+        // Best score first; shorter paths win ties
+        scored.sort_unstable_by(|a, b| {
+            b.0.cmp(&a.0).then(a.1.len().cmp(&b.1.len())).then_with(|| a.1.cmp(b.1))
+        });
+        scored.truncate(limit);
+
+        scored
+            .into_iter()
+            .map(|(score, path)| {
+                let mut positions = Vec::new();
+                pattern.indices(Utf32Str::new(path, &mut buf), &mut matcher, &mut positions);
+                positions.sort_unstable();
+                positions.dedup();
+                to_match(path, score, positions)
+            })
+            .collect()
+    }
+}
+
+/// (Re)build the index for `root` on a background thread.
+/// Emits `file-index-ready` with the file count when done.
+pub fn spawn_build(app: &tauri::AppHandle, root: &Path) {
+    let state = app.state::<FileIndexState>().inner().clone();
+    let Ok(generation) = state.lock().map(|mut idx| idx.reset(root)) else { return };
+
+    let app = app.clone();
+    let root = root.to_path_buf();
+    std::thread::spawn(move || {
+        let started = std::time::Instant::now();
+        let files = walk_files(&root, &root);
+
+        let count = files.len();
+        match state.lock().ok().and_then(|mut idx| idx.install(&root, generation, files)) {
+            None => return, // superseded by a rebuild or the root was closed
+            Some(true) => return spawn_build(&app, &root), // ignore rules changed mid-walk
+            Some(false) => {}
+        }
+
+        tracing::info!(
+            "File index built for {}: {} files in {:?}",
+            root.display(),
+            count,
+            started.elapsed()
+        );
+        let _ = app.emit(
+            "file-index-ready",
+            serde_json::json!({ "root": root.to_string_lossy(), "count": count }),
+        );
+    });
+}
+
+/// Feed watcher paths into the index (called from the debouncer thread).
+pub fn on_fs_events(app: &tauri::AppHandle, paths: &[PathBuf]) {
+    let state = app.state::<FileIndexState>();
+    let rebuild = state.lock().map(|mut idx| idx.apply_changes(paths)).unwrap_or_default();
+    for root in rebuild {
+        spawn_build(app, &root);
+    }
+}
+
+/// Drop a root's files (or every root's) when it's no longer watched.
+pub fn forget(state: &FileIndexState, root: Option<&str>) -> Result<(), KodiqError> {
+    state.lock()?.forget(root.map(Path::new));
+    Ok(())
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// Quick-open: fuzzy-find files in the watched project at `root`.
+#[tauri::command]
+pub fn fs_find_files(
+    webview: tauri::Webview,
+    index: tauri::State<FileIndexState>,
+    root: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<FileMatch>, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    let idx = index.lock()?;
+    Ok(idx.find(Path::new(&root), &query, limit.unwrap_or(DEFAULT_LIMIT)))
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/components")).unwrap();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(root.join(".github")).unwrap();
+        fs::write(root.join(".gitignore"), "node_modules/\n*.log\n").unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        fs::write(root.join("src/components/Button.tsx"), "").unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "").unwrap();
+        fs::write(root.join("debug.log"), "").unwrap();
+        fs::write(root.join(".github/ci.yml"), "").unwrap();
+        dir
+    }
+
+    fn built(root: &Path) -> FileIndex {
+        let mut idx = FileIndex::default();
+        let generation = idx.reset(root);
+        assert_eq!(idx.install(root, generation, walk_files(root, root)), Some(false));
+        idx
+    }
+
+    fn files<'a>(idx: &'a FileIndex, root: &Path) -> &'a BTreeSet<String> {
+        &idx.roots[root].files
+    }
+
+    fn sep(p: &str) -> String {
+        p.replace('/', std::path::MAIN_SEPARATOR_STR)
+    }
+
+    #[test]
+    fn test_walk_respects_gitignore() {
+        let dir = project();
+        let idx = built(dir.path());
+        let files: Vec<&str> = files(&idx, dir.path()).iter().map(|s| s.as_str()).collect();
+        assert_eq!(
+            files,
+            vec![
+                sep(".github/ci.yml").as_str(),
+                ".gitignore",
+                sep("src/components/Button.tsx").as_str(),
+                sep("src/main.rs").as_str()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_ranks_and_highlights() {
+        let dir = project();
+        let root = dir.path();
+        let idx = built(root);
+
+        let hits = idx.find(root, "btn", 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].relative_path, sep("src/components/Button.tsx"));
+        let chars: Vec<char> = hits[0].relative_path.chars().collect();
+        let highlighted: String = hits[0].positions.iter().map(|&i| chars[i as usize]).collect();
+        assert_eq!(highlighted.to_lowercase(), "btn");
+
+        assert!(idx.find(root, "zzz", 10).is_empty());
+        assert_eq!(idx.find(root, "", 2).len(), 2);
+        assert!(idx.find(Path::new("/elsewhere"), "", 10).is_empty());
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        let dir = project();
+        let root = dir.path();
+        let mut idx = built(root);
+
+        // New file, ignored file, new directory
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        fs::write(root.join("trace.log"), "").unwrap();
+        fs::create_dir_all(root.join("docs/guide")).unwrap();
+        fs::write(root.join("docs/guide/intro.md"), "").unwrap();
+        let rebuild = idx.apply_changes(&[
+            root.join("src/lib.rs"),
+            root.join("trace.log"),
+            root.join("docs"),
+            root.join("node_modules/pkg/new.js"),
+        ]);
+        assert!(rebuild.is_empty());
+        let indexed = files(&idx, root);
+        assert!(indexed.contains(&sep("src/lib.rs")));
+        assert!(indexed.contains(&sep("docs/guide/intro.md")));
+        assert!(!indexed.contains("trace.log"));
+        assert!(!indexed.iter().any(|f| f.starts_with("node_modules")));
+
+        // Removed directory drops everything below it
+        fs::remove_dir_all(root.join("src/components")).unwrap();
+        idx.apply_changes(&[root.join("src/components")]);
+        assert!(!files(&idx, root).iter().any(|f| f.contains("Button")));
+
+        // Ignore rules changed → caller must rebuild
+        assert_eq!(idx.apply_changes(&[root.join(".gitignore")]), vec![root.to_path_buf()]);
+    }
+
+    #[test]
+    fn test_events_during_build_are_replayed() {
+        let dir = project();
+        let root = dir.path();
+        let mut idx = FileIndex::default();
+        let generation = idx.reset(root);
+        let walked = walk_files(root, root);
+
+        fs::write(root.join("late.txt"), "").unwrap();
+        idx.apply_changes(&[root.join("late.txt")]);
+        assert!(files(&idx, root).is_empty());
+
+        assert_eq!(idx.install(root, generation, walked.clone()), Some(false));
+        assert!(files(&idx, root).contains("late.txt"));
+
+        // An ignore file changing mid-walk asks for a rebuild
+        let generation = idx.reset(root);
+        idx.apply_changes(&[root.join(".gitignore")]);
+        assert_eq!(idx.install(root, generation, walked), Some(true));
+    }
+
+    #[test]
+    fn test_roots_are_independent() {
+        let (a, b) = (project(), project());
+        let mut idx = built(a.path());
+        let stale = idx.reset(b.path());
+        let current = idx.reset(b.path());
+        assert_eq!(idx.install(b.path(), stale, Vec::new()), None); // superseded
+        let walked = walk_files(b.path(), b.path());
+        assert_eq!(idx.install(b.path(), current, walked), Some(false));
+
+        fs::write(a.path().join("only_a.rs"), "").unwrap();
+        idx.apply_changes(&[a.path().join("only_a.rs")]);
+        assert_eq!(idx.find(a.path(), "only_a", 10).len(), 1);
+        assert!(idx.find(b.path(), "only_a", 10).is_empty());
+
+        idx.forget(Some(a.path()));
+        assert!(idx.find(a.path(), "", 10).is_empty());
+        assert_eq!(idx.find(b.path(), "", 10).len(), 4);
+        idx.forget(None);
+        assert!(idx.roots.is_empty());
+    }
+}
@@ -0,0 +1,768 @@
+use super::{sandbox, search};
+use crate::error::KodiqError;
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config, Matcher, Utf32Str};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+use tree_sitter::{Language, Node, Parser};
+
+const DEFAULT_LIMIT: usize = 100;
+/// Larger files are usually generated or minified; skip them.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Class,
+    Interface,
+    Struct,
+    Enum,
+    Trait,
+    Impl,
+    Module,
+    Type,
+    Constant,
+}
+
+/// Zero-based line and column; the column counts chars, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SymbolRange {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A declaration in one file, nested under its enclosing class, impl, etc.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The whole declaration.
+    pub range: SymbolRange,
+    /// Just the name, for placing the cursor.
+    pub selection_range: SymbolRange,
+    /// Enclosing symbol (or a Go method's receiver type).
+    pub container: Option<String>,
+    pub children: Vec<DocumentSymbol>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub path: String,
+    pub relative_path: String,
+    pub range: SymbolRange,
+    pub selection_range: SymbolRange,
+    pub container: Option<String>,
+    pub score: u32,
+    /// Char indices into `name` to highlight.
+    pub positions: Vec<u32>,
+}
+
+// ── Parsing ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lang {
+    TypeScript,
+    Tsx,
+    JavaScript,
+    Rust,
+    Python,
+    Go,
+}
+
+impl Lang {
+    fn from_path(path: &Path) -> Option<Self> {
+        Some(match path.extension()?.to_str()? {
+            "ts" | "mts" | "cts" => Lang::TypeScript,
+            "tsx" => Lang::Tsx,
+            "js" | "jsx" | "mjs" | "cjs" => Lang::JavaScript,
+            "rs" => Lang::Rust,
+            "py" | "pyi" => Lang::Python,
+            "go" => Lang::Go,
+            _ => return None,
+        })
+    }
+
+    fn grammar(self) -> Language {
+        match self {
+            Lang::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Lang::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Lang::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Lang::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Lang::Python => tree_sitter_python::LANGUAGE.into(),
+            Lang::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+}
+
+/// Whether `path` has a language the indexer understands.
+pub fn is_supported(path: &Path) -> bool {
+    Lang::from_path(path).is_some()
+}
+
+/// What a node declares, if anything.
+struct Decl<'a> {
+    kind: SymbolKind,
+    name: String,
+    name_node: Node<'a>,
+    container: Option<String>,
+}
+
+fn text<'a>(node: Node, src: &'a [u8]) -> &'a str {
+    node.utf8_text(src).unwrap_or_default()
+}
+
+fn position(src: &[u8], byte: usize, point: tree_sitter::Point) -> Position {
+    let line_start = byte.saturating_sub(point.column);
+    let column =
+        std::str::from_utf8(&src[line_start..byte]).map_or(point.column, |s| s.chars().count());
+    Position { line: point.row as u32, column: column as u32 }
+}
+
+fn range(node: Node, src: &[u8]) -> SymbolRange {
+    SymbolRange {
+        start: position(src, node.start_byte(), node.start_position()),
+        end: position(src, node.end_byte(), node.end_position()),
+    }
+}
+
+fn is_function_value(node: Option<Node>) -> bool {
+    node.is_some_and(|v| {
+        matches!(
+            v.kind(),
+            "arrow_function" | "function_expression" | "function" | "generator_function"
+        )
+    })
+}
+
+fn classify<'a>(
+    lang: Lang,
+    node: Node<'a>,
+    src: &[u8],
+    parent: Option<&(SymbolKind, String)>,
+) -> Option<Decl<'a>> {
+    let in_type = parent.is_some_and(|(kind, _)| {
+        matches!(
+            kind,
+            SymbolKind::Class | SymbolKind::Interface | SymbolKind::Impl | SymbolKind::Trait
+        )
+    });
+    let function = if in_type { SymbolKind::Method } else { SymbolKind::Function };
+    let container = parent.map(|(_, name)| name.clone());
+    let named = |kind: SymbolKind, field: &str| {
+        let name_node = node.child_by_field_name(field)?;
+        Some(Decl {
+            kind,
+            name: text(name_node, src).to_string(),
+            name_node,
+            container: container.clone(),
+        })
+    };
+
+    match (lang, node.kind()) {
+        (Lang::Rust, "function_item" | "function_signature_item") => named(function, "name"),
+        (Lang::Rust, "struct_item" | "union_item") => named(SymbolKind::Struct, "name"),
+        (Lang::Rust, "enum_item") => named(SymbolKind::Enum, "name"),
+        (Lang::Rust, "trait_item") => named(SymbolKind::Trait, "name"),
+        (Lang::Rust, "mod_item") => named(SymbolKind::Module, "name"),
+        (Lang::Rust, "type_item") => named(SymbolKind::Type, "name"),
+        (Lang::Rust, "const_item" | "static_item") => named(SymbolKind::Constant, "name"),
+        (Lang::Rust, "impl_item") => {
+            let ty = node.child_by_field_name("type")?;
+            let name = match node.child_by_field_name("trait") {
+                Some(tr) => format!("impl {} for {}", text(tr, src), text(ty, src)),
+                None => format!("impl {}", text(ty, src)),
+            };
+            Some(Decl { kind: SymbolKind::Impl, name, name_node: ty, container })
+        }
+
+        (Lang::Python, "function_definition") => named(function, "name"),
+        (Lang::Python, "class_definition") => named(SymbolKind::Class, "name"),
+
+        (Lang::Go, "function_declaration") => named(SymbolKind::Function, "name"),
+        (Lang::Go, "method_declaration") => {
+            let mut decl = named(SymbolKind::Method, "name")?;
+            let receiver = node.child_by_field_name("receiver")?;
+            let mut cursor = receiver.walk();
+            decl.container = receiver
+                .named_children(&mut cursor)
+                .find_map(|p| p.child_by_field_name("type"))
+                .map(|t| text(t, src).trim_start_matches('*').to_string());
+            Some(decl)
+        }
+        (Lang::Go, "type_spec" | "type_alias") => {
+            let kind = match node.child_by_field_name("type").map(|t| t.kind()) {
+                Some("struct_type") => SymbolKind::Struct,
+                Some("interface_type") => SymbolKind::Interface,
+                _ => SymbolKind::Type,
+            };
+            named(kind, "name")
+        }
+
+        (_, "function_declaration" | "generator_function_declaration" | "function_signature") => {
+            named(SymbolKind::Function, "name")
+        }
+        (_, "class_declaration" | "abstract_class_declaration" | "class") => {
+            named(SymbolKind::Class, "name")
+        }
+        (_, "method_definition" | "method_signature" | "abstract_method_signature") => {
+            named(SymbolKind::Method, "name")
+        }
+        (_, "interface_declaration") => named(SymbolKind::Interface, "name"),
+        (_, "type_alias_declaration") => named(SymbolKind::Type, "name"),
+        (_, "enum_declaration") => named(SymbolKind::Enum, "name"),
+        (_, "internal_module" | "module") => named(SymbolKind::Module, "name"),
+        // const handler = () => {}
+        (_, "variable_declarator") if is_function_value(node.child_by_field_name("value")) => {
+            named(SymbolKind::Function, "name")
+        }
+        // class fields holding functions: `onClick = () => {}`
+        (_, "public_field_definition") if is_function_value(node.child_by_field_name("value")) => {
+            named(SymbolKind::Method, "name")
+        }
+        (_, "field_definition") if is_function_value(node.child_by_field_name("value")) => {
+            named(SymbolKind::Method, "property")
+        }
+        _ => None,
+    }
+}
+
+fn collect(
+    lang: Lang,
+    node: Node,
+    src: &[u8],
+    parent: Option<&(SymbolKind, String)>,
+    out: &mut Vec<DocumentSymbol>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match classify(lang, child, src, parent) {
+            Some(decl) if !decl.name.is_empty() => {
+                let mut symbol = DocumentSymbol {
+                    name: decl.name,
+                    kind: decl.kind,
+                    range: range(child, src),
+                    selection_range: range(decl.name_node, src),
+                    container: decl.container,
+                    children: Vec::new(),
+                };
+                let scope = (symbol.kind, symbol.name.clone());
+                collect(lang, child, src, Some(&scope), &mut symbol.children);
+                out.push(symbol);
+            }
+            _ => collect(lang, child, src, parent, out),
+        }
+    }
+}
+
+/// Outline of `source`, or `None` if `path`'s language isn't supported.
+pub fn parse_symbols(path: &Path, source: &str) -> Option<Vec<DocumentSymbol>> {
+    let lang = Lang::from_path(path)?;
+    let mut parser = Parser::new();
+    parser.set_language(&lang.grammar()).ok()?;
+    let tree = parser.parse(source, None)?;
+    let mut symbols = Vec::new();
+    collect(lang, tree.root_node(), source.as_bytes(), None, &mut symbols);
+    Some(symbols)
+}
+
+/// Symbols of a file on disk. `None` for unsupported, oversized, missing
+/// or non-UTF-8 files.
+fn parse_file(path: &Path) -> Option<Vec<DocumentSymbol>> {
+    Lang::from_path(path)?;
+    let meta = std::fs::metadata(path).ok()?;
+    if !meta.is_file() || meta.len() > MAX_FILE_BYTES {
+        return None;
+    }
+    let source = String::from_utf8(std::fs::read(path).ok()?).ok()?;
+    parse_symbols(path, &source)
+}
+
+/// Parse every supported file under `root`, respecting ignore files.
+fn walk_symbols(root: &Path) -> HashMap<PathBuf, Vec<DocumentSymbol>> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    search::project_walker(root, true).build_parallel().run(|| {
+        let tx = tx.clone();
+        Box::new(move |result| {
+            if let Ok(entry) = result {
+                if entry.file_type().is_some_and(|t| t.is_file()) && is_supported(entry.path()) {
+                    if let Some(symbols) = parse_file(entry.path()) {
+                        let _ = tx.send((entry.into_path(), symbols));
+                    }
+                }
+            }
+            ignore::WalkState::Continue
+        })
+    });
+    drop(tx);
+    rx.into_iter().collect()
+}
+
+// ── State ────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct RootSymbols {
+    files: HashMap<PathBuf, Vec<DocumentSymbol>>,
+    ready: bool,
+    generation: u64,
+    /// Watcher paths that arrived while the initial parse was running.
+    pending: Vec<PathBuf>,
+}
+
+/// Parsed symbols per watched root, kept current from watcher events.
+#[derive(Default)]
+pub struct SymbolIndex {
+    roots: HashMap<PathBuf, RootSymbols>,
+    /// Bumped on every rebuild so a stale background parse never wins.
+    generation: u64,
+}
+
+pub type SymbolIndexState = Arc<Mutex<SymbolIndex>>;
+
+pub fn new_symbol_index_state() -> SymbolIndexState {
+    Arc::new(Mutex::new(SymbolIndex::default()))
+}
+
+/// A file's new symbols, or `None` once it's gone (or no longer parseable).
+type Update = (PathBuf, Option<Vec<DocumentSymbol>>);
+
+impl SymbolIndex {
+    fn reset(&mut self, root: &Path) -> u64 {
+        self.generation += 1;
+        let generation = self.generation;
+        self.roots.insert(root.to_path_buf(), RootSymbols { generation, ..Default::default() });
+        generation
+    }
+
+    /// Install a finished parse. Returns the paths to replay, or `None` if
+    /// the build was superseded.
+    fn install(
+        &mut self,
+        root: &Path,
+        generation: u64,
+        files: HashMap<PathBuf, Vec<DocumentSymbol>>,
+    ) -> Option<Vec<PathBuf>> {
+        let entry = self.roots.get_mut(root).filter(|r| r.generation == generation)?;
+        entry.files = files;
+        entry.ready = true;
+        Some(std::mem::take(&mut entry.pending))
+    }
+
+    fn forget(&mut self, root: Option<&Path>) {
+        match root {
+            Some(root) => {
+                self.roots.remove(root);
+            }
+            None => self.roots.clear(),
+        }
+    }
+
+    /// Queue `paths` for roots still building. Returns the ones to parse now.
+    fn defer_pending(&mut self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        let mut now = Vec::new();
+        for path in paths {
+            let Some(entry) = self.roots.iter_mut().find(|(r, _)| path.starts_with(r)) else {
+                continue;
+            };
+            if entry.1.ready {
+                now.push(path.clone());
+            } else {
+                entry.1.pending.push(path.clone());
+            }
+        }
+        now
+    }
+
+    fn apply(&mut self, updates: Vec<Update>) {
+        for (path, symbols) in updates {
+            let Some(entry) = self.roots.iter_mut().find(|(r, _)| path.starts_with(r)) else {
+                continue;
+            };
+            let files = &mut entry.1.files;
+            match symbols {
+                Some(symbols) => {
+                    files.insert(path, symbols);
+                }
+                // A deleted file, or a directory taking its files with it
+                None => files.retain(|p, _| !p.starts_with(&path)),
+            }
+        }
+    }
+
+    /// Fuzzy-rank symbol names against `query`, across `root` or every root.
+    fn find(&self, query: &str, root: Option<&Path>, limit: usize) -> Vec<WorkspaceSymbol> {
+        let mut all: Vec<(&Path, &Path, &DocumentSymbol)> = Vec::new();
+        for (r, entry) in self.roots.iter().filter(|(r, _)| root.map_or(true, |root| root == *r)) {
+            for (path, symbols) in &entry.files {
+                let mut stack: Vec<&DocumentSymbol> = symbols.iter().collect();
+                while let Some(symbol) = stack.pop() {
+                    all.push((r, path, symbol));
+                    stack.extend(symbol.children.iter());
+                }
+            }
+        }
+        let to_match =
+            |(r, path, s): (&Path, &Path, &DocumentSymbol), score, positions| WorkspaceSymbol {
+                name: s.name.clone(),
+                kind: s.kind,
+                path: path.to_string_lossy().to_string(),
+                relative_path: path.strip_prefix(r).unwrap_or(path).to_string_lossy().to_string(),
+                range: s.range,
+                selection_range: s.selection_range,
+                container: s.container.clone(),
+                score,
+                positions,
+            };
+
+        if query.trim().is_empty() {
+            all.sort_unstable_by(|a, b| {
+                (a.1, a.2.range.start.line).cmp(&(b.1, b.2.range.start.line))
+            });
+            return all.into_iter().take(limit).map(|s| to_match(s, 0, Vec::new())).collect();
+        }
+
+        let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+        let mut matcher = Matcher::new(Config::DEFAULT);
+        let mut buf = Vec::new();
+        let mut scored: Vec<(u32, (&Path, &Path, &DocumentSymbol))> = all
+            .into_iter()
+            .filter_map(|s| {
+                pattern.score(Utf32Str::new(&s.2.name, &mut buf), &mut matcher).map(|sc| (sc, s))
+            })
+            .collect();
+        // Best score first; shorter names, then shorter paths win ties
+        scored.sort_unstable_by(|(sa, a), (sb, b)| {
+            sb.cmp(sa)
+                .then(a.2.name.len().cmp(&b.2.name.len()))
+                .then(a.1.as_os_str().len().cmp(&b.1.as_os_str().len()))
+                .then_with(|| (a.1, a.2.range.start.line).cmp(&(b.1, b.2.range.start.line)))
+        });
+        scored.truncate(limit);
+
+        scored
+            .into_iter()
+            .map(|(score, s)| {
+                let mut positions = Vec::new();
+                pattern.indices(Utf32Str::new(&s.2.name, &mut buf), &mut matcher, &mut positions);
+                positions.sort_unstable();
+                positions.dedup();
+                to_match(s, score, positions)
+            })
+            .collect()
+    }
+}
+
+/// Parse changed paths outside the lock. Missing paths become removals;
+/// new or renamed directories are walked, since their files get no events.
+fn updates_for(paths: &[PathBuf]) -> Vec<Update> {
+    let mut updates = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            updates.extend(walk_symbols(path).into_iter().map(|(p, s)| (p, Some(s))));
+        } else if !path.exists() || is_supported(path) {
+            updates.push((path.clone(), parse_file(path)));
+        }
+    }
+    updates
+}
+
+/// (Re)index `root` on a background thread.
+/// Emits `symbol-index-ready` with file and symbol counts when done.
+pub fn spawn_build(app: &tauri::AppHandle, root: &Path) {
+    let state = app.state::<SymbolIndexState>().inner().clone();
+    let Ok(generation) = state.lock().map(|mut idx| idx.reset(root)) else { return };
+
+    let app = app.clone();
+    let root = root.to_path_buf();
+    std::thread::spawn(move || {
+        let started = std::time::Instant::now();
+        let files = walk_symbols(&root);
+        let (file_count, symbol_count) = (files.len(), files.values().map(Vec::len).sum::<usize>());
+
+        let Some(pending) =
+            state.lock().ok().and_then(|mut idx| idx.install(&root, generation, files))
+        else {
+            return; // superseded by a rebuild or the root was closed
+        };
+        let updates = updates_for(&pending);
+        if let Ok(mut idx) = state.lock() {
+            idx.apply(updates);
+        }
+
+        tracing::info!(
+            "Symbol index built for {}: {} symbols in {} files in {:?}",
+            root.display(),
+            symbol_count,
+            file_count,
+            started.elapsed()
+        );
+        let _ = app.emit(
+            "symbol-index-ready",
+            serde_json::json!({
+                "root": root.to_string_lossy(),
+                "files": file_count,
+                "symbols": symbol_count,
+            }),
+        );
+    });
+}
+
+/// Re-parse files reported by the watcher (called from the debouncer thread).
+pub fn on_fs_events(app: &tauri::AppHandle, paths: &[PathBuf]) {
+    let state = app.state::<SymbolIndexState>();
+    let Ok(now) = state.lock().map(|mut idx| idx.defer_pending(paths)) else { return };
+    if now.is_empty() {
+        return;
+    }
+    let updates = updates_for(&now);
+    let Ok(mut idx) = state.lock() else { return };
+    idx.apply(updates);
+}
+
+/// Drop a root's symbols (or every root's) when it's no longer watched.
+pub fn forget(state: &SymbolIndexState, root: Option<&str>) -> Result<(), KodiqError> {
+    state.lock()?.forget(root.map(Path::new));
+    Ok(())
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// Outline of a local file. Parses `content` (the editor buffer) when
+/// given, otherwise the file on disk, which also refreshes the index.
+/// Unsupported languages yield no symbols.
+#[tauri::command(async)]
+pub fn fs_document_symbols(
+    webview: tauri::Webview,
+    path: String,
+    content: Option<String>,
+    index: tauri::State<'_, SymbolIndexState>,
+) -> Result<Vec<DocumentSymbol>, KodiqError> {
+    sandbox::check(&webview, &path, None)?;
+    let file = Path::new(&path);
+    if let Some(content) = content {
+        return Ok(parse_symbols(file, &content).unwrap_or_default());
+    }
+    if !is_supported(file) {
+        return Ok(Vec::new());
+    }
+    let source = String::from_utf8_lossy(&std::fs::read(file)?).to_string();
+    let symbols = parse_symbols(file, &source).unwrap_or_default();
+    let mut idx = index.lock()?;
+    if idx.defer_pending(&[file.to_path_buf()]).len() == 1 {
+        idx.apply(vec![(file.to_path_buf(), Some(symbols.clone()))]);
+    }
+    Ok(symbols)
+}
+
+/// Fuzzy-find symbols by name in every watched project, or just `root`.
+#[tauri::command]
+pub fn fs_workspace_symbols(
+    webview: tauri::Webview,
+    query: String,
+    root: Option<String>,
+    limit: Option<usize>,
+    index: tauri::State<'_, SymbolIndexState>,
+) -> Result<Vec<WorkspaceSymbol>, KodiqError> {
+    sandbox::check_webview(&webview)?;
+    let idx = index.lock()?;
+    Ok(idx.find(&query, root.as_deref().map(Path::new), limit.unwrap_or(DEFAULT_LIMIT)))
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// `(kind, name, container, start line)` for a flattened outline.
+    fn flat(symbols: &[DocumentSymbol]) -> Vec<(SymbolKind, String, Option<String>, u32)> {
+        let mut out = Vec::new();
+        for s in symbols {
+            out.push((s.kind, s.name.clone(), s.container.clone(), s.range.start.line));
+            out.extend(flat(&s.children));
+        }
+        out
+    }
+
+    fn names(path: &str, source: &str) -> Vec<(SymbolKind, String, Option<String>)> {
+        flat(&parse_symbols(Path::new(path), source).unwrap())
+            .into_iter()
+            .map(|(k, n, c, _)| (k, n, c))
+            .collect()
+    }
+
+    fn sym(
+        kind: SymbolKind,
+        name: &str,
+        container: Option<&str>,
+    ) -> (SymbolKind, String, Option<String>) {
+        (kind, name.to_string(), container.map(String::from))
+    }
+
+    #[test]
+    fn test_typescript_symbols() {
+        let source = r#"
+export interface Props { onSave(): void }
+type Id = string;
+export class Store {
+  count = 0;
+  reset = () => {};
+  add(n: number) { return n; }
+}
+export function main() {}
+const handler = async () => {};
+const answer = 42;
+enum Mode { A, B }
+"#;
+        assert_eq!(
+            names("src/store.ts", source),
+            vec![
+                sym(SymbolKind::Interface, "Props", None),
+                sym(SymbolKind::Method, "onSave", Some("Props")),
+                sym(SymbolKind::Type, "Id", None),
+                sym(SymbolKind::Class, "Store", None),
+                sym(SymbolKind::Method, "reset", Some("Store")),
+                sym(SymbolKind::Method, "add", Some("Store")),
+                sym(SymbolKind::Function, "main", None),
+                sym(SymbolKind::Function, "handler", None),
+                sym(SymbolKind::Enum, "Mode", None),
+            ]
+        );
+        // JSX needs the TSX grammar
+        let tsx = names("App.tsx", "export const App = () => <div className=\"x\" />;\n");
+        assert_eq!(tsx, vec![sym(SymbolKind::Function, "App", None)]);
+        let js = names("a.js", "class A { b() {} }\nfunction c() {}\n");
+        assert_eq!(js.len(), 3);
+    }
+
+    #[test]
+    fn test_rust_symbols() {
+        let source = "struct Point { x: i32 }\n\
+                      impl Point {\n    fn new() -> Self { todo!() }\n}\n\
+                      impl Display for Point {\n    fn fmt(&self) {}\n}\n\
+                      trait Shape { fn area(&self) -> f64; }\n\
+                      enum E { A }\nmod inner { pub fn f() {} }\nconst MAX: u32 = 1;\n";
+        assert_eq!(
+            names("lib.rs", source),
+            vec![
+                sym(SymbolKind::Struct, "Point", None),
+                sym(SymbolKind::Impl, "impl Point", None),
+                sym(SymbolKind::Method, "new", Some("impl Point")),
+                sym(SymbolKind::Impl, "impl Display for Point", None),
+                sym(SymbolKind::Method, "fmt", Some("impl Display for Point")),
+                sym(SymbolKind::Trait, "Shape", None),
+                sym(SymbolKind::Method, "area", Some("Shape")),
+                sym(SymbolKind::Enum, "E", None),
+                sym(SymbolKind::Module, "inner", None),
+                sym(SymbolKind::Function, "f", Some("inner")),
+                sym(SymbolKind::Constant, "MAX", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_python_and_go_symbols() {
+        let python = "class Api:\n    @property\n    def url(self):\n        def inner(): pass\n\ndef main():\n    pass\n";
+        assert_eq!(
+            names("api.py", python),
+            vec![
+                sym(SymbolKind::Class, "Api", None),
+                sym(SymbolKind::Method, "url", Some("Api")),
+                sym(SymbolKind::Function, "inner", Some("url")),
+                sym(SymbolKind::Function, "main", None),
+            ]
+        );
+
+        let go = "package main\n\ntype Server struct{}\ntype Handler interface{ Serve() }\ntype ID = int\n\n\
+                  func (s *Server) Start() error { return nil }\nfunc main() {}\n";
+        assert_eq!(
+            names("main.go", go),
+            vec![
+                sym(SymbolKind::Struct, "Server", None),
+                sym(SymbolKind::Interface, "Handler", None),
+                sym(SymbolKind::Type, "ID", None),
+                sym(SymbolKind::Method, "Start", Some("Server")),
+                sym(SymbolKind::Function, "main", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ranges_count_chars() {
+        let symbols = parse_symbols(Path::new("a.ts"), "/* é */ function go() {}\n").unwrap();
+        assert_eq!(symbols[0].range.start, Position { line: 0, column: 8 });
+        assert_eq!(symbols[0].selection_range.start, Position { line: 0, column: 17 });
+        assert_eq!(symbols[0].range.end, Position { line: 0, column: 24 });
+        assert!(parse_symbols(Path::new("notes.md"), "# x").is_none());
+    }
+
+    #[test]
+    fn test_index_build_events_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/util")).unwrap();
+        fs::create_dir_all(root.join("dist")).unwrap();
+        fs::write(root.join(".gitignore"), "dist/\n").unwrap();
+        fs::write(root.join("src/app.ts"), "export function startServer() {}\n").unwrap();
+        fs::write(root.join("src/util/fmt.rs"), "pub fn format_size() {}\n").unwrap();
+        fs::write(root.join("dist/app.js"), "function startServer() {}\n").unwrap();
+
+        let mut idx = SymbolIndex::default();
+        let generation = idx.reset(root);
+        // Arrives mid-build and is replayed afterwards
+        assert!(idx.defer_pending(&[root.join("src/late.py")]).is_empty());
+        fs::write(root.join("src/late.py"), "def serve(): pass\n").unwrap();
+        let pending = idx.install(root, generation, walk_symbols(root)).unwrap();
+        idx.apply(updates_for(&pending));
+
+        let found = idx.find("srvr", None, 10);
+        assert_eq!(found[0].name, "startServer");
+        assert_eq!(found[0].relative_path, Path::new("src/app.ts").to_string_lossy());
+        assert_eq!(found.iter().filter(|s| s.name == "startServer").count(), 1);
+        assert_eq!(idx.find("serve", Some(root), 10)[0].name, "serve");
+        assert_eq!(idx.find("", None, 10).len(), 3);
+
+        // Edit one file, delete a directory
+        fs::write(root.join("src/app.ts"), "export function stopServer() {}\n").unwrap();
+        fs::remove_dir_all(root.join("src/util")).unwrap();
+        let now = idx.defer_pending(&[root.join("src/app.ts"), root.join("src/util")]);
+        idx.apply(updates_for(&now));
+        let names: Vec<String> = idx.find("", None, 10).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["stopServer", "serve"]);
+
+        // A directory moved in brings its files along
+        fs::create_dir_all(root.join("moved")).unwrap();
+        fs::write(root.join("moved/lib.go"), "package x\nfunc Run() {}\n").unwrap();
+        let now = idx.defer_pending(&[root.join("moved")]);
+        idx.apply(updates_for(&now));
+        assert_eq!(
+            idx.find("Run", None, 10)[0].relative_path,
+            Path::new("moved/lib.go").to_string_lossy()
+        );
+
+        // A stale build is discarded
+        let stale = idx.reset(root);
+        idx.reset(root);
+        assert!(idx.install(root, stale, HashMap::new()).is_none());
+
+        idx.forget(Some(root));
+        assert!(idx.find("", None, 10).is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+use super::ops;
+use crate::error::KodiqError;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+// Freedesktop.org Trash spec: `$XDG_DATA_HOME/Trash/files/<name>` holds the
+// item, `info/<name>.trashinfo` records where it came from. File managers
+// show and restore these items too.
+
+const INFO_EXT: &str = ".trashinfo";
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// An item in the trash.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedItem {
+    /// Name under `Trash/files`, used to restore.
+    pub id: String,
+    pub original_path: String,
+    pub deleted_at: String,
+    pub is_dir: bool,
+}
+
+/// The home trash, `None` if there is no data dir.
+pub fn home_trash() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("Trash"))
+}
+
+// ── Percent-encoding (trashinfo `Path=`) ─────────────────────────────
+
+fn encode_path(path: &Path) -> String {
+    let mut out = String::new();
+    for &b in path.to_string_lossy().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn decode_path(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%').then(|| encoded.get(i + 1..i + 3)).flatten();
+        match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            Some(b) => {
+                out.push(b);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// ── Operations ───────────────────────────────────────────────────────
+
+/// Reserve a unique trash name by creating its info file (`create_new` is atomic).
+fn reserve(trash: &Path, path: &Path, info: &str) -> Result<String, KodiqError> {
+    let base = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    for n in 1..1000 {
+        let id = if n == 1 { base.clone() } else { format!("{}.{}", base, n) };
+        if trash.join("files").join(&id).symlink_metadata().is_ok() {
+            continue;
+        }
+        let info_path = trash.join("info").join(format!("{}{}", id, INFO_EXT));
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(mut file) => {
+                std::io::Write::write_all(&mut file, info.as_bytes())?;
+                return Ok(id);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(KodiqError::Conflict(format!("No free trash name for {}", path.display())))
+}
+
+/// Move `path` into `trash`. Falls back to copy + delete across filesystems.
+pub fn trash_in(trash: &Path, path: &Path) -> Result<TrashedItem, KodiqError> {
+    let meta = path.symlink_metadata()?;
+    std::fs::create_dir_all(trash.join("files"))?;
+    std::fs::create_dir_all(trash.join("info"))?;
+
+    let deleted_at = chrono::Local::now().format(DATE_FORMAT).to_string();
+    let info = format!("[Trash Info]\nPath={}\nDeletionDate={}\n", encode_path(path), deleted_at);
+    let id = reserve(trash, path, &info)?;
+    let target = trash.join("files").join(&id);
+
+    let moved = std::fs::rename(path, &target).or_else(|_| {
+        ops::copy_recursive(path, &target)?;
+        ops::remove_recursive(path)
+    });
+    if let Err(e) = moved {
+        let _ = std::fs::remove_file(trash.join("info").join(format!("{}{}", id, INFO_EXT)));
+        return Err(e.into());
+    }
+
+    Ok(TrashedItem {
+        id,
+        original_path: path.to_string_lossy().to_string(),
+        deleted_at,
+        is_dir: meta.is_dir(),
+    })
+}
+
+fn read_info(trash: &Path, id: &str) -> Option<TrashedItem> {
+    let raw =
+        std::fs::read_to_string(trash.join("info").join(format!("{}{}", id, INFO_EXT))).ok()?;
+    let mut original_path = None;
+    let mut deleted_at = String::new();
+    for line in raw.lines() {
+        if let Some(p) = line.strip_prefix("Path=") {
+            original_path = Some(decode_path(p));
+        } else if let Some(d) = line.strip_prefix("DeletionDate=") {
+            deleted_at = d.to_string();
+        }
+    }
+    let is_dir = trash.join("files").join(id).symlink_metadata().ok()?.is_dir();
+    Some(TrashedItem { id: id.to_string(), original_path: original_path?, deleted_at, is_dir })
+}
+
+/// Trashed items that came from inside `under`, newest first.
+pub fn list_in(trash: &Path, under: &Path) -> Vec<TrashedItem> {
+    let Ok(infos) = std::fs::read_dir(trash.join("info")) else {
+        return Vec::new();
+    };
+    let mut items: Vec<_> = infos
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().to_string_lossy().strip_suffix(INFO_EXT).map(String::from))
+        .filter_map(|id| read_info(trash, &id))
+        .filter(|item| Path::new(&item.original_path).starts_with(under))
+        .collect();
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    items
+}
+
+/// Put a trashed item back where it was. Refuses to overwrite whatever is
+/// there now; recreates missing parent directories.
+pub fn restore_in(trash: &Path, id: &str) -> Result<TrashedItem, KodiqError> {
+    if id.contains('/') || id == ".." {
+        return Err(KodiqError::NotFound(format!("Trash item {}", id)));
+    }
+    let item =
+        read_info(trash, id).ok_or_else(|| KodiqError::NotFound(format!("Trash item {}", id)))?;
+    let original = Path::new(&item.original_path);
+    if original.symlink_metadata().is_ok() {
+        return Err(KodiqError::Conflict(format!("{} already exists", item.original_path)));
+    }
+    if let Some(parent) = original.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let source = trash.join("files").join(id);
+    std::fs::rename(&source, original).or_else(|_| {
+        ops::copy_recursive(&source, original)?;
+        ops::remove_recursive(&source)
+    })?;
+    std::fs::remove_file(trash.join("info").join(format!("{}{}", id, INFO_EXT)))?;
+    Ok(item)
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_encoding_round_trip() {
+        let path = Path::new("/home/me/my notes/café%.md");
+        let encoded = encode_path(path);
+        assert_eq!(encoded, "/home/me/my%20notes/caf%C3%A9%25.md");
+        assert_eq!(decode_path(&encoded), path.to_string_lossy());
+    }
+
+    #[test]
+    fn test_trash_list_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = dir.path().join("Trash");
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(project.join("src/a.txt"), "one").unwrap();
+
+        let first = trash_in(&trash, &project.join("src/a.txt")).unwrap();
+        assert_eq!(first.id, "a.txt");
+        assert!(!project.join("src/a.txt").exists());
+
+        // Same name again gets a distinct trash slot
+        std::fs::write(project.join("src/a.txt"), "two").unwrap();
+        let second = trash_in(&trash, &project.join("src/a.txt")).unwrap();
+        assert_eq!(second.id, "a.txt.2");
+
+        let dir_item = trash_in(&trash, &project.join("src")).unwrap();
+        assert!(dir_item.is_dir);
+
+        assert_eq!(list_in(&trash, &project).len(), 3);
+        assert!(list_in(&trash, Path::new("/elsewhere")).is_empty());
+
+        restore_in(&trash, &dir_item.id).unwrap();
+        assert!(project.join("src").is_dir());
+        // The original spot is taken again
+        std::fs::write(project.join("src/a.txt"), "three").unwrap();
+        assert!(matches!(restore_in(&trash, "a.txt"), Err(KodiqError::Conflict(_))));
+
+        std::fs::remove_file(project.join("src/a.txt")).unwrap();
+        restore_in(&trash, "a.txt.2").unwrap();
+        assert_eq!(std::fs::read_to_string(project.join("src/a.txt")).unwrap(), "two");
+        assert_eq!(list_in(&trash, &project), vec![first]);
+    }
+}
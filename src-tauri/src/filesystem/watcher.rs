@@ -1,33 +1,166 @@
+use super::filter::{self, FileFilterSettings, ListOptions};
+use super::local_history::HistoryState;
+use super::sandbox;
+use super::search::{self, FileIndexState};
+use super::symbols::{self, SymbolIndexState};
+use super::write;
 use crate::error::KodiqError;
-use notify::RecursiveMode;
-use notify_debouncer_mini::{new_debouncer, DebouncedEvent, Debouncer};
-use std::path::PathBuf;
-use std::sync::Mutex;
+use crate::prompts;
+use crate::ssh::{self, watcher::RemoteWatchState, SshState};
+use crate::state::DbState;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use notify::event::{CreateKind, EventKind, ModifyKind, RemoveKind, RenameMode};
+use notify::{ErrorKind, RecursiveMode};
+use notify_debouncer_full::{
+    new_debouncer, new_debouncer_opt, DebounceEventResult, Debouncer, NoCache, RecommendedCache,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
+
+const DEBOUNCE: Duration = Duration::from_millis(500);
 
 // ── State ────────────────────────────────────────────────────────────
 
-type NotifyDebouncer = Debouncer<notify::RecommendedWatcher>;
+/// Native notifications, or a stat-polling fallback.
+enum Backend {
+    Native(Debouncer<notify::RecommendedWatcher, RecommendedCache>),
+    Poll(Debouncer<notify::PollWatcher, NoCache>),
+}
+
+impl Backend {
+    fn stop(self) {
+        match self {
+            Backend::Native(d) => d.stop_nonblocking(),
+            Backend::Poll(d) => d.stop_nonblocking(),
+        }
+    }
+}
 
+/// Watched project roots.
 pub struct WatcherState {
-    debouncer: Mutex<Option<NotifyDebouncer>>,
+    roots: Mutex<HashMap<String, Backend>>,
 }
 
 impl WatcherState {
     pub fn new() -> Self {
-        Self { debouncer: Mutex::new(None) }
+        Self { roots: Mutex::new(HashMap::new()) }
+    }
+}
+
+// ── Settings ─────────────────────────────────────────────────────────
+
+/// Watcher settings, stored under `"watcher"` in a project's `settings` JSON.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatcherSettings {
+    /// Gitignore-style patterns, relative to the project root, whose
+    /// changes are not reported. Replaces the built-in list when set.
+    pub ignore: Vec<String>,
+    /// Also skip paths matched by the project's root `.gitignore`.
+    pub use_gitignore: bool,
+    /// Poll instead of using OS notifications (e.g. on network mounts).
+    pub force_polling: bool,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for WatcherSettings {
+    fn default() -> Self {
+        Self {
+            ignore: IGNORED_DIRS.iter().map(|d| d.to_string()).collect(),
+            use_gitignore: false,
+            force_polling: false,
+            poll_interval_ms: 2000,
+        }
+    }
+}
+
+// ── Events ───────────────────────────────────────────────────────────
+
+/// What happened to a path.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FsChangeKind {
+    Created,
+    Modified,
+    Removed,
+    Renamed,
+}
+
+/// One precise change, from an explorer operation or the watcher.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsChange {
+    pub kind: FsChangeKind,
+    pub path: String,
+    /// Previous path for renames and moves.
+    pub from: Option<String>,
+    pub is_dir: bool,
+}
+
+/// Payload of the `fs-operation` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsOperationEvent {
+    pub root: String,
+    pub connection_id: Option<String>,
+    pub changes: Vec<FsChange>,
+}
+
+/// Payload of the `fs-events` event: one debounced batch from the watcher.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsEventBatch {
+    pub root: String,
+    pub connection_id: Option<String>,
+    pub changes: Vec<FsChange>,
+    /// Events were lost; re-read the whole tree.
+    pub rescan: bool,
+}
+
+/// Payload of the `fs-watch-warning` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchWarning {
+    pub root: String,
+    pub connection_id: Option<String>,
+    pub message: String,
+    /// The root is now polled rather than watched natively.
+    pub polling: bool,
+}
+
+/// Tell the frontend exactly what an operation changed. Remote projects have
+/// no watcher, so they also get the coarse `fs-changed` refresh signal.
+pub fn emit_changes(
+    app: &tauri::AppHandle,
+    root: &str,
+    connection_id: Option<&str>,
+    changes: Vec<FsChange>,
+) {
+    if changes.is_empty() {
+        return;
+    }
+    if connection_id.is_some() {
+        let _ = app.emit("fs-changed", root.to_string());
     }
+    let event = FsOperationEvent {
+        root: root.to_string(),
+        connection_id: connection_id.map(str::to_string),
+        changes,
+    };
+    let _ = app.emit("fs-operation", event);
 }
 
 // ── Helpers ──────────────────────────────────────────────────────────
 
-/// Directories that should NOT trigger fs-changed events.
-/// Changes inside these are noise — build artifacts, caches, etc.
+/// Default ignore list: changes inside these are noise — build
+/// artifacts, caches, etc.
 const IGNORED_DIRS: &[&str] = &[
     "node_modules",
     "target",
-    ".git",
     "__pycache__",
     ".next",
     "dist",
@@ -38,18 +171,44 @@ const IGNORED_DIRS: &[&str] = &[
     ".venv",
 ];
 
-fn is_ignored(path: &std::path::Path) -> bool {
-    path.components().any(|c| {
-        if let std::path::Component::Normal(name) = c {
-            if let Some(s) = name.to_str() {
-                return IGNORED_DIRS.contains(&s);
-            }
+/// Decides which watcher events reach the frontend.
+pub(crate) struct IgnorePolicy {
+    root: PathBuf,
+    patterns: Gitignore,
+    gitignore: Option<filter::FilterPolicy>,
+}
+
+impl IgnorePolicy {
+    pub(crate) fn new(root: &Path, settings: &WatcherSettings) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in &settings.ignore {
+            let _ = builder.add_line(None, pattern);
         }
-        false
-    })
+        let gitignore = settings.use_gitignore.then(|| gitignore_policy(root));
+        Self {
+            root: root.to_path_buf(),
+            patterns: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+            gitignore,
+        }
+    }
+
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if !path.starts_with(&self.root) || path == self.root {
+            return false;
+        }
+        write::is_temp_name(path)
+            || self.patterns.matched_path_or_any_parents(path, is_dir).is_ignore()
+            || self.gitignore.as_ref().is_some_and(|g| g.is_ignored(path, is_dir))
+    }
 }
 
-fn is_git_event(path: &std::path::Path) -> bool {
+/// The project's .gitignore rules, without the tree's hidden/ignored display settings.
+fn gitignore_policy(root: &Path) -> filter::FilterPolicy {
+    let opts = ListOptions { root: Some(root.to_string_lossy().to_string()), ..Default::default() };
+    filter::local_policy(root, &FileFilterSettings::default(), &opts)
+}
+
+pub(crate) fn is_git_event(path: &std::path::Path) -> bool {
     path.components().any(|c| {
         if let std::path::Component::Normal(name) = c {
             return name == ".git";
@@ -58,80 +217,446 @@ fn is_git_event(path: &std::path::Path) -> bool {
     })
 }
 
-// ── Core logic ───────────────────────────────────────────────────────
+pub(crate) fn change(
+    kind: FsChangeKind,
+    path: &Path,
+    from: Option<&Path>,
+    is_dir: bool,
+) -> FsChange {
+    FsChange {
+        kind,
+        path: path.to_string_lossy().to_string(),
+        from: from.map(|f| f.to_string_lossy().to_string()),
+        is_dir,
+    }
+}
 
-fn start(app: tauri::AppHandle, root: &str, state: &WatcherState) -> Result<(), KodiqError> {
-    let root_path = PathBuf::from(root);
-    let root_clone = root_path.clone();
-
-    // Create a debouncer with 500ms window — batches rapid FS events
-    let debouncer = new_debouncer(
-        Duration::from_millis(500),
-        move |result: Result<Vec<DebouncedEvent>, notify::Error>| {
-            let events = match result {
-                Ok(events) => events,
-                Err(e) => {
-                    tracing::warn!("Watcher error: {}", e);
-                    return;
+/// Merge a batch into one change per path, in order. A path created and
+/// then modified stays "created"; created and then deleted disappears.
+pub(crate) fn coalesce(changes: impl IntoIterator<Item = FsChange>) -> Vec<FsChange> {
+    let mut out: Vec<Option<FsChange>> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for next in changes {
+        let Some(&i) = index.get(&next.path) else {
+            index.insert(next.path.clone(), out.len());
+            out.push(Some(next));
+            continue;
+        };
+        let merged = match (out[i].take(), next.kind) {
+            (Some(prev), FsChangeKind::Modified)
+                if matches!(prev.kind, FsChangeKind::Created | FsChangeKind::Renamed) =>
+            {
+                Some(prev)
+            }
+            (Some(prev), FsChangeKind::Removed) if prev.kind == FsChangeKind::Created => None,
+            (Some(prev), FsChangeKind::Created) if prev.kind == FsChangeKind::Removed => {
+                Some(FsChange { kind: FsChangeKind::Modified, ..next })
+            }
+            _ => Some(next),
+        };
+        out[i] = merged;
+    }
+    out.into_iter().flatten().collect()
+}
+
+/// Turn raw notify events into coalesced changes.
+fn translate<'a>(events: impl IntoIterator<Item = &'a notify::Event>) -> Vec<FsChange> {
+    let mut raw = Vec::new();
+    let mut push = |c: FsChange| raw.push(c);
+
+    for event in events {
+        let exists_dir = |p: &Path| p.is_dir();
+        match event.kind {
+            EventKind::Create(kind) => {
+                for p in &event.paths {
+                    let is_dir = kind == CreateKind::Folder || exists_dir(p);
+                    push(change(FsChangeKind::Created, p, None, is_dir));
+                }
+            }
+            EventKind::Remove(kind) => {
+                for p in &event.paths {
+                    push(change(FsChangeKind::Removed, p, None, kind == RemoveKind::Folder));
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                let (from, to) = (&event.paths[0], &event.paths[1]);
+                if write::is_temp_name(from) {
+                    // An atomic save landing on its target
+                    push(change(FsChangeKind::Modified, to, None, false));
+                } else {
+                    push(change(FsChangeKind::Renamed, to, Some(from), exists_dir(to)));
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                for p in &event.paths {
+                    push(change(FsChangeKind::Removed, p, None, false));
                 }
-            };
+            }
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                for p in &event.paths {
+                    let kind =
+                        if p.exists() { FsChangeKind::Created } else { FsChangeKind::Removed };
+                    push(change(kind, p, None, exists_dir(p)));
+                }
+            }
+            EventKind::Access(_) => {}
+            _ => {
+                for p in &event.paths {
+                    let kind =
+                        if p.exists() { FsChangeKind::Modified } else { FsChangeKind::Removed };
+                    push(change(kind, p, None, exists_dir(p)));
+                }
+            }
+        }
+    }
+    coalesce(raw)
+}
+
+// ── Core logic ───────────────────────────────────────────────────────
 
-            let mut has_fs = false;
-            let mut has_git = false;
+pub(crate) fn load_settings(app: &tauri::AppHandle, root: &str) -> WatcherSettings {
+    let db = app.state::<DbState>();
+    let conn = db.connection.lock();
+    conn.map(|c| filter::project_section(&c, root, "watcher")).unwrap_or_default()
+}
+
+pub(crate) fn warn(
+    app: &tauri::AppHandle,
+    root: &str,
+    connection_id: Option<&str>,
+    message: String,
+    polling: bool,
+) {
+    tracing::warn!("Watcher for {}: {}", root, message);
+    let warning = WatchWarning {
+        root: root.to_string(),
+        connection_id: connection_id.map(str::to_string),
+        message,
+        polling,
+    };
+    let _ = app.emit("fs-watch-warning", warning);
+}
+
+fn is_limit(e: &notify::Error) -> bool {
+    matches!(e.kind, ErrorKind::MaxFilesWatch)
+        || matches!(&e.kind, ErrorKind::Io(io) if io.raw_os_error() == Some(28) || io.raw_os_error() == Some(24))
+}
 
-            for event in &events {
-                if is_git_event(&event.path) {
-                    has_git = true;
-                } else if !is_ignored(&event.path) {
-                    has_fs = true;
+/// Build the debouncer callback for one root.
+fn handler(
+    app: tauri::AppHandle,
+    root: PathBuf,
+    policy: IgnorePolicy,
+    polling: bool,
+) -> impl FnMut(DebounceEventResult) + Send + 'static {
+    let root_str = root.to_string_lossy().to_string();
+    let fell_back = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let subscribers = subscribers(&root);
+
+    move |result: DebounceEventResult| {
+        let events = match result {
+            Ok(events) => events,
+            Err(errors) => {
+                // inotify ran out of watches while following new directories
+                let limited = errors.iter().any(is_limit);
+                if limited && !polling && !fell_back.swap(true, std::sync::atomic::Ordering::SeqCst)
+                {
+                    let (app, root) = (app.clone(), root_str.clone());
+                    std::thread::spawn(move || restart_polling(&app, &root));
+                } else {
+                    for e in errors {
+                        tracing::warn!("Watcher error: {}", e);
+                    }
                 }
+                return;
+            }
+        };
+
+        let mut has_git = false;
+        let mut rescan = false;
+        let mut paths = Vec::new();
+        let mut fs_events = Vec::new();
+
+        for event in &events {
+            rescan |= event.need_rescan();
+            if event.paths.iter().any(|p| is_git_event(p)) {
+                has_git = true;
+                continue;
             }
+            paths.extend(event.paths.iter().cloned());
+            fs_events.push(&event.event);
+        }
+
+        let changes: Vec<FsChange> = translate(fs_events)
+            .into_iter()
+            .filter(|c| !policy.is_ignored(Path::new(&c.path), c.is_dir))
+            .collect();
+
+        let batch = WatchBatch { root: &root, paths: &paths, changes: &changes };
+        for subscriber in &subscribers {
+            subscriber(&app, &batch);
+        }
+
+        if !changes.is_empty() || rescan {
+            let _ = app.emit("fs-changed", root_str.clone());
+            let batch =
+                FsEventBatch { root: root_str.clone(), connection_id: None, changes, rescan };
+            let _ = app.emit("fs-events", batch);
+        }
+        if has_git {
+            let _ = app.emit("git-changed", root_str.clone());
+        }
+    }
+}
+
+// ── Subscribers ──────────────────────────────────────────────────────
 
-            if has_fs {
-                let _ = app.emit("fs-changed", root_clone.to_string_lossy().to_string());
+/// One debounced batch, as handed to the subsystems that follow the tree.
+struct WatchBatch<'a> {
+    root: &'a Path,
+    /// Every path notify reported outside `.git`, before the ignore policy.
+    paths: &'a [PathBuf],
+    /// Coalesced changes that passed the ignore policy.
+    changes: &'a [FsChange],
+}
+
+type Subscriber = Box<dyn Fn(&tauri::AppHandle, &WatchBatch) + Send>;
+
+/// Subsystems notified of every local batch, in order. Built once per root.
+fn subscribers(root: &Path) -> Vec<Subscriber> {
+    // History skips gitignored files even when the watcher reports them
+    let history_filter = gitignore_policy(root);
+    vec![
+        Box::new(notify_search),
+        Box::new(notify_symbols),
+        Box::new(|app, batch| {
+            if !batch.changes.is_empty() {
+                crate::lsp::host::on_fs_events(app, batch.changes);
             }
-            if has_git {
-                let _ = app.emit("git-changed", root_clone.to_string_lossy().to_string());
+        }),
+        Box::new(|app, batch| {
+            if !batch.changes.is_empty() {
+                crate::tasks::discover::on_fs_events(app, batch.root, batch.changes);
             }
-        },
+        }),
+        Box::new(|app, batch| {
+            if !batch.changes.is_empty() {
+                crate::workspace::sync::on_fs_events(app, batch.root, batch.changes);
+            }
+        }),
+        Box::new(move |app, batch| notify_history(app, &history_filter, batch)),
+        Box::new(notify_prompts),
+    ]
+}
+
+fn notify_search(app: &tauri::AppHandle, batch: &WatchBatch) {
+    // The file index applies its own gitignore rules
+    if !batch.paths.is_empty() {
+        search::on_fs_events(app, batch.paths);
+    }
+}
+
+fn notify_symbols(app: &tauri::AppHandle, batch: &WatchBatch) {
+    let touched: Vec<PathBuf> = batch
+        .changes
+        .iter()
+        .flat_map(|c| std::iter::once(&c.path).chain(c.from.as_ref()))
+        .map(PathBuf::from)
+        .collect();
+    if !touched.is_empty() {
+        symbols::on_fs_events(app, &touched);
+    }
+}
+
+/// External edits (agents, formatters, git) join the local history.
+fn notify_history(app: &tauri::AppHandle, gitignore: &filter::FilterPolicy, batch: &WatchBatch) {
+    let history = app.state::<HistoryState>();
+    for c in batch.changes.iter().filter(|c| !c.is_dir && c.kind != FsChangeKind::Removed) {
+        if !gitignore.is_ignored(Path::new(&c.path), false) {
+            history.snapshot_later(PathBuf::from(&c.path));
+        }
+    }
+}
+
+fn notify_prompts(app: &tauri::AppHandle, batch: &WatchBatch) {
+    let root = batch.root;
+    if batch.changes.iter().any(|c| prompts::library::is_prompt_path(root, Path::new(&c.path))) {
+        prompts::library::refresh(app, &root.to_string_lossy());
+    }
+}
+
+fn native(app: &tauri::AppHandle, root: &Path, policy: IgnorePolicy) -> notify::Result<Backend> {
+    let mut debouncer =
+        new_debouncer(DEBOUNCE, None, handler(app.clone(), root.into(), policy, false))?;
+    debouncer.watch(root, RecursiveMode::Recursive)?;
+    Ok(Backend::Native(debouncer))
+}
+
+fn poll(
+    app: &tauri::AppHandle,
+    root: &Path,
+    policy: IgnorePolicy,
+    interval: Duration,
+) -> notify::Result<Backend> {
+    let config = notify::Config::default().with_poll_interval(interval);
+    let mut debouncer = new_debouncer_opt::<_, notify::PollWatcher, NoCache>(
+        DEBOUNCE,
+        None,
+        handler(app.clone(), root.into(), policy, true),
+        NoCache,
+        config,
     )?;
+    debouncer.watch(root, RecursiveMode::Recursive)?;
+    Ok(Backend::Poll(debouncer))
+}
 
-    // Store debouncer, replacing any previous one (auto-drops old watcher)
-    let mut guard = state.debouncer.lock()?;
-    *guard = Some(debouncer);
+/// Watch `root`, replacing any earlier watcher for it. Other roots keep
+/// their watchers. Falls back to polling when the OS watch limit is hit.
+fn start(app: tauri::AppHandle, root: &str, state: &WatcherState) -> Result<(), KodiqError> {
+    let root_path = PathBuf::from(root);
+    let settings = load_settings(&app, root);
+    let interval = Duration::from_millis(settings.poll_interval_ms.max(250));
 
-    // Start watching the project root recursively
-    if let Some(ref mut d) = *guard {
-        d.watcher().watch(root_path.as_ref(), RecursiveMode::Recursive)?;
+    // Drop any previous watcher first: a native one's watches count toward
+    // the limit again, and two backends never report the same root
+    if let Some(previous) = state.roots.lock()?.remove(root) {
+        previous.stop();
+    }
+    let backend = if settings.force_polling {
+        poll(&app, &root_path, IgnorePolicy::new(&root_path, &settings), interval)?
+    } else {
+        match native(&app, &root_path, IgnorePolicy::new(&root_path, &settings)) {
+            Ok(backend) => backend,
+            Err(e) if is_limit(&e) => {
+                let message = format!("{}; falling back to polling", e);
+                warn(&app, root, None, message, true);
+                poll(&app, &root_path, IgnorePolicy::new(&root_path, &settings), interval)?
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+    if let Some(previous) = state.roots.lock()?.insert(root.to_string(), backend) {
+        previous.stop();
     }
 
+    // Discover repo prompts and index files for the newly opened project
+    prompts::library::refresh(&app, root);
+    search::spawn_build(&app, &root_path);
+    symbols::spawn_build(&app, &root_path);
+
     tracing::info!("File watcher started for: {}", root);
     Ok(())
 }
 
-fn stop(state: &WatcherState) -> Result<(), KodiqError> {
-    let mut guard = state.debouncer.lock()?;
-    if guard.is_some() {
-        *guard = None; // Drop stops the watcher
-        tracing::info!("File watcher stopped");
+/// Swap a native watcher that ran out of watches for a polling one.
+fn restart_polling(app: &tauri::AppHandle, root: &str) {
+    let state = app.state::<WatcherState>();
+    let Ok(mut roots) = state.roots.lock() else { return };
+    if !matches!(roots.get(root), Some(Backend::Native(_))) {
+        return;
+    }
+    if let Some(previous) = roots.remove(root) {
+        previous.stop();
+    }
+
+    let root_path = PathBuf::from(root);
+    let settings = load_settings(app, root);
+    let interval = Duration::from_millis(settings.poll_interval_ms.max(250));
+    match poll(app, &root_path, IgnorePolicy::new(&root_path, &settings), interval) {
+        Ok(backend) => {
+            roots.insert(root.to_string(), backend);
+            warn(app, root, None, "OS file watch limit reached; polling instead".to_string(), true);
+        }
+        Err(e) => warn(app, root, None, format!("Watching stopped: {}", e), false),
+    }
+}
+
+fn stop(state: &WatcherState, root: Option<&str>) -> Result<(), KodiqError> {
+    let mut roots = state.roots.lock()?;
+    match root {
+        Some(root) => {
+            if let Some(backend) = roots.remove(root) {
+                backend.stop();
+                tracing::info!("File watcher stopped for: {}", root);
+            }
+        }
+        None => {
+            if !roots.is_empty() {
+                roots.drain().for_each(|(_, backend)| backend.stop());
+                tracing::info!("All file watchers stopped");
+            }
+        }
     }
     Ok(())
 }
 
 // ── Tauri Commands ───────────────────────────────────────────────────
 
-#[tauri::command]
-pub fn start_watching(
+/// Start (or restart, e.g. after settings changed) watching a project root.
+/// With `connection_id`, watches the remote root over SSH instead.
+#[tauri::command(async)]
+pub async fn start_watching(
+    webview: tauri::Webview,
     app: tauri::AppHandle,
     path: String,
-    watcher: tauri::State<WatcherState>,
+    connection_id: Option<String>,
+    watcher: tauri::State<'_, WatcherState>,
+    remote: tauri::State<'_, RemoteWatchState>,
+    ssh_state: tauri::State<'_, SshState>,
 ) -> Result<(), KodiqError> {
+    sandbox::check(&webview, &path, connection_id.as_deref())?;
+    if let Some(ref conn_id) = connection_id {
+        return ssh::watcher::start(&app, &ssh_state, &remote, conn_id, &path).await;
+    }
     start(app, &path, &watcher)
 }
 
-#[tauri::command]
-pub fn stop_watching(watcher: tauri::State<WatcherState>) -> Result<(), KodiqError> {
-    stop(&watcher)
+/// Stop watching one root, or every root (on the connection, if given)
+/// when `path` is omitted.
+#[tauri::command(async)]
+pub async fn stop_watching(
+    webview: tauri::Webview,
+    path: Option<String>,
+    connection_id: Option<String>,
+    watcher: tauri::State<'_, WatcherState>,
+    remote: tauri::State<'_, RemoteWatchState>,
+    symbol_index: tauri::State<'_, SymbolIndexState>,
+    file_index: tauri::State<'_, FileIndexState>,
+) -> Result<(), KodiqError> {
+    sandbox::check_webview(&webview)?;
+    match (connection_id, path) {
+        (Some(conn_id), path) => ssh::watcher::stop(&remote, &conn_id, path.as_deref()).await,
+        (None, Some(path)) => {
+            stop(&watcher, Some(&path))?;
+            symbols::forget(&symbol_index, Some(&path))?;
+            search::forget(&file_index, Some(&path))?;
+        }
+        (None, None) => {
+            stop(&watcher, None)?;
+            symbols::forget(&symbol_index, None)?;
+            search::forget(&file_index, None)?;
+            ssh::watcher::stop_all(&remote).await;
+        }
+    }
+    Ok(())
+}
+
+/// Directories expanded in the explorer. Remote roots without inotify poll
+/// these; local watchers already cover the whole tree.
+#[tauri::command(async)]
+pub async fn set_watched_dirs(
+    webview: tauri::Webview,
+    path: String,
+    connection_id: Option<String>,
+    dirs: Vec<String>,
+    remote: tauri::State<'_, RemoteWatchState>,
+) -> Result<(), KodiqError> {
+    sandbox::check(&webview, &path, connection_id.as_deref())?;
+    if let Some(ref conn_id) = connection_id {
+        ssh::watcher::set_dirs(&remote, conn_id, &path, dirs).await;
+    }
+    Ok(())
 }
 
 // ── Tests ────────────────────────────────────────────────────────────
@@ -141,12 +666,114 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_is_ignored() {
-        assert!(is_ignored(std::path::Path::new("/project/node_modules/pkg/index.js")));
-        assert!(is_ignored(std::path::Path::new("/project/target/debug/app")));
-        assert!(is_ignored(std::path::Path::new("/project/__pycache__/mod.pyc")));
-        assert!(!is_ignored(std::path::Path::new("/project/src/main.rs")));
-        assert!(!is_ignored(std::path::Path::new("/project/package.json")));
+    fn test_ignore_policy() {
+        let root = Path::new("/project");
+        let policy = IgnorePolicy::new(root, &WatcherSettings::default());
+        assert!(policy.is_ignored(Path::new("/project/node_modules/pkg/index.js"), false));
+        assert!(policy.is_ignored(Path::new("/project/target/debug/app"), false));
+        assert!(policy.is_ignored(Path::new("/project/web/__pycache__/mod.pyc"), false));
+        assert!(!policy.is_ignored(Path::new("/project/src/main.rs"), false));
+        assert!(!policy.is_ignored(Path::new("/project/package.json"), false));
+
+        let settings = WatcherSettings {
+            ignore: vec!["*.log".into(), "/generated/".into()],
+            ..Default::default()
+        };
+        let policy = IgnorePolicy::new(root, &settings);
+        assert!(policy.is_ignored(Path::new("/project/logs/app.log"), false));
+        assert!(policy.is_ignored(Path::new("/project/generated/api.ts"), false));
+        assert!(!policy.is_ignored(Path::new("/project/src/generated/api.ts"), false));
+        assert!(!policy.is_ignored(Path::new("/project/node_modules/pkg/index.js"), false));
+    }
+
+    fn event(kind: EventKind, paths: &[&str]) -> notify::Event {
+        let mut event = notify::Event::new(kind);
+        event.paths = paths.iter().map(PathBuf::from).collect();
+        event
+    }
+
+    #[test]
+    fn test_translate_kinds() {
+        let events = [
+            event(EventKind::Create(CreateKind::Folder), &["/nope/dir"]),
+            event(
+                EventKind::Modify(ModifyKind::Data(notify::event::DataChange::Any)),
+                &["/nope/a"],
+            ),
+            event(EventKind::Remove(RemoveKind::File), &["/nope/b"]),
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/nope/c", "/nope/d"]),
+            event(EventKind::Access(notify::event::AccessKind::Any), &["/nope/e"]),
+        ];
+        let changes = translate(&events);
+        assert_eq!(changes.len(), 4);
+        assert_eq!(changes[0], change(FsChangeKind::Created, Path::new("/nope/dir"), None, true));
+        // Modified but gone by the time we look
+        assert_eq!(changes[1].kind, FsChangeKind::Removed);
+        assert_eq!(changes[2].kind, FsChangeKind::Removed);
+        assert_eq!(
+            changes[3],
+            change(FsChangeKind::Renamed, Path::new("/nope/d"), Some(Path::new("/nope/c")), false)
+        );
+    }
+
+    #[test]
+    fn test_translate_coalesces() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "x").unwrap();
+        let p = file.to_str().unwrap();
+        let modify = EventKind::Modify(ModifyKind::Data(notify::event::DataChange::Content));
+
+        let created_then_modified =
+            [event(EventKind::Create(CreateKind::File), &[p]), event(modify, &[p])];
+        let changes = translate(&created_then_modified);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, FsChangeKind::Created);
+
+        let replaced = [
+            event(EventKind::Remove(RemoveKind::File), &[p]),
+            event(EventKind::Create(CreateKind::File), &[p]),
+        ];
+        assert_eq!(translate(&replaced)[0].kind, FsChangeKind::Modified);
+
+        let temp = [
+            event(EventKind::Create(CreateKind::File), &["/nope/tmp"]),
+            event(EventKind::Remove(RemoveKind::File), &["/nope/tmp"]),
+        ];
+        assert!(translate(&temp).is_empty());
+    }
+
+    #[test]
+    fn test_atomic_save_is_a_modify() {
+        let target = Path::new("/project/src/main.rs");
+        let temp = write::temp_name(target);
+        let t = temp.to_str().unwrap();
+        let events = [
+            event(EventKind::Create(CreateKind::File), &[t]),
+            event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                &[t, "/project/src/main.rs"],
+            ),
+        ];
+        let policy = IgnorePolicy::new(Path::new("/project"), &WatcherSettings::default());
+        let changes: Vec<FsChange> = translate(&events)
+            .into_iter()
+            .filter(|c| !policy.is_ignored(Path::new(&c.path), c.is_dir))
+            .collect();
+        assert_eq!(changes, vec![change(FsChangeKind::Modified, target, None, false)]);
+        assert!(policy.is_ignored(&temp, false));
+    }
+
+    #[test]
+    fn test_change_kind_wire_names() {
+        let kinds = [
+            FsChangeKind::Created,
+            FsChangeKind::Modified,
+            FsChangeKind::Removed,
+            FsChangeKind::Renamed,
+        ];
+        let names: Vec<String> = kinds.iter().map(|k| serde_json::to_string(k).unwrap()).collect();
+        assert_eq!(names, ["\"created\"", "\"modified\"", "\"removed\"", "\"renamed\""]);
     }
 
     #[test]
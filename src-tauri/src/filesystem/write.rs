@@ -1,9 +1,141 @@
-use crate::error::KodiqError;
+use super::encoding::{self, TextEncoding};
+use super::format::{self, FormatDiagnostic};
+use super::local_history::{HistorySource, HistoryState};
+use super::sandbox;
+use crate::error::{FileSnapshot, KodiqError, WriteConflict};
+use crate::lsp::host::LspState;
 use crate::ssh::{self, SshState};
+use crate::state::DbState;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// ── Versions & conflicts ─────────────────────────────────────────────
+
+/// Identifies the on-disk state a buffer was loaded from. `read_file`
+/// returns both fields; a save may pass back either one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FileVersion {
+    /// Milliseconds since the Unix epoch (whole seconds over SFTP).
+    pub mtime: Option<u64>,
+    /// sha256 of the bytes on disk, hex.
+    pub hash: Option<String>,
+}
+
+/// What a save produced. `formatted` is set when the format step changed
+/// the content, so the editor can replace its buffer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteResult {
+    #[serde(flatten)]
+    pub version: FileVersion,
+    pub formatted: Option<String>,
+    pub diagnostics: Vec<FormatDiagnostic>,
+}
+
+pub fn version_of(bytes: &[u8], mtime: Option<u64>) -> FileVersion {
+    FileVersion { mtime, hash: Some(format!("{:x}", Sha256::digest(bytes))) }
+}
+
+pub fn millis(time: std::io::Result<std::time::SystemTime>) -> Option<u64> {
+    let since = time.ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since.as_millis() as u64)
+}
+
+/// Compare what is on disk (`None` = missing) against the version the
+/// caller loaded. A hash wins over an mtime when both are given.
+/// `ours` is the text being saved and `ours_bytes` its encoded form.
+pub fn check_version(
+    path: &str,
+    expected: &FileVersion,
+    disk: Option<(&[u8], Option<u64>)>,
+    ours: &str,
+    ours_bytes: &[u8],
+) -> Result<(), KodiqError> {
+    let current = disk.map(|(bytes, mtime)| version_of(bytes, mtime));
+    let unchanged = match (&current, &expected.hash, expected.mtime) {
+        (None, None, None) => true,
+        (None, ..) => false,
+        (Some(cur), Some(hash), _) => cur.hash.as_ref() == Some(hash),
+        (Some(cur), None, Some(mtime)) => cur.mtime == Some(mtime),
+        (Some(_), None, None) => true,
+    };
+    if unchanged {
+        return Ok(());
+    }
+
+    let ours_version = version_of(ours_bytes, None);
+    Err(KodiqError::WriteConflict(Box::new(WriteConflict {
+        path: path.to_string(),
+        disk: disk.zip(current).map(|((bytes, mtime), version)| FileSnapshot {
+            content: encoding::decode_detected(bytes),
+            mtime,
+            hash: version.hash.unwrap_or_default(),
+        }),
+        ours: FileSnapshot {
+            content: ours.to_string(),
+            mtime: None,
+            hash: ours_version.hash.unwrap_or_default(),
+        },
+    })))
+}
+
+/// Temp file name next to `target`, hidden and unique per write.
+pub fn temp_name(target: &Path) -> PathBuf {
+    let name = target.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let id = &uuid::Uuid::new_v4().simple().to_string()[..8];
+    target.with_file_name(format!(".{}.kodiq-{}.tmp", name, id))
+}
+
+/// Whether `path` is an in-flight save file made by [`temp_name`].
+pub fn is_temp_name(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with('.') && n.ends_with(".tmp") && n.contains(".kodiq-"))
+}
+
+// ── Local ────────────────────────────────────────────────────────────
+
+/// Replace `path` without ever exposing a truncated file: write a temp file
+/// beside it, fsync, then rename over the original. Keeps the original's
+/// permissions and writes through symlinks instead of replacing them.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let target = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let permissions = std::fs::metadata(&target).ok().map(|m| m.permissions());
+    let temp = temp_name(&target);
+
+    let written = (|| {
+        let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(&temp)?;
+        file.write_all(bytes)?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.sync_all()?;
+        std::fs::rename(&temp, &target)
+    })();
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+        return written;
+    }
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    if let Some(dir) = target.parent() {
+        let _ = std::fs::File::open(dir).and_then(|d| d.sync_all());
+    }
+    Ok(())
+}
 
 /// Local file write logic — extracted for testability without Tauri state.
-fn write_file_local(path: &str, content: &str) -> Result<(), KodiqError> {
-    let file_path = std::path::Path::new(path);
+fn write_file_local(
+    path: &str,
+    content: &str,
+    encoding: TextEncoding,
+    expected: Option<&FileVersion>,
+) -> Result<FileVersion, KodiqError> {
+    let file_path = Path::new(path);
 
     // Ensure parent directory exists
     if let Some(parent) = file_path.parent() {
@@ -15,28 +147,92 @@ fn write_file_local(path: &str, content: &str) -> Result<(), KodiqError> {
         }
     }
 
-    std::fs::write(path, content)?;
-    Ok(())
+    let bytes = encoding::encode(content, encoding)?;
+    if let Some(expected) = expected {
+        let disk = std::fs::read(file_path).ok();
+        let mtime = millis(std::fs::metadata(file_path).and_then(|m| m.modified()));
+        check_version(path, expected, disk.as_deref().map(|d| (d, mtime)), content, &bytes)?;
+    }
+
+    write_atomic(file_path, &bytes)?;
+    let mtime = millis(std::fs::metadata(file_path).and_then(|m| m.modified()));
+    Ok(version_of(&bytes, mtime))
 }
 
-/// Write content to a file (create or overwrite).
-/// Used by the editor save action (Cmd+S).
-/// If `connection_id` is provided, writes to remote via SFTP.
-#[tracing::instrument(skip(content, ssh_state))]
+/// Save the editor buffer, formatting it first when asked, and return the new version.
+#[tracing::instrument(skip(content, ssh_state, history, db, lsp))]
+#[allow(clippy::too_many_arguments)]
 #[tauri::command(async)]
 pub async fn write_file(
+    webview: tauri::Webview,
     path: String,
     content: String,
+    encoding: Option<TextEncoding>,
+    expected: Option<FileVersion>,
     connection_id: Option<String>,
+    format: Option<bool>,
     ssh_state: tauri::State<'_, SshState>,
-) -> Result<(), KodiqError> {
+    history: tauri::State<'_, HistoryState>,
+    db: tauri::State<'_, DbState>,
+    lsp: tauri::State<'_, LspState>,
+) -> Result<WriteResult, KodiqError> {
+    sandbox::check(&webview, &path, connection_id.as_deref())?;
+    let encoding = encoding.unwrap_or(TextEncoding::UTF8);
+
     // Remote: delegate to SFTP
     if let Some(ref conn_id) = connection_id {
-        return ssh::filesystem::sftp_write_file(&path, &content, &ssh_state, conn_id).await;
+        let version = ssh::filesystem::sftp_write_file(
+            &path,
+            &content,
+            encoding,
+            expected.as_ref(),
+            &ssh_state,
+            conn_id,
+        )
+        .await?;
+        return Ok(WriteResult { version, formatted: None, diagnostics: Vec::new() });
     }
 
-    // Local
-    write_file_local(&path, &content)
+    let file_path = Path::new(&path);
+    // Formatting never blocks a save
+    let outcome = match format::settings_for(&db, file_path) {
+        Ok((root, settings)) if format.unwrap_or(settings.on_save) => {
+            let (file, input) = (file_path.to_path_buf(), content.clone());
+            tauri::async_runtime::spawn_blocking(move || {
+                format::format_content(&root, &file, &input, &settings)
+            })
+            .await
+            .map_err(|e| tracing::warn!("Formatter failed for {}: {}", path, e))
+            .ok()
+        }
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!("Format settings unavailable for {}: {}", path, e);
+            None
+        }
+    };
+    let content = match &outcome {
+        Some(outcome) => &outcome.content,
+        None => &content,
+    };
+
+    // Local — keep what the save replaces; the watcher records the result.
+    // History failures never block a save.
+    if let Err(e) = history.snapshot_file(file_path, HistorySource::Save) {
+        tracing::warn!("History snapshot failed for {}: {}", path, e);
+    }
+    let version = write_file_local(&path, content, encoding, expected.as_ref())?;
+    if let Err(e) = lsp.document_saved(file_path, content) {
+        tracing::warn!("LSP save sync failed for {}: {}", path, e);
+    }
+    Ok(match outcome {
+        Some(outcome) => WriteResult {
+            version,
+            formatted: outcome.changed.then_some(outcome.content),
+            diagnostics: outcome.diagnostics,
+        },
+        None => WriteResult { version, formatted: None, diagnostics: Vec::new() },
+    })
 }
 
 #[cfg(test)]
@@ -49,7 +245,7 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("test.txt").to_string_lossy().to_string();
 
-        write_file_local(&path, "hello world").unwrap();
+        write_file_local(&path, "hello world", TextEncoding::UTF8, None).unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(content, "hello world");
@@ -60,8 +256,8 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("test.txt").to_string_lossy().to_string();
 
-        write_file_local(&path, "first").unwrap();
-        write_file_local(&path, "second").unwrap();
+        write_file_local(&path, "first", TextEncoding::UTF8, None).unwrap();
+        write_file_local(&path, "second", TextEncoding::UTF8, None).unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(content, "second");
@@ -69,9 +265,67 @@ mod tests {
 
     #[test]
     fn test_write_nonexistent_parent() {
-        let result = write_file_local("/nonexistent/dir/file.txt", "data");
+        let result =
+            write_file_local("/nonexistent/dir/file.txt", "data", TextEncoding::UTF8, None);
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(err.contains("Parent directory does not exist"));
     }
+
+    #[test]
+    fn test_write_detects_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.txt").to_string_lossy().to_string();
+
+        let loaded = write_file_local(&path, "v1", TextEncoding::UTF8, None).unwrap();
+        let saved = write_file_local(&path, "v2", TextEncoding::UTF8, Some(&loaded)).unwrap();
+
+        // Someone else edits the file after our save
+        fs::write(&path, "agent edit").unwrap();
+        let by_hash = FileVersion { mtime: None, hash: saved.hash.clone() };
+        match write_file_local(&path, "v3", TextEncoding::UTF8, Some(&by_hash)) {
+            Err(KodiqError::WriteConflict(c)) => {
+                assert_eq!(c.disk.unwrap().content, "agent edit");
+                assert_eq!(c.ours.content, "v3");
+            }
+            other => panic!("expected conflict, got {:?}", other),
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "agent edit");
+
+        // Deleted on disk counts as a conflict too
+        fs::remove_file(&path).unwrap();
+        match write_file_local(&path, "v3", TextEncoding::UTF8, Some(&saved)) {
+            Err(KodiqError::WriteConflict(c)) => assert!(c.disk.is_none()),
+            other => panic!("expected conflict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_version_by_mtime() {
+        let expected = FileVersion { mtime: Some(1000), hash: None };
+        assert!(check_version("f", &expected, Some((b"x", Some(1000))), "y", b"y").is_ok());
+        assert!(check_version("f", &expected, Some((b"x", Some(2000))), "y", b"y").is_err());
+        assert!(check_version("f", &FileVersion::default(), None, "y", b"y").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_preserves_mode_and_symlink() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().join("run.sh");
+        fs::write(&real, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&real, fs::Permissions::from_mode(0o750)).unwrap();
+        let link = dir.path().join("link.sh");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        write_file_local(&link.to_string_lossy(), "#!/bin/sh\necho hi\n", TextEncoding::UTF8, None)
+            .unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), "#!/bin/sh\necho hi\n");
+        assert_eq!(fs::metadata(&real).unwrap().permissions().mode() & 0o777, 0o750);
+        // No temp files left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
 }
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 use std::process::Command;
 
 use crate::error::KodiqError;
+use crate::filesystem::sandbox;
 use crate::ssh::{self, SshState};
 
 // ── Git helpers ──────────────────────────────────────────────────────────────
@@ -21,7 +22,7 @@ fn git_run(path: &str, args: &[&str]) -> Result<String, KodiqError> {
 }
 
 /// Run a git command, return None on failure instead of error
-fn git_try(path: &str, args: &[&str]) -> Option<String> {
+pub(crate) fn git_try(path: &str, args: &[&str]) -> Option<String> {
     Command::new("git")
         .args(args)
         .current_dir(path)
@@ -36,11 +37,13 @@ fn git_try(path: &str, args: &[&str]) -> Option<String> {
 
 #[tauri::command(async)]
 pub async fn git_stage(
+    webview: tauri::Webview,
     path: String,
     files: Vec<String>,
     connection_id: Option<String>,
     ssh_state: tauri::State<'_, SshState>,
 ) -> Result<(), KodiqError> {
+    sandbox::check_webview(&webview)?;
     if files.is_empty() {
         return Ok(());
     }
@@ -61,11 +64,13 @@ pub async fn git_stage(
 
 #[tauri::command(async)]
 pub async fn git_unstage(
+    webview: tauri::Webview,
     path: String,
     files: Vec<String>,
     connection_id: Option<String>,
     ssh_state: tauri::State<'_, SshState>,
 ) -> Result<(), KodiqError> {
+    sandbox::check_webview(&webview)?;
     if files.is_empty() {
         return Ok(());
     }
@@ -91,10 +96,12 @@ pub async fn git_unstage(
 
 #[tauri::command(async)]
 pub async fn git_stage_all(
+    webview: tauri::Webview,
     path: String,
     connection_id: Option<String>,
     ssh_state: tauri::State<'_, SshState>,
 ) -> Result<(), KodiqError> {
+    sandbox::check_webview(&webview)?;
     if let Some(ref conn_id) = connection_id {
         ssh::git::ssh_git_run(&ssh_state, conn_id, &path, "add -A").await?;
         return Ok(());
@@ -105,10 +112,12 @@ pub async fn git_stage_all(
 
 #[tauri::command(async)]
 pub async fn git_unstage_all(
+    webview: tauri::Webview,
     path: String,
     connection_id: Option<String>,
     ssh_state: tauri::State<'_, SshState>,
 ) -> Result<(), KodiqError> {
+    sandbox::check_webview(&webview)?;
     if let Some(ref conn_id) = connection_id {
         ssh::git::ssh_git_run(&ssh_state, conn_id, &path, "reset HEAD").await?;
         return Ok(());
@@ -121,11 +130,13 @@ pub async fn git_unstage_all(
 
 #[tauri::command(async)]
 pub async fn git_commit(
+    webview: tauri::Webview,
     path: String,
     message: String,
     connection_id: Option<String>,
     ssh_state: tauri::State<'_, SshState>,
 ) -> Result<serde_json::Value, KodiqError> {
+    sandbox::check_webview(&webview)?;
     if message.trim().is_empty() {
         return Err(KodiqError::Other("Commit message cannot be empty".into()));
     }
@@ -150,12 +161,14 @@ pub async fn git_commit(
 
 #[tauri::command(async)]
 pub async fn git_diff(
+    webview: tauri::Webview,
     path: String,
     file: String,
     staged: bool,
     connection_id: Option<String>,
     ssh_state: tauri::State<'_, SshState>,
 ) -> Result<String, KodiqError> {
+    sandbox::check_webview(&webview)?;
     if let Some(ref conn_id) = connection_id {
         let quoted_file = ssh::git::shell_quote(&file);
         let args = if staged {
@@ -175,10 +188,12 @@ pub async fn git_diff(
 /// Note: remote project stats are not yet supported (returns error).
 #[tauri::command(async)]
 pub async fn get_project_stats(
+    webview: tauri::Webview,
     path: String,
     connection_id: Option<String>,
     _ssh_state: tauri::State<'_, SshState>,
 ) -> Result<serde_json::Value, KodiqError> {
+    sandbox::check_webview(&webview)?;
     if connection_id.is_some() {
         // Remote stats via SFTP walk would be very slow.
         // Return a minimal placeholder; Phase 6 will add full remote stats.
@@ -299,7 +314,7 @@ pub async fn get_project_stats(
     );
 
     let mut ext_vec: Vec<_> = ext_counts.into_iter().collect();
-    ext_vec.sort_by(|a, b| b.1.cmp(&a.1));
+    ext_vec.sort_by_key(|e| std::cmp::Reverse(e.1));
     ext_vec.truncate(10);
     stack.sort();
     stack.dedup();
@@ -367,15 +382,99 @@ fn parse_status_porcelain(
     (staged_files, unstaged_files, changed_files)
 }
 
+/// Changed paths as `(repo-relative path, "XY", kind)` for decorating file
+/// listings. Renames report the new path; quoted paths are unescaped.
+pub(crate) fn status_entries(status_raw: &str) -> Vec<(String, String, String)> {
+    let (_, _, changed) = parse_status_porcelain(status_raw);
+    changed
+        .iter()
+        .filter_map(|c| {
+            let file = c["file"].as_str()?;
+            let file = file.rsplit(" -> ").next().unwrap_or(file);
+            Some((
+                unquote_path(file),
+                c["status"].as_str()?.to_string(),
+                c["kind"].as_str()?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Undo git's C-style quoting of unusual paths (`"caf\303\251.txt"`).
+fn unquote_path(file: &str) -> String {
+    let Some(inner) = file.strip_prefix('"').and_then(|f| f.strip_suffix('"')) else {
+        return file.to_string();
+    };
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.bytes().peekable();
+    while let Some(b) = chars.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        match chars.next() {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(d @ b'0'..=b'7') => {
+                let mut value = (d - b'0') as u32;
+                for _ in 0..2 {
+                    match chars.peek() {
+                        Some(&o @ b'0'..=b'7') => {
+                            value = value * 8 + (o - b'0') as u32;
+                            chars.next();
+                        }
+                        _ => break,
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            Some(other) => bytes.push(other),
+            None => {}
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Repo toplevel and raw `status --porcelain` for a local path.
+/// Unlike `git_try`, keeps the leading space of the first status line.
+pub(crate) fn local_status(path: &str) -> Option<(String, String)> {
+    let toplevel = git_try(path, &["rev-parse", "--show-toplevel"])?;
+    let output = Command::new("git")
+        .args(["status", "--porcelain"])
+        .current_dir(path)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    Some((toplevel, String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// Remote counterpart of `local_status`, in a single exec round trip.
+/// The toplevel goes first so trimming the output cannot eat a status column.
+pub(crate) async fn remote_status(
+    ssh_state: &tauri::State<'_, SshState>,
+    connection_id: &str,
+    path: &str,
+) -> Option<(String, String)> {
+    let command = format!(
+        "cd {} && git rev-parse --show-toplevel && git status --porcelain",
+        ssh::git::shell_quote(path)
+    );
+    let output = ssh::git::ssh_run_command(ssh_state, connection_id, &command).await.ok()?;
+    let (toplevel, status) = output.split_once('\n').unwrap_or((&output, ""));
+    toplevel.starts_with('/').then(|| (toplevel.to_string(), status.to_string()))
+}
+
 /// Get git info for a project: branch, status, staged/unstaged files.
 /// If `connection_id` is provided, runs git on remote host via SSH exec.
 #[tracing::instrument(skip(ssh_state))]
 #[tauri::command(async)]
 pub async fn get_git_info(
+    webview: tauri::Webview,
     path: String,
     connection_id: Option<String>,
     ssh_state: tauri::State<'_, SshState>,
 ) -> Result<serde_json::Value, KodiqError> {
+    sandbox::check_webview(&webview)?;
     // ── Remote: git via SSH exec ────────────────────────────────────────
     if let Some(ref conn_id) = connection_id {
         let is_git =
@@ -0,0 +1,535 @@
+use super::readiness::{self, LogWatch, Wait};
+use super::schema::{self, CompoundConfig, LaunchIssue, Readiness, Step, StepKind};
+use crate::db;
+use crate::error::KodiqError;
+use crate::filesystem::sandbox;
+use crate::preview::server::{self as preview, ServerConfig, ServerState};
+use crate::ssh::port_forward::{self, PfState};
+use crate::ssh::SshState;
+use crate::state::{AppState, DbState};
+use crate::workspace;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+
+/// Finished launches kept for `launch_list`.
+const KEEP_FINISHED: usize = 20;
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StepStatus {
+    Pending,
+    Starting,
+    /// Started; its readiness check hasn't passed yet.
+    Waiting,
+    Ready,
+    Failed,
+    /// Never started: an earlier step failed or the launch was stopped.
+    Skipped,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LaunchStatus {
+    Starting,
+    /// Every step is ready.
+    Running,
+    Failed,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepRun {
+    pub name: String,
+    pub kind: String,
+    pub status: StepStatus,
+    /// The terminal, server or forward id once started.
+    pub handle: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A started compound launch; steps are in start order.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchRun {
+    pub id: String,
+    pub config_id: String,
+    pub name: String,
+    pub status: LaunchStatus,
+    pub steps: Vec<StepRun>,
+    pub started_at: i64,
+}
+
+// ── State ────────────────────────────────────────────────────────────
+
+struct Compound {
+    /// Start order, for pruning the oldest finished launches.
+    seq: u32,
+    info: Mutex<LaunchRun>,
+    cancelled: AtomicBool,
+}
+
+#[derive(Default)]
+pub struct Launches {
+    runs: Mutex<HashMap<String, Arc<Compound>>>,
+    next_id: AtomicU32,
+}
+
+pub type LaunchState = Arc<Launches>;
+
+pub fn new_launch_state() -> LaunchState {
+    Arc::new(Launches::default())
+}
+
+impl Launches {
+    /// Add a launch, dropping the oldest finished ones past `KEEP_FINISHED`.
+    fn insert(&self, compound: Arc<Compound>) -> Result<(), KodiqError> {
+        let id = compound.info.lock()?.id.clone();
+        let mut runs = self.runs.lock()?;
+        runs.insert(id, compound);
+        let mut finished: Vec<(u32, String)> = runs
+            .iter()
+            .filter(|(_, c)| c.is_finished())
+            .map(|(id, c)| (c.seq, id.clone()))
+            .collect();
+        finished.sort();
+        let excess = finished.len().saturating_sub(KEEP_FINISHED);
+        for (_, id) in finished.into_iter().take(excess) {
+            runs.remove(&id);
+        }
+        Ok(())
+    }
+}
+
+fn publish(app: &AppHandle, info: &LaunchRun) {
+    let _ = app.emit("launch-changed", info);
+}
+
+impl Compound {
+    fn is_finished(&self) -> bool {
+        self.info
+            .lock()
+            .is_ok_and(|i| matches!(i.status, LaunchStatus::Failed | LaunchStatus::Stopped))
+    }
+
+    /// Change step `slot` and tell the frontend.
+    fn update(&self, app: &AppHandle, slot: usize, change: impl FnOnce(&mut StepRun)) {
+        let Ok(mut info) = self.info.lock() else { return };
+        change(&mut info.steps[slot]);
+        publish(app, &info);
+    }
+
+    /// End the launch: steps that never started are skipped.
+    fn finish(&self, app: &AppHandle, status: LaunchStatus) {
+        let Ok(mut info) = self.info.lock() else { return };
+        for step in info.steps.iter_mut().filter(|s| s.status == StepStatus::Pending) {
+            step.status = StepStatus::Skipped;
+        }
+        if info.status == LaunchStatus::Starting {
+            info.status = status;
+        }
+        publish(app, &info);
+    }
+}
+
+// ── Steps ────────────────────────────────────────────────────────────
+
+/// Relative paths are the project's; no path means the project root.
+fn resolve_cwd(root: Option<&Path>, cwd: Option<&str>) -> Option<String> {
+    let path = match (root, cwd) {
+        (Some(root), Some(cwd)) => root.join(cwd),
+        (None, Some(cwd)) => PathBuf::from(cwd),
+        (Some(root), None) => root.to_path_buf(),
+        (None, None) => return None,
+    };
+    Some(path.to_string_lossy().to_string())
+}
+
+/// The workspace env files under the step's own `env`.
+fn step_env(root: Option<&Path>, env: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut merged = root.map(workspace::config::env).unwrap_or_default();
+    merged.extend(env.clone());
+    merged
+}
+
+/// Start a step. Returns its terminal, server or forward id.
+fn start(app: &AppHandle, step: &Step, root: Option<&Path>) -> Result<String, String> {
+    match &step.kind {
+        StepKind::Terminal { command, cwd, env } => crate::terminal::manager::spawn(
+            app,
+            &app.state::<AppState>(),
+            command.clone(),
+            resolve_cwd(root, cwd.as_deref()),
+            None,
+            Some(step_env(root, env)),
+        ),
+        StepKind::Server { command, args, cwd, env } => preview::preview_start_server(
+            app.clone(),
+            app.state::<ServerState>(),
+            ServerConfig {
+                name: step.name.clone(),
+                command: command.clone(),
+                args: args.clone(),
+                cwd: resolve_cwd(root, cwd.as_deref()),
+                env: Some(step_env(root, env)),
+            },
+        ),
+        StepKind::PortForward { connection_id, local_port, remote_host, remote_port } => {
+            tauri::async_runtime::block_on(port_forward::ssh_start_forward(
+                connection_id.clone(),
+                *local_port,
+                remote_host.clone(),
+                *remote_port,
+                app.state::<SshState>(),
+                app.state::<PfState>(),
+            ))
+            .map_err(|e| e.to_string())
+        }
+    }
+}
+
+fn stop(app: &AppHandle, kind: &str, handle: &str) {
+    let result = match kind {
+        "terminal" => {
+            crate::terminal::manager::close_terminal(
+                app.clone(),
+                handle.to_string(),
+                app.state::<AppState>(),
+            );
+            Ok(())
+        }
+        "server" => preview::preview_stop_server(
+            app.clone(),
+            app.state::<ServerState>(),
+            handle.to_string(),
+        ),
+        _ => tauri::async_runtime::block_on(port_forward::ssh_stop_forward(
+            handle.to_string(),
+            app.state::<PfState>(),
+        ))
+        .map_err(|e| e.to_string()),
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to stop {} {}: {}", kind, handle, e);
+    }
+}
+
+/// Listen to the output of terminals or servers (whichever `kind` starts)
+/// for `pattern`. Set up before the step starts so no line is missed.
+fn watch_output(
+    app: &AppHandle,
+    kind: &StepKind,
+    pattern: Regex,
+) -> (Arc<Mutex<LogWatch>>, tauri::EventId) {
+    let watch = Arc::new(Mutex::new(LogWatch::new(pattern)));
+    let sink = watch.clone();
+    let terminal = matches!(kind, StepKind::Terminal { .. });
+    let event = if terminal { "pty-output" } else { "preview://server-log" };
+    let id = app.listen(event, move |event| {
+        let Ok(payload) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+            return;
+        };
+        let Some(source) = payload["id"].as_str() else { return };
+        let text = if terminal {
+            payload["data"].as_str().map(String::from)
+        } else {
+            payload["entry"]["message"].as_str().map(|m| format!("{}\n", m))
+        };
+        if let (Some(text), Ok(mut watch)) = (text, sink.lock()) {
+            watch.feed(source, &text);
+        }
+    });
+    (watch, id)
+}
+
+/// Start one step and wait until it's ready.
+fn launch_step(
+    app: &AppHandle,
+    compound: &Compound,
+    slot: usize,
+    step: &Step,
+    root: Option<&Path>,
+) -> Result<Wait, String> {
+    compound.update(app, slot, |s| s.status = StepStatus::Starting);
+    let output = match &step.ready {
+        Some(Readiness::Log { pattern }) => {
+            let pattern = Regex::new(pattern).map_err(|e| e.to_string())?;
+            Some(watch_output(app, &step.kind, pattern))
+        }
+        _ => None,
+    };
+    let started = start(app, step, root);
+    let outcome = started.and_then(|handle| {
+        compound.update(app, slot, |s| {
+            s.handle = Some(handle.clone());
+            s.status = StepStatus::Waiting;
+        });
+        let timeout = Duration::from_millis(step.timeout_ms.unwrap_or(schema::DEFAULT_TIMEOUT_MS));
+        let wait = match &step.ready {
+            None => Wait::Ready,
+            Some(Readiness::Port { port, host }) => {
+                let host = host.as_deref().unwrap_or("localhost");
+                readiness::wait_until(timeout, &compound.cancelled, || {
+                    readiness::port_open(host, *port)
+                })
+            }
+            Some(Readiness::Http { url }) => {
+                readiness::wait_until(timeout, &compound.cancelled, || readiness::http_ok(url))
+            }
+            Some(Readiness::Log { .. }) => {
+                let watch = output.as_ref().map(|(watch, _)| watch.clone());
+                readiness::wait_until(timeout, &compound.cancelled, || {
+                    watch.as_ref().is_some_and(|w| w.lock().is_ok_and(|w| w.matched(&handle)))
+                })
+            }
+        };
+        match wait {
+            Wait::TimedOut => Err(format!("Not ready after {}s", timeout.as_secs())),
+            other => Ok(other),
+        }
+    });
+    if let Some((_, id)) = output {
+        app.unlisten(id);
+    }
+    outcome
+}
+
+/// Start the steps in order, each once the previous one is ready. A
+/// failure skips the rest; already started steps keep running.
+fn run(
+    app: AppHandle,
+    compound: Arc<Compound>,
+    config: CompoundConfig,
+    order: Vec<usize>,
+    root: Option<PathBuf>,
+) {
+    for (slot, index) in order.into_iter().enumerate() {
+        if compound.cancelled.load(Ordering::SeqCst) {
+            break;
+        }
+        let step = &config.steps[index];
+        match launch_step(&app, &compound, slot, step, root.as_deref()) {
+            Ok(Wait::Ready) => compound.update(&app, slot, |s| {
+                if s.status == StepStatus::Waiting {
+                    s.status = StepStatus::Ready;
+                }
+            }),
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("Launch step {} failed: {}", step.name, e);
+                compound.update(&app, slot, |s| {
+                    s.status = StepStatus::Failed;
+                    s.error = Some(e);
+                });
+                compound.finish(&app, LaunchStatus::Failed);
+                return;
+            }
+        }
+        // Stopped while this step was starting, before `stop_group` could
+        // see its handle
+        if compound.cancelled.load(Ordering::SeqCst) {
+            stop_group(&app, &compound);
+        }
+    }
+    compound.finish(&app, LaunchStatus::Running);
+}
+
+/// Stop every started step, last started first. Each is stopped once, by
+/// whichever caller claims it.
+fn stop_group(app: &AppHandle, compound: &Compound) {
+    compound.cancelled.store(true, Ordering::SeqCst);
+    let claimed: Vec<(String, String)> = {
+        let Ok(mut info) = compound.info.lock() else { return };
+        info.status = LaunchStatus::Stopped;
+        let mut claimed = Vec::new();
+        for step in info.steps.iter_mut().rev() {
+            match (&step.handle, step.status) {
+                (_, StepStatus::Pending) => step.status = StepStatus::Skipped,
+                (Some(handle), status) if status != StepStatus::Stopped => {
+                    claimed.push((step.kind.clone(), handle.clone()));
+                    step.status = StepStatus::Stopped;
+                }
+                _ => {}
+            }
+        }
+        publish(app, &info);
+        claimed
+    };
+    for (kind, handle) in claimed {
+        stop(app, &kind, &handle);
+    }
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// Check a compound launch's JSON without saving it. Empty when valid.
+#[tauri::command]
+pub fn launch_validate(
+    webview: tauri::Webview,
+    config: String,
+) -> Result<Vec<LaunchIssue>, KodiqError> {
+    sandbox::check_webview(&webview)?;
+    Ok(match schema::parse(&config) {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors.into_iter().map(Into::into).collect(),
+    })
+}
+
+/// Start a saved compound launch, or one from the workspace file of `root`
+/// (`repo:` ids). Progress is emitted as `launch-changed`.
+#[tracing::instrument(skip(app, webview, db, launches))]
+#[tauri::command]
+pub fn launch_start(
+    app: AppHandle,
+    webview: tauri::Webview,
+    config_id: String,
+    root: Option<String>,
+    db: tauri::State<'_, DbState>,
+    launches: tauri::State<'_, LaunchState>,
+) -> Result<LaunchRun, KodiqError> {
+    let (saved, root) = if config_id.starts_with(workspace::sync::REPO_ID_PREFIX) {
+        let root =
+            root.ok_or_else(|| KodiqError::NotFound(format!("Project for {}", config_id)))?;
+        sandbox::check(&webview, &root, None)?;
+        let saved = workspace::sync::find_launch_config(Path::new(&root), &config_id)
+            .ok_or_else(|| KodiqError::NotFound(format!("Launch config {}", config_id)))?;
+        (saved, Some(PathBuf::from(root)))
+    } else {
+        sandbox::check_webview(&webview)?;
+        let conn = db.connection.lock()?;
+        let saved = db::launch_configs::get(&conn, &config_id)
+            .map_err(|_| KodiqError::NotFound(format!("Launch config {}", config_id)))?;
+        let root = match &saved.project_id {
+            Some(project) => db::projects::path_of(&conn, project)?.map(PathBuf::from),
+            None => None,
+        };
+        (saved, root)
+    };
+    if saved.cli_name != schema::COMPOUND {
+        return Err(KodiqError::Other(format!("{} is not a compound launch", saved.profile_name)));
+    }
+    let config = schema::check(&saved.config)?;
+    let order = schema::order(&config);
+
+    let seq = launches.next_id.fetch_add(1, Ordering::SeqCst);
+    let info = LaunchRun {
+        id: format!("launch-{}", seq),
+        config_id,
+        name: saved.profile_name,
+        status: LaunchStatus::Starting,
+        steps: order
+            .iter()
+            .map(|&i| StepRun {
+                name: config.steps[i].name.clone(),
+                kind: config.steps[i].kind.as_str().to_string(),
+                status: StepStatus::Pending,
+                handle: None,
+                error: None,
+            })
+            .collect(),
+        started_at: now(),
+    };
+    let compound = Arc::new(Compound {
+        seq,
+        info: Mutex::new(info.clone()),
+        cancelled: AtomicBool::new(false),
+    });
+    launches.insert(compound.clone())?;
+    tracing::info!("Starting compound launch {} ({} steps)", info.name, order.len());
+
+    std::thread::spawn(move || run(app, compound, config, order, root));
+    Ok(info)
+}
+
+/// Stop a compound launch and everything it started.
+#[tauri::command]
+pub fn launch_stop(
+    app: AppHandle,
+    webview: tauri::Webview,
+    id: String,
+    launches: tauri::State<'_, LaunchState>,
+) -> Result<LaunchRun, KodiqError> {
+    sandbox::check_webview(&webview)?;
+    let compound = launches
+        .runs
+        .lock()?
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| KodiqError::NotFound(format!("Launch {}", id)))?;
+    stop_group(&app, &compound);
+    let info = compound.info.lock()?.clone();
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn launch_list(
+    webview: tauri::Webview,
+    launches: tauri::State<'_, LaunchState>,
+) -> Result<Vec<LaunchRun>, KodiqError> {
+    sandbox::check_webview(&webview)?;
+    let runs = launches.runs.lock()?;
+    let mut list = Vec::new();
+    for compound in runs.values() {
+        list.push(compound.info.lock()?.clone());
+    }
+    list.sort_by_key(|r| r.started_at);
+    Ok(list)
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_cwd() {
+        let root = Path::new("/work/app");
+        assert_eq!(resolve_cwd(Some(root), Some("web")).as_deref(), Some("/work/app/web"));
+        assert_eq!(resolve_cwd(Some(root), Some("/srv")).as_deref(), Some("/srv"));
+        assert_eq!(resolve_cwd(Some(root), None).as_deref(), Some("/work/app"));
+        assert_eq!(resolve_cwd(None, None), None);
+    }
+
+    #[test]
+    fn test_insert_prunes_oldest_finished() {
+        let launches = Launches::default();
+        let compound = |seq: u32, status: LaunchStatus| {
+            Arc::new(Compound {
+                seq,
+                info: Mutex::new(LaunchRun {
+                    id: format!("launch-{}", seq),
+                    config_id: "c".into(),
+                    name: "dev".into(),
+                    status,
+                    steps: Vec::new(),
+                    started_at: 0,
+                }),
+                cancelled: AtomicBool::new(false),
+            })
+        };
+        launches.insert(compound(0, LaunchStatus::Running)).unwrap();
+        for seq in 1..=KEEP_FINISHED as u32 + 5 {
+            launches.insert(compound(seq, LaunchStatus::Stopped)).unwrap();
+        }
+
+        let runs = launches.runs.lock().unwrap();
+        assert_eq!(runs.len(), KEEP_FINISHED + 1);
+        assert!(runs.contains_key("launch-0")); // still running
+        assert!(!runs.contains_key("launch-5"));
+        assert!(runs.contains_key("launch-6"));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod compound;
+pub mod readiness;
+pub mod schema;
@@ -0,0 +1,178 @@
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+/// Output kept per source while waiting for a log pattern.
+const LOG_WINDOW: usize = 16 * 1024;
+
+fn ansi_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\x1b\[[0-9;?]*[a-zA-Z]|\x1b\].*?(?:\x07|\x1b\\)").unwrap())
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Wait {
+    Ready,
+    TimedOut,
+    Cancelled,
+}
+
+/// Poll `check` until it passes, `timeout` runs out or `cancelled` is set.
+pub fn wait_until(
+    timeout: Duration,
+    cancelled: &AtomicBool,
+    mut check: impl FnMut() -> bool,
+) -> Wait {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if cancelled.load(Ordering::SeqCst) {
+            return Wait::Cancelled;
+        }
+        if check() {
+            return Wait::Ready;
+        }
+        if Instant::now() >= deadline {
+            return Wait::TimedOut;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Whether something accepts connections on `host:port`.
+pub fn port_open(host: &str, port: u16) -> bool {
+    let Ok(addrs) = (host, port).to_socket_addrs() else { return false };
+    addrs.into_iter().any(|addr| TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok())
+}
+
+/// Whether a GET of the `http://` `url` answers 200.
+pub fn http_ok(url: &str) -> bool {
+    let Ok(url) = url::Url::parse(url) else { return false };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+    let Some(addr) = (host, port).to_socket_addrs().ok().and_then(|mut a| a.next()) else {
+        return false;
+    };
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) else { return false };
+    let _ = stream.set_read_timeout(Some(CONNECT_TIMEOUT));
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let request =
+        format!("GET {} HTTP/1.1\r\nHost: {}:{}\r\nConnection: close\r\n\r\n", path, host, port);
+    if stream.write_all(request.as_bytes()).is_err() {
+        return false;
+    }
+    // Only the status line matters
+    let mut head = [0u8; 64];
+    let mut read = 0;
+    while read < 12 {
+        match stream.read(&mut head[read..]) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => read += n,
+        }
+    }
+    let status = String::from_utf8_lossy(&head[..read]);
+    status.starts_with("HTTP/1.") && status.get(9..12) == Some("200")
+}
+
+/// Matches a pattern against output from several sources (terminals,
+/// servers), remembering which ones printed it.
+pub struct LogWatch {
+    pattern: Regex,
+    windows: HashMap<String, String>,
+    matched: HashSet<String>,
+}
+
+impl LogWatch {
+    pub fn new(pattern: Regex) -> Self {
+        Self { pattern, windows: HashMap::new(), matched: HashSet::new() }
+    }
+
+    /// Add a chunk of `source`'s output. Chunks may split lines.
+    pub fn feed(&mut self, source: &str, text: &str) {
+        if self.matched.contains(source) {
+            return;
+        }
+        let window = self.windows.entry(source.to_string()).or_default();
+        window.push_str(&ansi_regex().replace_all(text, ""));
+        if self.pattern.is_match(window) {
+            self.matched.insert(source.to_string());
+            self.windows.remove(source);
+        } else if window.len() > LOG_WINDOW {
+            // Keep whole lines so a match can still span the cut
+            let cut = window.len() - LOG_WINDOW;
+            let cut = (cut..=window.len()).find(|i| window.is_char_boundary(*i)).unwrap_or(0);
+            let cut = window[cut..].find('\n').map_or(cut, |i| cut + i + 1);
+            window.drain(..cut);
+        }
+    }
+
+    pub fn matched(&self, source: &str) -> bool {
+        self.matched.contains(source)
+    }
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_log_watch() {
+        let mut watch = LogWatch::new(Regex::new(r"ready in \d+ ms").unwrap());
+        watch.feed("term-1", "VITE v5 \x1b[32mready\x1b[0m in ");
+        watch.feed("term-2", "ready in 12 ms\n");
+        assert!(!watch.matched("term-1"));
+        watch.feed("term-1", "340 ms\r\n");
+        assert!(watch.matched("term-1") && watch.matched("term-2"));
+
+        watch.feed("term-3", &"x".repeat(LOG_WINDOW * 2));
+        assert!(watch.windows["term-3"].len() <= LOG_WINDOW);
+        assert!(!watch.matched("term-3"));
+    }
+
+    #[test]
+    fn test_port_and_http() {
+        let open = TcpListener::bind("127.0.0.1:0").unwrap();
+        assert!(port_open("localhost", open.local_addr().unwrap().port()));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            for (i, stream) in listener.incoming().take(2).enumerate() {
+                let mut stream = stream.unwrap();
+                let mut request = [0u8; 512];
+                let n = stream.read(&mut request).unwrap();
+                assert!(String::from_utf8_lossy(&request[..n]).starts_with("GET /health?deep=1 "));
+                let status = if i == 0 { "503 Service Unavailable" } else { "200 OK" };
+                let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+            }
+        });
+        let url = format!("http://127.0.0.1:{}/health?deep=1", port);
+        assert!(!http_ok(&url));
+        assert!(http_ok(&url));
+        server.join().unwrap();
+
+        let cancelled = AtomicBool::new(false);
+        let mut calls = 0;
+        let outcome = wait_until(Duration::from_secs(5), &cancelled, || {
+            calls += 1;
+            calls == 2
+        });
+        assert_eq!((outcome, calls), (Wait::Ready, 2));
+        assert_eq!(wait_until(Duration::ZERO, &cancelled, || false), Wait::TimedOut);
+        cancelled.store(true, Ordering::SeqCst);
+        assert_eq!(wait_until(Duration::from_secs(5), &cancelled, || true), Wait::Cancelled);
+    }
+}
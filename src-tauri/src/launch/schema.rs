@@ -0,0 +1,370 @@
+use crate::error::{Issue, KodiqError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// `cli_name` of launch configs whose `config` is a `CompoundConfig`.
+pub const COMPOUND: &str = "compound";
+
+/// Readiness checks give up after this long unless a step says otherwise.
+pub const DEFAULT_TIMEOUT_MS: u64 = 60_000;
+
+// ── Types ────────────────────────────────────────────────────────────
+
+/// A launch that starts several terminals, preview servers and port
+/// forwards, each after the steps it depends on are ready.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompoundConfig {
+    #[serde(default)]
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Step {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(flatten)]
+    pub kind: StepKind,
+    /// When the step counts as started; right away if unset.
+    pub ready: Option<Readiness>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum StepKind {
+    /// A terminal running `command` (a shell when unset). Relative `cwd`s
+    /// are resolved against the project.
+    #[serde(rename_all = "camelCase")]
+    Terminal {
+        command: Option<String>,
+        cwd: Option<String>,
+        #[serde(default)]
+        env: HashMap<String, String>,
+    },
+    /// A preview dev server.
+    #[serde(rename_all = "camelCase")]
+    Server {
+        #[serde(default)]
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        cwd: Option<String>,
+        #[serde(default)]
+        env: HashMap<String, String>,
+    },
+    /// A local → remote forward over an open SSH connection.
+    #[serde(rename_all = "camelCase")]
+    PortForward {
+        connection_id: String,
+        local_port: u16,
+        remote_host: Option<String>,
+        remote_port: u16,
+    },
+}
+
+impl StepKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Terminal { .. } => "terminal",
+            Self::Server { .. } => "server",
+            Self::PortForward { .. } => "portForward",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Readiness {
+    /// Something accepts TCP connections on `host` (localhost) and `port`.
+    Port { port: u16, host: Option<String> },
+    /// The step's output matched `pattern` (a regex).
+    Log { pattern: String },
+    /// `url` answers a GET with 200. Plain `http://` only.
+    Http { url: String },
+}
+
+// ── Errors ───────────────────────────────────────────────────────────
+
+/// Why a compound launch can't be saved or started.
+#[derive(Debug, Clone, PartialEq, Error, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum LaunchConfigError {
+    #[error("Invalid launch JSON at {line}:{column}: {reason}")]
+    Syntax { reason: String, line: usize, column: usize },
+
+    #[error("A compound launch needs at least one step")]
+    NoSteps,
+
+    #[error("Step {index} has no name")]
+    MissingName { index: usize },
+
+    #[error("More than one step is named '{step}'")]
+    DuplicateStep { step: String },
+
+    #[error("Step '{step}' depends on unknown step '{dependency}'")]
+    UnknownDependency { step: String, dependency: String },
+
+    #[error("Steps depend on each other in a cycle: {}", .steps.join(" → "))]
+    Cycle { steps: Vec<String> },
+
+    #[error("Step '{step}' has no command")]
+    MissingCommand { step: String },
+
+    #[error("Step '{step}' uses port 0")]
+    InvalidPort { step: String },
+
+    #[error("Step '{step}' has an invalid log pattern: {reason}")]
+    InvalidPattern { step: String, reason: String },
+
+    #[error("Step '{step}' checks '{url}', which is not an http:// URL")]
+    InvalidUrl { step: String, url: String },
+
+    #[error("Step '{step}' has no output to match a log pattern against")]
+    NoOutput { step: String },
+}
+
+/// A validation error with its message, as sent to the frontend.
+pub type LaunchIssue = Issue;
+
+impl From<LaunchConfigError> for Issue {
+    fn from(error: LaunchConfigError) -> Self {
+        let detail = match serde_json::to_value(&error) {
+            Ok(serde_json::Value::Object(fields)) => fields,
+            _ => serde_json::Map::new(),
+        };
+        Self { detail, message: error.to_string() }
+    }
+}
+
+// ── Validation ───────────────────────────────────────────────────────
+
+/// Parse and check a compound launch's JSON. All problems are reported,
+/// not just the first.
+pub fn parse(json: &str) -> Result<CompoundConfig, Vec<LaunchConfigError>> {
+    let config: CompoundConfig = serde_json::from_str(json).map_err(|e| {
+        vec![LaunchConfigError::Syntax {
+            reason: e.to_string(),
+            line: e.line(),
+            column: e.column(),
+        }]
+    })?;
+    let errors = validate(&config);
+    if errors.is_empty() {
+        Ok(config)
+    } else {
+        Err(errors)
+    }
+}
+
+/// `parse` for callers that report through `KodiqError`.
+pub fn check(json: &str) -> Result<CompoundConfig, KodiqError> {
+    parse(json).map_err(|errors| {
+        KodiqError::InvalidLaunchConfig(errors.into_iter().map(Into::into).collect())
+    })
+}
+
+fn validate(config: &CompoundConfig) -> Vec<LaunchConfigError> {
+    use LaunchConfigError as E;
+    let mut errors = Vec::new();
+    if config.steps.is_empty() {
+        errors.push(E::NoSteps);
+    }
+    let mut seen: Vec<&str> = Vec::new();
+    for (index, step) in config.steps.iter().enumerate() {
+        let name = step.name.trim();
+        if name.is_empty() {
+            errors.push(E::MissingName { index });
+            continue;
+        }
+        if seen.contains(&name) {
+            errors.push(E::DuplicateStep { step: name.to_string() });
+        }
+        seen.push(name);
+    }
+
+    for step in &config.steps {
+        let name = || step.name.clone();
+        for dependency in &step.depends_on {
+            if !config.steps.iter().any(|s| &s.name == dependency) {
+                errors.push(E::UnknownDependency { step: name(), dependency: dependency.clone() });
+            }
+        }
+        match &step.kind {
+            StepKind::Server { command, .. } if command.trim().is_empty() => {
+                errors.push(E::MissingCommand { step: name() });
+            }
+            StepKind::PortForward { local_port, remote_port, .. }
+                if *local_port == 0 || *remote_port == 0 =>
+            {
+                errors.push(E::InvalidPort { step: name() });
+            }
+            _ => {}
+        }
+        match &step.ready {
+            Some(Readiness::Port { port: 0, .. }) => errors.push(E::InvalidPort { step: name() }),
+            Some(Readiness::Log { pattern }) => {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    errors.push(E::InvalidPattern { step: name(), reason: e.to_string() });
+                }
+                if let StepKind::PortForward { .. } = step.kind {
+                    errors.push(E::NoOutput { step: name() });
+                }
+            }
+            Some(Readiness::Http { url }) => {
+                let parsed = url::Url::parse(url);
+                if !parsed.is_ok_and(|u| u.scheme() == "http" && u.host_str().is_some()) {
+                    errors.push(E::InvalidUrl { step: name(), url: url.clone() });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(steps) = cycle(config) {
+        errors.push(E::Cycle { steps });
+    }
+    errors
+}
+
+/// Indices of steps in start order: each after its dependencies, otherwise
+/// as declared. Steps caught in a cycle are left out.
+pub fn order(config: &CompoundConfig) -> Vec<usize> {
+    let steps = &config.steps;
+    let mut placed: Vec<usize> = Vec::new();
+    loop {
+        let next = (0..steps.len()).find(|i| {
+            !placed.contains(i)
+                && steps[*i].depends_on.iter().all(|dep| {
+                    placed.iter().any(|p| &steps[*p].name == dep)
+                        || !steps.iter().any(|s| &s.name == dep)
+                })
+        });
+        match next {
+            Some(i) => placed.push(i),
+            None => return placed,
+        }
+    }
+}
+
+/// Names along one dependency cycle, if there is one.
+fn cycle(config: &CompoundConfig) -> Option<Vec<String>> {
+    let placed = order(config);
+    let steps = &config.steps;
+    let stuck: Vec<usize> = (0..steps.len()).filter(|i| !placed.contains(i)).collect();
+    // Every stuck step waits on another stuck one; follow that until a repeat
+    let mut path: Vec<usize> = vec![*stuck.first()?];
+    loop {
+        let current = &steps[*path.last()?];
+        let next = stuck.iter().copied().find(|i| current.depends_on.contains(&steps[*i].name))?;
+        if let Some(start) = path.iter().position(|&i| i == next) {
+            let mut names: Vec<String> =
+                path[start..].iter().map(|&i| steps[i].name.clone()).collect();
+            names.push(steps[next].name.clone());
+            return Some(names);
+        }
+        path.push(next);
+    }
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"{
+        "steps": [
+            { "name": "web", "type": "terminal", "command": "npm run dev", "dependsOn": ["api"],
+              "ready": { "kind": "log", "pattern": "ready in \\d+ ms" } },
+            { "name": "api", "type": "server", "command": "cargo", "args": ["run"],
+              "dependsOn": ["db"], "timeoutMs": 120000,
+              "ready": { "kind": "http", "url": "http://localhost:8080/health" } },
+            { "name": "db", "type": "portForward", "connectionId": "conn-1",
+              "localPort": 5432, "remotePort": 5432, "ready": { "kind": "port", "port": 5432 } }
+        ]
+    }"#;
+
+    fn errors(json: &str) -> Vec<LaunchConfigError> {
+        parse(json).unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_and_order() {
+        let config = parse(EXAMPLE).unwrap();
+        assert_eq!(order(&config), vec![2, 1, 0]);
+        assert_eq!(config.steps[1].timeout_ms, Some(120_000));
+        assert_eq!(
+            config.steps[2].kind,
+            StepKind::PortForward {
+                connection_id: "conn-1".into(),
+                local_port: 5432,
+                remote_host: None,
+                remote_port: 5432,
+            }
+        );
+        assert!(matches!(config.steps[0].ready, Some(Readiness::Log { .. })));
+    }
+
+    #[test]
+    fn test_typed_errors() {
+        assert!(matches!(
+            errors(r#"{ "steps": [ { "name": "a", "type": "shell" } ] }"#)[..],
+            [LaunchConfigError::Syntax { line: 1, .. }]
+        ));
+        assert_eq!(errors(r#"{ "steps": [] }"#), vec![LaunchConfigError::NoSteps]);
+
+        let found = errors(
+            r#"{ "steps": [
+                { "name": "a", "type": "server", "dependsOn": ["ghost"] },
+                { "name": "a", "type": "terminal", "ready": { "kind": "log", "pattern": "(" } },
+                { "type": "terminal" },
+                { "name": "fwd", "type": "portForward", "connectionId": "c", "localPort": 0,
+                  "remotePort": 80, "ready": { "kind": "log", "pattern": "x" } },
+                { "name": "h", "type": "terminal", "ready": { "kind": "http", "url": "https://x.dev" } }
+            ] }"#,
+        );
+        let kinds: Vec<String> = found
+            .iter()
+            .map(|e| serde_json::to_value(e).unwrap()["kind"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "duplicateStep",
+                "missingName",
+                "unknownDependency",
+                "missingCommand",
+                "invalidPattern",
+                "invalidPort",
+                "noOutput",
+                "invalidUrl",
+            ]
+        );
+        assert_eq!(found[2].to_string(), "Step 'a' depends on unknown step 'ghost'");
+    }
+
+    #[test]
+    fn test_cycle() {
+        let found = errors(
+            r#"{ "steps": [
+                { "name": "root", "type": "terminal" },
+                { "name": "after", "type": "terminal", "dependsOn": ["b"] },
+                { "name": "a", "type": "terminal", "dependsOn": ["root", "b"] },
+                { "name": "b", "type": "terminal", "dependsOn": ["a"] }
+            ] }"#,
+        );
+        let cycle = LaunchConfigError::Cycle { steps: vec!["b".into(), "a".into(), "b".into()] };
+        assert_eq!(found, vec![cycle.clone()]);
+        assert_eq!(cycle.to_string(), "Steps depend on each other in a cycle: b → a → b");
+
+        let issue = serde_json::to_value(LaunchIssue::from(cycle)).unwrap();
+        assert_eq!(issue["kind"], "cycle");
+        assert_eq!(issue["steps"][1], "a");
+        assert!(issue["message"].as_str().unwrap().starts_with("Steps depend"));
+    }
+}
@@ -2,13 +2,20 @@ mod academy;
 mod chat;
 mod cli;
 mod db;
+mod diagnostics;
 pub mod error;
 mod filesystem;
 mod git;
+mod launch;
+mod lsp;
 mod preview;
+mod prompts;
 mod ssh;
 mod state;
+mod tasks;
 mod terminal;
+mod test_runner;
+mod workspace;
 
 use tauri::menu::{MenuBuilder, MenuItemBuilder, SubmenuBuilder};
 use tauri::Emitter;
@@ -55,13 +62,26 @@ pub fn run() {
         .manage(state::new_app_state())
         .manage(db_state)
         .manage(filesystem::watcher::WatcherState::new())
+        .manage(filesystem::search::new_file_index_state())
+        .manage(filesystem::symbols::new_symbol_index_state())
+        .manage(filesystem::grep::new_search_state())
+        .manage(filesystem::replace::new_replace_state())
+        .manage(filesystem::local_history::new_history_state())
+        .manage(filesystem::sandbox::new_sandbox_state())
+        .manage(lsp::host::new_lsp_state())
+        .manage(diagnostics::store::new_diagnostics_state())
+        .manage(test_runner::runner::new_test_run_state())
+        .manage(tasks::runner::new_task_job_state())
+        .manage(launch::compound::new_launch_state())
         .manage(academy::manager::new_academy_state())
         .manage(preview::manager::new_preview_state())
         .manage(preview::server::new_server_state())
         .manage(ssh::new_ssh_state())
         .manage(ssh::terminal::new_ssh_terminal_state())
         .manage(ssh::port_forward::new_port_forward_state())
+        .manage(ssh::watcher::new_remote_watch_state())
         .manage(chat::new_chat_state())
+        .manage(prompts::library::new_prompt_library_state())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::default().build())
@@ -132,10 +152,68 @@ pub fn run() {
             terminal::manager::close_terminal,
             // Filesystem
             filesystem::read::read_dir,
+            filesystem::read::read_dir_page,
             filesystem::read::read_file,
             filesystem::write::write_file,
+            filesystem::format::fs_format,
             filesystem::watcher::start_watching,
             filesystem::watcher::stop_watching,
+            filesystem::watcher::set_watched_dirs,
+            filesystem::search::fs_find_files,
+            filesystem::symbols::fs_document_symbols,
+            filesystem::symbols::fs_workspace_symbols,
+            filesystem::grep::fs_search_content,
+            filesystem::grep::fs_cancel_search,
+            filesystem::replace::fs_replace_preview,
+            filesystem::replace::fs_replace_apply,
+            filesystem::replace::fs_replace_undo,
+            filesystem::ops::fs_create_file,
+            filesystem::ops::fs_create_dir,
+            filesystem::ops::fs_rename,
+            filesystem::ops::fs_move,
+            filesystem::ops::fs_copy,
+            filesystem::ops::fs_delete,
+            filesystem::ops::fs_trash_list,
+            filesystem::ops::fs_trash_restore,
+            filesystem::local_history::fs_history_list,
+            filesystem::local_history::fs_history_diff,
+            filesystem::local_history::fs_history_restore,
+            filesystem::sandbox::fs_grant_path,
+            filesystem::sandbox::fs_revoke_path,
+            // LSP
+            lsp::host::lsp_registry,
+            lsp::host::lsp_start,
+            lsp::host::lsp_send,
+            lsp::host::lsp_stop,
+            lsp::host::lsp_restart,
+            lsp::host::lsp_list,
+            lsp::host::lsp_open_document,
+            lsp::host::lsp_change_document,
+            lsp::host::lsp_close_document,
+            // Diagnostics
+            diagnostics::store::diagnostics_list,
+            diagnostics::store::diagnostics_clear,
+            // Tests
+            test_runner::runner::tests_detect,
+            test_runner::runner::tests_run,
+            test_runner::runner::tests_cancel,
+            test_runner::runner::tests_last_results,
+            test_runner::runner::tests_clear_results,
+            // Tasks
+            tasks::runner::task_list,
+            tasks::runner::task_run,
+            tasks::runner::task_stop,
+            tasks::runner::task_jobs,
+            tasks::runner::task_recent,
+            tasks::runner::task_clear_recent,
+            // Compound launches
+            launch::compound::launch_validate,
+            launch::compound::launch_start,
+            launch::compound::launch_stop,
+            launch::compound::launch_list,
+            // Workspace config
+            workspace::sync::workspace_load,
+            workspace::sync::workspace_export,
             // Git
             git::info::get_git_info,
             git::info::get_project_stats,
@@ -167,10 +245,22 @@ pub fn run() {
             db::history::db_search_history,
             db::history::db_recent_history,
             db::history::db_add_history,
+            db::history::db_suggest_commands,
             // Database — Snippets
             db::snippets::db_list_snippets,
+            db::snippets::db_get_snippet,
             db::snippets::db_create_snippet,
+            db::snippets::db_update_snippet,
+            db::snippets::db_delete_snippet,
             db::snippets::db_use_snippet,
+            db::snippets::db_list_snippet_tags,
+            db::snippets::db_export_snippet_pack,
+            db::snippets::db_import_snippet_pack,
+            // Prompt templates
+            prompts::template::prompt_placeholders,
+            prompts::template::prompt_render,
+            prompts::library::prompt_library_list,
+            prompts::library::prompt_promote_snippet,
             // Database — Launch Configs
             db::launch_configs::db_list_launch_configs,
             db::launch_configs::db_create_launch_config,
@@ -206,6 +296,7 @@ pub fn run() {
             ssh::connection::ssh_list_connections,
             ssh::connection::ssh_test_connection,
             ssh::connection::ssh_connection_status,
+            ssh::connection::ssh_list_keys,
             // SSH — Terminal
             ssh::terminal::ssh_spawn_terminal,
             ssh::terminal::ssh_write,
@@ -229,6 +320,11 @@ pub fn run() {
             db::chat::db_list_chat_messages,
             db::chat::db_save_chat_message,
             db::chat::db_clear_chat,
+            db::export::db_export_chat,
+            db::export::db_export_transcript,
+            db::export::db_import_chat,
+            // Database — Search
+            db::search::db_search,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
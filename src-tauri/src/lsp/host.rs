@@ -0,0 +1,504 @@
+use super::registry::{self, LspSettings, ServerSpec};
+use super::server::{LspEvent, LspServer, ServerInfo, ServerStatus, Sink};
+use super::transport::file_uri;
+use crate::db;
+use crate::error::KodiqError;
+use crate::filesystem::watcher::{FsChange, FsChangeKind};
+use crate::filesystem::{filter, sandbox};
+use crate::state::DbState;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::ipc::Channel;
+use tauri::Manager;
+
+// LSP `FileChangeType`
+const FILE_CREATED: u8 = 1;
+const FILE_CHANGED: u8 = 2;
+const FILE_DELETED: u8 = 3;
+
+// ── State ────────────────────────────────────────────────────────────
+
+/// Language servers by id, at most one per project root and language.
+pub struct LspHost {
+    servers: Mutex<HashMap<String, Arc<LspServer>>>,
+    next_id: AtomicU32,
+}
+
+pub type LspState = Arc<LspHost>;
+
+pub fn new_lsp_state() -> LspState {
+    Arc::new(LspHost { servers: Mutex::new(HashMap::new()), next_id: AtomicU32::new(0) })
+}
+
+impl LspHost {
+    /// The server for `root` and `spec.language`, started if it isn't
+    /// running. An existing server sends its events to `sink` from now on.
+    pub fn start(
+        &self,
+        root: &Path,
+        spec: ServerSpec,
+        settings: &LspSettings,
+        sink: Sink,
+    ) -> Result<Arc<LspServer>, KodiqError> {
+        let mut servers = self.servers.lock()?;
+        if let Some(existing) =
+            servers.values().find(|s| s.root == root && s.spec.language == spec.language)
+        {
+            existing.set_sink(sink)?;
+            if matches!(existing.status(), ServerStatus::Stopped | ServerStatus::Failed) {
+                existing.start()?;
+            }
+            return Ok(existing.clone());
+        }
+
+        let id = format!("lsp-{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        let server = LspServer::new(id.clone(), root.to_path_buf(), spec, settings, sink);
+        server.start()?;
+        servers.insert(id, server.clone());
+        Ok(server)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Arc<LspServer>, KodiqError> {
+        self.servers
+            .lock()?
+            .get(id)
+            .cloned()
+            .ok_or_else(|| KodiqError::NotFound(format!("Language server {}", id)))
+    }
+
+    pub fn list(&self) -> Result<Vec<ServerInfo>, KodiqError> {
+        let mut infos: Vec<ServerInfo> = self.servers.lock()?.values().map(|s| s.info()).collect();
+        infos.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(infos)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<Option<Arc<LspServer>>, KodiqError> {
+        Ok(self.servers.lock()?.remove(id))
+    }
+
+    /// Servers whose project contains `path` and whose language handles it.
+    fn servers_for(&self, path: &Path) -> Vec<Arc<LspServer>> {
+        let Ok(servers) = self.servers.lock() else { return Vec::new() };
+        servers
+            .values()
+            .filter(|s| path.starts_with(&s.root) && s.spec.handles(path))
+            .cloned()
+            .collect()
+    }
+
+    pub fn open_document(&self, path: &Path, text: &str) -> Result<(), KodiqError> {
+        let uri = file_uri(path);
+        for server in self.servers_for(path) {
+            server.open(&uri, registry::language_id(path), text)?;
+        }
+        Ok(())
+    }
+
+    pub fn change_document(&self, path: &Path, text: &str) -> Result<(), KodiqError> {
+        let uri = file_uri(path);
+        for server in self.servers_for(path) {
+            server.change(&uri, text)?;
+        }
+        Ok(())
+    }
+
+    pub fn close_document(&self, path: &Path) -> Result<(), KodiqError> {
+        let uri = file_uri(path);
+        for server in self.servers_for(path) {
+            server.close(&uri)?;
+        }
+        Ok(())
+    }
+
+    /// Called by `write_file` after a local save.
+    pub fn document_saved(&self, path: &Path, text: &str) -> Result<(), KodiqError> {
+        let uri = file_uri(path);
+        for server in self.servers_for(path) {
+            server.saved(&uri, text)?;
+        }
+        Ok(())
+    }
+
+    /// Forward watcher changes to the servers that care about the files.
+    pub fn files_changed(&self, changes: &[FsChange]) -> Result<(), KodiqError> {
+        let servers: Vec<Arc<LspServer>> = self.servers.lock()?.values().cloned().collect();
+        for server in servers {
+            let relevant = |path: &str| {
+                let path = Path::new(path);
+                path.starts_with(&server.root) && server.spec.watches(path)
+            };
+            let mut events = Vec::new();
+            for change in changes.iter().filter(|c| !c.is_dir) {
+                if let Some(from) = change.from.as_deref().filter(|f| relevant(f)) {
+                    events.push((file_uri(Path::new(from)), FILE_DELETED));
+                }
+                if relevant(&change.path) {
+                    let kind = match change.kind {
+                        FsChangeKind::Created | FsChangeKind::Renamed => FILE_CREATED,
+                        FsChangeKind::Modified => FILE_CHANGED,
+                        FsChangeKind::Removed => FILE_DELETED,
+                    };
+                    events.push((file_uri(Path::new(&change.path)), kind));
+                }
+            }
+            server.watched(&events)?;
+        }
+        Ok(())
+    }
+}
+
+/// Watcher hook (called from the debouncer thread).
+pub fn on_fs_events(app: &tauri::AppHandle, changes: &[FsChange]) {
+    if let Err(e) = app.state::<LspState>().files_changed(changes) {
+        tracing::warn!("LSP file sync failed: {}", e);
+    }
+}
+
+/// The project's `lsp` settings. Servers from an untrusted repo's workspace
+/// file would let it run any binary when a file is opened.
+fn settings_for(db: &DbState, root: &str) -> Result<LspSettings, KodiqError> {
+    let conn = db.connection.lock()?;
+    Ok(LspSettings {
+        trusted: db::projects::is_trusted(&conn, root),
+        ..filter::trusted_section(&conn, root, "lsp")
+    })
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// Language servers available for a project: built-ins plus its `lsp`
+/// settings.
+#[tauri::command]
+pub fn lsp_registry(
+    webview: tauri::Webview,
+    root: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<Vec<ServerSpec>, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    Ok(registry::registry(&settings_for(&db, &root)?))
+}
+
+/// Start the project's server for `language`, or attach to the running
+/// one. Its messages, status changes and stderr arrive on `channel`.
+#[tauri::command(async)]
+pub fn lsp_start(
+    webview: tauri::Webview,
+    root: String,
+    language: String,
+    channel: Channel<LspEvent>,
+    db: tauri::State<'_, DbState>,
+    lsp: tauri::State<'_, LspState>,
+) -> Result<ServerInfo, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    let settings = settings_for(&db, &root)?;
+    let spec = registry::registry(&settings)
+        .into_iter()
+        .find(|s| s.language == language)
+        .ok_or_else(|| KodiqError::NotFound(format!("No language server for {}", language)))?;
+    let sink: Sink = Arc::new(move |event| {
+        let _ = channel.send(event);
+    });
+    let server = lsp.start(Path::new(&root), spec, &settings, sink)?;
+    Ok(server.info())
+}
+
+/// Proxy a JSON-RPC request, response or notification to a server.
+#[tauri::command]
+pub fn lsp_send(
+    webview: tauri::Webview,
+    id: String,
+    message: serde_json::Value,
+    lsp: tauri::State<'_, LspState>,
+) -> Result<(), KodiqError> {
+    sandbox::check_webview(&webview)?;
+    lsp.get(&id)?.send(message)
+}
+
+#[tauri::command(async)]
+pub fn lsp_stop(
+    webview: tauri::Webview,
+    id: String,
+    lsp: tauri::State<'_, LspState>,
+) -> Result<(), KodiqError> {
+    sandbox::check_webview(&webview)?;
+    match lsp.remove(&id)? {
+        Some(server) => server.stop(),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command(async)]
+pub fn lsp_restart(
+    webview: tauri::Webview,
+    id: String,
+    lsp: tauri::State<'_, LspState>,
+) -> Result<ServerInfo, KodiqError> {
+    sandbox::check_webview(&webview)?;
+    let server = lsp.get(&id)?;
+    server.restart()?;
+    Ok(server.info())
+}
+
+#[tauri::command]
+pub fn lsp_list(
+    webview: tauri::Webview,
+    lsp: tauri::State<'_, LspState>,
+) -> Result<Vec<ServerInfo>, KodiqError> {
+    sandbox::check_webview(&webview)?;
+    lsp.list()
+}
+
+/// An editor opened `path`; servers for its language start tracking it.
+#[tauri::command]
+pub fn lsp_open_document(
+    webview: tauri::Webview,
+    path: String,
+    content: String,
+    lsp: tauri::State<'_, LspState>,
+) -> Result<(), KodiqError> {
+    sandbox::check(&webview, &path, None)?;
+    lsp.open_document(Path::new(&path), &content)
+}
+
+/// Unsaved edits (full text). Saves sync through `write_file`.
+#[tauri::command]
+pub fn lsp_change_document(
+    webview: tauri::Webview,
+    path: String,
+    content: String,
+    lsp: tauri::State<'_, LspState>,
+) -> Result<(), KodiqError> {
+    sandbox::check(&webview, &path, None)?;
+    lsp.change_document(Path::new(&path), &content)
+}
+
+#[tauri::command]
+pub fn lsp_close_document(
+    webview: tauri::Webview,
+    path: String,
+    lsp: tauri::State<'_, LspState>,
+) -> Result<(), KodiqError> {
+    sandbox::check(&webview, &path, None)?;
+    lsp.close_document(Path::new(&path))
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    /// A stdio language server in POSIX sh: answers `initialize`,
+    /// `shutdown` and `test/ping`, asks for configuration once initialized,
+    /// exits on `test/crash`, and echoes everything else back as a
+    /// `test/received` notification.
+    const FAKE_SERVER: &str = r#"
+reply() { printf 'Content-Length: %s\r\n\r\n%s' "${#1}" "$1"; }
+while IFS= read -r header; do
+  case "$header" in
+    Content-Length:*) len=$(printf '%s' "$header" | tr -cd 0-9) ;;
+    *) continue ;;
+  esac
+  IFS= read -r _
+  body=$(head -c "$len")
+  id=$(printf '%s' "$body" | sed -n -E 's/.*"id":("[^"]*"|[0-9]+).*/\1/p')
+  case "$body" in
+    *'"method":"initialize"'*)
+      echo "fake server starting" >&2
+      reply '{"jsonrpc":"2.0","id":'"$id"',"result":{"capabilities":{"hoverProvider":true}}}' ;;
+    *'"method":"initialized"'*)
+      reply '{"jsonrpc":"2.0","id":"cfg","method":"workspace/configuration","params":{"items":[{},{}]}}' ;;
+    *'"method":"shutdown"'*) reply '{"jsonrpc":"2.0","id":'"$id"',"result":null}' ;;
+    *'"method":"exit"'*) exit 0 ;;
+    *'"method":"test/crash"'*) exit 3 ;;
+    *'"method":"test/ping"'*) reply '{"jsonrpc":"2.0","id":'"$id"',"result":"pong"}' ;;
+    *) reply '{"jsonrpc":"2.0","method":"test/received","params":'"$body"'}' ;;
+  esac
+done
+"#;
+
+    struct Events {
+        rx: mpsc::Receiver<LspEvent>,
+        seen: Vec<LspEvent>,
+    }
+
+    impl Events {
+        /// First event (seen or upcoming) matching `pred`, consumed.
+        fn expect(&mut self, what: &str, pred: impl Fn(&LspEvent) -> bool) -> LspEvent {
+            if let Some(i) = self.seen.iter().position(&pred) {
+                return self.seen.remove(i);
+            }
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while let Ok(event) =
+                self.rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                if pred(&event) {
+                    return event;
+                }
+                self.seen.push(event);
+                if Instant::now() >= deadline {
+                    break;
+                }
+            }
+            panic!("timed out waiting for {}; got {:?}", what, self.seen);
+        }
+
+        fn received(&mut self, method: &str) -> Value {
+            let event = self.expect(method, |e| {
+                matches!(e, LspEvent::Message { message }
+                    if message["method"] == "test/received" && message["params"]["method"] == method)
+            });
+            match event {
+                LspEvent::Message { message } => message["params"]["params"].clone(),
+                _ => unreachable!(),
+            }
+        }
+
+        fn status(&mut self, status: ServerStatus) -> u32 {
+            match self.expect(
+                &format!("{:?}", status),
+                |e| matches!(e, LspEvent::Status { status: s, .. } if *s == status),
+            ) {
+                LspEvent::Status { restarts, .. } => restarts,
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn capture() -> (Sink, Events) {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let sink: Sink = Arc::new(move |event| {
+            let _ = tx.lock().unwrap().send(event);
+        });
+        (sink, Events { rx, seen: Vec::new() })
+    }
+
+    fn fake_spec(dir: &Path) -> ServerSpec {
+        let script = dir.join("fake-lsp.sh");
+        std::fs::write(&script, FAKE_SERVER).unwrap();
+        ServerSpec {
+            language: "fake".into(),
+            command: "sh".into(),
+            args: vec![script.to_string_lossy().to_string()],
+            extensions: vec!["fk".into()],
+            watch_files: vec!["fake.toml".into()],
+            initialization_options: None,
+            env: HashMap::new(),
+        }
+    }
+
+    fn restarts(max_restarts: u32) -> LspSettings {
+        LspSettings { max_restarts, ..Default::default() }
+    }
+
+    fn change(kind: FsChangeKind, path: &Path) -> FsChange {
+        FsChange { kind, path: path.to_string_lossy().to_string(), from: None, is_dir: false }
+    }
+
+    #[test]
+    fn test_fake_server_lifecycle_and_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(&root).unwrap();
+        let doc = root.join("main.fk");
+        let host = new_lsp_state();
+        let (sink, mut events) = capture();
+
+        let server = host.start(&root, fake_spec(dir.path()), &restarts(1), sink).unwrap();
+        // Held until initialize completes, then delivered in order
+        server.send(json!({ "jsonrpc": "2.0", "id": 7, "method": "test/ping" })).unwrap();
+        host.open_document(&doc, "one").unwrap();
+        host.open_document(&root.join("notes.txt"), "ignored").unwrap();
+
+        assert_eq!(events.status(ServerStatus::Running), 0);
+        assert_eq!(server.info().capabilities.unwrap()["hoverProvider"], true);
+        let opened = events.received("textDocument/didOpen");
+        assert_eq!(opened["textDocument"]["text"], "one");
+        assert_eq!(opened["textDocument"]["uri"], file_uri(&doc));
+        events.expect("pong", |e| {
+            matches!(e, LspEvent::Message { message } if message["id"] == 7 && message["result"] == "pong")
+        });
+        events.expect(
+            "stderr",
+            |e| matches!(e, LspEvent::Log { line } if line == "fake server starting"),
+        );
+        // The host answered the server's configuration request itself
+        let answered = events.expect(
+            "configuration reply",
+            |e| matches!(e, LspEvent::Message { message } if message["params"]["id"] == "cfg"),
+        );
+        match answered {
+            LspEvent::Message { message } => {
+                assert_eq!(message["params"]["result"], json!([null, null]))
+            }
+            _ => unreachable!(),
+        }
+
+        // Saves and watcher events
+        host.document_saved(&doc, "two").unwrap();
+        let changed = events.received("textDocument/didChange");
+        assert_eq!(changed["textDocument"]["version"], 2);
+        assert_eq!(changed["contentChanges"][0]["text"], "two");
+        assert_eq!(events.received("textDocument/didSave")["text"], "two");
+        host.files_changed(&[
+            change(FsChangeKind::Created, &root.join("lib.fk")),
+            change(FsChangeKind::Modified, &root.join("fake.toml")),
+            change(FsChangeKind::Modified, &root.join("README.md")),
+        ])
+        .unwrap();
+        let watched = events.received("workspace/didChangeWatchedFiles");
+        assert_eq!(
+            watched["changes"],
+            json!([
+                { "uri": file_uri(&root.join("lib.fk")), "type": FILE_CREATED },
+                { "uri": file_uri(&root.join("fake.toml")), "type": FILE_CHANGED },
+            ])
+        );
+
+        // A crash restarts the server and reopens the document
+        server.send(json!({ "jsonrpc": "2.0", "method": "test/crash" })).unwrap();
+        assert_eq!(events.status(ServerStatus::Restarting), 1);
+        assert_eq!(events.status(ServerStatus::Running), 1);
+        let reopened = events.received("textDocument/didOpen");
+        assert_eq!(
+            (
+                reopened["textDocument"]["text"].as_str(),
+                reopened["textDocument"]["version"].as_i64()
+            ),
+            (Some("two"), Some(2))
+        );
+
+        // Out of restarts
+        server.send(json!({ "jsonrpc": "2.0", "method": "test/crash" })).unwrap();
+        events.status(ServerStatus::Failed);
+        assert!(server.info().error.unwrap().contains("exited unexpectedly"));
+        assert!(server.send(json!({ "jsonrpc": "2.0", "method": "x" })).is_err());
+
+        // Starting again reuses the server; stopping is graceful and final
+        let (sink, mut events) = capture();
+        let again = host.start(&root, fake_spec(dir.path()), &restarts(1), sink).unwrap();
+        assert_eq!(again.id, server.id);
+        events.status(ServerStatus::Running);
+        host.remove(&server.id).unwrap().unwrap().stop().unwrap();
+        events.status(ServerStatus::Stopped);
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(events.rx.try_iter().all(|e| !matches!(e, LspEvent::Status { .. })));
+        assert!(host.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_binary_fails_to_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut spec = fake_spec(dir.path());
+        spec.command = "kodiq-no-such-language-server".into();
+        let (sink, mut events) = capture();
+        assert!(new_lsp_state().start(dir.path(), spec, &restarts(3), sink).is_err());
+        events.status(ServerStatus::Failed);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod host;
+pub mod registry;
+pub mod server;
+pub mod transport;
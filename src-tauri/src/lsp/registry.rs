@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_MAX_RESTARTS: u32 = 3;
+
+// ── Settings ─────────────────────────────────────────────────────────
+
+/// How to run a language server. `language` is the registry key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerSpec {
+    pub language: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// File extensions the server handles, without the dot.
+    pub extensions: Vec<String>,
+    /// Other file names whose changes the server wants to hear about
+    /// (manifests, compiler configs).
+    #[serde(default)]
+    pub watch_files: Vec<String>,
+    #[serde(default)]
+    pub initialization_options: Option<serde_json::Value>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// Per-project LSP settings, stored under `"lsp"` in the project's
+/// settings JSON. `servers` replace built-ins of the same language or
+/// add new ones.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LspSettings {
+    pub servers: Vec<ServerSpec>,
+    /// Languages not to start servers for.
+    pub disabled: Vec<String>,
+    /// Crash restarts before giving up; reset once a server stays up.
+    pub max_restarts: u32,
+    /// Whether servers inside the project (`node_modules/.bin`, relative
+    /// commands) may run. Set from the project's trust, never from the
+    /// settings JSON.
+    #[serde(skip)]
+    pub trusted: bool,
+}
+
+impl Default for LspSettings {
+    fn default() -> Self {
+        Self {
+            servers: Vec::new(),
+            disabled: Vec::new(),
+            max_restarts: DEFAULT_MAX_RESTARTS,
+            trusted: false,
+        }
+    }
+}
+
+// ── Registry ─────────────────────────────────────────────────────────
+
+fn builtin(
+    language: &str,
+    command: &str,
+    args: &[&str],
+    extensions: &[&str],
+    watch_files: &[&str],
+) -> ServerSpec {
+    let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+    ServerSpec {
+        language: language.into(),
+        command: command.into(),
+        args: strings(args),
+        extensions: strings(extensions),
+        watch_files: strings(watch_files),
+        initialization_options: None,
+        env: HashMap::new(),
+    }
+}
+
+fn builtins() -> Vec<ServerSpec> {
+    vec![
+        builtin("rust", "rust-analyzer", &[], &["rs"], &["Cargo.toml", "Cargo.lock"]),
+        builtin(
+            "typescript",
+            "typescript-language-server",
+            &["--stdio"],
+            &["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"],
+            &["package.json", "tsconfig.json", "jsconfig.json"],
+        ),
+        builtin(
+            "python",
+            "pyright-langserver",
+            &["--stdio"],
+            &["py", "pyi"],
+            &["pyproject.toml", "pyrightconfig.json", "setup.cfg"],
+        ),
+    ]
+}
+
+/// Built-ins merged with the project's servers, minus disabled languages.
+pub fn registry(settings: &LspSettings) -> Vec<ServerSpec> {
+    let mut specs = builtins();
+    for spec in &settings.servers {
+        match specs.iter_mut().find(|s| s.language == spec.language) {
+            Some(existing) => *existing = spec.clone(),
+            None => specs.push(spec.clone()),
+        }
+    }
+    specs.retain(|s| !settings.disabled.contains(&s.language));
+    specs
+}
+
+impl ServerSpec {
+    pub fn handles(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| self.extensions.iter().any(|e| e == ext))
+    }
+
+    /// Whether a change to `path` should reach the server as a watched file.
+    pub fn watches(&self, path: &Path) -> bool {
+        self.handles(path)
+            || path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| self.watch_files.iter().any(|w| w == name))
+    }
+
+    /// The project's own copy (`node_modules/.bin`) if there is one and the
+    /// project is trusted, otherwise the command as given, looked up on PATH.
+    /// None for a relative path into an untrusted project.
+    pub fn program(&self, root: &Path, trusted: bool) -> Option<PathBuf> {
+        let command = Path::new(&self.command);
+        if self.command.contains('/') {
+            return (trusted || command.is_absolute()).then(|| command.to_path_buf());
+        }
+        let local = root.join("node_modules/.bin").join(&self.command);
+        Some(if trusted && local.is_file() { local } else { command.to_path_buf() })
+    }
+}
+
+/// The `languageId` servers expect in `textDocument/didOpen`.
+pub fn language_id(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or_default() {
+        "rs" => "rust",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "typescriptreact",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "javascriptreact",
+        "py" | "pyi" => "python",
+        "go" => "go",
+        _ => "plaintext",
+    }
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_merges_project_servers() {
+        let settings: LspSettings = serde_json::from_value(serde_json::json!({
+            "servers": [
+                { "language": "python", "command": "pylsp", "extensions": ["py"] },
+                { "language": "go", "command": "gopls", "extensions": ["go"], "watchFiles": ["go.mod"] }
+            ],
+            "disabled": ["rust"]
+        }))
+        .unwrap();
+        let specs = registry(&settings);
+        let languages: Vec<&str> = specs.iter().map(|s| s.language.as_str()).collect();
+        assert_eq!(languages, vec!["typescript", "python", "go"]);
+        assert_eq!(specs[1].command, "pylsp");
+        assert_eq!(settings.max_restarts, DEFAULT_MAX_RESTARTS);
+
+        let go = &specs[2];
+        assert!(go.handles(Path::new("/p/main.go")));
+        assert!(go.watches(Path::new("/p/go.mod")));
+        assert!(!go.watches(Path::new("/p/main.rs")));
+    }
+
+    #[test]
+    fn test_program_prefers_trusted_project_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let ts = registry(&LspSettings::default()).remove(1);
+        let global = Some(PathBuf::from("typescript-language-server"));
+        assert_eq!(ts.program(dir.path(), true), global);
+
+        let bin = dir.path().join("node_modules/.bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("typescript-language-server"), "").unwrap();
+        assert_eq!(ts.program(dir.path(), false), global);
+        assert_eq!(ts.program(dir.path(), true), Some(bin.join("typescript-language-server")));
+
+        let script = ServerSpec { command: "./tools/lsp".into(), ..ts.clone() };
+        assert_eq!(script.program(dir.path(), false), None);
+        assert_eq!(script.program(dir.path(), true), Some(PathBuf::from("./tools/lsp")));
+        assert_eq!(language_id(Path::new("App.tsx")), "typescriptreact");
+    }
+}
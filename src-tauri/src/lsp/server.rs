@@ -0,0 +1,576 @@
+use super::registry::{LspSettings, ServerSpec};
+use super::transport::{self, file_uri, notification, request, response};
+use crate::error::KodiqError;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Ids of the requests the host sends itself; frontend ids are numbers.
+const INITIALIZE_ID: &str = "kodiq:initialize";
+const SHUTDOWN_ID: &str = "kodiq:shutdown";
+/// A server that stayed up this long gets its restart budget back.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ServerStatus {
+    Starting,
+    Running,
+    Restarting,
+    Stopped,
+    Failed,
+}
+
+/// What a server sends to its frontend channel.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LspEvent {
+    /// A JSON-RPC message from the server: a notification, a request, or
+    /// the response to a proxied request.
+    Message {
+        message: Value,
+    },
+    Status {
+        status: ServerStatus,
+        restarts: u32,
+        error: Option<String>,
+    },
+    /// A line the server wrote to stderr.
+    Log {
+        line: String,
+    },
+}
+
+/// Where a server's events go. Replaced when a frontend reattaches.
+pub type Sink = Arc<dyn Fn(LspEvent) + Send + Sync>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub id: String,
+    pub language: String,
+    pub root: String,
+    pub status: ServerStatus,
+    pub restarts: u32,
+    pub error: Option<String>,
+    /// From the `initialize` response, once running.
+    pub capabilities: Option<Value>,
+}
+
+struct Document {
+    language_id: &'static str,
+    version: i32,
+    text: String,
+}
+
+struct Inner {
+    status: ServerStatus,
+    restarts: u32,
+    error: Option<String>,
+    capabilities: Option<Value>,
+    sink: Sink,
+    /// Writer thread of the current process.
+    tx: Option<Sender<Value>>,
+    child: Option<Child>,
+    /// Bumped per process so threads of a dead one are ignored.
+    generation: u64,
+    started_at: Instant,
+    /// Frontend messages held until `initialize` completes.
+    queue: Vec<Value>,
+    /// Open documents by URI, replayed after a restart.
+    documents: HashMap<String, Document>,
+    shutdown_ack: Option<Sender<()>>,
+}
+
+/// One language server process for a project root, restarted on crashes.
+pub struct LspServer {
+    pub id: String,
+    pub root: PathBuf,
+    pub spec: ServerSpec,
+    max_restarts: u32,
+    trusted: bool,
+    inner: Mutex<Inner>,
+}
+
+// ── Protocol helpers ─────────────────────────────────────────────────
+
+fn client_capabilities() -> Value {
+    json!({
+        "textDocument": {
+            "synchronization": { "didSave": true, "dynamicRegistration": false },
+            "publishDiagnostics": { "relatedInformation": true },
+            "hover": { "contentFormat": ["markdown", "plaintext"] },
+            "definition": { "linkSupport": true },
+            "completion": { "completionItem": { "snippetSupport": false } },
+            "documentSymbol": { "hierarchicalDocumentSymbolSupport": true }
+        },
+        "workspace": {
+            "workspaceFolders": true,
+            "configuration": true,
+            "didChangeWatchedFiles": { "dynamicRegistration": true }
+        },
+        "window": { "workDoneProgress": true }
+    })
+}
+
+/// Answers for server requests the host handles itself, so a server
+/// never stalls waiting on a frontend that doesn't know them.
+fn auto_reply(method: &str, message: &Value) -> Option<Value> {
+    match method {
+        "client/registerCapability"
+        | "client/unregisterCapability"
+        | "window/workDoneProgress/create" => Some(Value::Null),
+        "workspace/configuration" => {
+            let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+            Some(Value::Array(vec![Value::Null; items]))
+        }
+        _ => None,
+    }
+}
+
+fn did_open(uri: &str, doc: &Document) -> Value {
+    notification(
+        "textDocument/didOpen",
+        json!({ "textDocument": {
+            "uri": uri,
+            "languageId": doc.language_id,
+            "version": doc.version,
+            "text": doc.text,
+        }}),
+    )
+}
+
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(250 << attempt.saturating_sub(1).min(4))
+}
+
+// ── Server ───────────────────────────────────────────────────────────
+
+impl LspServer {
+    pub fn new(
+        id: String,
+        root: PathBuf,
+        spec: ServerSpec,
+        settings: &LspSettings,
+        sink: Sink,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            id,
+            root,
+            spec,
+            max_restarts: settings.max_restarts,
+            trusted: settings.trusted,
+            inner: Mutex::new(Inner {
+                status: ServerStatus::Stopped,
+                restarts: 0,
+                error: None,
+                capabilities: None,
+                sink,
+                tx: None,
+                child: None,
+                generation: 0,
+                started_at: Instant::now(),
+                queue: Vec::new(),
+                documents: HashMap::new(),
+                shutdown_ack: None,
+            }),
+        })
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Inner>, KodiqError> {
+        Ok(self.inner.lock()?)
+    }
+
+    pub fn info(&self) -> ServerInfo {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        ServerInfo {
+            id: self.id.clone(),
+            language: self.spec.language.clone(),
+            root: self.root.to_string_lossy().to_string(),
+            status: inner.status,
+            restarts: inner.restarts,
+            error: inner.error.clone(),
+            capabilities: inner.capabilities.clone(),
+        }
+    }
+
+    pub fn status(&self) -> ServerStatus {
+        self.info().status
+    }
+
+    pub fn set_sink(&self, sink: Sink) -> Result<(), KodiqError> {
+        self.lock()?.sink = sink;
+        Ok(())
+    }
+
+    fn emit_status(inner: &Inner) {
+        (inner.sink)(LspEvent::Status {
+            status: inner.status,
+            restarts: inner.restarts,
+            error: inner.error.clone(),
+        });
+    }
+
+    fn set_status(inner: &mut Inner, status: ServerStatus) {
+        inner.status = status;
+        Self::emit_status(inner);
+    }
+
+    /// Send to the process now if it's initialized; dropped otherwise
+    /// (open documents are replayed once it is).
+    fn notify_running(inner: &Inner, message: Value) {
+        if inner.status == ServerStatus::Running {
+            if let Some(tx) = &inner.tx {
+                let _ = tx.send(message);
+            }
+        }
+    }
+
+    /// Spawn the process and send `initialize`.
+    fn spawn(self: &Arc<Self>, inner: &mut Inner) -> Result<(), KodiqError> {
+        let program = self.spec.program(&self.root, self.trusted).ok_or_else(|| {
+            KodiqError::Other(format!(
+                "{} runs from inside the project; trust the project to start it",
+                self.spec.command
+            ))
+        })?;
+        let mut child = Command::new(&program)
+            .args(&self.spec.args)
+            .envs(&self.spec.env)
+            .current_dir(&self.root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                KodiqError::Other(format!("Failed to start {}: {}", program.display(), e))
+            })?;
+
+        inner.generation += 1;
+        let generation = inner.generation;
+        let (mut stdin, stdout, stderr) =
+            match (child.stdin.take(), child.stdout.take(), child.stderr.take()) {
+                (Some(stdin), Some(stdout), Some(stderr)) => (stdin, stdout, stderr),
+                _ => return Err(KodiqError::Other("Language server pipes unavailable".into())),
+            };
+
+        // Writer: exits when the channel closes, closing the server's stdin
+        let (tx, rx) = mpsc::channel::<Value>();
+        std::thread::spawn(move || {
+            for message in rx {
+                if transport::write_message(&mut stdin, &message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let server = self.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines() {
+                let Ok(line) = line else { break };
+                tracing::debug!("[{}] {}", server.spec.language, line);
+                if let Ok(inner) = server.inner.lock() {
+                    if inner.generation == generation {
+                        (inner.sink)(LspEvent::Log { line });
+                    }
+                }
+            }
+        });
+
+        let server = self.clone();
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                match transport::read_message(&mut reader) {
+                    Ok(Some(message)) => server.handle(generation, message),
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::warn!("{} sent an unreadable message: {}", server.spec.command, e);
+                        break;
+                    }
+                }
+            }
+            server.exited(generation);
+        });
+
+        let name = self.root.file_name().map(|n| n.to_string_lossy().to_string());
+        let root_uri = file_uri(&self.root);
+        let _ = tx.send(request(
+            INITIALIZE_ID,
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "clientInfo": { "name": "Kodiq", "version": env!("CARGO_PKG_VERSION") },
+                "rootPath": self.root,
+                "rootUri": root_uri,
+                "workspaceFolders": [{ "uri": root_uri, "name": name }],
+                "initializationOptions": self.spec.initialization_options,
+                "capabilities": client_capabilities(),
+            }),
+        ));
+
+        inner.tx = Some(tx);
+        inner.child = Some(child);
+        inner.started_at = Instant::now();
+        inner.capabilities = None;
+        tracing::info!("Language server {} started for {}", self.spec.command, self.root.display());
+        Ok(())
+    }
+
+    /// Start (or start over after a stop or failure) with a fresh restart budget.
+    pub fn start(self: &Arc<Self>) -> Result<(), KodiqError> {
+        let mut inner = self.lock()?;
+        inner.restarts = 0;
+        inner.error = None;
+        Self::set_status(&mut inner, ServerStatus::Starting);
+        if let Err(e) = self.spawn(&mut inner) {
+            inner.error = Some(e.to_string());
+            Self::set_status(&mut inner, ServerStatus::Failed);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn restart(self: &Arc<Self>) -> Result<(), KodiqError> {
+        self.stop()?;
+        self.start()
+    }
+
+    /// Graceful `shutdown` + `exit`, then kill if the process lingers.
+    pub fn stop(&self) -> Result<(), KodiqError> {
+        let (ack_tx, ack_rx) = mpsc::channel();
+        let (tx, child, asked) = {
+            let mut inner = self.lock()?;
+            let asked = inner.status == ServerStatus::Running;
+            let tx = inner.tx.take();
+            if let (true, Some(tx)) = (asked, &tx) {
+                inner.shutdown_ack = Some(ack_tx);
+                let _ = tx.send(request(SHUTDOWN_ID, "shutdown", Value::Null));
+            }
+            inner.queue.clear();
+            inner.error = None;
+            Self::set_status(&mut inner, ServerStatus::Stopped);
+            (tx, inner.child.take(), asked)
+        };
+
+        if asked {
+            let _ = ack_rx.recv_timeout(SHUTDOWN_TIMEOUT);
+        }
+        if let Some(tx) = tx {
+            let _ = tx.send(notification("exit", Value::Null));
+        }
+        if let Some(mut child) = child {
+            let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+            while matches!(child.try_wait(), Ok(None)) && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        tracing::info!("Language server {} stopped for {}", self.spec.command, self.root.display());
+        Ok(())
+    }
+
+    /// Forward a frontend message, holding it while the server initializes.
+    pub fn send(&self, message: Value) -> Result<(), KodiqError> {
+        let mut inner = self.lock()?;
+        match (inner.status, &inner.tx) {
+            (ServerStatus::Running, Some(tx)) => {
+                let _ = tx.send(message);
+            }
+            (ServerStatus::Starting | ServerStatus::Restarting, _) => inner.queue.push(message),
+            _ => return Err(KodiqError::Other(format!("{} is not running", self.spec.command))),
+        }
+        Ok(())
+    }
+
+    fn handle(self: &Arc<Self>, generation: u64, message: Value) {
+        let Ok(mut inner) = self.inner.lock() else { return };
+        if inner.generation != generation {
+            return;
+        }
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str);
+        match (&id, method) {
+            (Some(id), None) if id == INITIALIZE_ID => self.initialized(&mut inner, &message),
+            (Some(id), None) if id == SHUTDOWN_ID => {
+                if let Some(ack) = inner.shutdown_ack.take() {
+                    let _ = ack.send(());
+                }
+            }
+            (Some(id), Some(method)) => match (auto_reply(method, &message), &inner.tx) {
+                (Some(result), Some(tx)) => {
+                    let _ = tx.send(response(id, result));
+                }
+                _ => (inner.sink)(LspEvent::Message { message }),
+            },
+            _ => (inner.sink)(LspEvent::Message { message }),
+        }
+    }
+
+    /// `initialize` answered: finish the handshake, reopen documents and
+    /// flush what the frontend sent meanwhile.
+    fn initialized(&self, inner: &mut Inner, message: &Value) {
+        if let Some(error) = message.get("error") {
+            inner.error =
+                Some(error["message"].as_str().unwrap_or("initialize failed").to_string());
+            if let Some(child) = inner.child.as_mut() {
+                let _ = child.kill();
+            }
+            Self::set_status(inner, ServerStatus::Failed);
+            return;
+        }
+        inner.capabilities = message["result"].get("capabilities").cloned();
+        let Some(tx) = inner.tx.clone() else { return };
+        let _ = tx.send(notification("initialized", json!({})));
+        for (uri, doc) in &inner.documents {
+            let _ = tx.send(did_open(uri, doc));
+        }
+        for queued in inner.queue.drain(..) {
+            let _ = tx.send(queued);
+        }
+        Self::set_status(inner, ServerStatus::Running);
+    }
+
+    /// The process closed its stdout: reap it and restart unless stopped.
+    fn exited(self: &Arc<Self>, generation: u64) {
+        let delay = {
+            let Ok(mut inner) = self.inner.lock() else { return };
+            if inner.generation != generation {
+                return;
+            }
+            inner.tx = None;
+            let status = inner.child.take().and_then(|mut child| {
+                let _ = child.kill();
+                child.wait().ok()
+            });
+            if matches!(inner.status, ServerStatus::Stopped | ServerStatus::Failed) {
+                return;
+            }
+
+            if inner.started_at.elapsed() >= STABLE_AFTER {
+                inner.restarts = 0;
+            }
+            let exit = status.map_or("no status".to_string(), |s| s.to_string());
+            inner.error = Some(format!("{} exited unexpectedly ({})", self.spec.command, exit));
+            tracing::warn!("{}", inner.error.as_deref().unwrap_or_default());
+            if inner.restarts >= self.max_restarts {
+                Self::set_status(&mut inner, ServerStatus::Failed);
+                return;
+            }
+            inner.restarts += 1;
+            Self::set_status(&mut inner, ServerStatus::Restarting);
+            backoff(inner.restarts)
+        };
+
+        std::thread::sleep(delay);
+        let Ok(mut inner) = self.inner.lock() else { return };
+        if inner.status != ServerStatus::Restarting || inner.generation != generation {
+            return; // stopped or started over during the backoff
+        }
+        if let Err(e) = self.spawn(&mut inner) {
+            inner.error = Some(e.to_string());
+            Self::set_status(&mut inner, ServerStatus::Failed);
+        }
+    }
+
+    // ── Document sync ────────────────────────────────────────────────
+
+    pub fn open(&self, uri: &str, language_id: &'static str, text: &str) -> Result<(), KodiqError> {
+        let mut inner = self.lock()?;
+        if inner.documents.contains_key(uri) {
+            drop(inner);
+            return self.change(uri, text).map(|_| ());
+        }
+        let doc = Document { language_id, version: 1, text: text.to_string() };
+        Self::notify_running(&inner, did_open(uri, &doc));
+        inner.documents.insert(uri.to_string(), doc);
+        Ok(())
+    }
+
+    /// Full-text change. Returns whether the document is open.
+    pub fn change(&self, uri: &str, text: &str) -> Result<bool, KodiqError> {
+        let mut inner = self.lock()?;
+        let Some(doc) = inner.documents.get_mut(uri) else { return Ok(false) };
+        if doc.text == text {
+            return Ok(true);
+        }
+        doc.version += 1;
+        doc.text = text.to_string();
+        let message = notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": doc.version },
+                "contentChanges": [{ "text": text }],
+            }),
+        );
+        Self::notify_running(&inner, message);
+        Ok(true)
+    }
+
+    pub fn close(&self, uri: &str) -> Result<(), KodiqError> {
+        let mut inner = self.lock()?;
+        if inner.documents.remove(uri).is_some() {
+            let message =
+                notification("textDocument/didClose", json!({ "textDocument": { "uri": uri } }));
+            Self::notify_running(&inner, message);
+        }
+        Ok(())
+    }
+
+    /// A save of an open document: sync its text, then `didSave`.
+    pub fn saved(&self, uri: &str, text: &str) -> Result<(), KodiqError> {
+        if self.change(uri, text)? {
+            let message = notification(
+                "textDocument/didSave",
+                json!({ "textDocument": { "uri": uri }, "text": text }),
+            );
+            Self::notify_running(&*self.lock()?, message);
+        }
+        Ok(())
+    }
+
+    /// `workspace/didChangeWatchedFiles` with `(uri, FileChangeType)` pairs.
+    pub fn watched(&self, changes: &[(String, u8)]) -> Result<(), KodiqError> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let changes: Vec<Value> =
+            changes.iter().map(|(uri, kind)| json!({ "uri": uri, "type": kind })).collect();
+        let message =
+            notification("workspace/didChangeWatchedFiles", json!({ "changes": changes }));
+        Self::notify_running(&*self.lock()?, message);
+        Ok(())
+    }
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_reply() {
+        let config = json!({ "params": { "items": [{ "section": "a" }, { "section": "b" }] } });
+        assert_eq!(auto_reply("workspace/configuration", &config), Some(json!([null, null])));
+        assert_eq!(auto_reply("client/registerCapability", &json!({})), Some(Value::Null));
+        assert_eq!(auto_reply("workspace/applyEdit", &json!({})), None);
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), Duration::from_millis(250));
+        assert_eq!(backoff(3), Duration::from_millis(1000));
+        assert_eq!(backoff(20), Duration::from_millis(4000));
+    }
+}
@@ -0,0 +1,107 @@
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+// ── Framing ──────────────────────────────────────────────────────────
+
+/// Read one `Content-Length`-framed JSON-RPC message. `Ok(None)` at a
+/// clean end of stream.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return match length {
+                None => Ok(None),
+                Some(_) => Err(io::ErrorKind::UnexpectedEof.into()),
+            };
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue; // stray blank line between messages
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let mut body = vec![0; length.unwrap_or_default()];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n", body.len())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+// ── Messages ─────────────────────────────────────────────────────────
+
+pub fn request(id: &str, method: &str, params: Value) -> Value {
+    serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+pub fn notification(method: &str, params: Value) -> Value {
+    serde_json::json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+pub fn response(id: &Value, result: Value) -> Value {
+    serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+/// `file://` URI for an absolute path, percent-encoded.
+pub fn file_uri(path: &Path) -> String {
+    url::Url::from_file_path(path)
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| format!("file://{}", path.display()))
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_round_trip_and_headers() {
+        let mut buf = Vec::new();
+        write_message(&mut buf, &json!({ "id": 1, "text": "héllo" })).unwrap();
+        write_message(&mut buf, &notification("exit", Value::Null)).unwrap();
+        // Content-Length counts bytes, not chars
+        assert!(buf.starts_with(b"Content-Length: 24\r\n\r\n"));
+
+        let mut reader = Cursor::new(buf);
+        assert_eq!(read_message(&mut reader).unwrap(), Some(json!({ "id": 1, "text": "héllo" })));
+        assert_eq!(read_message(&mut reader).unwrap().unwrap()["method"], "exit");
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+
+        // Extra headers, any case
+        let raw = "content-length: 2\r\nContent-Type: application/vscode-jsonrpc\r\n\r\n{}";
+        assert_eq!(read_message(&mut Cursor::new(raw)).unwrap(), Some(json!({})));
+    }
+
+    #[test]
+    fn test_truncated_and_invalid() {
+        let truncated = "Content-Length: 10\r\n\r\n{}";
+        assert!(read_message(&mut Cursor::new(truncated)).is_err());
+        let headers_only = "Content-Length: 10\r\n";
+        assert!(read_message(&mut Cursor::new(headers_only)).is_err());
+        let invalid = "Content-Length: 3\r\n\r\nnot";
+        assert_eq!(
+            read_message(&mut Cursor::new(invalid)).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
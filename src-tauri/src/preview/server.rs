@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::sync::{Arc, Mutex, OnceLock};
 
@@ -7,6 +8,8 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter};
 
+use crate::diagnostics::store::{self, ProblemStream};
+
 // -- Regex patterns (allocated once) ──────────────────────────────────
 
 fn url_regex() -> &'static Regex {
@@ -88,10 +91,11 @@ fn detect_level(line: &str) -> &'static str {
     }
 }
 
-/// Read lines from a stream, detect ports & log levels, store logs, emit events.
+/// Read lines from a stream, detect ports, log levels & problems, store logs, emit events.
 fn spawn_reader_thread(
     reader: impl std::io::Read + Send + 'static,
     server_id: String,
+    cwd: PathBuf,
     state: ServerState,
     app: AppHandle,
 ) {
@@ -99,6 +103,7 @@ fn spawn_reader_thread(
         let buf = BufReader::new(reader);
         let url_re = url_regex();
         let ansi_re = ansi_regex();
+        let mut problems = ProblemStream::new(&app, &server_id, &cwd);
 
         for line in buf.lines() {
             let Ok(raw) = line else { break };
@@ -108,6 +113,7 @@ fn spawn_reader_thread(
 
             let clean = ansi_re.replace_all(&raw, "").to_string();
             let level = detect_level(&clean);
+            problems.push(&format!("{}\n", clean));
 
             let entry = LogEntry { timestamp: now(), level: level.into(), message: clean.clone() };
 
@@ -257,11 +263,14 @@ pub fn preview_start_server(
     }
 
     // Spawn reader threads
+    let cwd = config.cwd.map(PathBuf::from).or_else(|| std::env::current_dir().ok());
+    let cwd = cwd.unwrap_or_default();
     if let Some(out) = stdout {
-        spawn_reader_thread(out, server_id.clone(), state.inner().clone(), app.clone());
+        let state = state.inner().clone();
+        spawn_reader_thread(out, server_id.clone(), cwd.clone(), state, app.clone());
     }
     if let Some(err) = stderr {
-        spawn_reader_thread(err, server_id.clone(), state.inner().clone(), app);
+        spawn_reader_thread(err, server_id.clone(), cwd, state.inner().clone(), app);
     }
 
     tracing::info!("Preview server started: {} ({})", server_id, config.name);
@@ -269,7 +278,11 @@ pub fn preview_start_server(
 }
 
 #[tauri::command]
-pub fn preview_stop_server(state: tauri::State<'_, ServerState>, id: String) -> Result<(), String> {
+pub fn preview_stop_server(
+    app: AppHandle,
+    state: tauri::State<'_, ServerState>,
+    id: String,
+) -> Result<(), String> {
     let mut mgr = state.lock().map_err(|e| e.to_string())?;
     if let Some(server) = mgr.servers.get_mut(&id) {
         if let Some(ref mut child) = server.child {
@@ -279,6 +292,8 @@ pub fn preview_stop_server(state: tauri::State<'_, ServerState>, id: String) ->
         server.info.status = "stopped".into();
         tracing::info!("Preview server stopped: {}", id);
     }
+    drop(mgr);
+    store::forget(&app, &id);
     Ok(())
 }
 
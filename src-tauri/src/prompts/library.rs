@@ -0,0 +1,500 @@
+use crate::db::snippets::{self, Snippet};
+use crate::error::KodiqError;
+use crate::filesystem::sandbox;
+use crate::state::DbState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+
+/// Repo-shared prompts live here, relative to the project root.
+pub const PROMPTS_DIR: &str = ".kodiq/prompts";
+
+/// Id prefix that marks a library entry as file-backed.
+const REPO_ID_PREFIX: &str = "repo:";
+
+// ── State ────────────────────────────────────────────────────────────
+
+/// Parsed repo prompts, keyed by project root.
+pub struct PromptLibrary {
+    projects: HashMap<String, Vec<Snippet>>,
+}
+
+pub type PromptLibraryState = Arc<Mutex<PromptLibrary>>;
+
+pub fn new_prompt_library_state() -> PromptLibraryState {
+    Arc::new(Mutex::new(PromptLibrary { projects: HashMap::new() }))
+}
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptSource {
+    Db,
+    Repo,
+}
+
+/// A snippet as shown in the prompt library, tagged with where it came from.
+#[derive(Debug, Serialize)]
+pub struct LibraryEntry {
+    #[serde(flatten)]
+    pub snippet: Snippet,
+    pub source: PromptSource,
+    /// Prompt file path relative to the project root (repo entries only).
+    pub path: Option<String>,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct FrontMatter {
+    title: Option<String>,
+    cli: Option<String>,
+    tags: Vec<String>,
+}
+
+// ── Parsing ──────────────────────────────────────────────────────────
+
+/// Strip YAML-style quotes. Double quotes support `\"`, `\\`, `\n`, `\r`, `\t`;
+/// single quotes escape themselves as `''`.
+fn unquote(value: &str) -> String {
+    let v = value.trim();
+    if v.len() >= 2 && v.starts_with('"') && v.ends_with('"') {
+        let mut out = String::with_capacity(v.len());
+        let mut chars = v[1..v.len() - 1].chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some('t') => out.push('\t'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        }
+        return out;
+    }
+    if v.len() >= 2 && v.starts_with('\'') && v.ends_with('\'') {
+        return v[1..v.len() - 1].replace("''", "'");
+    }
+    v.to_string()
+}
+
+/// Double-quote `value` so any title, cli or tag survives `unquote`.
+fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Split an inline `[a, "b, c"]` list on commas outside quotes.
+fn parse_list(value: &str) -> Vec<String> {
+    let v = value.trim();
+    let v = v.strip_prefix('[').and_then(|v| v.strip_suffix(']')).unwrap_or(v);
+
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut open: Option<char> = None;
+    let mut escaped = false;
+    for (i, c) in v.char_indices() {
+        match open {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => open = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => open = Some(c),
+            None if c == ',' => {
+                items.push(&v[start..i]);
+                start = i + 1;
+            }
+            None => {}
+        }
+    }
+    items.push(&v[start..]);
+    items.into_iter().map(unquote).filter(|t| !t.is_empty()).collect()
+}
+
+/// Split a prompt file into front-matter and body. Supports the YAML subset
+/// we document: `key: value`, inline `[a, b]` lists and `- item` lists for tags.
+fn parse_front_matter(text: &str) -> (FrontMatter, &str) {
+    let mut meta = FrontMatter::default();
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return (meta, text);
+    };
+
+    let mut offset = 0;
+    let mut in_tags = false;
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        let line = line.trim_end();
+        if line == "---" {
+            return (meta, &rest[offset..]);
+        }
+        if in_tags {
+            if let Some(item) = line.trim_start().strip_prefix("- ") {
+                meta.tags.push(unquote(item));
+                continue;
+            }
+            in_tags = false;
+        }
+        let Some((key, value)) = line.split_once(':') else { continue };
+        match key.trim() {
+            "title" => meta.title = Some(unquote(value)),
+            "cli" => meta.cli = Some(unquote(value)).filter(|c| !c.is_empty()),
+            "tags" if value.trim().is_empty() => in_tags = true,
+            "tags" => meta.tags = parse_list(value),
+            _ => {}
+        }
+    }
+
+    // No closing fence — treat the whole file as body
+    (FrontMatter::default(), text)
+}
+
+fn render_front_matter(snippet: &Snippet) -> String {
+    let mut out = format!("---\ntitle: {}\n", quote(&snippet.title));
+    if let Some(ref cli) = snippet.cli_name {
+        out.push_str(&format!("cli: {}\n", quote(cli)));
+    }
+    if !snippet.tags.is_empty() {
+        let tags: Vec<String> = snippet.tags.iter().map(|t| quote(t)).collect();
+        out.push_str(&format!("tags: [{}]\n", tags.join(", ")));
+    }
+    out.push_str("---\n\n");
+    out.push_str(&snippet.content);
+    if !snippet.content.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.ends_with('-') && !slug.is_empty() {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-').to_string();
+    if slug.is_empty() {
+        "prompt".to_string()
+    } else {
+        slug
+    }
+}
+
+fn mtime(path: &Path) -> i64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+// ── Core logic ───────────────────────────────────────────────────────
+
+/// Read every `*.md` under `<root>/.kodiq/prompts` (recursively), sorted by path.
+/// Symlinked directories are skipped so a link loop can't recurse forever.
+pub fn scan(root: &Path) -> Vec<Snippet> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.join(PROMPTS_DIR)];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else { continue };
+            if file_type.is_dir() {
+                stack.push(path);
+            } else if file_type.is_symlink() && path.is_dir() {
+                continue;
+            } else if path.extension().is_some_and(|e| e == "md") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let text = match std::fs::read_to_string(&path) {
+                Ok(t) => t,
+                Err(e) => {
+                    tracing::warn!("Skipping prompt file {}: {}", path.display(), e);
+                    return None;
+                }
+            };
+            let (meta, body) = parse_front_matter(&text);
+            let rel = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().to_string();
+            let stem = path.file_stem().map(|s| s.to_string_lossy().to_string());
+            let modified = mtime(&path);
+
+            let mut tags = meta.tags;
+            tags.sort_by_key(|t| t.to_lowercase());
+            tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+
+            Some(Snippet {
+                id: format!("{}{}", REPO_ID_PREFIX, rel),
+                title: meta.title.filter(|t| !t.is_empty()).or(stem).unwrap_or_default(),
+                content: body.trim_start_matches(['\r', '\n']).to_string(),
+                cli_name: meta.cli,
+                tags,
+                project_id: None,
+                usage_count: 0,
+                created_at: modified,
+                updated_at: modified,
+            })
+        })
+        .collect()
+}
+
+/// Re-scan a project's prompt folder and notify the frontend.
+pub fn refresh(app: &tauri::AppHandle, root: &str) {
+    let prompts = scan(Path::new(root));
+    let state = app.state::<PromptLibraryState>();
+    if let Ok(mut lib) = state.lock() {
+        lib.projects.insert(root.to_string(), prompts);
+    }
+    let _ = app.emit("prompts-changed", root.to_string());
+}
+
+/// True if a watcher event path touches the prompt folder of `root`.
+pub fn is_prompt_path(root: &Path, path: &Path) -> bool {
+    path.starts_with(root.join(PROMPTS_DIR))
+}
+
+fn repo_entry(snippet: Snippet) -> LibraryEntry {
+    let path = snippet.id.strip_prefix(REPO_ID_PREFIX).map(String::from);
+    LibraryEntry { snippet, source: PromptSource::Repo, path, read_only: true }
+}
+
+/// Write `snippet` as a new prompt file. Never overwrites an existing file.
+fn write_prompt_file(root: &Path, snippet: &Snippet) -> Result<PathBuf, KodiqError> {
+    let dir = root.join(PROMPTS_DIR);
+    std::fs::create_dir_all(&dir)?;
+
+    let path = dir.join(format!("{}.md", slugify(&snippet.title)));
+    let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(&path).map_err(
+        |e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => {
+                KodiqError::Other(format!("Prompt file already exists: {}", path.display()))
+            }
+            _ => e.into(),
+        },
+    )?;
+    std::io::Write::write_all(&mut file, render_front_matter(snippet).as_bytes())?;
+    Ok(path)
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// DB snippets (global + project) followed by the project's repo prompts.
+#[tauri::command]
+pub fn prompt_library_list(
+    webview: tauri::Webview,
+    db: tauri::State<DbState>,
+    library: tauri::State<PromptLibraryState>,
+    project_path: Option<String>,
+    project_id: Option<String>,
+    cli_name: Option<String>,
+    tag: Option<String>,
+) -> Result<Vec<LibraryEntry>, KodiqError> {
+    match project_path {
+        Some(ref root) => sandbox::check(&webview, root, None)?,
+        None => sandbox::check_webview(&webview)?,
+    }
+    let mut entries: Vec<LibraryEntry> = {
+        let conn = db.connection.lock()?;
+        snippets::list(&conn, cli_name.as_deref(), project_id.as_deref(), tag.as_deref())?
+    }
+    .into_iter()
+    .map(|snippet| LibraryEntry { snippet, source: PromptSource::Db, path: None, read_only: false })
+    .collect();
+
+    if let Some(root) = project_path {
+        let repo = {
+            let mut lib = library.lock()?;
+            lib.projects.entry(root.clone()).or_insert_with(|| scan(Path::new(&root))).clone()
+        };
+        entries.extend(
+            repo.into_iter()
+                .filter(|s| {
+                    cli_name.as_ref().map_or(true, |c| s.cli_name.as_ref().map_or(true, |n| n == c))
+                })
+                .filter(|s| {
+                    tag.as_ref().map_or(true, |t| s.tags.iter().any(|x| x.eq_ignore_ascii_case(t)))
+                })
+                .map(repo_entry),
+        );
+    }
+
+    Ok(entries)
+}
+
+/// Copy a personal snippet into `.kodiq/prompts/<slug>.md` so the team gets it.
+/// Once the file is in the library the DB copy is removed, unless `keep` is set.
+#[tauri::command]
+pub fn prompt_promote_snippet(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    db: tauri::State<DbState>,
+    id: String,
+    project_path: String,
+    keep: Option<bool>,
+) -> Result<LibraryEntry, KodiqError> {
+    sandbox::check(&webview, &project_path, None)?;
+    let not_found = |e| match e {
+        rusqlite::Error::QueryReturnedNoRows => KodiqError::NotFound(format!("Snippet: {}", id)),
+        other => other.into(),
+    };
+    let snippet = {
+        let conn = db.connection.lock()?;
+        snippets::get(&conn, &id).map_err(not_found)?
+    };
+
+    let root = Path::new(&project_path);
+    let path = write_prompt_file(root, &snippet)?;
+
+    refresh(&app, &project_path);
+    let rel = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().to_string();
+    let promoted = scan(root)
+        .into_iter()
+        .find(|s| s.id == format!("{}{}", REPO_ID_PREFIX, rel))
+        .ok_or_else(|| KodiqError::NotFound(format!("Prompt file: {}", rel)))?;
+
+    if !keep.unwrap_or(false) {
+        let conn = db.connection.lock()?;
+        snippets::delete(&conn, &id).map_err(not_found)?;
+    }
+    Ok(repo_entry(promoted))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter_inline_tags() {
+        let text =
+            "---\ntitle: \"Review PR\"\ncli: claude\ntags: [review, 'git']\n---\n\nBody {{diff}}\n";
+        let (meta, body) = parse_front_matter(text);
+        assert_eq!(meta.title.as_deref(), Some("Review PR"));
+        assert_eq!(meta.cli.as_deref(), Some("claude"));
+        assert_eq!(meta.tags, vec!["review", "git"]);
+        assert_eq!(body, "\nBody {{diff}}\n");
+    }
+
+    #[test]
+    fn test_front_matter_block_list_and_missing() {
+        let (meta, body) = parse_front_matter("---\ntags:\n  - a\n  - b\ncli:\n---\nx");
+        assert_eq!(meta.tags, vec!["a", "b"]);
+        assert_eq!(meta.cli, None);
+        assert_eq!(body, "x");
+
+        let (meta, body) = parse_front_matter("# Just markdown");
+        assert_eq!(meta, FrontMatter::default());
+        assert_eq!(body, "# Just markdown");
+
+        // Unterminated front-matter is plain content
+        let (meta, body) = parse_front_matter("---\ntitle: x\nno end");
+        assert_eq!(meta.title, None);
+        assert_eq!(body, "---\ntitle: x\nno end");
+    }
+
+    #[test]
+    fn test_front_matter_roundtrip() {
+        let snippet = Snippet {
+            id: "s1".into(),
+            title: "Fix \"it\": now\n---\nreally \\ 'ok'".into(),
+            content: "---\nBody\n".into(),
+            cli_name: Some("my cli, v2".into()),
+            tags: vec!["a,b".into(), "it's".into(), "[x]".into(), "---".into()],
+            project_id: None,
+            usage_count: 0,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let text = render_front_matter(&snippet);
+        let (meta, body) = parse_front_matter(&text);
+        assert_eq!(meta.title.as_deref(), Some(snippet.title.as_str()));
+        assert_eq!(meta.cli, snippet.cli_name);
+        assert_eq!(meta.tags, snippet.tags);
+        assert_eq!(body, "\n---\nBody\n");
+    }
+
+    #[test]
+    fn test_scan_and_promote_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let prompts = dir.path().join(PROMPTS_DIR).join("team");
+        std::fs::create_dir_all(&prompts).unwrap();
+        std::fs::write(prompts.join("explain.md"), "Explain {{selection}}").unwrap();
+        std::fs::write(prompts.join("notes.txt"), "ignored").unwrap();
+
+        let found = scan(dir.path());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "repo:.kodiq/prompts/team/explain.md");
+        assert_eq!(found[0].title, "explain");
+
+        let snippet = Snippet {
+            id: "s1".into(),
+            title: "Write tests!".into(),
+            content: "Add tests for {{file}}".into(),
+            cli_name: Some("claude".into()),
+            tags: vec!["qa".into(), "rust".into()],
+            project_id: None,
+            usage_count: 3,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let path = write_prompt_file(dir.path(), &snippet).unwrap();
+        assert!(path.ends_with("write-tests.md"));
+        assert!(write_prompt_file(dir.path(), &snippet).is_err()); // never overwrites
+
+        let promoted = scan(dir.path()).into_iter().find(|s| s.title == "Write tests!").unwrap();
+        assert_eq!(promoted.content, "Add tests for {{file}}\n");
+        assert_eq!(promoted.cli_name.as_deref(), Some("claude"));
+        assert_eq!(promoted.tags, vec!["qa", "rust"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_skips_symlinked_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let prompts = dir.path().join(PROMPTS_DIR);
+        std::fs::create_dir_all(&prompts).unwrap();
+        std::fs::write(prompts.join("a.md"), "A").unwrap();
+        std::os::unix::fs::symlink(&prompts, prompts.join("loop")).unwrap();
+
+        let found = scan(dir.path());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "repo:.kodiq/prompts/a.md");
+    }
+
+    #[test]
+    fn test_slugify_and_prompt_path() {
+        assert_eq!(slugify("  Fix: the BUG (now) "), "fix-the-bug-now");
+        assert_eq!(slugify("!!!"), "prompt");
+        let root = Path::new("/p");
+        assert!(is_prompt_path(root, Path::new("/p/.kodiq/prompts/a.md")));
+        assert!(!is_prompt_path(root, Path::new("/p/src/a.md")));
+    }
+}
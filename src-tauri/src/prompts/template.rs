@@ -0,0 +1,231 @@
+use crate::error::KodiqError;
+use crate::filesystem::sandbox;
+use crate::ssh::{self, SshState};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Max bytes of `git diff` inlined into a prompt — keeps CLI argv sane.
+const MAX_DIFF_BYTES: usize = 64 * 1024;
+
+// ── Types ────────────────────────────────────────────────────────────
+
+/// A `{{...}}` variable inside snippet content.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "kind", content = "label", rename_all = "lowercase")]
+pub enum Placeholder {
+    File,
+    Selection,
+    Branch,
+    Diff,
+    Project,
+    /// `{{input:Label}}` — asked from the user before sending.
+    Input(String),
+}
+
+/// Values supplied by the frontend; branch and diff are resolved here.
+#[derive(Debug, Default, Deserialize)]
+pub struct TemplateContext {
+    pub project_path: Option<String>,
+    pub connection_id: Option<String>,
+    pub file: Option<String>,
+    pub selection: Option<String>,
+    #[serde(default)]
+    pub inputs: HashMap<String, String>,
+}
+
+fn placeholder_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\{\{\s*(file|selection|branch|diff|project|input:\s*([^{}]+?))\s*\}\}")
+            .unwrap()
+    })
+}
+
+fn parse(caps: &regex::Captures) -> Placeholder {
+    match &caps[1] {
+        "file" => Placeholder::File,
+        "selection" => Placeholder::Selection,
+        "branch" => Placeholder::Branch,
+        "diff" => Placeholder::Diff,
+        "project" => Placeholder::Project,
+        _ => Placeholder::Input(caps[2].trim().to_string()),
+    }
+}
+
+// ── Pure functions ───────────────────────────────────────────────────
+
+/// Placeholders used in `content`, in order of first appearance.
+/// Unknown `{{...}}` sequences are not placeholders and stay literal.
+pub fn placeholders(content: &str) -> Vec<Placeholder> {
+    let mut out: Vec<Placeholder> = Vec::new();
+    for caps in placeholder_re().captures_iter(content) {
+        let p = parse(&caps);
+        if !out.contains(&p) {
+            out.push(p);
+        }
+    }
+    out
+}
+
+/// Substitute every placeholder via `lookup`. Fails listing all missing values.
+pub fn render(
+    content: &str,
+    lookup: impl Fn(&Placeholder) -> Option<String>,
+) -> Result<String, KodiqError> {
+    let mut missing: Vec<String> = Vec::new();
+    let rendered = placeholder_re().replace_all(content, |caps: &regex::Captures| {
+        let p = parse(caps);
+        lookup(&p).unwrap_or_else(|| {
+            let name = match p {
+                Placeholder::Input(label) => format!("input:{}", label),
+                _ => caps[1].to_string(),
+            };
+            if !missing.contains(&name) {
+                missing.push(name);
+            }
+            String::new()
+        })
+    });
+
+    if !missing.is_empty() {
+        return Err(KodiqError::Template(format!("missing values for {}", missing.join(", "))));
+    }
+    Ok(rendered.into_owned())
+}
+
+/// Path of `file` relative to the project root, if it lies inside it.
+fn relative_to(file: &str, root: Option<&str>) -> String {
+    root.and_then(|r| std::path::Path::new(file).strip_prefix(r).ok())
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|| file.to_string())
+}
+
+fn truncate_diff(mut diff: String) -> String {
+    if diff.len() > MAX_DIFF_BYTES {
+        let mut end = MAX_DIFF_BYTES;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        diff.truncate(end);
+        diff.push_str("\n… (diff truncated)");
+    }
+    diff
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn prompt_placeholders(content: String) -> Vec<Placeholder> {
+    placeholders(&content)
+}
+
+/// Render a snippet template. Git values are only fetched when used.
+#[tauri::command(async)]
+pub async fn prompt_render(
+    webview: tauri::Webview,
+    content: String,
+    context: TemplateContext,
+    ssh_state: tauri::State<'_, SshState>,
+) -> Result<String, KodiqError> {
+    match context.project_path {
+        Some(ref path) => sandbox::check(&webview, path, context.connection_id.as_deref())?,
+        None => sandbox::check_webview(&webview)?,
+    }
+    let used = placeholders(&content);
+    let root = context.project_path.as_deref();
+
+    let mut branch = None;
+    let mut diff = None;
+    if let Some(path) = root {
+        let wants_branch = used.contains(&Placeholder::Branch);
+        let wants_diff = used.contains(&Placeholder::Diff);
+
+        if let Some(ref conn_id) = context.connection_id {
+            if wants_branch {
+                branch =
+                    ssh::git::ssh_git_try(&ssh_state, conn_id, path, "branch --show-current").await;
+            }
+            if wants_diff {
+                diff = ssh::git::ssh_git_try(&ssh_state, conn_id, path, "diff HEAD").await;
+            }
+        } else {
+            let path = path.to_string();
+            (branch, diff) = tauri::async_runtime::spawn_blocking(move || {
+                let git = crate::git::info::git_try;
+                let branch = wants_branch.then(|| git(&path, &["branch", "--show-current"]));
+                let diff = wants_diff.then(|| git(&path, &["diff", "HEAD"]));
+                (branch.flatten(), diff.flatten())
+            })
+            .await
+            .map_err(|e| KodiqError::Other(format!("git: {}", e)))?;
+        }
+    }
+    let diff = diff.map(truncate_diff);
+
+    render(&content, |p| match p {
+        Placeholder::File => context.file.as_deref().map(|f| relative_to(f, root)),
+        Placeholder::Selection => context.selection.clone(),
+        Placeholder::Branch => branch.clone().filter(|b| !b.is_empty()),
+        Placeholder::Diff => diff.clone(),
+        Placeholder::Project => root.map(|r| {
+            std::path::Path::new(r)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| r.to_string())
+        }),
+        Placeholder::Input(label) => context.inputs.get(label).cloned(),
+    })
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders_unique_and_ordered() {
+        let found = placeholders(
+            "Fix {{ file }} on {{branch}}: {{input: Goal}} {{file}} {{unknown}} {{input:Goal }}",
+        );
+        assert_eq!(
+            found,
+            vec![Placeholder::File, Placeholder::Branch, Placeholder::Input("Goal".into())]
+        );
+    }
+
+    #[test]
+    fn test_render_substitutes_and_keeps_unknown() {
+        let out = render("Explain {{selection}} in {{project}} {{other}}", |p| match p {
+            Placeholder::Selection => Some("fn main()".into()),
+            Placeholder::Project => Some("kodiq".into()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(out, "Explain fn main() in kodiq {{other}}");
+    }
+
+    #[test]
+    fn test_render_reports_missing() {
+        let err = render("{{diff}} {{input:Ticket}} {{diff}}", |_| None).unwrap_err();
+        assert_eq!(err.to_string(), "Template error: missing values for diff, input:Ticket");
+    }
+
+    #[test]
+    fn test_relative_file_and_truncation() {
+        assert_eq!(relative_to("/repo/src/main.rs", Some("/repo")), "src/main.rs");
+        assert_eq!(relative_to("/elsewhere/a.rs", Some("/repo")), "/elsewhere/a.rs");
+
+        let diff = truncate_diff("é".repeat(MAX_DIFF_BYTES));
+        assert!(diff.ends_with("(diff truncated)"));
+        assert!(diff.len() < MAX_DIFF_BYTES + 32);
+    }
+
+    #[test]
+    fn test_placeholder_serialization() {
+        let json = serde_json::to_string(&placeholders("{{file}} {{input:Name}}")).unwrap();
+        assert_eq!(json, r#"[{"kind":"file"},{"kind":"input","label":"Name"}]"#);
+    }
+}
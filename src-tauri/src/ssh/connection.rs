@@ -3,6 +3,7 @@ use super::{
     SshConnectionConfig, SshState,
 };
 use crate::error::KodiqError;
+use crate::filesystem::sandbox;
 use crate::state::DbState;
 use russh::client;
 use std::sync::Arc;
@@ -223,6 +224,34 @@ pub async fn ssh_connection_status(
     }
 }
 
+/// Private key files in `~/.ssh`, for the connection dialog's key picker.
+/// Listed here so the webview never needs filesystem access to `~/.ssh`.
+#[tauri::command(async)]
+pub fn ssh_list_keys(webview: tauri::Webview) -> Result<Vec<String>, KodiqError> {
+    sandbox::check_webview(&webview)?;
+    let Some(dir) = dirs::home_dir().map(|h| h.join(".ssh")) else {
+        return Ok(Vec::new());
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut keys: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|e| is_private_key_name(&e.file_name().to_string_lossy()))
+        .map(|e| e.path().to_string_lossy().to_string())
+        .collect();
+    keys.sort();
+    Ok(keys)
+}
+
+/// Everything in `~/.ssh` except public keys and the well-known non-key files.
+fn is_private_key_name(name: &str) -> bool {
+    !name.ends_with(".pub")
+        && !name.starts_with("known_hosts")
+        && !matches!(name, "config" | "authorized_keys" | "environment" | "rc")
+}
+
 /// Detect remote home directory via `echo $HOME`.
 async fn detect_remote_home(
     handle: &client::Handle<KodiqSshHandler>,
@@ -265,3 +294,19 @@ fn expand_tilde(path: &str) -> String {
     }
     path.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_private_key_name() {
+        assert!(is_private_key_name("id_ed25519"));
+        assert!(is_private_key_name("work.pem"));
+        assert!(!is_private_key_name("id_ed25519.pub"));
+        assert!(!is_private_key_name("known_hosts"));
+        assert!(!is_private_key_name("known_hosts.old"));
+        assert!(!is_private_key_name("config"));
+        assert!(!is_private_key_name("authorized_keys"));
+    }
+}
@@ -1,12 +1,16 @@
 use super::{ConnectionStatus, SshState};
 use crate::error::KodiqError;
+use crate::filesystem::encoding::{self, TextEncoding};
+use crate::filesystem::filter::{self, FileFilterSettings, FilterPolicy, ListOptions};
+use crate::filesystem::metadata::{self, DirPage, GitDecorations, Listed, PageRequest};
+use crate::filesystem::write::{self, FileVersion};
 use russh_sftp::client::SftpSession;
-use serde_json::json;
+use std::path::Path;
 
 /// Create a new SFTP session for a connection.
 /// Each call opens a new SFTP subsystem channel (lightweight).
 /// Lock is released before any network I/O to avoid deadlocking concurrent operations.
-async fn create_sftp(
+pub(super) async fn create_sftp(
     ssh_state: &tauri::State<'_, SshState>,
     connection_id: &str,
 ) -> Result<SftpSession, KodiqError> {
@@ -39,106 +43,395 @@ async fn create_sftp(
         .map_err(|e| KodiqError::Sftp(format!("Init SFTP session: {}", e)))
 }
 
+/// Read a small remote text file, `None` if it is missing or unreadable.
+async fn sftp_read_optional(sftp: &SftpSession, path: &str) -> Option<String> {
+    use tokio::io::AsyncReadExt;
+    let mut file = sftp.open(path).await.ok()?;
+    let mut content = String::new();
+    file.read_to_string(&mut content).await.ok()?;
+    Some(content)
+}
+
 /// Read remote directory via SFTP.
+/// Applies the same hidden/ignored policy as local listings, reading the
+/// remote project's ignore files over the same SFTP session. Metadata comes
+/// from the readdir attributes; only symlinks on the page cost extra calls.
 pub async fn sftp_read_dir(
     path: &str,
     ssh_state: &tauri::State<'_, SshState>,
     connection_id: &str,
-) -> Result<Vec<serde_json::Value>, KodiqError> {
+    settings: &FileFilterSettings,
+    opts: &ListOptions,
+    git: Option<&GitDecorations>,
+    page: PageRequest<'_>,
+) -> Result<DirPage, KodiqError> {
     let sftp = create_sftp(ssh_state, connection_id).await?;
 
+    let dir = Path::new(path);
+    let root = filter::listing_root(dir, opts);
+    let mut sources = Vec::new();
+    for (layer, file) in filter::ignore_files(&root, dir) {
+        if let Some(content) = sftp_read_optional(&sftp, &file.to_string_lossy()).await {
+            sources.push((layer, content));
+        }
+    }
+    let policy = FilterPolicy::new(&root, settings, opts, None, sources);
+
     let entries = sftp
         .read_dir(path)
         .await
         .map_err(|e| KodiqError::Sftp(format!("Read dir {}: {}", path, e)))?;
 
-    let skip = ["node_modules", "target", "__pycache__", ".git"];
-
-    let mut items: Vec<_> = entries
-        .into_iter()
-        .filter(|e| {
-            let name = e.file_name();
-            !name.starts_with('.') && !skip.contains(&name.as_str())
-        })
-        .collect();
-
-    items.sort_by(|a, b| {
-        let a_dir = a.file_type().is_dir();
-        let b_dir = b.file_type().is_dir();
-        match (a_dir, b_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.file_name().cmp(&b.file_name()),
-        }
-    });
-
-    let mut result: Vec<serde_json::Value> = Vec::new();
-    for entry in items {
+    let mut items = Vec::new();
+    for entry in entries {
         let name = entry.file_name();
-        let is_dir = entry.file_type().is_dir();
         let full_path = if path.ends_with('/') {
             format!("{}{}", path, name)
         } else {
             format!("{}/{}", path, name)
         };
+        let attrs = entry.metadata();
+        // Resolve symlinks up front so linked directories sort and expand as directories
+        let target = match attrs.is_symlink() {
+            true => Some(sftp.metadata(full_path.as_str()).await.ok()),
+            false => None,
+        };
+        let is_dir = match target {
+            Some(ref followed) => followed.as_ref().is_some_and(|m| m.is_dir()),
+            None => attrs.is_dir(),
+        };
 
-        result.push(json!({
-            "name": name,
-            "path": full_path,
-            "isDir": is_dir,
-        }));
+        let Some(verdict) = policy.classify(Path::new(&full_path), &name, is_dir) else {
+            continue;
+        };
+        items.push(Listed { name, path: full_path, is_dir, verdict, meta: (attrs, target) });
     }
 
-    Ok(result)
+    let (items, next_cursor, total) = metadata::paginate(items, page);
+    let mut result = Vec::with_capacity(items.len());
+    for item in items {
+        let mut entry = item.entry();
+        let (attrs, target) = &item.meta;
+        let stat = match target {
+            Some(Some(followed)) => followed,
+            _ => attrs,
+        };
+
+        if let Some(followed) = target {
+            entry.is_symlink = true;
+            entry.broken_link = followed.is_none();
+            entry.symlink_target = sftp.read_link(item.path.as_str()).await.ok();
+        }
+        if !entry.is_dir {
+            entry.size = stat.size;
+            if !entry.broken_link {
+                entry.kind = metadata::kind_from_name(&entry.name);
+            }
+        }
+        entry.modified = stat.mtime.map(|t| t as u64 * 1000);
+        if let Some(perms) = stat.permissions {
+            let mode = perms & 0o7777;
+            entry.mode = Some(mode);
+            entry.executable = !stat.is_dir() && !entry.broken_link && mode & 0o111 != 0;
+        }
+        if let Some(git) = git {
+            git.decorate(&mut entry);
+        }
+        result.push(entry);
+    }
+
+    Ok(DirPage { entries: result, next_cursor, total })
 }
 
-/// Read remote file via SFTP (up to 1MB).
-pub async fn sftp_read_file(
+/// Open a remote file for reading in chunks. Returns the handle with the
+/// file's size and mtime (ms); the handle keeps its SFTP channel alive.
+pub async fn sftp_open(
     path: &str,
     ssh_state: &tauri::State<'_, SshState>,
     connection_id: &str,
-) -> Result<String, KodiqError> {
+) -> Result<(russh_sftp::client::fs::File, u64, Option<u64>), KodiqError> {
     let sftp = create_sftp(ssh_state, connection_id).await?;
+    let meta = sftp.metadata(path).await.map_err(op_error("Stat", path))?;
+    if meta.is_dir() {
+        return Err(KodiqError::NotFound(format!("Not a file: {}", path)));
+    }
+    let file = sftp.open(path).await.map_err(op_error("Open", path))?;
+    Ok((file, meta.size.unwrap_or(0), meta.mtime.map(|t| t as u64 * 1000)))
+}
 
-    let metadata =
-        sftp.metadata(path).await.map_err(|e| KodiqError::Sftp(format!("Stat {}: {}", path, e)))?;
+/// Write remote file via SFTP with the same guarantees as local saves: the
+/// content goes to a temp file that is fsynced (where the server supports
+/// `fsync@openssh.com`) and renamed over the original, keeping its mode.
+/// With `expected`, a file changed since it was read is left alone and a
+/// conflict with both versions is returned.
+pub async fn sftp_write_file(
+    path: &str,
+    content: &str,
+    encoding: TextEncoding,
+    expected: Option<&FileVersion>,
+    ssh_state: &tauri::State<'_, SshState>,
+    connection_id: &str,
+) -> Result<FileVersion, KodiqError> {
+    let bytes = encoding::encode(content, encoding)?;
+    let sftp = create_sftp(ssh_state, connection_id).await?;
+    let existing = sftp.metadata(path).await.ok();
 
-    if let Some(size) = metadata.size {
-        if size > 1_048_576 {
-            return Err(KodiqError::Other("File too large (>1MB)".to_string()));
-        }
+    if let Some(expected) = expected {
+        let disk = match existing {
+            Some(ref meta) => Some((
+                sftp.read(path).await.map_err(op_error("Read", path))?,
+                meta.mtime.map(|t| t as u64 * 1000),
+            )),
+            None => None,
+        };
+        let disk = disk.as_ref().map(|(bytes, mtime)| (bytes.as_slice(), *mtime));
+        write::check_version(path, expected, disk, content, &bytes)?;
     }
+    replace_file(&sftp, path, &bytes, existing).await
+}
 
-    use tokio::io::AsyncReadExt;
-    let mut file =
-        sftp.open(path).await.map_err(|e| KodiqError::Sftp(format!("Open {}: {}", path, e)))?;
+/// Write raw bytes with the same atomic replace as `sftp_write_file`.
+pub async fn sftp_write_bytes(
+    path: &str,
+    bytes: &[u8],
+    ssh_state: &tauri::State<'_, SshState>,
+    connection_id: &str,
+) -> Result<FileVersion, KodiqError> {
+    let sftp = create_sftp(ssh_state, connection_id).await?;
+    let existing = sftp.metadata(path).await.ok();
+    replace_file(&sftp, path, bytes, existing).await
+}
 
-    let mut content = Vec::new();
-    file.read_to_end(&mut content)
-        .await
-        .map_err(|e| KodiqError::Sftp(format!("Read {}: {}", path, e)))?;
+/// Temp file, fsync, rename over `path` (or create it), keeping its mode.
+async fn replace_file(
+    sftp: &SftpSession,
+    path: &str,
+    bytes: &[u8],
+    existing: Option<russh_sftp::client::fs::Metadata>,
+) -> Result<FileVersion, KodiqError> {
+    use russh_sftp::protocol::{FileAttributes, OpenFlags};
+    use tokio::io::AsyncWriteExt;
+
+    // Write through symlinks rather than replacing them
+    let target = match existing {
+        Some(_) => sftp.canonicalize(path).await.unwrap_or_else(|_| path.to_string()),
+        None => path.to_string(),
+    };
+    let temp = write::temp_name(Path::new(&target)).to_string_lossy().to_string();
+    let attrs = FileAttributes {
+        permissions: existing.as_ref().and_then(|m| m.permissions),
+        ..FileAttributes::empty()
+    };
 
-    String::from_utf8(content).map_err(|_| KodiqError::Other("File is not valid UTF-8".to_string()))
+    let written = async {
+        let flags = OpenFlags::CREATE | OpenFlags::EXCLUDE | OpenFlags::WRITE;
+        let mut file = sftp
+            .open_with_flags_and_attributes(temp.as_str(), flags, attrs)
+            .await
+            .map_err(op_error("Create", &temp))?;
+        file.write_all(bytes)
+            .await
+            .map_err(|e| KodiqError::Sftp(format!("Write {}: {}", temp, e)))?;
+        file.sync_all().await.map_err(op_error("Sync", &temp))?;
+        file.shutdown().await.map_err(|e| KodiqError::Sftp(format!("Close {}: {}", temp, e)))?;
+        sftp_replace(sftp, &temp, &target).await
+    }
+    .await;
+    if written.is_err() {
+        let _ = sftp.remove_file(temp.as_str()).await;
+        written?;
+    }
+
+    let mtime = sftp.metadata(target.as_str()).await.ok().and_then(|m| m.mtime);
+    Ok(write::version_of(bytes, mtime.map(|t| t as u64 * 1000)))
 }
 
-/// Write remote file via SFTP.
-pub async fn sftp_write_file(
+/// Move `temp` over `target`. Plain SFTP rename refuses to overwrite, so an
+/// existing target is first moved aside and only removed once the new file
+/// is in place — at no point is the old content lost.
+async fn sftp_replace(sftp: &SftpSession, temp: &str, target: &str) -> Result<(), KodiqError> {
+    if sftp.rename(temp, target).await.is_ok() {
+        return Ok(());
+    }
+    let backup = format!("{}.bak", temp.trim_end_matches(".tmp"));
+    sftp.rename(target, backup.as_str()).await.map_err(op_error("Rename", target))?;
+    if let Err(e) = sftp.rename(temp, target).await {
+        let _ = sftp.rename(backup.as_str(), target).await;
+        return Err(op_error("Rename", temp)(e));
+    }
+    let _ = sftp.remove_file(backup.as_str()).await;
+    Ok(())
+}
+
+// ── File operations ──────────────────────────────────────────────────
+
+fn op_error(
+    action: &str,
+    path: &str,
+) -> impl FnOnce(russh_sftp::client::error::Error) -> KodiqError {
+    let message = format!("{} {}", action, path);
+    move |e| KodiqError::Sftp(format!("{}: {}", message, e))
+}
+
+/// Whether anything (file, directory or link) exists at a remote path.
+async fn sftp_lexists(sftp: &SftpSession, path: &str) -> bool {
+    sftp.symlink_metadata(path).await.is_ok()
+}
+
+async fn ensure_free(sftp: &SftpSession, path: &str) -> Result<(), KodiqError> {
+    match sftp_lexists(sftp, path).await {
+        true => Err(KodiqError::Conflict(format!("{} already exists", path))),
+        false => Ok(()),
+    }
+}
+
+/// `None` if nothing is at a remote path, otherwise whether it is a directory
+/// (links are not followed).
+pub async fn sftp_probe(
+    path: &str,
+    ssh_state: &tauri::State<'_, SshState>,
+    connection_id: &str,
+) -> Result<Option<bool>, KodiqError> {
+    let sftp = create_sftp(ssh_state, connection_id).await?;
+    Ok(sftp.symlink_metadata(path).await.ok().map(|m| m.is_dir()))
+}
+
+/// Create missing directories along `path` (like `mkdir -p`).
+async fn sftp_mkdir_all(sftp: &SftpSession, path: &Path) -> Result<(), KodiqError> {
+    let mut missing = Vec::new();
+    for dir in path.ancestors() {
+        if dir.as_os_str().is_empty() || sftp_lexists(sftp, &dir.to_string_lossy()).await {
+            break;
+        }
+        missing.push(dir.to_string_lossy().to_string());
+    }
+    for dir in missing.into_iter().rev() {
+        sftp.create_dir(dir.as_str()).await.map_err(op_error("Create dir", &dir))?;
+    }
+    Ok(())
+}
+
+/// Create an empty remote file, failing if anything is already there.
+pub async fn sftp_create_file(
+    path: &str,
+    ssh_state: &tauri::State<'_, SshState>,
+    connection_id: &str,
+) -> Result<(), KodiqError> {
+    use russh_sftp::protocol::OpenFlags;
+    let sftp = create_sftp(ssh_state, connection_id).await?;
+    ensure_free(&sftp, path).await?;
+    if let Some(parent) = Path::new(path).parent() {
+        sftp_mkdir_all(&sftp, parent).await?;
+    }
+    let flags = OpenFlags::CREATE | OpenFlags::EXCLUDE | OpenFlags::WRITE;
+    sftp.open_with_flags(path, flags).await.map_err(op_error("Create", path))?;
+    Ok(())
+}
+
+/// Create a remote directory and any missing parents.
+pub async fn sftp_create_dir(
     path: &str,
-    content: &str,
     ssh_state: &tauri::State<'_, SshState>,
     connection_id: &str,
 ) -> Result<(), KodiqError> {
     let sftp = create_sftp(ssh_state, connection_id).await?;
+    ensure_free(&sftp, path).await?;
+    sftp_mkdir_all(&sftp, Path::new(path)).await
+}
+
+/// Rename or move a remote entry. Never replaces an existing target.
+/// Returns whether the entry is a directory.
+pub async fn sftp_rename(
+    from: &str,
+    to: &str,
+    ssh_state: &tauri::State<'_, SshState>,
+    connection_id: &str,
+) -> Result<bool, KodiqError> {
+    let sftp = create_sftp(ssh_state, connection_id).await?;
+    let meta = sftp.symlink_metadata(from).await.map_err(op_error("Stat", from))?;
+    ensure_free(&sftp, to).await?;
+    sftp.rename(from, to).await.map_err(op_error("Rename", from))?;
+    Ok(meta.is_dir())
+}
 
+/// Copy a remote file or directory tree. SFTP has no copy request, so
+/// contents travel through the client; links are recreated, not followed.
+/// Returns whether the copied entry is a directory.
+pub async fn sftp_copy(
+    from: &str,
+    to: &str,
+    ssh_state: &tauri::State<'_, SshState>,
+    connection_id: &str,
+) -> Result<bool, KodiqError> {
+    use russh_sftp::protocol::{FileAttributes, OpenFlags};
     use tokio::io::AsyncWriteExt;
-    let mut file =
-        sftp.create(path).await.map_err(|e| KodiqError::Sftp(format!("Create {}: {}", path, e)))?;
 
-    file.write_all(content.as_bytes())
-        .await
-        .map_err(|e| KodiqError::Sftp(format!("Write {}: {}", path, e)))?;
+    let sftp = create_sftp(ssh_state, connection_id).await?;
+    ensure_free(&sftp, to).await?;
+    let is_dir = sftp.symlink_metadata(from).await.map_err(op_error("Stat", from))?.is_dir();
 
-    file.shutdown().await.map_err(|e| KodiqError::Sftp(format!("Close {}: {}", path, e)))?;
+    let mut stack = vec![(from.to_string(), to.to_string())];
+    while let Some((src, dst)) = stack.pop() {
+        let meta = sftp.symlink_metadata(src.as_str()).await.map_err(op_error("Stat", &src))?;
+        let attrs = FileAttributes { permissions: meta.permissions, ..FileAttributes::empty() };
 
-    Ok(())
+        if meta.is_symlink() {
+            let target = sftp.read_link(src.as_str()).await.map_err(op_error("Read link", &src))?;
+            // OpenSSH's server takes (target, link) — the reverse of the draft spec
+            sftp.symlink(target, dst.as_str()).await.map_err(op_error("Link", &dst))?;
+        } else if meta.is_dir() {
+            sftp.create_dir(dst.as_str()).await.map_err(op_error("Create dir", &dst))?;
+            let _ = sftp.set_metadata(dst.as_str(), attrs).await;
+            let entries = sftp.read_dir(src.as_str()).await.map_err(op_error("Read dir", &src))?;
+            for entry in entries {
+                let name = entry.file_name();
+                stack.push((format!("{}/{}", src, name), format!("{}/{}", dst, name)));
+            }
+        } else {
+            let data = sftp.read(src.as_str()).await.map_err(op_error("Read", &src))?;
+            let flags = OpenFlags::CREATE | OpenFlags::EXCLUDE | OpenFlags::WRITE;
+            let mut file = sftp
+                .open_with_flags_and_attributes(dst.as_str(), flags, attrs)
+                .await
+                .map_err(op_error("Create", &dst))?;
+            file.write_all(&data)
+                .await
+                .map_err(|e| KodiqError::Sftp(format!("Write {}: {}", dst, e)))?;
+            file.shutdown().await.map_err(|e| KodiqError::Sftp(format!("Close {}: {}", dst, e)))?;
+        }
+    }
+    Ok(is_dir)
+}
+
+/// Permanently delete a remote file or directory tree.
+/// Returns whether it was a directory.
+pub async fn sftp_remove(
+    path: &str,
+    ssh_state: &tauri::State<'_, SshState>,
+    connection_id: &str,
+) -> Result<bool, KodiqError> {
+    let sftp = create_sftp(ssh_state, connection_id).await?;
+    let is_dir = sftp.symlink_metadata(path).await.map_err(op_error("Stat", path))?.is_dir();
+
+    // (path, children already queued) — directories are removed on the second visit
+    let mut stack = vec![(path.to_string(), false)];
+    while let Some((current, expanded)) = stack.pop() {
+        if expanded {
+            sftp.remove_dir(current.as_str()).await.map_err(op_error("Remove dir", &current))?;
+            continue;
+        }
+        let meta =
+            sftp.symlink_metadata(current.as_str()).await.map_err(op_error("Stat", &current))?;
+        if meta.is_dir() {
+            let entries =
+                sftp.read_dir(current.as_str()).await.map_err(op_error("Read dir", &current))?;
+            stack.push((current.clone(), true));
+            for entry in entries {
+                stack.push((format!("{}/{}", current, entry.file_name()), false));
+            }
+        } else {
+            sftp.remove_file(current.as_str()).await.map_err(op_error("Remove", &current))?;
+        }
+    }
+    Ok(is_dir)
 }
@@ -1,15 +1,17 @@
 use super::{ConnectionStatus, SshState};
 use crate::error::KodiqError;
+use russh::client::Msg;
+use russh::Channel;
 use tokio::io::AsyncReadExt;
 
-/// Run a command on remote host via SSH exec channel and return stdout.
-/// Acquires the SSH manager lock only to clone the handle, then releases it
-/// before any network I/O to avoid deadlocking concurrent SSH operations.
-pub async fn ssh_run_command(
+/// Open an SSH channel running `command`. Acquires the SSH manager lock only
+/// to clone the handle, then releases it before any network I/O to avoid
+/// deadlocking concurrent SSH operations.
+pub async fn ssh_exec(
     ssh_state: &tauri::State<'_, SshState>,
     connection_id: &str,
     command: &str,
-) -> Result<String, KodiqError> {
+) -> Result<Channel<Msg>, KodiqError> {
     // Clone the handle inside a tight lock scope — never hold lock across await
     let handle = {
         let manager = ssh_state.lock().await;
@@ -33,6 +35,16 @@ pub async fn ssh_run_command(
         .exec(true, command)
         .await
         .map_err(|e| KodiqError::Ssh(format!("Remote exec: {}", e)))?;
+    Ok(channel)
+}
+
+/// Run a command on remote host via SSH exec channel and return stdout.
+pub async fn ssh_run_command(
+    ssh_state: &tauri::State<'_, SshState>,
+    connection_id: &str,
+    command: &str,
+) -> Result<String, KodiqError> {
+    let channel = ssh_exec(ssh_state, connection_id, command).await?;
 
     let mut stream = channel.into_stream();
     let mut output = Vec::new();
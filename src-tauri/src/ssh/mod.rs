@@ -5,6 +5,7 @@ pub mod filesystem;
 pub mod git;
 pub mod port_forward;
 pub mod terminal;
+pub mod watcher;
 
 use russh::client::Handle;
 use serde::{Deserialize, Serialize};
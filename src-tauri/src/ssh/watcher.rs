@@ -0,0 +1,522 @@
+use super::{filesystem, git, ConnectionStatus, SshState};
+use crate::error::KodiqError;
+use crate::filesystem::watcher::{
+    self, FsChange, FsChangeKind, FsEventBatch, IgnorePolicy, WatcherSettings,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tokio::io::AsyncReadExt;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// Flush an inotify batch once the stream has been quiet this long…
+const QUIET: Duration = Duration::from_millis(500);
+/// …or once it has been collecting for this long.
+const MAX_BATCH_WAIT: Duration = Duration::from_secs(2);
+
+// ── State ────────────────────────────────────────────────────────────
+
+/// One remote root being watched.
+pub struct RemoteWatch {
+    id: String,
+    cancel: CancellationToken,
+    /// Directories the polling fallback lists (root + expanded tree nodes).
+    dirs: Arc<std::sync::Mutex<BTreeSet<String>>>,
+}
+
+/// Remote watches keyed by connection and root.
+pub type RemoteWatchState = Arc<Mutex<HashMap<String, RemoteWatch>>>;
+
+pub fn new_remote_watch_state() -> RemoteWatchState {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+fn key(connection_id: &str, root: &str) -> String {
+    format!("{}:{}", connection_id, root)
+}
+
+// ── inotifywait output ───────────────────────────────────────────────
+
+/// Split `CREATE,ISDIR /path` (our `--format '%e %w%f'`) into flags and path.
+/// Anything else (warnings, errors) is `None`.
+fn parse_line(line: &str) -> Option<(Vec<&str>, &str)> {
+    let (events, path) = line.split_once(' ')?;
+    let flags: Vec<&str> = events.split(',').collect();
+    let valid = flags
+        .iter()
+        .all(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_uppercase() || c == '_'));
+    (valid && path.starts_with('/')).then_some((flags, path))
+}
+
+/// Turn inotifywait lines into changes. A `MOVED_FROM` directly followed by
+/// `MOVED_TO` is a rename. Returns whether anything under `.git` changed.
+fn parse_events(lines: &[String]) -> (Vec<FsChange>, bool) {
+    let mut changes = Vec::new();
+    let mut git_changed = false;
+    let mut moved_from: Option<(String, bool)> = None;
+
+    for (flags, path) in lines.iter().filter_map(|l| parse_line(l)) {
+        let p = Path::new(path);
+        if watcher::is_git_event(p) {
+            git_changed = true;
+            continue;
+        }
+        let is_dir = flags.contains(&"ISDIR");
+        let has = |name: &str| flags.contains(&name);
+
+        let pending = moved_from.take();
+        if has("MOVED_TO") {
+            let from = pending.as_ref().map(|(f, _)| Path::new(f.as_str()));
+            let kind = if from.is_some() { FsChangeKind::Renamed } else { FsChangeKind::Created };
+            changes.push(watcher::change(kind, p, from, is_dir));
+            continue;
+        }
+        if let Some((from, from_dir)) = pending {
+            changes.push(watcher::change(FsChangeKind::Removed, Path::new(&from), None, from_dir));
+        }
+
+        if has("MOVED_FROM") {
+            moved_from = Some((path.to_string(), is_dir));
+        } else if has("CREATE") {
+            changes.push(watcher::change(FsChangeKind::Created, p, None, is_dir));
+        } else if has("DELETE") {
+            changes.push(watcher::change(FsChangeKind::Removed, p, None, is_dir));
+        } else if has("MODIFY") || has("ATTRIB") || has("CLOSE_WRITE") {
+            changes.push(watcher::change(FsChangeKind::Modified, p, None, is_dir));
+        }
+    }
+    if let Some((from, from_dir)) = moved_from {
+        changes.push(watcher::change(FsChangeKind::Removed, Path::new(&from), None, from_dir));
+    }
+    (watcher::coalesce(changes), git_changed)
+}
+
+/// `--exclude` regex for the plain directory names in the ignore list, so
+/// inotifywait doesn't spend watches on `node_modules` and friends.
+fn exclude_regex(settings: &WatcherSettings) -> Option<String> {
+    let names: Vec<String> = settings
+        .ignore
+        .iter()
+        .map(|p| p.trim_end_matches('/'))
+        .filter(|p| !p.is_empty() && !p.contains(['/', '*', '?', '[', '!']))
+        .map(regex::escape)
+        .collect();
+    (!names.is_empty()).then(|| format!("/({})(/|$)", names.join("|")))
+}
+
+fn inotify_command(root: &str, settings: &WatcherSettings) -> String {
+    let exclude = exclude_regex(settings)
+        .map(|r| format!(" --exclude {}", git::shell_quote(&r)))
+        .unwrap_or_default();
+    format!(
+        "inotifywait -mrq -e create,delete,modify,move,attrib --format '%e %w%f'{} {} 2>&1",
+        exclude,
+        git::shell_quote(root)
+    )
+}
+
+// ── SFTP polling ─────────────────────────────────────────────────────
+
+/// What polling remembers about an entry: (is_dir, size, mtime).
+type Stat = (bool, Option<u64>, Option<u32>);
+
+/// Changes between two listings of `dir`.
+fn diff_listing(
+    dir: &str,
+    old: &HashMap<String, Stat>,
+    new: &HashMap<String, Stat>,
+) -> Vec<FsChange> {
+    let path = |name: &str| format!("{}/{}", dir.trim_end_matches('/'), name);
+    let mut changes = Vec::new();
+    for (name, stat) in new {
+        let kind = match old.get(name) {
+            None => FsChangeKind::Created,
+            Some(prev) if !stat.0 && prev != stat => FsChangeKind::Modified,
+            Some(_) => continue,
+        };
+        changes.push(watcher::change(kind, Path::new(&path(name)), None, stat.0));
+    }
+    for (name, stat) in old.iter().filter(|(name, _)| !new.contains_key(*name)) {
+        changes.push(watcher::change(FsChangeKind::Removed, Path::new(&path(name)), None, stat.0));
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+async fn is_connected(app: &tauri::AppHandle, connection_id: &str) -> bool {
+    let ssh_state = app.state::<SshState>();
+    let manager = ssh_state.lock().await;
+    manager.get(connection_id).is_some_and(|c| c.status == ConnectionStatus::Connected)
+}
+
+// ── Watch loops ──────────────────────────────────────────────────────
+
+/// Everything one watch loop needs.
+struct Watch {
+    app: tauri::AppHandle,
+    connection_id: String,
+    root: String,
+    settings: WatcherSettings,
+    policy: IgnorePolicy,
+    cancel: CancellationToken,
+    dirs: Arc<std::sync::Mutex<BTreeSet<String>>>,
+}
+
+impl Watch {
+    fn emit(&self, changes: Vec<FsChange>, git_changed: bool) {
+        let changes: Vec<FsChange> = changes
+            .into_iter()
+            .filter(|c| !self.policy.is_ignored(Path::new(&c.path), c.is_dir))
+            .collect();
+        if !changes.is_empty() {
+            let _ = self.app.emit("fs-changed", self.root.clone());
+            let batch = FsEventBatch {
+                root: self.root.clone(),
+                connection_id: Some(self.connection_id.clone()),
+                changes,
+                rescan: false,
+            };
+            let _ = self.app.emit("fs-events", batch);
+        }
+        if git_changed {
+            let _ = self.app.emit("git-changed", self.root.clone());
+        }
+    }
+
+    /// Stream `inotifywait -m`. Returns `Ok` when cancelled, `Err` when the
+    /// command dies (missing, watch limit, connection lost).
+    async fn inotify(&self) -> Result<(), KodiqError> {
+        let handle = {
+            let ssh_state = self.app.state::<SshState>();
+            let manager = ssh_state.lock().await;
+            let conn = manager
+                .get(&self.connection_id)
+                .ok_or_else(|| KodiqError::ConnectionNotFound(self.connection_id.clone()))?;
+            conn.handle.clone()
+        }; // lock released here
+
+        let channel = handle
+            .channel_open_session()
+            .await
+            .map_err(|e| KodiqError::Ssh(format!("Open channel: {}", e)))?;
+        channel
+            .exec(true, inotify_command(&self.root, &self.settings))
+            .await
+            .map_err(|e| KodiqError::Ssh(format!("Remote exec: {}", e)))?;
+
+        let mut stream = channel.into_stream();
+        let mut buf = [0u8; 8192];
+        let mut partial = String::new();
+        let mut lines: Vec<String> = Vec::new();
+        let mut started: Option<Instant> = None;
+        let mut last_error = String::new();
+
+        loop {
+            tokio::select! {
+                _ = self.cancel.cancelled() => return Ok(()),
+                read = stream.read(&mut buf) => {
+                    let n = match read {
+                        Ok(0) | Err(_) => 0,
+                        Ok(n) => n,
+                    };
+                    if n == 0 {
+                        let (changes, git_changed) = parse_events(&lines);
+                        self.emit(changes, git_changed);
+                        let reason = if last_error.is_empty() { "exited" } else { &last_error };
+                        return Err(KodiqError::Ssh(format!("inotifywait {}", reason)));
+                    }
+                    partial.push_str(&String::from_utf8_lossy(&buf[..n]));
+                    while let Some(end) = partial.find('\n') {
+                        let line: String = partial.drain(..=end).collect();
+                        let line = line.trim_end().to_string();
+                        if parse_line(&line).is_none() {
+                            tracing::debug!("inotifywait: {}", line);
+                            last_error = line;
+                            continue;
+                        }
+                        lines.push(line);
+                        started.get_or_insert_with(Instant::now);
+                    }
+                    if started.is_some_and(|s| s.elapsed() >= MAX_BATCH_WAIT) {
+                        let (changes, git_changed) = parse_events(&std::mem::take(&mut lines));
+                        self.emit(changes, git_changed);
+                        started = None;
+                    }
+                }
+                _ = tokio::time::sleep(QUIET), if !lines.is_empty() => {
+                    let (changes, git_changed) = parse_events(&std::mem::take(&mut lines));
+                    self.emit(changes, git_changed);
+                    started = None;
+                }
+            }
+        }
+    }
+
+    /// List the watched directories over SFTP every poll interval and
+    /// report differences. Returns `Ok` when cancelled.
+    async fn poll(&self) -> Result<(), KodiqError> {
+        let ssh_state = self.app.state::<SshState>();
+        let sftp = filesystem::create_sftp(&ssh_state, &self.connection_id).await?;
+        let interval = Duration::from_millis(self.settings.poll_interval_ms.max(1000));
+        let git_files = [format!("{}/.git/index", self.root), format!("{}/.git/HEAD", self.root)];
+
+        let mut listings: HashMap<String, HashMap<String, Stat>> = HashMap::new();
+        let mut git_stamp: Option<Vec<Stat>> = None;
+        loop {
+            let dirs: Vec<String> = match self.dirs.lock() {
+                Ok(dirs) => dirs.iter().cloned().collect(),
+                Err(_) => vec![self.root.clone()],
+            };
+            listings.retain(|dir, _| dirs.contains(dir));
+
+            let mut changes = Vec::new();
+            for dir in dirs {
+                let Ok(entries) = sftp.read_dir(dir.as_str()).await else {
+                    if !is_connected(&self.app, &self.connection_id).await {
+                        return Err(KodiqError::ConnectionNotFound(self.connection_id.clone()));
+                    }
+                    // Gone; the parent listing reports the deletion
+                    listings.remove(&dir);
+                    continue;
+                };
+                let listing: HashMap<String, Stat> = entries
+                    .map(|e| {
+                        let meta = e.metadata();
+                        (e.file_name(), (meta.is_dir(), meta.size, meta.mtime))
+                    })
+                    .collect();
+                if let Some(old) = listings.get(&dir) {
+                    changes.extend(diff_listing(&dir, old, &listing));
+                }
+                listings.insert(dir, listing);
+            }
+
+            let mut stamp = Vec::new();
+            for file in &git_files {
+                let meta = sftp.metadata(file.as_str()).await.ok();
+                stamp.push(meta.map_or((false, None, None), |m| (false, m.size, m.mtime)));
+            }
+            let git_changed = git_stamp.as_ref().is_some_and(|prev| prev != &stamp);
+            git_stamp = Some(stamp);
+
+            self.emit(changes, git_changed);
+
+            tokio::select! {
+                _ = self.cancel.cancelled() => return Ok(()),
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+    }
+
+    async fn run(self, use_inotify: bool, id: String) {
+        let conn = Some(self.connection_id.as_str());
+        let mut result = Ok(());
+        if use_inotify {
+            result = self.inotify().await;
+            if let Err(ref e) = result {
+                if is_connected(&self.app, &self.connection_id).await {
+                    let message = format!("{}; falling back to polling", e);
+                    watcher::warn(&self.app, &self.root, conn, message, true);
+                    result = self.poll().await;
+                }
+            }
+        } else if !self.cancel.is_cancelled() {
+            result = self.poll().await;
+        }
+
+        match result {
+            Err(KodiqError::ConnectionNotFound(_)) => {
+                tracing::info!("Remote watcher for {} ended: disconnected", self.root);
+            }
+            Err(e) => watcher::warn(
+                &self.app,
+                &self.root,
+                conn,
+                format!("Watching stopped: {}", e),
+                false,
+            ),
+            Ok(()) => {}
+        }
+
+        // Forget ourselves unless a newer watch already replaced us
+        let state = self.app.state::<RemoteWatchState>();
+        let mut watches = state.lock().await;
+        let k = key(&self.connection_id, &self.root);
+        if watches.get(&k).is_some_and(|w| w.id == id) {
+            watches.remove(&k);
+        }
+    }
+}
+
+// ── Public API (called from filesystem::watcher commands) ────────────
+
+/// Watch a remote root: `inotifywait` when the host has it, SFTP polling
+/// of the root and expanded directories otherwise. Replaces an earlier
+/// watch of the same root.
+pub async fn start(
+    app: &tauri::AppHandle,
+    ssh_state: &tauri::State<'_, SshState>,
+    state: &tauri::State<'_, RemoteWatchState>,
+    connection_id: &str,
+    root: &str,
+) -> Result<(), KodiqError> {
+    let root = root.trim_end_matches('/').to_string();
+    let probe = git::ssh_run_command(ssh_state, connection_id, "command -v inotifywait").await?;
+    let use_inotify = !probe.is_empty();
+
+    let mut settings = watcher::load_settings(app, &root);
+    // Ignore files live on the remote host; only the pattern list applies
+    settings.use_gitignore = false;
+    let policy = IgnorePolicy::new(Path::new(&root), &settings);
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let cancel = CancellationToken::new();
+    let dirs = Arc::new(std::sync::Mutex::new(BTreeSet::from([root.clone()])));
+    {
+        let mut watches = state.lock().await;
+        let previous = watches.insert(
+            key(connection_id, &root),
+            RemoteWatch { id: id.clone(), cancel: cancel.clone(), dirs: dirs.clone() },
+        );
+        if let Some(previous) = previous {
+            previous.cancel.cancel();
+        }
+    }
+
+    let watch = Watch {
+        app: app.clone(),
+        connection_id: connection_id.to_string(),
+        root: root.clone(),
+        settings,
+        policy,
+        cancel,
+        dirs,
+    };
+    tokio::spawn(watch.run(use_inotify, id));
+
+    let mode = if use_inotify { "inotifywait" } else { "SFTP polling" };
+    tracing::info!("Remote watcher started for {} on {} ({})", root, connection_id, mode);
+    Ok(())
+}
+
+/// Stop one remote root, or every root on the connection when `root` is `None`.
+pub async fn stop(state: &RemoteWatchState, connection_id: &str, root: Option<&str>) {
+    let mut watches = state.lock().await;
+    let prefix = key(connection_id, "");
+    let target = root.map(|r| key(connection_id, r.trim_end_matches('/')));
+    watches.retain(|k, w| {
+        let matched = match target {
+            Some(ref t) => k == t,
+            None => k.starts_with(&prefix),
+        };
+        if matched {
+            w.cancel.cancel();
+        }
+        !matched
+    });
+}
+
+/// Stop every remote watch.
+pub async fn stop_all(state: &RemoteWatchState) {
+    let mut watches = state.lock().await;
+    for (_, w) in watches.drain() {
+        w.cancel.cancel();
+    }
+}
+
+/// Set which directories the polling fallback lists, besides the root.
+pub async fn set_dirs(
+    state: &RemoteWatchState,
+    connection_id: &str,
+    root: &str,
+    dirs: Vec<String>,
+) {
+    let root = root.trim_end_matches('/');
+    let watches = state.lock().await;
+    if let Some(watch) = watches.get(&key(connection_id, root)) {
+        if let Ok(mut set) = watch.dirs.lock() {
+            *set = dirs.into_iter().map(|d| d.trim_end_matches('/').to_string()).collect();
+            set.insert(root.to_string());
+        }
+    }
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let (changes, git_changed) = parse_events(&lines(&[
+            "Setting up watches.  Beware: since -r was given, this may take a while!",
+            "CREATE /p/new.txt",
+            "MODIFY /p/new.txt",
+            "CREATE,ISDIR /p/src",
+            "MOVED_FROM /p/a.rs",
+            "MOVED_TO /p/b.rs",
+            "MOVED_FROM /p/gone.rs",
+            "DELETE /p/old.txt",
+            "MODIFY /p/.git/index",
+            "MOVED_TO /p/incoming.rs",
+        ]));
+        assert!(git_changed);
+        let summary: Vec<_> = changes.iter().map(|c| (c.kind, c.path.as_str(), c.is_dir)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (FsChangeKind::Created, "/p/new.txt", false),
+                (FsChangeKind::Created, "/p/src", true),
+                (FsChangeKind::Renamed, "/p/b.rs", false),
+                (FsChangeKind::Removed, "/p/gone.rs", false),
+                (FsChangeKind::Removed, "/p/old.txt", false),
+                (FsChangeKind::Created, "/p/incoming.rs", false),
+            ]
+        );
+        assert_eq!(changes[2].from.as_deref(), Some("/p/a.rs"));
+    }
+
+    #[test]
+    fn test_inotify_command_excludes_plain_dirs() {
+        let settings = WatcherSettings {
+            ignore: vec!["node_modules".into(), "*.log".into(), ".venv/".into()],
+            ..Default::default()
+        };
+        assert_eq!(exclude_regex(&settings).unwrap(), r"/(node_modules|\.venv)(/|$)");
+        let cmd = inotify_command("/srv/my app", &settings);
+        assert!(cmd.starts_with("inotifywait -mrq "));
+        assert!(cmd.ends_with(" '/srv/my app' 2>&1"));
+    }
+
+    #[test]
+    fn test_diff_listing() {
+        let old = HashMap::from([
+            ("a.txt".to_string(), (false, Some(1), Some(10))),
+            ("b.txt".to_string(), (false, Some(1), Some(10))),
+            ("src".to_string(), (true, Some(4096), Some(10))),
+        ]);
+        let new = HashMap::from([
+            ("a.txt".to_string(), (false, Some(2), Some(11))),
+            ("src".to_string(), (true, Some(4096), Some(12))),
+            ("c.txt".to_string(), (false, Some(1), Some(12))),
+        ]);
+        let changes = diff_listing("/p/", &old, &new);
+        let summary: Vec<_> = changes.iter().map(|c| (c.kind, c.path.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (FsChangeKind::Modified, "/p/a.txt"),
+                (FsChangeKind::Removed, "/p/b.txt"),
+                (FsChangeKind::Created, "/p/c.txt"),
+            ]
+        );
+    }
+}
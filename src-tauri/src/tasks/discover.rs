@@ -0,0 +1,670 @@
+use crate::filesystem::watcher::FsChange;
+use crate::workspace::config;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::Emitter;
+
+const MAKEFILES: &[&str] = &["GNUmakefile", "makefile", "Makefile"];
+const JUSTFILES: &[&str] = &["justfile", "Justfile", ".justfile"];
+const COMPOSE_FILES: &[&str] =
+    &["compose.yaml", "compose.yml", "docker-compose.yaml", "docker-compose.yml"];
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskSource {
+    Npm,
+    Cargo,
+    Make,
+    Just,
+    Pyproject,
+    Compose,
+    /// Shared through `.kodiq/workspace.json`.
+    Workspace,
+}
+
+impl TaskSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Npm => "npm",
+            Self::Cargo => "cargo",
+            Self::Make => "make",
+            Self::Just => "just",
+            Self::Pyproject => "pyproject",
+            Self::Compose => "compose",
+            Self::Workspace => "workspace",
+        }
+    }
+}
+
+/// A runnable task found in one of the project's manifests.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    /// `<source>:<name>`, unique within the project.
+    pub id: String,
+    pub name: String,
+    pub source: TaskSource,
+    /// Program and arguments; no shell involved.
+    pub command: Vec<String>,
+    pub cwd: String,
+    /// The manifest the task was read from.
+    pub file: String,
+    pub description: Option<String>,
+}
+
+fn task(
+    source: TaskSource,
+    name: &str,
+    command: Vec<String>,
+    root: &Path,
+    file: &Path,
+    description: Option<String>,
+) -> Task {
+    Task {
+        id: format!("{}:{}", source.as_str(), name),
+        name: name.to_string(),
+        source,
+        command,
+        cwd: root.to_string_lossy().to_string(),
+        file: file.to_string_lossy().to_string(),
+        description: description.filter(|d| !d.is_empty()),
+    }
+}
+
+fn first_file(root: &Path, names: &[&str]) -> Option<PathBuf> {
+    names.iter().map(|n| root.join(n)).find(|p| p.is_file())
+}
+
+/// Package managers are `.cmd` shims on Windows.
+fn shim(program: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("{}.cmd", program)
+    } else {
+        program.to_string()
+    }
+}
+
+fn command(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|p| p.to_string()).collect()
+}
+
+// ── package.json ─────────────────────────────────────────────────────
+
+fn package_manager(root: &Path) -> String {
+    if root.join("pnpm-lock.yaml").is_file() {
+        shim("pnpm")
+    } else if root.join("yarn.lock").is_file() {
+        shim("yarn")
+    } else if root.join("bun.lockb").is_file() || root.join("bun.lock").is_file() {
+        "bun".to_string()
+    } else {
+        shim("npm")
+    }
+}
+
+/// `scripts`, run with the package manager the lockfile belongs to. The
+/// script itself is the description.
+fn npm(root: &Path) -> Vec<Task> {
+    let file = root.join("package.json");
+    let Ok(text) = std::fs::read_to_string(&file) else { return Vec::new() };
+    let package: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+    let Some(scripts) = package["scripts"].as_object() else { return Vec::new() };
+    let pm = package_manager(root);
+    scripts
+        .iter()
+        .map(|(name, script)| {
+            let description = script.as_str().map(String::from);
+            task(TaskSource::Npm, name, command(&[&pm, "run", name]), root, &file, description)
+        })
+        .collect()
+}
+
+// ── Cargo.toml ───────────────────────────────────────────────────────
+
+/// Binaries of the root package: `[[bin]]` targets, `src/main.rs` and
+/// `src/bin/*.rs`.
+fn cargo_bins(root: &Path, manifest: &toml::Value) -> BTreeSet<String> {
+    let mut bins = BTreeSet::new();
+    let Some(package) = manifest.get("package") else { return bins };
+    let targets = manifest.get("bin").and_then(|b| b.as_array()).cloned().unwrap_or_default();
+    bins.extend(targets.iter().filter_map(|b| b.get("name")?.as_str().map(String::from)));
+    if root.join("src/main.rs").is_file() {
+        if let Some(name) = package.get("name").and_then(|n| n.as_str()) {
+            bins.insert(name.to_string());
+        }
+    }
+    if let Ok(entries) = std::fs::read_dir(root.join("src/bin")) {
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.extension().is_some_and(|e| e == "rs") {
+                bins.insert(path.file_stem().unwrap_or_default().to_string_lossy().to_string());
+            } else if path.join("main.rs").is_file() {
+                bins.insert(path.file_name().unwrap_or_default().to_string_lossy().to_string());
+            }
+        }
+    }
+    bins
+}
+
+fn cargo(root: &Path) -> Vec<Task> {
+    let file = root.join("Cargo.toml");
+    let Ok(text) = std::fs::read_to_string(&file) else { return Vec::new() };
+    let manifest: toml::Value =
+        toml::from_str(&text).unwrap_or(toml::Value::Table(Default::default()));
+    let mut tasks: Vec<Task> = [
+        ("build", "Compile the project"),
+        ("check", "Check for errors without building"),
+        ("test", "Run the tests"),
+        ("clippy", "Lint with Clippy"),
+    ]
+    .iter()
+    .map(|(name, about)| {
+        task(
+            TaskSource::Cargo,
+            name,
+            command(&["cargo", name]),
+            root,
+            &file,
+            Some(about.to_string()),
+        )
+    })
+    .collect();
+
+    let bins = cargo_bins(root, &manifest);
+    if bins.len() == 1 {
+        let about = "Run the binary".to_string();
+        tasks.push(task(
+            TaskSource::Cargo,
+            "run",
+            command(&["cargo", "run"]),
+            root,
+            &file,
+            Some(about),
+        ));
+    } else {
+        for bin in &bins {
+            let name = format!("run {}", bin);
+            let about = format!("Run the {} binary", bin);
+            let run = command(&["cargo", "run", "--bin", bin]);
+            tasks.push(task(TaskSource::Cargo, &name, run, root, &file, Some(about)));
+        }
+    }
+    tasks
+}
+
+// ── Makefile ─────────────────────────────────────────────────────────
+
+fn make_target_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^([A-Za-z0-9_][A-Za-z0-9_./-]*(?:[ \t]+[A-Za-z0-9_][A-Za-z0-9_./-]*)*)[ \t]*::?([^=].*)?$")
+            .unwrap()
+    })
+}
+
+/// The text of a `#` comment line, if `line` is one.
+fn comment(line: &str) -> Option<String> {
+    let text = line.trim_start().strip_prefix('#')?;
+    Some(text.trim_start_matches('#').trim().to_string())
+}
+
+/// Explicit targets. Descriptions come from a trailing `## help` or the
+/// comment line right above the rule.
+fn parse_makefile(text: &str) -> Vec<(String, Option<String>)> {
+    let mut found: Vec<(String, Option<String>)> = Vec::new();
+    let mut previous: Option<String> = None;
+    for line in text.lines() {
+        let above = previous.take();
+        if line.starts_with('\t') || line.starts_with(' ') {
+            continue;
+        }
+        if let Some(text) = comment(line) {
+            previous = Some(text);
+            continue;
+        }
+        let Some(caps) = make_target_regex().captures(line) else { continue };
+        let rest = caps.get(2).map_or("", |m| m.as_str());
+        if rest.starts_with(":=") {
+            continue;
+        }
+        let help = rest.split_once("##").map(|(_, help)| help.trim().to_string());
+        let description = help.or(above);
+        for name in caps[1].split_whitespace() {
+            if !found.iter().any(|(n, _)| n == name) {
+                found.push((name.to_string(), description.clone()));
+            }
+        }
+    }
+    found
+}
+
+fn make(root: &Path) -> Vec<Task> {
+    let Some(file) = first_file(root, MAKEFILES) else { return Vec::new() };
+    let text = std::fs::read_to_string(&file).unwrap_or_default();
+    parse_makefile(&text)
+        .into_iter()
+        .map(|(name, description)| {
+            task(TaskSource::Make, &name, command(&["make", &name]), root, &file, description)
+        })
+        .collect()
+}
+
+// ── justfile ─────────────────────────────────────────────────────────
+
+fn just_recipe_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^@?([A-Za-z_][A-Za-z0-9_-]*)(?:[ \t][^:]*)?:(?:[^=]|$)").unwrap()
+    })
+}
+
+fn just_doc_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"doc\(\s*["']([^"']*)["']\s*\)"#).unwrap())
+}
+
+/// Public recipes. `[private]` and `_`-prefixed ones are left out; the
+/// description is a `[doc(...)]` attribute or the comment above.
+fn parse_justfile(text: &str) -> Vec<(String, Option<String>)> {
+    let mut found = Vec::new();
+    let mut doc: Option<String> = None;
+    let mut private = false;
+    for line in text.lines() {
+        if line.trim().is_empty() || line.starts_with([' ', '\t']) {
+            doc = None;
+            private = false;
+            continue;
+        }
+        if let Some(text) = comment(line) {
+            if !line.starts_with("#!") {
+                doc = Some(text);
+            }
+            continue;
+        }
+        if line.starts_with('[') {
+            private |= line.contains("private");
+            if let Some(caps) = just_doc_regex().captures(line) {
+                doc = Some(caps[1].to_string());
+            }
+            continue;
+        }
+        if let Some(caps) = just_recipe_regex().captures(line) {
+            let name = &caps[1];
+            if !private && !name.starts_with('_') {
+                found.push((name.to_string(), doc.clone()));
+            }
+        }
+        doc = None;
+        private = false;
+    }
+    found
+}
+
+fn just(root: &Path) -> Vec<Task> {
+    let Some(file) = first_file(root, JUSTFILES) else { return Vec::new() };
+    let text = std::fs::read_to_string(&file).unwrap_or_default();
+    parse_justfile(&text)
+        .into_iter()
+        .map(|(name, description)| {
+            task(TaskSource::Just, &name, command(&["just", &name]), root, &file, description)
+        })
+        .collect()
+}
+
+// ── pyproject.toml ───────────────────────────────────────────────────
+
+/// How entry points are launched: through the project's tool, or from
+/// its virtualenv.
+fn python_runner(root: &Path, doc: &toml::Value, script: &str) -> Vec<String> {
+    let tool = |name: &str| doc.get("tool").and_then(|t| t.get(name)).is_some();
+    if root.join("uv.lock").is_file() {
+        command(&["uv", "run", script])
+    } else if tool("poetry") || root.join("poetry.lock").is_file() {
+        command(&["poetry", "run", script])
+    } else if tool("pdm") || root.join("pdm.lock").is_file() {
+        command(&["pdm", "run", script])
+    } else {
+        let bin = if cfg!(target_os = "windows") { "Scripts" } else { "bin" };
+        let local = [".venv", "venv"]
+            .iter()
+            .map(|d| root.join(d).join(bin).join(script))
+            .find(|p| p.is_file() || p.with_extension("exe").is_file());
+        vec![local.map_or_else(|| script.to_string(), |p| p.to_string_lossy().to_string())]
+    }
+}
+
+/// `[project.scripts]` and `[tool.poetry.scripts]` entry points, and
+/// `[tool.pdm.scripts]`.
+fn pyproject(root: &Path) -> Vec<Task> {
+    let file = root.join("pyproject.toml");
+    let Ok(text) = std::fs::read_to_string(&file) else { return Vec::new() };
+    let Ok(doc) = toml::from_str::<toml::Value>(&text) else { return Vec::new() };
+    let table = |path: &[&str]| {
+        path.iter().try_fold(&doc, |v, key| v.get(key)).and_then(|v| v.as_table()).cloned()
+    };
+
+    let mut tasks: Vec<Task> = Vec::new();
+    let entry_points = [table(&["project", "scripts"]), table(&["tool", "poetry", "scripts"])];
+    for (name, target) in entry_points.into_iter().flatten().flatten() {
+        if tasks.iter().any(|t| t.name == name) {
+            continue;
+        }
+        let description = match &target {
+            toml::Value::String(s) => Some(s.clone()),
+            other => other.get("callable").and_then(|c| c.as_str()).map(String::from),
+        };
+        let run = python_runner(root, &doc, &name);
+        tasks.push(task(TaskSource::Pyproject, &name, run, root, &file, description));
+    }
+
+    for (name, script) in table(&["tool", "pdm", "scripts"]).into_iter().flatten() {
+        if name == "_" || tasks.iter().any(|t| t.name == name) {
+            continue;
+        }
+        let description = match &script {
+            toml::Value::String(s) => Some(s.clone()),
+            other => ["help", "cmd", "shell", "call"]
+                .iter()
+                .find_map(|k| other.get(k).and_then(|v| v.as_str()))
+                .map(String::from),
+        };
+        let run = command(&["pdm", "run", &name]);
+        tasks.push(task(TaskSource::Pyproject, &name, run, root, &file, description));
+    }
+    tasks
+}
+
+// ── docker compose ───────────────────────────────────────────────────
+
+fn compose_key_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"^(\s*)["']?([A-Za-z0-9][A-Za-z0-9._-]*)["']?\s*:\s*(.*?)\s*(?:#.*)?$"#)
+            .unwrap()
+    })
+}
+
+/// Services under the top-level `services:` key with their image or build
+/// context. Only the indentation structure is read, which is all compose
+/// files use for these keys.
+fn parse_compose(text: &str) -> Vec<(String, Option<String>)> {
+    let mut found: Vec<(String, Option<String>)> = Vec::new();
+    let mut in_services = false;
+    let mut service_indent: Option<usize> = None;
+    for line in text.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let Some(caps) = compose_key_regex().captures(line) else { continue };
+        let indent = caps[1].len();
+        let (key, value) = (&caps[2], caps[3].trim_matches(['"', '\'']));
+        if indent == 0 {
+            in_services = key == "services";
+            service_indent = None;
+            continue;
+        }
+        if !in_services {
+            continue;
+        }
+        let service_indent = *service_indent.get_or_insert(indent);
+        if indent == service_indent {
+            found.push((key.to_string(), None));
+        } else if indent > service_indent && !value.is_empty() {
+            if let Some((_, description)) = found.last_mut() {
+                match key {
+                    "image" => *description = Some(value.to_string()),
+                    "build" if description.is_none() => {
+                        *description = Some(format!("build {}", value))
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    found
+}
+
+fn compose(root: &Path) -> Vec<Task> {
+    let Some(file) = first_file(root, COMPOSE_FILES) else { return Vec::new() };
+    let text = std::fs::read_to_string(&file).unwrap_or_default();
+    parse_compose(&text)
+        .into_iter()
+        .map(|(name, description)| {
+            let up = command(&["docker", "compose", "up", &name]);
+            task(TaskSource::Compose, &name, up, root, &file, description)
+        })
+        .collect()
+}
+
+fn workspace(root: &Path) -> Vec<Task> {
+    let Some(file) = config::load(root).file else { return Vec::new() };
+    let path = config::path(root);
+    file.tasks
+        .into_iter()
+        .map(|shared| {
+            let mut task = task(
+                TaskSource::Workspace,
+                &shared.name,
+                shared.command,
+                root,
+                &path,
+                shared.description,
+            );
+            if let Some(cwd) = shared.cwd {
+                task.cwd = root.join(cwd).to_string_lossy().to_string();
+            }
+            task
+        })
+        .collect()
+}
+
+// ── Discovery ────────────────────────────────────────────────────────
+
+/// Every task the project root's manifests define.
+pub fn discover(root: &Path) -> Vec<Task> {
+    let sources: [fn(&Path) -> Vec<Task>; 7] =
+        [npm, cargo, make, just, pyproject, compose, workspace];
+    sources.iter().flat_map(|source| source(root)).collect()
+}
+
+pub fn find(root: &Path, id: &str) -> Option<Task> {
+    discover(root).into_iter().find(|t| t.id == id)
+}
+
+/// Whether `path` is a manifest tasks are read from, directly in `root`,
+/// or the workspace file.
+pub fn is_task_source(root: &Path, path: &Path) -> bool {
+    if path == config::path(root) {
+        return true;
+    }
+    if path.parent() != Some(root) {
+        return false;
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    ["package.json", "Cargo.toml", "pyproject.toml"].contains(&name.as_ref())
+        || [MAKEFILES, JUSTFILES, COMPOSE_FILES].concat().contains(&name.as_ref())
+}
+
+/// Payload of the `tasks-changed` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TasksChanged {
+    pub root: String,
+    pub tasks: Vec<Task>,
+}
+
+/// Watcher hook: rediscover when one of the root's task manifests changes.
+pub fn on_fs_events(app: &tauri::AppHandle, root: &Path, changes: &[FsChange]) {
+    let touched = changes.iter().flat_map(|c| std::iter::once(&c.path).chain(c.from.as_ref()));
+    if !touched.into_iter().any(|p| is_task_source(root, Path::new(p))) {
+        return;
+    }
+    let payload = TasksChanged { root: root.to_string_lossy().to_string(), tasks: discover(root) };
+    let _ = app.emit("tasks-changed", payload);
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn names(found: &[(String, Option<String>)]) -> Vec<(&str, Option<&str>)> {
+        found.iter().map(|(n, d)| (n.as_str(), d.as_deref())).collect()
+    }
+
+    #[test]
+    fn test_parse_makefile() {
+        let text = "\
+CC := gcc
+LD ::= ld
+VERSION = 1:2
+.PHONY: build test
+
+# Compile everything
+build: deps
+\t$(CC) -o app main.c
+
+test: build ## Run the suite
+\t./app --test
+
+%.o: %.c
+\t$(CC) -c $<
+
+clean install::
+\trm -rf out
+build:
+";
+        assert_eq!(
+            names(&parse_makefile(text)),
+            vec![
+                ("build", Some("Compile everything")),
+                ("test", Some("Run the suite")),
+                ("clean", None),
+                ("install", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_justfile() {
+        let text = "\
+set shell := [\"bash\", \"-c\"]
+alias t := test
+version := \"1.0\"
+
+# Run the dev server
+serve port=\"8080\":
+    cargo run -- --port {{port}}
+
+[doc('Run all tests')]
+test *args: build
+    cargo test {{args}}
+
+[private]
+build:
+    cargo build
+
+_helper:
+    echo hi
+
+@fmt:
+    cargo fmt
+";
+        assert_eq!(
+            names(&parse_justfile(text)),
+            vec![
+                ("serve", Some("Run the dev server")),
+                ("test", Some("Run all tests")),
+                ("fmt", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_compose() {
+        let text = "\
+version: '3.9'
+services:
+  # the database
+  db:
+    image: \"postgres:16\"
+    ports:
+      - \"5432:5432\"
+  api:
+    build: ./api
+    depends_on:
+      - db
+volumes:
+  data:
+";
+        assert_eq!(
+            names(&parse_compose(text)),
+            vec![("db", Some("postgres:16")), ("api", Some("build ./api"))]
+        );
+    }
+
+    #[test]
+    fn test_discover() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        assert!(discover(root).is_empty());
+
+        fs::write(root.join("package.json"), r#"{ "scripts": { "dev": "vite" } }"#).unwrap();
+        fs::write(root.join("pnpm-lock.yaml"), "").unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[[bin]]\nname = \"tool\"\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(
+            root.join("pyproject.toml"),
+            "[project.scripts]\nserve = \"app.cli:main\"\n\n[tool.pdm.scripts]\nlint = { cmd = \"ruff check\", help = \"Lint\" }\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join(".kodiq")).unwrap();
+        fs::write(
+            config::path(root),
+            r#"{ "version": 1, "tasks": [{ "name": "seed", "command": ["npm", "run", "seed"], "cwd": "web" }] }"#,
+        )
+        .unwrap();
+
+        let tasks = discover(root);
+        let ids: Vec<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "npm:dev",
+                "cargo:build",
+                "cargo:check",
+                "cargo:test",
+                "cargo:clippy",
+                "cargo:run app",
+                "cargo:run tool",
+                "pyproject:serve",
+                "pyproject:lint",
+                "workspace:seed",
+            ]
+        );
+        let dev = find(root, "npm:dev").unwrap();
+        assert_eq!(dev.command, vec![shim("pnpm"), "run".into(), "dev".into()]);
+        assert_eq!(dev.description.as_deref(), Some("vite"));
+        assert_eq!(tasks[6].command, ["cargo", "run", "--bin", "tool"]);
+        // pdm scripts make pdm the runner for entry points too
+        assert_eq!(tasks[7].command, ["pdm", "run", "serve"]);
+        assert_eq!(tasks[8].description.as_deref(), Some("Lint"));
+        assert_eq!(tasks[9].cwd, root.join("web").to_string_lossy());
+
+        assert!(is_task_source(root, &root.join("justfile")));
+        assert!(is_task_source(root, &root.join(".kodiq/workspace.json")));
+        assert!(!is_task_source(root, &root.join("web/package.json")));
+        assert!(!is_task_source(root, &root.join("src/main.rs")));
+    }
+}
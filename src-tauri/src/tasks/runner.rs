@@ -0,0 +1,449 @@
+use super::discover::{self, Task};
+use crate::db;
+use crate::db::recent_tasks::RecentTask;
+use crate::diagnostics::store::{self, ProblemStream};
+use crate::error::KodiqError;
+use crate::filesystem::sandbox;
+use crate::state::{AppState, DbState};
+use crate::test_runner::runner::Run;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::Emitter;
+
+/// Lines of output kept per job.
+const OUTPUT_TAIL: usize = 500;
+/// Finished jobs kept per project, for the jobs panel.
+const KEEP_FINISHED: usize = 20;
+const RECENT_LIMIT: u32 = 20;
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskMode {
+    /// In a new terminal, like any other command.
+    #[default]
+    Terminal,
+    /// In the background, with output and exit status captured.
+    Headless,
+}
+
+/// A headless run of a task. Timestamps are Unix seconds.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskJob {
+    pub id: String,
+    pub root: String,
+    pub task: Task,
+    pub started_at: i64,
+    /// `None` while running.
+    pub finished_at: Option<i64>,
+    pub exit_code: Option<i32>,
+    pub cancelled: bool,
+    /// Last lines of stdout and stderr, in the order they arrived.
+    pub output: Vec<String>,
+}
+
+/// What `task_run` started: a terminal or a job.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStarted {
+    pub task: Task,
+    pub terminal_id: Option<String>,
+    pub job: Option<TaskJob>,
+}
+
+// ── Jobs ─────────────────────────────────────────────────────────────
+
+struct Job {
+    run: Run,
+    info: Mutex<TaskJob>,
+}
+
+impl Job {
+    fn push(&self, line: &str) {
+        let Ok(mut info) = self.info.lock() else { return };
+        info.output.push(line.to_string());
+        if info.output.len() > OUTPUT_TAIL {
+            info.output.remove(0);
+        }
+    }
+
+    fn snapshot(&self) -> Result<TaskJob, KodiqError> {
+        Ok(self.info.lock()?.clone())
+    }
+
+    fn is_running(&self) -> bool {
+        self.info.lock().is_ok_and(|info| info.finished_at.is_none())
+    }
+}
+
+/// Headless jobs, running and recently finished.
+#[derive(Default)]
+pub struct TaskJobs {
+    jobs: Mutex<Vec<Arc<Job>>>,
+    next_id: AtomicU32,
+}
+
+pub type TaskJobState = Arc<TaskJobs>;
+
+pub fn new_task_job_state() -> TaskJobState {
+    Arc::new(TaskJobs::default())
+}
+
+impl TaskJobs {
+    fn next_id(&self) -> String {
+        format!("job-{}", self.next_id.fetch_add(1, Ordering::SeqCst))
+    }
+
+    /// Add a job, dropping the project's oldest finished ones past
+    /// `KEEP_FINISHED`.
+    fn insert(&self, job: Arc<Job>) -> Result<(), KodiqError> {
+        let root = job.info.lock()?.root.clone();
+        let mut jobs = self.jobs.lock()?;
+        jobs.push(job);
+        let finished: Vec<usize> = jobs
+            .iter()
+            .enumerate()
+            .filter(|(_, j)| !j.is_running() && j.info.lock().is_ok_and(|i| i.root == root))
+            .map(|(i, _)| i)
+            .collect();
+        let excess = finished.len().saturating_sub(KEEP_FINISHED);
+        for index in finished.into_iter().take(excess).rev() {
+            jobs.remove(index);
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Arc<Job>>, KodiqError> {
+        Ok(self.jobs.lock()?.iter().find(|j| j.info.lock().is_ok_and(|i| i.id == id)).cloned())
+    }
+
+    fn running(&self, root: &str, task_id: &str) -> Result<bool, KodiqError> {
+        Ok(self.jobs.lock()?.iter().any(|j| {
+            j.is_running() && j.info.lock().is_ok_and(|i| i.root == root && i.task.id == task_id)
+        }))
+    }
+
+    fn project(&self, root: &str) -> Result<Vec<TaskJob>, KodiqError> {
+        let jobs = self.jobs.lock()?;
+        let mut list = Vec::new();
+        for job in jobs.iter() {
+            let info = job.snapshot()?;
+            if info.root == root {
+                list.push(info);
+            }
+        }
+        Ok(list)
+    }
+}
+
+fn spawn(task: &Task, env: &HashMap<String, String>) -> Result<Child, KodiqError> {
+    let (program, args) = task
+        .command
+        .split_first()
+        .ok_or_else(|| KodiqError::Other(format!("Task {} has no command", task.id)))?;
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .envs(env)
+        .current_dir(&task.cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+    cmd.spawn().map_err(|e| KodiqError::Other(format!("Failed to start {}: {}", program, e)))
+}
+
+/// Read both streams into the job until the process exits, passing each
+/// line to `on_line`. Returns the finished job.
+fn watch(
+    job: &Arc<Job>,
+    stdout: impl Read + Send + 'static,
+    stderr: impl Read + Send + 'static,
+    on_line: Arc<dyn Fn(&str) + Send + Sync>,
+) -> Result<TaskJob, KodiqError> {
+    let reader = |stream: Box<dyn Read + Send>| {
+        let job = job.clone();
+        let on_line = on_line.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else { break };
+                job.push(&line);
+                on_line(&line);
+            }
+        })
+    };
+    let out = reader(Box::new(stdout));
+    let err = reader(Box::new(stderr));
+
+    let status = job.run.wait();
+    let _ = out.join();
+    let _ = err.join();
+    let mut info = job.info.lock()?;
+    info.finished_at = Some(now());
+    info.exit_code = status.ok().and_then(|s| s.code());
+    info.cancelled = job.run.cancelled();
+    Ok(info.clone())
+}
+
+/// Start `task` as a job. Output streams as `task-output` and through the
+/// problem matchers; the finished job is emitted as `task-finished`.
+fn start_job(
+    app: &tauri::AppHandle,
+    jobs: &TaskJobs,
+    root: &str,
+    task: &Task,
+    env: &HashMap<String, String>,
+) -> Result<TaskJob, KodiqError> {
+    if jobs.running(root, &task.id)? {
+        return Err(KodiqError::Conflict(format!("{} is already running", task.name)));
+    }
+    let mut child = spawn(task, env)?;
+    let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        let _ = child.kill();
+        return Err(KodiqError::Other("Task pipes unavailable".into()));
+    };
+    let info = TaskJob {
+        id: jobs.next_id(),
+        root: root.to_string(),
+        task: task.clone(),
+        started_at: now(),
+        finished_at: None,
+        exit_code: None,
+        cancelled: false,
+        output: Vec::new(),
+    };
+    let job = Arc::new(Job { run: Run::new(child), info: Mutex::new(info.clone()) });
+    jobs.insert(job.clone())?;
+    tracing::info!("Running task {} in {}", task.id, root);
+
+    // Reruns replace the previous run's diagnostics
+    let source = format!("task:{}", task.id);
+    store::reset(app, &source);
+    let problems = Mutex::new(ProblemStream::new(app, &source, Path::new(&task.cwd)));
+    let emitter = app.clone();
+    let job_id = info.id.clone();
+    let on_line: Arc<dyn Fn(&str) + Send + Sync> = Arc::new(move |line| {
+        let _ = emitter.emit("task-output", serde_json::json!({ "jobId": job_id, "line": line }));
+        if let Ok(mut problems) = problems.lock() {
+            problems.push(&format!("{}\n", line));
+        }
+    });
+    let app = app.clone();
+    std::thread::spawn(move || match watch(&job, stdout, stderr, on_line) {
+        Ok(finished) => {
+            let _ = app.emit("task-finished", finished);
+        }
+        Err(e) => tracing::warn!("Task job failed: {}", e),
+    });
+    Ok(info)
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// Tasks from the project's package.json, Cargo.toml, Makefile, justfile,
+/// pyproject.toml and compose file, plus those shared in the workspace file.
+#[tauri::command]
+pub fn task_list(webview: tauri::Webview, root: String) -> Result<Vec<Task>, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    Ok(discover::discover(Path::new(&root)))
+}
+
+/// Start a task in a new terminal or as a headless job, with the workspace
+/// env files applied, and remember it as recently run.
+#[tracing::instrument(skip(app, webview, db, terminals, jobs))]
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub fn task_run(
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+    root: String,
+    task_id: String,
+    mode: Option<TaskMode>,
+    db: tauri::State<'_, DbState>,
+    terminals: tauri::State<'_, AppState>,
+    jobs: tauri::State<'_, TaskJobState>,
+) -> Result<TaskStarted, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    let task = discover::find(Path::new(&root), &task_id)
+        .ok_or_else(|| KodiqError::NotFound(format!("Task {} in {}", task_id, root)))?;
+    let env = workspace::config::env(Path::new(&root));
+
+    let started = match mode.unwrap_or_default() {
+        TaskMode::Terminal => {
+            let (program, args) = task
+                .command
+                .split_first()
+                .ok_or_else(|| KodiqError::Other(format!("Task {} has no command", task.id)))?;
+            let terminal_id = crate::terminal::manager::spawn_argv(
+                &app,
+                &terminals,
+                program,
+                args,
+                task.name.clone(),
+                Some(task.cwd.clone()),
+                Some(env),
+            )
+            .map_err(KodiqError::Other)?;
+            TaskStarted { task, terminal_id: Some(terminal_id), job: None }
+        }
+        TaskMode::Headless => {
+            let job = start_job(&app, &jobs, &root, &task, &env)?;
+            TaskStarted { task, terminal_id: None, job: Some(job) }
+        }
+    };
+
+    let task = &started.task;
+    let conn = db.connection.lock()?;
+    db::recent_tasks::record(&conn, &root, &task.id, &task.name, task.source.as_str(), now())?;
+    Ok(started)
+}
+
+/// Stop a running job. Returns false if it had already finished.
+#[tauri::command]
+pub fn task_stop(
+    webview: tauri::Webview,
+    job_id: String,
+    jobs: tauri::State<'_, TaskJobState>,
+) -> Result<bool, KodiqError> {
+    sandbox::check_webview(&webview)?;
+    let job = jobs.get(&job_id)?.ok_or_else(|| KodiqError::NotFound(job_id.clone()))?;
+    if !job.is_running() {
+        return Ok(false);
+    }
+    job.run.cancel()?;
+    Ok(true)
+}
+
+/// The project's running and recently finished jobs, oldest first.
+#[tauri::command]
+pub fn task_jobs(
+    webview: tauri::Webview,
+    root: String,
+    jobs: tauri::State<'_, TaskJobState>,
+) -> Result<Vec<TaskJob>, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    jobs.project(&root)
+}
+
+/// Recently run tasks, most recent first.
+#[tauri::command]
+pub fn task_recent(
+    webview: tauri::Webview,
+    root: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<Vec<RecentTask>, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    Ok(db::recent_tasks::list(&*db.connection.lock()?, &root, RECENT_LIMIT)?)
+}
+
+#[tauri::command]
+pub fn task_clear_recent(
+    webview: tauri::Webview,
+    root: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<u64, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    Ok(db::recent_tasks::clear(&*db.connection.lock()?, &root)?)
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tasks::discover::TaskSource;
+    use std::time::{Duration, Instant};
+
+    fn sh(script: &str) -> Task {
+        Task {
+            id: "make:test".into(),
+            name: "test".into(),
+            source: TaskSource::Make,
+            command: vec!["sh".into(), "-c".into(), script.into()],
+            cwd: "/".into(),
+            file: "/Makefile".into(),
+            description: None,
+        }
+    }
+
+    fn start(
+        jobs: &TaskJobs,
+        root: &str,
+        task: &Task,
+    ) -> (Arc<Job>, impl Read + Send, impl Read + Send) {
+        let mut child = spawn(task, &HashMap::new()).unwrap();
+        let (stdout, stderr) = (child.stdout.take().unwrap(), child.stderr.take().unwrap());
+        let info = TaskJob {
+            id: jobs.next_id(),
+            root: root.into(),
+            task: task.clone(),
+            started_at: now(),
+            finished_at: None,
+            exit_code: None,
+            cancelled: false,
+            output: Vec::new(),
+        };
+        let job = Arc::new(Job { run: Run::new(child), info: Mutex::new(info) });
+        jobs.insert(job.clone()).unwrap();
+        (job, stdout, stderr)
+    }
+
+    #[test]
+    fn test_watch_captures_output_and_exit() {
+        let jobs = TaskJobs::default();
+        let task = sh("echo one; echo two >&2; exit 3");
+        let (job, stdout, stderr) = start(&jobs, "/p", &task);
+        assert!(jobs.running("/p", "make:test").unwrap());
+
+        let seen = Arc::new(Mutex::new(0));
+        let counter = seen.clone();
+        let finished =
+            watch(&job, stdout, stderr, Arc::new(move |_| *counter.lock().unwrap() += 1)).unwrap();
+        assert_eq!((finished.exit_code, finished.cancelled), (Some(3), false));
+        assert!(finished.finished_at.is_some());
+        let mut output = finished.output.clone();
+        output.sort();
+        assert_eq!(output, vec!["one", "two"]);
+        assert_eq!(*seen.lock().unwrap(), 2);
+
+        assert!(!jobs.running("/p", "make:test").unwrap());
+        assert_eq!(jobs.project("/p").unwrap(), vec![finished]);
+        assert!(jobs.project("/q").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stop_and_prune() {
+        let jobs = TaskJobs::default();
+        let (job, stdout, stderr) = start(&jobs, "/p", &sh("sleep 30 & wait"));
+        let stopper = job.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            stopper.run.cancel().unwrap();
+        });
+        let started = Instant::now();
+        let finished = watch(&job, stdout, stderr, Arc::new(|_| {})).unwrap();
+        assert!(finished.cancelled);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        for _ in 0..KEEP_FINISHED + 2 {
+            let (job, stdout, stderr) = start(&jobs, "/p", &sh("true"));
+            watch(&job, stdout, stderr, Arc::new(|_| {})).unwrap();
+        }
+        let kept = jobs.project("/p").unwrap();
+        assert_eq!(kept.len(), KEEP_FINISHED + 1);
+        // The cancelled job was the oldest and went first
+        assert!(jobs.get(&finished.id).unwrap().is_none());
+    }
+}
@@ -1,8 +1,11 @@
+use crate::diagnostics::{matcher, store};
+use crate::filesystem::sandbox;
 use crate::state::{AppState, PtyInstance};
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use regex::Regex;
 use std::collections::HashSet;
 use std::io::{Read, Write};
+use std::path::PathBuf;
 use std::sync::OnceLock;
 use tauri::Emitter;
 
@@ -26,12 +29,42 @@ use super::parser::resolve_command;
 #[tracing::instrument(skip(app, state))]
 #[tauri::command]
 pub fn spawn_terminal(
+    webview: tauri::Webview,
     app: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
     command: Option<String>,
     cwd: Option<String>,
     shell: Option<String>,
     env: Option<std::collections::HashMap<String, String>>,
+) -> Result<String, String> {
+    sandbox::check_webview(&webview).map_err(|e| e.to_string())?;
+    spawn(&app, &state, command, cwd, shell, env)
+}
+
+/// `spawn_terminal` for backend callers (tasks, compound launches).
+pub(crate) fn spawn(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    command: Option<String>,
+    cwd: Option<String>,
+    shell: Option<String>,
+    env: Option<std::collections::HashMap<String, String>>,
+) -> Result<String, String> {
+    let cmd_str = command.unwrap_or_default();
+    let (program, args, label) = resolve_command(&cmd_str, shell.as_deref());
+    spawn_argv(app, state, &program, &args, label, cwd, env)
+}
+
+/// Spawn a terminal running `program` with `args` as given — no splitting
+/// or shell quoting involved.
+pub(crate) fn spawn_argv(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    program: &str,
+    args: &[String],
+    label: String,
+    cwd: Option<String>,
+    env: Option<std::collections::HashMap<String, String>>,
 ) -> Result<String, String> {
     let pty_system = native_pty_system();
 
@@ -39,11 +72,8 @@ pub fn spawn_terminal(
         .openpty(PtySize { rows: 24, cols: 80, pixel_width: 0, pixel_height: 0 })
         .map_err(|e| format!("Failed to open PTY: {}", e))?;
 
-    let cmd_str = command.clone().unwrap_or_default();
-    let (program, args, label) = resolve_command(&cmd_str, shell.as_deref());
-
-    let mut cmd = CommandBuilder::new(&program);
-    for arg in &args {
+    let mut cmd = CommandBuilder::new(program);
+    for arg in args {
         cmd.arg(arg);
     }
 
@@ -88,6 +118,7 @@ pub fn spawn_terminal(
     }
 
     // Set working directory
+    let start_dir = cwd.clone().map(PathBuf::from).or_else(dirs::home_dir).unwrap_or_default();
     if let Some(ref dir) = cwd {
         cmd.cwd(dir);
     } else {
@@ -118,12 +149,13 @@ pub fn spawn_terminal(
         id
     };
 
-    // Read PTY output in background thread + detect localhost URLs
+    // Read PTY output in background thread + detect localhost URLs and problems
     let app_handle = app.clone();
     let tid = terminal_id.clone();
     std::thread::spawn(move || {
         let mut buf = [0u8; 4096];
         let mut emitted_ports: HashSet<u16> = HashSet::new();
+        let mut problems = store::ProblemStream::new(&app_handle, &tid, &start_dir);
 
         let url_re = url_regex();
         let ansi_re = ansi_regex();
@@ -142,8 +174,14 @@ pub fn spawn_terminal(
                         }),
                     );
 
+                    // Shells report `cd` with OSC 7, so relative paths stay resolvable
+                    if let Some(dir) = matcher::osc7_cwd(&text) {
+                        problems.set_cwd(dir);
+                    }
+
                     // Strip ANSI escape codes for cleaner matching
                     let clean = ansi_re.replace_all(&text, "").to_string();
+                    problems.push(&clean);
 
                     // Scan for localhost URLs
                     for cap in url_re.captures_iter(&clean) {
@@ -178,9 +216,22 @@ pub fn spawn_terminal(
     Ok(terminal_id)
 }
 
-/// Write data to a specific terminal
+/// Write data to a specific terminal.
+/// Enter starts a new command, so the terminal's old problems are dropped.
 #[tauri::command]
-pub fn write_to_pty(id: String, data: String, state: tauri::State<'_, AppState>) {
+pub fn write_to_pty(
+    webview: tauri::Webview,
+    app: tauri::AppHandle,
+    id: String,
+    data: String,
+    state: tauri::State<'_, AppState>,
+) {
+    if sandbox::check_webview(&webview).is_err() {
+        return;
+    }
+    if data.contains('\r') {
+        store::reset(&app, &id);
+    }
     let Ok(mut guard) = state.lock() else { return };
     if let Some(ref mut pty) = guard.terminals.get_mut(&id) {
         let _ = pty.writer.write_all(data.as_bytes());
@@ -198,11 +249,12 @@ pub fn resize_pty(id: String, cols: u16, rows: u16, state: tauri::State<'_, AppS
 }
 
 /// Close a terminal
-#[tracing::instrument(skip(state))]
+#[tracing::instrument(skip(app, state))]
 #[tauri::command]
-pub fn close_terminal(id: String, state: tauri::State<'_, AppState>) {
+pub fn close_terminal(app: tauri::AppHandle, id: String, state: tauri::State<'_, AppState>) {
     if let Ok(mut guard) = state.lock() {
         guard.terminals.remove(&id);
     }
+    store::forget(&app, &id);
     tracing::info!("Terminal closed: {}", id);
 }
@@ -0,0 +1,709 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TestStatus {
+    Skipped,
+    Passed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NodeKind {
+    /// A test binary (cargo) or package (go).
+    Suite,
+    File,
+    /// A module, `describe` block or test class.
+    Group,
+    Test,
+}
+
+/// One node of the results tree. Groups roll up their children's status
+/// and duration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestNode {
+    /// For tests, what the framework accepts to run just this one.
+    pub id: String,
+    pub name: String,
+    pub kind: NodeKind,
+    pub status: TestStatus,
+    pub duration_ms: Option<u64>,
+    /// Absolute path of the file to open for this node.
+    pub file: Option<String>,
+    /// 1-based; for failures, where the failure was raised if known.
+    pub line: Option<u32>,
+    pub message: Option<String>,
+    pub children: Vec<TestNode>,
+}
+
+impl TestNode {
+    fn new(id: &str, name: &str, kind: NodeKind) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            kind,
+            status: TestStatus::Skipped,
+            duration_ms: None,
+            file: None,
+            line: None,
+            message: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// `(passed, failed, skipped)` leaf tests.
+pub fn counts(nodes: &[TestNode]) -> (u32, u32, u32) {
+    nodes.iter().fold((0, 0, 0), |(p, f, s), node| {
+        let (cp, cf, cs) = if node.children.is_empty() {
+            match node.status {
+                TestStatus::Passed => (1, 0, 0),
+                TestStatus::Failed => (0, 1, 0),
+                TestStatus::Skipped => (0, 0, 1),
+            }
+        } else {
+            counts(&node.children)
+        };
+        (p + cp, f + cf, s + cs)
+    })
+}
+
+// ── Tree building ────────────────────────────────────────────────────
+
+/// The node at `path` (`(id, name, kind)` per level), created as needed.
+fn upsert<'a>(
+    nodes: &'a mut Vec<TestNode>,
+    path: &[(String, String, NodeKind)],
+) -> &'a mut TestNode {
+    let ((id, name, kind), rest) = path.split_first().expect("non-empty test path");
+    let index = match nodes.iter().position(|n| n.name == *name && n.kind == *kind) {
+        Some(i) => i,
+        None => {
+            nodes.push(TestNode::new(id, name, *kind));
+            nodes.len() - 1
+        }
+    };
+    if rest.is_empty() {
+        &mut nodes[index]
+    } else {
+        upsert(&mut nodes[index].children, rest)
+    }
+}
+
+/// Parents take the worst status among themselves and their children and,
+/// without a duration of their own, the sum of theirs.
+fn roll_up(nodes: &mut [TestNode]) {
+    for node in nodes {
+        if node.children.is_empty() {
+            continue;
+        }
+        roll_up(&mut node.children);
+        let worst = node.children.iter().map(|c| c.status).max().unwrap_or(TestStatus::Skipped);
+        node.status = node.status.max(worst);
+        if node.duration_ms.is_none() {
+            let durations: Vec<u64> = node.children.iter().filter_map(|c| c.duration_ms).collect();
+            if !durations.is_empty() {
+                node.duration_ms = Some(durations.iter().sum());
+            }
+        }
+    }
+}
+
+fn seconds_to_ms(secs: f64) -> u64 {
+    (secs * 1000.0).round() as u64
+}
+
+fn relative(cwd: &Path, path: &Path) -> String {
+    path.strip_prefix(cwd).unwrap_or(path).to_string_lossy().to_string()
+}
+
+fn absolute(cwd: &Path, path: &str) -> PathBuf {
+    crate::filesystem::ops::normalize(&cwd.join(path))
+}
+
+fn ansi_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\x1b\[[0-9;]*[a-zA-Z]").unwrap())
+}
+
+fn strip_ansi(text: &str) -> String {
+    ansi_regex().replace_all(text, "").trim_end().to_string()
+}
+
+// ── libtest JSON (cargo test) ────────────────────────────────────────
+
+fn panic_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"panicked at (\S+?):(\d+):\d+").unwrap())
+}
+
+/// Test binary names from cargo's stderr (`Running unittests src/lib.rs
+/// (…)`, `Doc-tests app`), in the order their suites report.
+pub fn cargo_suites(stderr: &str) -> Vec<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(r"^\s*(?:Running (?:unittests )?(\S+)|Doc-tests (\S+))").unwrap()
+    });
+    stderr
+        .lines()
+        .filter_map(|line| re.captures(line))
+        .filter_map(|c| match (c.get(1), c.get(2)) {
+            (Some(file), _) => Some(file.as_str().to_string()),
+            (_, Some(name)) => Some(format!("doc-tests {}", name.as_str())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The node of libtest test `name` in `suite`, created as needed.
+fn libtest_case<'a>(tree: &'a mut Vec<TestNode>, suite: &str, name: &str) -> &'a mut TestNode {
+    // Doc-test names carry the file: "src/lib.rs - add (line 3)"
+    let mut path = vec![(suite.to_string(), suite.to_string(), NodeKind::Suite)];
+    let segments: Vec<&str> =
+        if name.contains(" - ") { vec![name] } else { name.split("::").collect() };
+    let mut prefix = String::new();
+    for segment in &segments[..segments.len() - 1] {
+        prefix = if prefix.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{}::{}", prefix, segment)
+        };
+        path.push((prefix.clone(), segment.to_string(), NodeKind::Group));
+    }
+    path.push((name.to_string(), segments[segments.len() - 1].to_string(), NodeKind::Test));
+    upsert(tree, &path)
+}
+
+/// Attach a test's captured output, locating the panic if it names one.
+fn libtest_output(node: &mut TestNode, output: &str, cwd: &Path) {
+    node.message = Some(strip_ansi(output));
+    if let Some(caps) = panic_regex().captures(output) {
+        node.file = Some(absolute(cwd, &caps[1]).to_string_lossy().to_string());
+        node.line = caps[2].parse().ok();
+    }
+}
+
+fn suite_name(suites: &[String], index: usize) -> String {
+    suites.get(index).cloned().unwrap_or_else(|| format!("suite {}", index + 1))
+}
+
+/// Events from `cargo test -- -Z unstable-options --format json` (nightly):
+/// one suite per test binary, named from `suites`.
+pub fn parse_libtest(stdout: &str, suites: &[String], cwd: &Path) -> Vec<TestNode> {
+    let mut tree = Vec::new();
+    let mut suite: Option<String> = None;
+    let mut started = 0;
+    for line in stdout.lines().filter(|l| l.starts_with('{')) {
+        let Ok(event) = serde_json::from_str::<Value>(line) else { continue };
+        let kind = event["type"].as_str().unwrap_or_default();
+        let what = event["event"].as_str().unwrap_or_default();
+        if kind == "suite" && what == "started" {
+            suite = Some(suite_name(suites, started));
+            started += 1;
+            continue;
+        }
+        let status = match (kind, what) {
+            ("test", "ok") => TestStatus::Passed,
+            ("test", "failed") => TestStatus::Failed,
+            ("test", "ignored") => TestStatus::Skipped,
+            _ => continue,
+        };
+        let Some(name) = event["name"].as_str() else { continue };
+        let suite_name = suite.as_deref().unwrap_or("tests");
+
+        let node = libtest_case(&mut tree, suite_name, name);
+        node.status = status;
+        node.duration_ms = event["exec_time"].as_f64().map(seconds_to_ms);
+        if let Some(output) = event["stdout"].as_str().filter(|s| !s.trim().is_empty()) {
+            libtest_output(node, output, cwd);
+        }
+    }
+    roll_up(&mut tree);
+    tree
+}
+
+/// libtest's default human output (stable toolchains): `test <name> ... ok`
+/// lines, then a `---- <name> stdout ----` section per failure. No durations.
+pub fn parse_libtest_pretty(stdout: &str, suites: &[String], cwd: &Path) -> Vec<TestNode> {
+    static SUITE: OnceLock<Regex> = OnceLock::new();
+    static RESULT: OnceLock<Regex> = OnceLock::new();
+    static OUTPUT: OnceLock<Regex> = OnceLock::new();
+    let suite_start = SUITE.get_or_init(|| Regex::new(r"^running \d+ tests?$").unwrap());
+    let result =
+        RESULT.get_or_init(|| Regex::new(r"^test (.+) \.\.\. (ok|FAILED|ignored)").unwrap());
+    let output = OUTPUT.get_or_init(|| Regex::new(r"^---- (.+) stdout ----$").unwrap());
+
+    let mut tree = Vec::new();
+    let mut suite = "tests".to_string();
+    let mut started = 0;
+    // The failure whose output is being read, and its lines so far
+    let mut capture: Option<(String, Vec<&str>)> = None;
+
+    for line in stdout.lines() {
+        let is_suite = suite_start.is_match(line);
+        let ends_capture = is_suite
+            || line.starts_with("---- ")
+            || line.starts_with("test result:")
+            || line == "failures:"
+            || line == "successes:";
+        if ends_capture {
+            if let Some((name, lines)) = capture.take() {
+                let node = libtest_case(&mut tree, &suite, &name);
+                libtest_output(node, lines.join("\n").trim(), cwd);
+            }
+        }
+
+        if is_suite {
+            suite = suite_name(suites, started);
+            started += 1;
+        } else if let Some(caps) = output.captures(line) {
+            capture = Some((caps[1].to_string(), Vec::new()));
+        } else if let Some((_, lines)) = capture.as_mut() {
+            lines.push(line);
+        } else if let Some(caps) = result.captures(line) {
+            libtest_case(&mut tree, &suite, &caps[1]).status = match &caps[2] {
+                "ok" => TestStatus::Passed,
+                "FAILED" => TestStatus::Failed,
+                _ => TestStatus::Skipped,
+            };
+        }
+    }
+    if let Some((name, lines)) = capture {
+        let node = libtest_case(&mut tree, &suite, &name);
+        libtest_output(node, lines.join("\n").trim(), cwd);
+    }
+    roll_up(&mut tree);
+    tree
+}
+
+// ── Jest JSON (jest --json, vitest --reporter=json) ─────────────────
+
+/// The first `file:line` mention of `file` in a failure message.
+fn stack_location(message: &str, file: &str) -> Option<u32> {
+    let re = Regex::new(&format!(r"{}:(\d+)", regex::escape(file))).ok()?;
+    re.captures(message).and_then(|c| c[1].parse().ok())
+}
+
+pub fn parse_jest(json: &str, cwd: &Path) -> Result<Vec<TestNode>, String> {
+    let report: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let mut tree = Vec::new();
+    for result in report["testResults"].as_array().into_iter().flatten() {
+        let Some(file) = result["name"].as_str() else { continue };
+        let rel = relative(cwd, Path::new(file));
+        let file_path = vec![(rel.clone(), rel.clone(), NodeKind::File)];
+        let file_node = upsert(&mut tree, &file_path);
+        file_node.file = Some(file.to_string());
+
+        let assertions = result["assertionResults"].as_array().cloned().unwrap_or_default();
+        if assertions.is_empty() && result["status"] == "failed" {
+            // The file itself failed (syntax error, import failure)
+            file_node.status = TestStatus::Failed;
+            file_node.message = result["message"].as_str().map(strip_ansi);
+            continue;
+        }
+        for assertion in assertions {
+            let title = assertion["title"].as_str().unwrap_or_default();
+            let full_name = assertion["fullName"].as_str().unwrap_or(title);
+            let mut path = file_path.clone();
+            let mut prefix = rel.clone();
+            for ancestor in assertion["ancestorTitles"].as_array().into_iter().flatten() {
+                let ancestor = ancestor.as_str().unwrap_or_default();
+                prefix = format!("{} > {}", prefix, ancestor);
+                path.push((prefix.clone(), ancestor.to_string(), NodeKind::Group));
+            }
+            path.push((full_name.to_string(), title.to_string(), NodeKind::Test));
+
+            let node = upsert(&mut tree, &path);
+            node.status = match assertion["status"].as_str() {
+                Some("passed") => TestStatus::Passed,
+                Some("failed") => TestStatus::Failed,
+                _ => TestStatus::Skipped,
+            };
+            node.duration_ms = assertion["duration"].as_f64().map(|ms| ms.round() as u64);
+            node.file = Some(file.to_string());
+            node.line = assertion["location"]["line"].as_u64().map(|l| l as u32);
+            let failures: Vec<&str> = assertion["failureMessages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            if !failures.is_empty() {
+                let message = strip_ansi(&failures.join("\n\n"));
+                node.line = stack_location(&message, file).or(node.line);
+                node.message = Some(message);
+            }
+        }
+    }
+    roll_up(&mut tree);
+    Ok(tree)
+}
+
+// ── JUnit XML (pytest --junitxml) ────────────────────────────────────
+
+/// `zero_based` for producers that count lines from 0 (pytest). A
+/// `file:line:` in the failure text wins over the test's own line.
+pub fn parse_junit(xml: &str, cwd: &Path, zero_based: bool) -> Result<Vec<TestNode>, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    let mut tree = Vec::new();
+    for case in doc.descendants().filter(|n| n.has_tag_name("testcase")) {
+        let name = case.attribute("name").unwrap_or_default();
+        let classname = case.attribute("classname").unwrap_or_default();
+        let file = case.attribute("file");
+
+        // pytest: classname "tests.test_math.TestCalc" for tests/test_math.py
+        let module = file.map(|f| f.trim_end_matches(".py").replace(['/', '\\'], "."));
+        let classes: Vec<&str> = match &module {
+            Some(module) => classname.strip_prefix(module.as_str()).unwrap_or(classname),
+            None => classname,
+        }
+        .split('.')
+        .filter(|s| !s.is_empty())
+        .collect();
+
+        let mut path = Vec::new();
+        let mut id = String::new();
+        match file {
+            Some(file) => {
+                path.push((file.to_string(), file.to_string(), NodeKind::File));
+                id = file.to_string();
+                for class in &classes {
+                    id = format!("{}::{}", id, class);
+                    path.push((id.clone(), class.to_string(), NodeKind::Group));
+                }
+            }
+            None => {
+                let suite = case.parent().and_then(|p| p.attribute("name")).unwrap_or("tests");
+                path.push((suite.to_string(), suite.to_string(), NodeKind::Suite));
+                if !classname.is_empty() {
+                    id = classname.to_string();
+                    path.push((id.clone(), classname.to_string(), NodeKind::Group));
+                }
+            }
+        }
+        let id = if id.is_empty() { name.to_string() } else { format!("{}::{}", id, name) };
+        path.push((id, name.to_string(), NodeKind::Test));
+
+        let failure =
+            case.children().find(|c| c.has_tag_name("failure") || c.has_tag_name("error"));
+        let skipped = case.children().find(|c| c.has_tag_name("skipped"));
+        let node = upsert(&mut tree, &path);
+        node.duration_ms = case.attribute("time").and_then(|t| t.parse().ok()).map(seconds_to_ms);
+        node.file = file.map(|f| absolute(cwd, f).to_string_lossy().to_string());
+        node.line = case.attribute("line").and_then(|l| l.parse::<u32>().ok()).map(|l| {
+            if zero_based {
+                l + 1
+            } else {
+                l
+            }
+        });
+        node.status = match (failure, skipped) {
+            (Some(_), _) => TestStatus::Failed,
+            (None, Some(_)) => TestStatus::Skipped,
+            (None, None) => TestStatus::Passed,
+        };
+        if let Some(detail) = failure.or(skipped) {
+            let text = detail.text().unwrap_or_default().trim();
+            let message = detail.attribute("message").unwrap_or_default();
+            let message = match (message.is_empty(), text.is_empty()) {
+                (false, false) => format!("{}\n\n{}", message, text),
+                (false, true) => message.to_string(),
+                _ => text.to_string(),
+            };
+            if let Some(file) = file {
+                node.line = stack_location(&message, file).or(node.line);
+            }
+            node.message = Some(message).filter(|m| !m.is_empty());
+        }
+    }
+    roll_up(&mut tree);
+    Ok(tree)
+}
+
+// ── go test -json ────────────────────────────────────────────────────
+
+/// Directory of a package, from the module path in `go.mod`.
+fn package_dir(cwd: &Path, module: Option<&str>, package: &str) -> PathBuf {
+    match module.and_then(|m| package.strip_prefix(m)) {
+        Some(rest) => cwd.join(rest.trim_start_matches('/')),
+        None => cwd.to_path_buf(),
+    }
+}
+
+pub fn go_module(cwd: &Path) -> Option<String> {
+    let go_mod = std::fs::read_to_string(cwd.join("go.mod")).ok()?;
+    go_mod.lines().find_map(|l| l.strip_prefix("module ")).map(|m| m.trim().to_string())
+}
+
+/// Events from `go test -json`: one suite per package, subtests nested.
+pub fn parse_go(stdout: &str, cwd: &Path, module: Option<&str>) -> Vec<TestNode> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let location = RE.get_or_init(|| Regex::new(r"^\s+(\w+_test\.go):(\d+): ").unwrap());
+    let mut tree = Vec::new();
+    let mut output: HashMap<(String, String), String> = HashMap::new();
+    for line in stdout.lines().filter(|l| l.starts_with('{')) {
+        let Ok(event) = serde_json::from_str::<Value>(line) else { continue };
+        let (Some(package), Some(test)) = (event["Package"].as_str(), event["Test"].as_str())
+        else {
+            continue;
+        };
+        let key = (package.to_string(), test.to_string());
+        let status = match event["Action"].as_str() {
+            Some("output") => {
+                let text = event["Output"].as_str().unwrap_or_default();
+                output.entry(key).or_default().push_str(text);
+                continue;
+            }
+            Some("pass") => TestStatus::Passed,
+            Some("fail") => TestStatus::Failed,
+            Some("skip") => TestStatus::Skipped,
+            _ => continue,
+        };
+
+        let mut path = vec![(package.to_string(), package.to_string(), NodeKind::Suite)];
+        let mut prefix = String::new();
+        for part in test.split('/') {
+            prefix =
+                if prefix.is_empty() { part.to_string() } else { format!("{}/{}", prefix, part) };
+            path.push((prefix.clone(), part.to_string(), NodeKind::Test));
+        }
+        let node = upsert(&mut tree, &path);
+        node.status = status;
+        node.duration_ms = event["Elapsed"].as_f64().map(seconds_to_ms);
+        let text = output.remove(&key).unwrap_or_default();
+        if status == TestStatus::Failed {
+            let dir = package_dir(cwd, module, package);
+            if let Some(caps) = text.lines().find_map(|l| location.captures(l)) {
+                node.file = Some(dir.join(&caps[1]).to_string_lossy().to_string());
+                node.line = caps[2].parse().ok();
+            }
+            let lines: Vec<&str> = text
+                .lines()
+                .filter(|l| !l.starts_with("=== ") && !l.trim_start().starts_with("--- "))
+                .collect();
+            node.message = Some(lines.join("\n").trim().to_string()).filter(|m| !m.is_empty());
+        }
+    }
+    roll_up(&mut tree);
+    tree
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(nodes: &'a [TestNode], name: &str) -> &'a TestNode {
+        nodes.iter().find(|n| n.name == name).unwrap_or_else(|| panic!("no node {}", name))
+    }
+
+    #[test]
+    fn test_libtest() {
+        let stderr = "   Compiling app v0.1.0\n     Running unittests src/lib.rs (target/debug/deps/app-1)\n   Doc-tests app\n";
+        let stdout = r#"{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "math::tests::adds" }
+{ "type": "test", "name": "math::tests::adds", "event": "ok", "exec_time": 0.0012 }
+{ "type": "test", "name": "math::tests::divides", "event": "failed", "exec_time": 0.002, "stdout": "\nthread 'math::tests::divides' panicked at src/math.rs:20:9:\nattempt to divide by zero\n" }
+{ "type": "test", "name": "slow", "event": "ignored" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1 }
+{ "type": "suite", "event": "started", "test_count": 1 }
+{ "type": "test", "name": "src/lib.rs - add (line 3)", "event": "ok", "exec_time": 0.3 }
+"#;
+        let suites = cargo_suites(stderr);
+        assert_eq!(suites, vec!["src/lib.rs".to_string(), "doc-tests app".to_string()]);
+        let tree = parse_libtest(stdout, &suites, Path::new("/work/app"));
+        assert_eq!(counts(&tree), (2, 1, 1));
+
+        let unit = find(&tree, "src/lib.rs");
+        assert_eq!(
+            (unit.kind, unit.status, unit.duration_ms),
+            (NodeKind::Suite, TestStatus::Failed, Some(3))
+        );
+        let tests = find(&find(&unit.children, "math").children, "tests");
+        let divides = find(&tests.children, "divides");
+        assert_eq!(divides.id, "math::tests::divides");
+        assert_eq!(
+            (divides.file.as_deref(), divides.line),
+            (Some("/work/app/src/math.rs"), Some(20))
+        );
+        assert!(divides.message.as_deref().unwrap().contains("attempt to divide by zero"));
+        assert_eq!(find(&unit.children, "slow").status, TestStatus::Skipped);
+
+        let doc = find(&tree, "doc-tests app");
+        assert_eq!(doc.children[0].id, "src/lib.rs - add (line 3)");
+        assert_eq!(doc.status, TestStatus::Passed);
+    }
+
+    #[test]
+    fn test_libtest_pretty() {
+        let stderr =
+            "     Running unittests src/lib.rs (target/debug/deps/app-1)\n   Doc-tests app\n";
+        let stdout = "
+running 3 tests
+test math::tests::adds ... ok
+test math::tests::divides ... FAILED
+test slow ... ignored, needs a database
+
+failures:
+
+---- math::tests::divides stdout ----
+
+thread 'math::tests::divides' panicked at src/math.rs:20:9:
+attempt to divide by zero
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    math::tests::divides
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+
+running 1 test
+test src/lib.rs - add (line 3) ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.30s
+";
+        let suites = cargo_suites(stderr);
+        let tree = parse_libtest_pretty(stdout, &suites, Path::new("/work/app"));
+        assert_eq!(counts(&tree), (2, 1, 1));
+
+        let unit = find(&tree, "src/lib.rs");
+        assert_eq!(unit.status, TestStatus::Failed);
+        let tests = find(&find(&unit.children, "math").children, "tests");
+        let divides = find(&tests.children, "divides");
+        assert_eq!(
+            (divides.file.as_deref(), divides.line),
+            (Some("/work/app/src/math.rs"), Some(20))
+        );
+        let message = divides.message.as_deref().unwrap();
+        assert!(message.starts_with("thread 'math::tests::divides' panicked"));
+        assert!(message.contains("attempt to divide by zero"));
+        assert_eq!(find(&unit.children, "slow").status, TestStatus::Skipped);
+
+        let doc = find(&tree, "doc-tests app");
+        assert_eq!(doc.children[0].id, "src/lib.rs - add (line 3)");
+        assert_eq!(doc.status, TestStatus::Passed);
+    }
+
+    #[test]
+    fn test_jest() {
+        let json = serde_json::json!({
+            "numFailedTests": 1,
+            "testResults": [
+                {
+                    "name": "/web/src/math.test.ts",
+                    "status": "failed",
+                    "assertionResults": [
+                        { "ancestorTitles": ["math", "add"], "title": "adds", "fullName": "math add adds",
+                          "status": "passed", "duration": 3, "failureMessages": [], "location": { "line": 4, "column": 5 } },
+                        { "ancestorTitles": ["math"], "title": "divides", "fullName": "math divides",
+                          "status": "failed", "duration": 7.4,
+                          "failureMessages": ["Error: \u{1b}[31mexpected 1 to be 2\u{1b}[39m\n    at /web/src/math.test.ts:12:17"] },
+                        { "ancestorTitles": [], "title": "later", "fullName": "later", "status": "todo", "failureMessages": [] }
+                    ]
+                },
+                { "name": "/web/src/broken.test.ts", "status": "failed", "message": "SyntaxError: Unexpected token", "assertionResults": [] }
+            ]
+        })
+        .to_string();
+        let tree = parse_jest(&json, Path::new("/web")).unwrap();
+        assert_eq!(counts(&tree), (1, 2, 1));
+
+        let file = find(&tree, "src/math.test.ts");
+        assert_eq!(
+            (file.kind, file.status, file.duration_ms),
+            (NodeKind::File, TestStatus::Failed, Some(10))
+        );
+        let math = find(&file.children, "math");
+        let adds = find(&find(&math.children, "add").children, "adds");
+        assert_eq!((adds.id.as_str(), adds.line), ("math add adds", Some(4)));
+        let divides = find(&math.children, "divides");
+        assert_eq!(divides.line, Some(12));
+        assert!(divides.message.as_deref().unwrap().starts_with("Error: expected 1 to be 2"));
+
+        let broken = find(&tree, "src/broken.test.ts");
+        assert_eq!(broken.message.as_deref(), Some("SyntaxError: Unexpected token"));
+        assert!(parse_jest("not json", Path::new("/web")).is_err());
+    }
+
+    #[test]
+    fn test_junit_pytest() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<testsuites><testsuite name="pytest" errors="0" failures="1" skipped="1" tests="3" time="0.05">
+  <testcase classname="tests.test_math" file="tests/test_math.py" line="3" name="test_add" time="0.001" />
+  <testcase classname="tests.test_math.TestDiv" file="tests/test_math.py" line="9" name="test_zero" time="0.002">
+    <failure message="ZeroDivisionError: division by zero">def test_zero(self):
+&gt;       1 / 0
+E       ZeroDivisionError: division by zero
+
+tests/test_math.py:11: ZeroDivisionError</failure>
+  </testcase>
+  <testcase classname="tests.test_math" file="tests/test_math.py" line="14" name="test_later" time="0">
+    <skipped type="pytest.skip" message="not yet">tests/test_math.py:15: not yet</skipped>
+  </testcase>
+</testsuite></testsuites>"#;
+        let tree = parse_junit(xml, Path::new("/py"), true).unwrap();
+        assert_eq!(counts(&tree), (1, 1, 1));
+        let file = find(&tree, "tests/test_math.py");
+        assert_eq!(file.status, TestStatus::Failed);
+
+        let add = find(&file.children, "test_add");
+        assert_eq!(add.id, "tests/test_math.py::test_add");
+        assert_eq!((add.file.as_deref(), add.line), (Some("/py/tests/test_math.py"), Some(4)));
+        let zero = find(&find(&file.children, "TestDiv").children, "test_zero");
+        assert_eq!(zero.id, "tests/test_math.py::TestDiv::test_zero");
+        assert_eq!(zero.line, Some(11));
+        assert!(zero
+            .message
+            .as_deref()
+            .unwrap()
+            .starts_with("ZeroDivisionError: division by zero\n\n"));
+        assert_eq!(
+            find(&file.children, "test_later").message.as_deref(),
+            Some("not yet\n\ntests/test_math.py:15: not yet")
+        );
+
+        // Plain JUnit without file attributes groups by suite and class
+        let plain = r#"<testsuite name="unit"><testcase classname="CalcTest" name="adds" time="1.5"/></testsuite>"#;
+        let tree = parse_junit(plain, Path::new("/x"), false).unwrap();
+        let case = &find(&find(&tree, "unit").children, "CalcTest").children[0];
+        assert_eq!((case.id.as_str(), case.duration_ms), ("CalcTest::adds", Some(1500)));
+    }
+
+    #[test]
+    fn test_go() {
+        let stdout = r#"{"Action":"run","Package":"example.com/app/calc","Test":"TestAdd"}
+{"Action":"output","Package":"example.com/app/calc","Test":"TestAdd","Output":"=== RUN   TestAdd\n"}
+{"Action":"output","Package":"example.com/app/calc","Test":"TestAdd/negative","Output":"    calc_test.go:14: got -1, want 1\n"}
+{"Action":"fail","Package":"example.com/app/calc","Test":"TestAdd/negative","Elapsed":0.01}
+{"Action":"pass","Package":"example.com/app/calc","Test":"TestAdd/positive","Elapsed":0}
+{"Action":"fail","Package":"example.com/app/calc","Test":"TestAdd","Elapsed":0.02}
+{"Action":"skip","Package":"example.com/app/calc","Test":"TestLater","Elapsed":0}
+{"Action":"fail","Package":"example.com/app/calc","Elapsed":0.3}
+"#;
+        let tree = parse_go(stdout, Path::new("/go/app"), Some("example.com/app"));
+        assert_eq!(counts(&tree), (1, 1, 1));
+        let package = find(&tree, "example.com/app/calc");
+        assert_eq!(package.status, TestStatus::Failed);
+        let add = find(&package.children, "TestAdd");
+        assert_eq!((add.status, add.duration_ms), (TestStatus::Failed, Some(20)));
+        let negative = find(&add.children, "negative");
+        assert_eq!(negative.id, "TestAdd/negative");
+        assert_eq!(
+            (negative.file.as_deref(), negative.line),
+            (Some("/go/app/calc/calc_test.go"), Some(14))
+        );
+        assert_eq!(negative.message.as_deref(), Some("calc_test.go:14: got -1, want 1"));
+    }
+}
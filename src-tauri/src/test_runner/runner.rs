@@ -0,0 +1,787 @@
+use super::report::{self, TestNode};
+use crate::db;
+use crate::error::KodiqError;
+use crate::filesystem::sandbox;
+use crate::state::DbState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+/// Lines of plain output kept with a report (build errors, crashes).
+const OUTPUT_TAIL: usize = 200;
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Framework {
+    Cargo,
+    Vitest,
+    Jest,
+    Pytest,
+    Go,
+}
+
+impl Framework {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Vitest => "vitest",
+            Self::Jest => "jest",
+            Self::Pytest => "pytest",
+            Self::Go => "go",
+        }
+    }
+}
+
+/// What to run. `path` is absolute; `id` is a test's `TestNode::id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TestScope {
+    All,
+    File { path: String },
+    Test { path: String, id: String },
+}
+
+/// Outcome of one run. Timestamps are Unix seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestReport {
+    pub root: String,
+    pub framework: Framework,
+    pub scope: TestScope,
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub duration_ms: u64,
+    pub exit_code: Option<i32>,
+    pub cancelled: bool,
+    pub passed: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    pub tree: Vec<TestNode>,
+    /// Set when no results could be read (the build failed, the runner
+    /// crashed); `output` usually says why.
+    pub error: Option<String>,
+    pub output: String,
+}
+
+// ── Detection ────────────────────────────────────────────────────────
+
+fn read(root: &Path, file: &str) -> String {
+    std::fs::read_to_string(root.join(file)).unwrap_or_default()
+}
+
+fn any_file(root: &Path, names: &[&str]) -> bool {
+    names.iter().any(|n| root.join(n).is_file())
+}
+
+/// Frameworks the project is set up for, from its manifests and configs.
+pub fn detect(root: &Path) -> Vec<Framework> {
+    let mut found = Vec::new();
+    if root.join("Cargo.toml").is_file() {
+        found.push(Framework::Cargo);
+    }
+
+    let package: serde_json::Value =
+        serde_json::from_str(&read(root, "package.json")).unwrap_or_default();
+    let depends_on = |name: &str| {
+        ["dependencies", "devDependencies"].iter().any(|k| package[k].get(name).is_some())
+    };
+    let config = |tool: &str| {
+        ["ts", "mts", "cts", "js", "mjs", "cjs", "json"]
+            .iter()
+            .any(|ext| root.join(format!("{}.config.{}", tool, ext)).is_file())
+    };
+    if depends_on("vitest") || config("vitest") {
+        found.push(Framework::Vitest);
+    } else if depends_on("jest") || config("jest") || package.get("jest").is_some() {
+        found.push(Framework::Jest);
+    }
+
+    if any_file(root, &["pytest.ini", "conftest.py"])
+        || read(root, "pyproject.toml").contains("pytest")
+        || read(root, "setup.cfg").contains("[tool:pytest]")
+        || read(root, "tox.ini").contains("[pytest]")
+    {
+        found.push(Framework::Pytest);
+    }
+    if root.join("go.mod").is_file() {
+        found.push(Framework::Go);
+    }
+    found
+}
+
+// ── Commands per framework ───────────────────────────────────────────
+
+/// Where the machine-readable results end up.
+#[derive(Debug, PartialEq)]
+enum Results {
+    /// libtest JSON on stdout, suite names on stderr.
+    Libtest,
+    /// libtest's human output on stdout, suite names on stderr.
+    LibtestText,
+    /// `go test -json` on stdout.
+    GoJson,
+    JestJson(PathBuf),
+    Junit(PathBuf),
+}
+
+#[derive(Debug, PartialEq)]
+struct Invocation {
+    program: PathBuf,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    results: Results,
+}
+
+fn relative(root: &Path, path: &str) -> String {
+    let rel = Path::new(path).strip_prefix(root).unwrap_or(Path::new(path));
+    rel.to_string_lossy().replace('\\', "/")
+}
+
+/// The project's own binary, or `npx` to fetch it.
+fn node_bin(root: &Path, name: &str) -> (PathBuf, Vec<String>) {
+    let local = root.join("node_modules/.bin").join(name);
+    if local.is_file() {
+        (local, Vec::new())
+    } else if cfg!(target_os = "windows") {
+        (PathBuf::from("npx.cmd"), vec![name.to_string()])
+    } else {
+        (PathBuf::from("npx"), vec![name.to_string()])
+    }
+}
+
+fn python(root: &Path) -> PathBuf {
+    let venv = if cfg!(target_os = "windows") { "Scripts/python.exe" } else { "bin/python" };
+    [".venv", "venv"]
+        .iter()
+        .map(|dir| root.join(dir).join(venv))
+        .find(|p| p.is_file())
+        .unwrap_or_else(|| {
+            PathBuf::from(if cfg!(target_os = "windows") { "python" } else { "python3" })
+        })
+}
+
+/// Anchored name filter for `-t` / `-run`.
+fn exact(name: &str) -> String {
+    format!("^{}$", regex::escape(name))
+}
+
+/// The crate directory of `path` (nearest `Cargo.toml` inside `root`).
+fn crate_dir(root: &Path, path: &Path) -> PathBuf {
+    path.ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .unwrap_or(root)
+        .to_path_buf()
+}
+
+/// `src/net/http.rs` → `net::http`; crate roots and `mod.rs` add nothing.
+fn module_path(rel: &Path) -> String {
+    let Ok(rel) = rel.strip_prefix("src") else { return String::new() };
+    let mut parts: Vec<String> = rel
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if matches!(parts.last().map(String::as_str), Some("mod" | "lib" | "main")) {
+        parts.pop();
+    }
+    parts.join("::")
+}
+
+/// Whether the toolchain used in `root` (after `rust-toolchain` overrides)
+/// is nightly, where libtest's JSON output is available.
+fn nightly_toolchain(root: &Path) -> bool {
+    Command::new("rustc").arg("-V").current_dir(root).output().is_ok_and(|out| {
+        let version = String::from_utf8_lossy(&out.stdout);
+        version.contains("-nightly") || version.contains("-dev")
+    })
+}
+
+fn cargo(root: &Path, scope: &TestScope, nightly: bool) -> Invocation {
+    let mut args = vec!["test".to_string()];
+    let mut filters = Vec::new();
+    if let TestScope::File { path } | TestScope::Test { path, .. } = scope {
+        let path = Path::new(path);
+        let dir = crate_dir(root, path);
+        if dir != root {
+            args.extend([
+                "--manifest-path".into(),
+                dir.join("Cargo.toml").to_string_lossy().into(),
+            ]);
+        }
+        let rel = path.strip_prefix(&dir).unwrap_or(path);
+        if rel.parent() == Some(Path::new("tests")) {
+            let target = rel.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            args.extend(["--test".into(), target]);
+        } else if let TestScope::File { .. } = scope {
+            let module = module_path(rel);
+            if !module.is_empty() {
+                filters.push(format!("{}::", module));
+            }
+        }
+    }
+    args.push("--".into());
+    if nightly {
+        args.extend(
+            ["-Z", "unstable-options", "--format", "json", "--report-time"].map(String::from),
+        );
+    }
+    args.extend(filters);
+    if let TestScope::Test { id, .. } = scope {
+        args.extend([id.clone(), "--exact".into()]);
+    }
+    Invocation {
+        program: PathBuf::from("cargo"),
+        args,
+        env: Vec::new(),
+        results: if nightly { Results::Libtest } else { Results::LibtestText },
+    }
+}
+
+fn javascript(root: &Path, framework: Framework, scope: &TestScope, out: PathBuf) -> Invocation {
+    let (program, mut args) = node_bin(root, framework.as_str());
+    let output_file = format!("--outputFile={}", out.display());
+    if framework == Framework::Vitest {
+        args.extend(["run".into(), "--reporter=json".into(), output_file]);
+    } else {
+        args.extend(["--json".into(), output_file, "--testLocationInResults".into()]);
+    }
+    match scope {
+        TestScope::All => {}
+        TestScope::File { path } | TestScope::Test { path, .. } => {
+            if framework == Framework::Jest {
+                args.push("--runTestsByPath".into());
+            }
+            args.push(relative(root, path));
+        }
+    }
+    if let TestScope::Test { id, .. } = scope {
+        args.extend(["-t".into(), exact(id)]);
+    }
+    Invocation {
+        program,
+        args,
+        env: vec![("CI".into(), "1".into())],
+        results: Results::JestJson(out),
+    }
+}
+
+fn pytest(root: &Path, scope: &TestScope, out: PathBuf) -> Invocation {
+    let mut args: Vec<String> =
+        vec!["-m".into(), "pytest".into(), format!("--junitxml={}", out.display())];
+    // xunit1 adds `file` and `line` to each test case
+    args.extend(["-o", "junit_family=xunit1", "-q"].map(String::from));
+    match scope {
+        TestScope::All => {}
+        TestScope::File { path } => args.push(relative(root, path)),
+        TestScope::Test { id, .. } => args.push(id.clone()),
+    }
+    Invocation { program: python(root), args, env: Vec::new(), results: Results::Junit(out) }
+}
+
+fn go(root: &Path, scope: &TestScope) -> Invocation {
+    let mut args = vec!["test".to_string(), "-json".to_string()];
+    match scope {
+        TestScope::All => args.push("./...".into()),
+        TestScope::File { path } | TestScope::Test { path, .. } => {
+            let dir = Path::new(path).parent().unwrap_or(root);
+            let rel = relative(root, &dir.to_string_lossy());
+            args.push(if rel.is_empty() { ".".into() } else { format!("./{}", rel) });
+        }
+    }
+    if let TestScope::Test { id, .. } = scope {
+        let pattern: Vec<String> = id.split('/').map(exact).collect();
+        args.extend(["-run".into(), pattern.join("/")]);
+    }
+    Invocation { program: PathBuf::from("go"), args, env: Vec::new(), results: Results::GoJson }
+}
+
+fn invocation(root: &Path, framework: Framework, scope: &TestScope) -> Invocation {
+    static NEXT: AtomicU32 = AtomicU32::new(0);
+    let out = |ext: &str| {
+        let n = NEXT.fetch_add(1, Ordering::SeqCst);
+        std::env::temp_dir().join(format!("kodiq-tests-{}-{}.{}", std::process::id(), n, ext))
+    };
+    match framework {
+        Framework::Cargo => cargo(root, scope, nightly_toolchain(root)),
+        Framework::Vitest | Framework::Jest => javascript(root, framework, scope, out("json")),
+        Framework::Pytest => pytest(root, scope, out("xml")),
+        Framework::Go => go(root, scope),
+    }
+}
+
+// ── Running ──────────────────────────────────────────────────────────
+
+/// A test process that can be cancelled from another command. Task jobs
+/// run the same way.
+pub(crate) struct Run {
+    child: Mutex<Child>,
+    cancelled: AtomicBool,
+}
+
+impl Run {
+    pub(crate) fn new(child: Child) -> Self {
+        Self { child: Mutex::new(child), cancelled: AtomicBool::new(false) }
+    }
+
+    pub(crate) fn cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn cancel(&self) -> Result<(), KodiqError> {
+        self.cancelled.store(true, Ordering::SeqCst);
+        let mut child = self.child.lock()?;
+        // Runners fork workers and test binaries; take the whole group down
+        #[cfg(unix)]
+        let _ = Command::new("kill").args(["-KILL", "--", &format!("-{}", child.id())]).status();
+        let _ = child.kill();
+        Ok(())
+    }
+
+    pub(crate) fn wait(&self) -> Result<ExitStatus, KodiqError> {
+        loop {
+            if let Some(status) = self.child.lock()?.try_wait()? {
+                return Ok(status);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Runs in progress by project root.
+#[derive(Default)]
+pub struct TestRuns {
+    running: Mutex<HashMap<String, Arc<Run>>>,
+}
+
+pub type TestRunState = Arc<TestRuns>;
+
+pub fn new_test_run_state() -> TestRunState {
+    Arc::new(TestRuns::default())
+}
+
+struct Captured {
+    stdout: String,
+    stderr: String,
+    /// Plain (non-JSON) lines of both streams.
+    tail: Vec<String>,
+    exit_code: Option<i32>,
+    cancelled: bool,
+}
+
+fn spawn(invocation: &Invocation, root: &Path) -> Result<Child, KodiqError> {
+    let mut cmd = Command::new(&invocation.program);
+    cmd.args(&invocation.args)
+        .envs(invocation.env.iter().map(|(k, v)| (k, v)))
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+    cmd.spawn().map_err(|e| {
+        KodiqError::Other(format!("Failed to start {}: {}", invocation.program.display(), e))
+    })
+}
+
+/// Collect both streams until the process exits, passing plain lines to
+/// `on_line` as they arrive.
+fn capture(
+    run: &Run,
+    stdout: impl Read + Send + 'static,
+    stderr: impl Read + Send + 'static,
+    on_line: Arc<dyn Fn(&str) + Send + Sync>,
+) -> Result<Captured, KodiqError> {
+    let tail = Arc::new(Mutex::new(Vec::<String>::new()));
+    let reader = |stream: Box<dyn Read + Send>| {
+        let tail = tail.clone();
+        let on_line = on_line.clone();
+        std::thread::spawn(move || {
+            let mut all = String::new();
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else { break };
+                if !line.starts_with('{') {
+                    on_line(&line);
+                    if let Ok(mut tail) = tail.lock() {
+                        tail.push(line.clone());
+                        if tail.len() > OUTPUT_TAIL {
+                            tail.remove(0);
+                        }
+                    }
+                }
+                all.push_str(&line);
+                all.push('\n');
+            }
+            all
+        })
+    };
+    let out = reader(Box::new(stdout));
+    let err = reader(Box::new(stderr));
+
+    let status = run.wait()?;
+    let stdout = out.join().unwrap_or_default();
+    let stderr = err.join().unwrap_or_default();
+    let tail = std::mem::take(&mut *tail.lock()?);
+    Ok(Captured { stdout, stderr, tail, exit_code: status.code(), cancelled: run.cancelled() })
+}
+
+/// Read the framework's results into a tree.
+fn results(
+    invocation: &Invocation,
+    root: &Path,
+    captured: &Captured,
+) -> Result<Vec<TestNode>, String> {
+    let read_file = |path: &Path| {
+        let text =
+            std::fs::read_to_string(path).map_err(|_| "The runner wrote no results".to_string());
+        let _ = std::fs::remove_file(path);
+        text
+    };
+    let tree = match &invocation.results {
+        Results::Libtest => {
+            let suites = report::cargo_suites(&captured.stderr);
+            report::parse_libtest(&captured.stdout, &suites, root)
+        }
+        Results::LibtestText => {
+            let suites = report::cargo_suites(&captured.stderr);
+            report::parse_libtest_pretty(&captured.stdout, &suites, root)
+        }
+        Results::GoJson => {
+            report::parse_go(&captured.stdout, root, report::go_module(root).as_deref())
+        }
+        Results::JestJson(path) => report::parse_jest(&read_file(path)?, root)?,
+        Results::Junit(path) => report::parse_junit(&read_file(path)?, root, true)?,
+    };
+    if tree.is_empty() && captured.exit_code != Some(0) {
+        return Err("No test results".into());
+    }
+    Ok(tree)
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn tests_detect(webview: tauri::Webview, root: String) -> Result<Vec<Framework>, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    Ok(detect(Path::new(&root)))
+}
+
+/// Run a project's tests (all, one file or one test) and wait for the
+/// results. Plain output streams as `tests-output`; the report is also
+/// emitted as `tests-finished` and kept as the project's last run.
+#[tracing::instrument(skip(app, webview, db, runs))]
+#[allow(clippy::too_many_arguments)]
+#[tauri::command(async)]
+pub fn tests_run(
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+    root: String,
+    framework: Option<Framework>,
+    scope: Option<TestScope>,
+    db: tauri::State<'_, DbState>,
+    runs: tauri::State<'_, TestRunState>,
+) -> Result<TestReport, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    let root_path = PathBuf::from(&root);
+    let framework = match framework {
+        Some(framework) => framework,
+        None => *detect(&root_path)
+            .first()
+            .ok_or_else(|| KodiqError::NotFound(format!("No test framework in {}", root)))?,
+    };
+    let scope = scope.unwrap_or(TestScope::All);
+    let invocation = invocation(&root_path, framework, &scope);
+
+    let (run, stdout, stderr) = {
+        let mut running = runs.running.lock()?;
+        if running.contains_key(&root) {
+            return Err(KodiqError::Conflict(format!("Tests are already running in {}", root)));
+        }
+        let mut child = spawn(&invocation, &root_path)?;
+        let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+            let _ = child.kill();
+            return Err(KodiqError::Other("Test runner pipes unavailable".into()));
+        };
+        let run = Arc::new(Run::new(child));
+        running.insert(root.clone(), run.clone());
+        (run, stdout, stderr)
+    };
+    tracing::info!("Running {} tests in {}", framework.as_str(), root);
+
+    let started_at = now();
+    let started = Instant::now();
+    let emitter = app.clone();
+    let output_root = root.clone();
+    let on_line: Arc<dyn Fn(&str) + Send + Sync> = Arc::new(move |line| {
+        let _ =
+            emitter.emit("tests-output", serde_json::json!({ "root": output_root, "line": line }));
+    });
+    let captured = capture(&run, stdout, stderr, on_line);
+    runs.running.lock()?.remove(&root);
+    let captured = captured?;
+
+    let (tree, error) = match results(&invocation, &root_path, &captured) {
+        Ok(tree) => (tree, None),
+        Err(_) if captured.cancelled => (Vec::new(), None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    let (passed, failed, skipped) = report::counts(&tree);
+    let report = TestReport {
+        root,
+        framework,
+        scope,
+        started_at,
+        finished_at: now(),
+        duration_ms: started.elapsed().as_millis() as u64,
+        exit_code: captured.exit_code,
+        cancelled: captured.cancelled,
+        passed,
+        failed,
+        skipped,
+        tree,
+        error,
+        output: captured.tail.join("\n"),
+    };
+
+    if !report.cancelled {
+        db::test_results::save(&*db.connection.lock()?, &report)?;
+    }
+    let _ = app.emit("tests-finished", &report);
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn tests_cancel(
+    webview: tauri::Webview,
+    root: String,
+    runs: tauri::State<'_, TestRunState>,
+) -> Result<bool, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    let run = runs.running.lock()?.get(&root).cloned();
+    match run {
+        Some(run) => run.cancel().map(|_| true),
+        None => Ok(false),
+    }
+}
+
+/// The last run of each framework in the project, newest first.
+#[tauri::command]
+pub fn tests_last_results(
+    webview: tauri::Webview,
+    root: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<Vec<TestReport>, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    Ok(db::test_results::list(&*db.connection.lock()?, &root)?)
+}
+
+#[tauri::command]
+pub fn tests_clear_results(
+    webview: tauri::Webview,
+    root: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<u64, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    Ok(db::test_results::clear(&*db.connection.lock()?, &root)?)
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn scope_file(path: &Path) -> TestScope {
+        TestScope::File { path: path.to_string_lossy().to_string() }
+    }
+
+    #[test]
+    fn test_detect() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        assert!(detect(root).is_empty());
+
+        fs::write(root.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(root.join("package.json"), r#"{ "devDependencies": { "jest": "^29" } }"#)
+            .unwrap();
+        fs::write(root.join("pyproject.toml"), "[tool.pytest.ini_options]\n").unwrap();
+        fs::write(root.join("go.mod"), "module example.com/app\n").unwrap();
+        assert_eq!(
+            detect(root),
+            vec![Framework::Cargo, Framework::Jest, Framework::Pytest, Framework::Go]
+        );
+
+        // vitest wins over jest when both are around
+        fs::write(root.join("vitest.config.ts"), "").unwrap();
+        assert_eq!(detect(root)[1], Framework::Vitest);
+    }
+
+    #[test]
+    fn test_cargo_invocations() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Cargo.toml"), "[workspace]").unwrap();
+        fs::create_dir_all(root.join("crates/net/src/http")).unwrap();
+        fs::write(root.join("crates/net/Cargo.toml"), "[package]").unwrap();
+        let json = ["--", "-Z", "unstable-options", "--format", "json", "--report-time"];
+
+        let all = cargo(root, &TestScope::All, true);
+        assert_eq!(all.args, [&["test"][..], &json[..]].concat());
+        assert_eq!(all.results, Results::Libtest);
+        assert!(all.env.is_empty());
+
+        // Stable toolchains get the human output
+        let stable = cargo(root, &TestScope::All, false);
+        assert_eq!(stable.args, ["test", "--"]);
+        assert_eq!(stable.results, Results::LibtestText);
+
+        let manifest = root.join("crates/net/Cargo.toml").to_string_lossy().to_string();
+        let file = cargo(root, &scope_file(&root.join("crates/net/src/http/mod.rs")), true);
+        assert_eq!(
+            file.args,
+            [&["test", "--manifest-path", manifest.as_str()][..], &json[..], &["http::"][..]]
+                .concat()
+        );
+
+        let path = root.join("tests/api.rs").to_string_lossy().to_string();
+        let test = cargo(root, &TestScope::Test { path, id: "creates_user".into() }, true);
+        assert_eq!(
+            test.args,
+            [&["test", "--test", "api"][..], &json[..], &["creates_user", "--exact"][..]].concat()
+        );
+    }
+
+    #[test]
+    fn test_other_invocations() {
+        let root = Path::new("/nonexistent/web");
+        let out = PathBuf::from("/tmp/out.json");
+        let path = "/nonexistent/web/src/math.test.ts".to_string();
+
+        let vitest = javascript(
+            root,
+            Framework::Vitest,
+            &TestScope::Test { path: path.clone(), id: "math adds (1+1)".into() },
+            out.clone(),
+        );
+        assert_eq!(vitest.program, PathBuf::from(if cfg!(windows) { "npx.cmd" } else { "npx" }));
+        assert_eq!(
+            vitest.args,
+            [
+                "vitest",
+                "run",
+                "--reporter=json",
+                "--outputFile=/tmp/out.json",
+                "src/math.test.ts",
+                "-t",
+                r"^math adds \(1\+1\)$"
+            ]
+        );
+        let jest = javascript(root, Framework::Jest, &TestScope::File { path }, out.clone());
+        assert_eq!(
+            jest.args,
+            [
+                "jest",
+                "--json",
+                "--outputFile=/tmp/out.json",
+                "--testLocationInResults",
+                "--runTestsByPath",
+                "src/math.test.ts"
+            ]
+        );
+
+        let py = pytest(
+            root,
+            &TestScope::Test { path: String::new(), id: "tests/test_a.py::TestB::test_c".into() },
+            PathBuf::from("/tmp/out.xml"),
+        );
+        assert_eq!(py.args.last().map(String::as_str), Some("tests/test_a.py::TestB::test_c"));
+        assert_eq!(py.results, Results::Junit(PathBuf::from("/tmp/out.xml")));
+
+        let go_test = go(
+            root,
+            &TestScope::Test {
+                path: "/nonexistent/web/calc/calc_test.go".into(),
+                id: "TestAdd/neg".into(),
+            },
+        );
+        assert_eq!(go_test.args, ["test", "-json", "./calc", "-run", "^TestAdd$/^neg$"]);
+        assert_eq!(go(root, &TestScope::All).args, ["test", "-json", "./..."]);
+    }
+
+    #[cfg(unix)]
+    fn sh(script: &str) -> Invocation {
+        Invocation {
+            program: PathBuf::from("sh"),
+            args: vec!["-c".into(), script.into()],
+            env: Vec::new(),
+            results: Results::GoJson,
+        }
+    }
+
+    #[cfg(unix)]
+    fn start(invocation: &Invocation) -> (Arc<Run>, impl Read + Send, impl Read + Send) {
+        let mut child = spawn(invocation, Path::new("/")).unwrap();
+        let (stdout, stderr) = (child.stdout.take().unwrap(), child.stderr.take().unwrap());
+        (Arc::new(Run::new(child)), stdout, stderr)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_capture_and_results() {
+        let invocation = sh(r#"echo 'building'; echo 'warn: slow' >&2
+echo '{"Action":"pass","Package":"p","Test":"TestOk","Elapsed":0.5}'
+exit 1"#);
+        let (run, stdout, stderr) = start(&invocation);
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let seen = lines.clone();
+        let captured = capture(
+            &run,
+            stdout,
+            stderr,
+            Arc::new(move |l| seen.lock().unwrap().push(l.to_string())),
+        )
+        .unwrap();
+        assert_eq!((captured.exit_code, captured.cancelled), (Some(1), false));
+        let mut tail = captured.tail.clone();
+        tail.sort();
+        assert_eq!(tail, vec!["building", "warn: slow"]);
+        assert_eq!(lines.lock().unwrap().len(), 2);
+
+        let tree = results(&invocation, Path::new("/"), &captured).unwrap();
+        assert_eq!(report::counts(&tree), (1, 0, 0));
+
+        let empty = Captured { stdout: String::new(), ..captured };
+        assert!(results(&invocation, Path::new("/"), &empty).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cancel() {
+        let (run, stdout, stderr) = start(&sh("sleep 30 & wait"));
+        let canceller = run.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            canceller.cancel().unwrap();
+        });
+        let started = Instant::now();
+        let captured = capture(&run, stdout, stderr, Arc::new(|_| {})).unwrap();
+        assert!(captured.cancelled);
+        // The backgrounded sleep held the pipes too; the group kill ended it
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,431 @@
+use crate::launch::schema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// The checked-in workspace config, relative to the project root.
+pub const WORKSPACE_FILE: &str = ".kodiq/workspace.json";
+
+/// The newest file version this build understands.
+pub const VERSION: u64 = 1;
+
+const SECTIONS: &[&str] =
+    &["version", "launchConfigs", "tasks", "previewServers", "envFiles", "settings"];
+
+// ── Types ────────────────────────────────────────────────────────────
+
+/// `.kodiq/workspace.json`: what a team shares through the repository.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceFile {
+    pub version: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub launch_configs: Vec<SharedLaunchConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<SharedTask>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preview_servers: Vec<SharedServer>,
+    /// Dotenv files, relative to the root, applied in order to tasks and
+    /// launches.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_files: Vec<String>,
+    /// Project settings sections (`files`, `format`, `lsp`, ...). Local
+    /// project settings override them key by key.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub settings: Map<String, Value>,
+}
+
+/// A launch config as stored in the file; `config` is inline JSON rather
+/// than the string the DB keeps.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedLaunchConfig {
+    pub name: String,
+    pub cli: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub default: bool,
+    pub config: Value,
+}
+
+impl SharedLaunchConfig {
+    /// `config` as the JSON string launch configs carry.
+    pub fn config_json(&self) -> String {
+        match &self.config {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedTask {
+    pub name: String,
+    /// Program and arguments.
+    pub command: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedServer {
+    pub name: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+}
+
+/// A problem found while reading the file. Bad entries are skipped; the
+/// rest of the file still applies.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceIssue {
+    /// Where in the file, like `launchConfigs[1].config`; empty for the
+    /// whole file.
+    pub at: String,
+    pub message: String,
+    /// Set for JSON syntax errors.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+fn issue(at: impl Into<String>, message: impl Into<String>) -> WorkspaceIssue {
+    WorkspaceIssue { at: at.into(), message: message.into(), line: None, column: None }
+}
+
+/// The usable part of the file (`None` when missing or unreadable) and
+/// everything wrong with it.
+#[derive(Debug, Default)]
+pub struct Loaded {
+    pub file: Option<WorkspaceFile>,
+    pub issues: Vec<WorkspaceIssue>,
+}
+
+// ── Loading ──────────────────────────────────────────────────────────
+
+pub fn path(root: &Path) -> PathBuf {
+    root.join(WORKSPACE_FILE)
+}
+
+/// Read and check the project's workspace file.
+pub fn load(root: &Path) -> Loaded {
+    match std::fs::read_to_string(path(root)) {
+        Ok(text) => parse(&text, root),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Loaded::default(),
+        Err(e) => Loaded { file: None, issues: vec![issue("", e.to_string())] },
+    }
+}
+
+/// Entries of one array section that deserialize, with an issue for each
+/// one that doesn't.
+fn entries<T: serde::de::DeserializeOwned>(
+    doc: &Map<String, Value>,
+    key: &str,
+    issues: &mut Vec<WorkspaceIssue>,
+) -> Vec<(String, T)> {
+    let Some(value) = doc.get(key) else { return Vec::new() };
+    let Some(items) = value.as_array() else {
+        issues.push(issue(key, "Expected a list"));
+        return Vec::new();
+    };
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            let at = format!("{}[{}]", key, i);
+            match serde_json::from_value(item.clone()) {
+                Ok(entry) => Some((at, entry)),
+                Err(e) => {
+                    issues.push(issue(at, e.to_string()));
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Whether `name` was seen before under `at`; reports duplicates.
+fn duplicate(
+    seen: &mut Vec<String>,
+    name: String,
+    at: &str,
+    issues: &mut Vec<WorkspaceIssue>,
+) -> bool {
+    if seen.contains(&name) {
+        issues.push(issue(at, format!("Duplicate name '{}'", name)));
+        return true;
+    }
+    seen.push(name);
+    false
+}
+
+/// A relative path that stays inside the root.
+fn inside(rel: &str) -> bool {
+    let path = Path::new(rel);
+    !rel.is_empty()
+        && path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+pub fn parse(text: &str, root: &Path) -> Loaded {
+    let doc: Value = match serde_json::from_str(text) {
+        Ok(doc) => doc,
+        Err(e) => {
+            let mut syntax = issue("", e.to_string());
+            (syntax.line, syntax.column) = (Some(e.line()), Some(e.column()));
+            return Loaded { file: None, issues: vec![syntax] };
+        }
+    };
+    let Value::Object(doc) = doc else {
+        return Loaded { file: None, issues: vec![issue("", "Expected a JSON object")] };
+    };
+
+    let mut issues = Vec::new();
+    let version = match doc.get("version") {
+        Some(v) => match v.as_u64() {
+            Some(n) if n > VERSION => {
+                let message =
+                    format!("Version {} is newer than this app supports ({})", n, VERSION);
+                return Loaded { file: None, issues: vec![issue("version", message)] };
+            }
+            Some(n) if n > 0 => n,
+            _ => {
+                issues.push(issue(
+                    "version",
+                    format!("Expected a version number, assuming {}", VERSION),
+                ));
+                VERSION
+            }
+        },
+        None => {
+            issues.push(issue("version", format!("Missing version, assuming {}", VERSION)));
+            VERSION
+        }
+    };
+    for key in doc.keys().filter(|k| !SECTIONS.contains(&k.as_str())) {
+        issues.push(issue(key.as_str(), format!("Unknown key '{}'", key)));
+    }
+
+    let mut file = WorkspaceFile { version, ..Default::default() };
+
+    let mut seen = Vec::new();
+    for (at, config) in entries::<SharedLaunchConfig>(&doc, "launchConfigs", &mut issues) {
+        if config.name.trim().is_empty() || config.cli.trim().is_empty() {
+            issues.push(issue(at, "A launch config needs a name and a cli"));
+            continue;
+        }
+        if duplicate(&mut seen, format!("{}/{}", config.cli, config.name), &at, &mut issues) {
+            continue;
+        }
+        if config.cli == schema::COMPOUND {
+            if let Err(errors) = schema::parse(&config.config_json()) {
+                let at = format!("{}.config", at);
+                issues.extend(errors.iter().map(|e| issue(at.as_str(), e.to_string())));
+                continue;
+            }
+        }
+        file.launch_configs.push(config);
+    }
+
+    let mut seen = Vec::new();
+    for (at, task) in entries::<SharedTask>(&doc, "tasks", &mut issues) {
+        if task.name.trim().is_empty() || task.command.first().map_or(true, |p| p.is_empty()) {
+            issues.push(issue(at, "A task needs a name and a command"));
+        } else if !duplicate(&mut seen, task.name.clone(), &at, &mut issues) {
+            file.tasks.push(task);
+        }
+    }
+
+    let mut seen = Vec::new();
+    for (at, server) in entries::<SharedServer>(&doc, "previewServers", &mut issues) {
+        if server.name.trim().is_empty() || server.command.trim().is_empty() {
+            issues.push(issue(at, "A preview server needs a name and a command"));
+        } else if !duplicate(&mut seen, server.name.clone(), &at, &mut issues) {
+            file.preview_servers.push(server);
+        }
+    }
+
+    for (at, env_file) in entries::<String>(&doc, "envFiles", &mut issues) {
+        if !inside(&env_file) {
+            issues.push(issue(at, format!("'{}' must be a path inside the project", env_file)));
+        } else if !root.join(&env_file).is_file() {
+            issues.push(issue(at, format!("'{}' does not exist", env_file)));
+        } else {
+            file.env_files.push(env_file);
+        }
+    }
+
+    match doc.get("settings") {
+        Some(Value::Object(settings)) => file.settings = settings.clone(),
+        Some(_) => issues.push(issue("settings", "Expected an object")),
+        None => {}
+    }
+
+    Loaded { file: Some(file), issues }
+}
+
+// ── Settings & env ───────────────────────────────────────────────────
+
+/// `local` over `shared`: objects merge key by key, anything else is
+/// replaced.
+pub fn overlay(shared: Option<Value>, local: Option<Value>) -> Option<Value> {
+    match (shared, local) {
+        (Some(Value::Object(mut shared)), Some(Value::Object(local))) => {
+            shared.extend(local);
+            Some(Value::Object(shared))
+        }
+        (shared, local) => local.or(shared),
+    }
+}
+
+/// One section of the file's `settings`.
+pub fn settings_section(root: &Path, key: &str) -> Option<Value> {
+    load(root).file?.settings.remove(key)
+}
+
+/// `KEY=value` lines; `export` prefixes, comments and quotes are handled.
+fn parse_env(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = match value.chars().next() {
+                Some(q @ ('"' | '\'')) if value.len() >= 2 && value.ends_with(q) => {
+                    &value[1..value.len() - 1]
+                }
+                _ => value.split(" #").next().unwrap_or(value).trim_end(),
+            };
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
+
+/// Variables from the file's `envFiles`; later files win.
+pub fn env(root: &Path) -> HashMap<String, String> {
+    let Some(file) = load(root).file else { return HashMap::new() };
+    file.env_files
+        .iter()
+        .filter_map(|f| std::fs::read_to_string(root.join(f)).ok())
+        .flat_map(|text| parse_env(&text))
+        .collect()
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_reports_and_skips_bad_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join(".env"), "A=1\n").unwrap();
+        let text = json!({
+            "version": 1,
+            "launchConfigs": [
+                { "name": "review", "cli": "claude", "config": { "args": ["--verbose"] } },
+                { "name": "review", "cli": "claude", "config": {} },
+                { "name": "stack", "cli": "compound", "config": { "steps": [] } },
+                { "cli": "gemini", "config": {} }
+            ],
+            "tasks": [
+                { "name": "seed", "command": ["npm", "run", "seed"] },
+                { "name": "empty", "command": [] }
+            ],
+            "previewServers": [{ "name": "web", "command": "npm", "args": ["run", "dev"] }],
+            "envFiles": [".env", "../secrets.env", ".env.local"],
+            "settings": { "format": { "enabled": true } },
+            "extras": true
+        })
+        .to_string();
+
+        let loaded = parse(&text, root);
+        let file = loaded.file.unwrap();
+        assert_eq!(file.launch_configs.len(), 1);
+        assert_eq!(file.launch_configs[0].config_json(), r#"{"args":["--verbose"]}"#);
+        assert_eq!(file.tasks.len(), 1);
+        assert_eq!(file.preview_servers[0].args, ["run", "dev"]);
+        assert_eq!(file.env_files, [".env"]);
+        assert_eq!(file.settings["format"], json!({ "enabled": true }));
+
+        let at: Vec<&str> = loaded.issues.iter().map(|i| i.at.as_str()).collect();
+        assert_eq!(
+            at,
+            vec![
+                "extras",
+                "launchConfigs[3]",
+                "launchConfigs[1]",
+                "launchConfigs[2].config",
+                "tasks[1]",
+                "envFiles[1]",
+                "envFiles[2]",
+            ]
+        );
+        assert_eq!(loaded.issues[2].message, "Duplicate name 'claude/review'");
+        assert_eq!(loaded.issues[3].message, "A compound launch needs at least one step");
+    }
+
+    #[test]
+    fn test_parse_versions_and_syntax() {
+        let root = Path::new("/nonexistent");
+        let syntax = parse("{\n  \"version\": 1,\n}", root);
+        assert!(syntax.file.is_none());
+        assert_eq!((syntax.issues[0].line, syntax.issues[0].column), (Some(3), Some(1)));
+
+        let newer = parse(r#"{ "version": 2, "tasks": [] }"#, root);
+        assert!(newer.file.is_none());
+        assert_eq!(newer.issues[0].at, "version");
+
+        let unversioned = parse(r#"{ "tasks": [] }"#, root);
+        assert_eq!(unversioned.file.unwrap().version, VERSION);
+        assert_eq!(unversioned.issues.len(), 1);
+    }
+
+    #[test]
+    fn test_overlay_and_env() {
+        let merged = overlay(
+            Some(json!({ "enabled": true, "timeoutMs": 500 })),
+            Some(json!({ "timeoutMs": 900 })),
+        );
+        assert_eq!(merged, Some(json!({ "enabled": true, "timeoutMs": 900 })));
+        assert_eq!(overlay(Some(json!([1])), None), Some(json!([1])));
+        assert_eq!(overlay(Some(json!({})), Some(json!(false))), Some(json!(false)));
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join(".kodiq")).unwrap();
+        std::fs::write(
+            root.join(".env"),
+            "# shared\nexport API=http://localhost:8080\nMODE=dev # local\n",
+        )
+        .unwrap();
+        std::fs::write(root.join(".env.local"), "MODE='test'\nQUOTED=\"a b\"\n").unwrap();
+        std::fs::write(
+            path(root),
+            r#"{ "version": 1, "envFiles": [".env", ".env.local"], "settings": { "lsp": { "disabled": ["x"] } } }"#,
+        )
+        .unwrap();
+
+        let vars = env(root);
+        assert_eq!(vars["API"], "http://localhost:8080");
+        assert_eq!(vars["MODE"], "test");
+        assert_eq!(vars["QUOTED"], "a b");
+        assert_eq!(settings_section(root, "lsp"), Some(json!({ "disabled": ["x"] })));
+        assert_eq!(settings_section(root, "format"), None);
+    }
+}
@@ -0,0 +1,462 @@
+use super::config::{self, SharedLaunchConfig, SharedServer, WorkspaceIssue};
+use crate::db;
+use crate::db::launch_configs::LaunchConfig;
+use crate::error::KodiqError;
+use crate::filesystem::sandbox;
+use crate::filesystem::watcher::FsChange;
+use crate::state::DbState;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
+
+/// Id prefix that marks a launch config as read from the workspace file.
+pub const REPO_ID_PREFIX: &str = "repo:";
+
+// ── Types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    Db,
+    Repo,
+}
+
+/// A launch config tagged with where it came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourcedLaunchConfig {
+    #[serde(flatten)]
+    pub config: LaunchConfig,
+    pub source: ConfigSource,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcedServer {
+    #[serde(flatten)]
+    pub server: SharedServer,
+    pub source: ConfigSource,
+}
+
+/// One effective project settings section.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcedSetting {
+    pub key: String,
+    pub value: Value,
+    /// `db` when set locally, even if merged over a shared value.
+    pub source: ConfigSource,
+    /// Set locally and in the workspace file.
+    pub overrides_repo: bool,
+}
+
+/// Local configs merged with `.kodiq/workspace.json`. Emitted as
+/// `workspace-changed` whenever the file changes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceView {
+    pub root: String,
+    pub path: String,
+    pub exists: bool,
+    /// The file's version; `None` when it's missing or unusable.
+    pub version: Option<u64>,
+    /// A local config with the same CLI and name hides the shared one.
+    pub launch_configs: Vec<SourcedLaunchConfig>,
+    /// Ready to start: `cwd` is absolute and the env files are applied.
+    pub preview_servers: Vec<SourcedServer>,
+    pub env_files: Vec<String>,
+    pub settings: Vec<SourcedSetting>,
+    pub issues: Vec<WorkspaceIssue>,
+}
+
+// ── Merging ──────────────────────────────────────────────────────────
+
+fn mtime(path: &Path) -> i64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+fn repo_launch_config(shared: &SharedLaunchConfig, modified: i64) -> LaunchConfig {
+    LaunchConfig {
+        id: format!("{}{}/{}", REPO_ID_PREFIX, shared.cli, shared.name),
+        cli_name: shared.cli.clone(),
+        profile_name: shared.name.clone(),
+        config: shared.config_json(),
+        is_default: shared.default,
+        project_id: None,
+        created_at: modified,
+        updated_at: modified,
+    }
+}
+
+/// A launch config from the workspace file by its `repo:` id.
+pub fn find_launch_config(root: &Path, id: &str) -> Option<LaunchConfig> {
+    let file = config::load(root).file?;
+    let modified = mtime(&config::path(root));
+    file.launch_configs
+        .iter()
+        .map(|shared| repo_launch_config(shared, modified))
+        .find(|c| c.id == id)
+}
+
+/// A project's `settings` JSON as an object.
+fn local_settings(settings: Option<&str>) -> Map<String, Value> {
+    match settings.and_then(|s| serde_json::from_str(s).ok()) {
+        Some(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+pub fn view(conn: &rusqlite::Connection, root: &Path) -> Result<WorkspaceView, rusqlite::Error> {
+    let root_str = root.to_string_lossy().trim_end_matches('/').to_string();
+    let path = config::path(root);
+    let loaded = config::load(root);
+    let version = loaded.file.as_ref().map(|f| f.version);
+    let file = loaded.file.unwrap_or_default();
+    let project = db::projects::find(conn, &root_str)?;
+
+    let local = db::launch_configs::list(conn, project.as_ref().map(|p| p.id.as_str()))?;
+    let modified = mtime(&path);
+    let shared: Vec<SourcedLaunchConfig> = file
+        .launch_configs
+        .iter()
+        .filter(|s| !local.iter().any(|l| l.cli_name == s.cli && l.profile_name == s.name))
+        .map(|s| SourcedLaunchConfig {
+            config: repo_launch_config(s, modified),
+            source: ConfigSource::Repo,
+            read_only: true,
+        })
+        .collect();
+    let launch_configs = local
+        .into_iter()
+        .map(|config| SourcedLaunchConfig { config, source: ConfigSource::Db, read_only: false })
+        .chain(shared)
+        .collect();
+
+    let env = config::env(root);
+    let preview_servers = file
+        .preview_servers
+        .into_iter()
+        .map(|mut server| {
+            server.cwd =
+                Some(root.join(server.cwd.unwrap_or_default()).to_string_lossy().to_string());
+            let mut merged = env.clone();
+            merged.extend(server.env);
+            server.env = merged;
+            SourcedServer { server, source: ConfigSource::Repo }
+        })
+        .collect();
+
+    let mut local = local_settings(project.as_ref().and_then(|p| p.settings.as_deref()));
+    let mut settings: Vec<SourcedSetting> = file
+        .settings
+        .into_iter()
+        .map(|(key, shared)| {
+            let local = local.remove(&key);
+            let overrides_repo = local.is_some();
+            SourcedSetting {
+                value: config::overlay(Some(shared), local).unwrap_or_default(),
+                source: if overrides_repo { ConfigSource::Db } else { ConfigSource::Repo },
+                overrides_repo,
+                key,
+            }
+        })
+        .collect();
+    settings.extend(local.into_iter().map(|(key, value)| SourcedSetting {
+        key,
+        value,
+        source: ConfigSource::Db,
+        overrides_repo: false,
+    }));
+    settings.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(WorkspaceView {
+        root: root_str,
+        path: path.to_string_lossy().to_string(),
+        exists: path.is_file(),
+        version,
+        launch_configs,
+        preview_servers,
+        env_files: file.env_files,
+        settings,
+        issues: loaded.issues,
+    })
+}
+
+// ── Export ───────────────────────────────────────────────────────────
+
+/// Key order for written files; anything unknown follows, untouched.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Ordered {
+    version: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    launch_configs: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tasks: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preview_servers: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    env_files: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    settings: Option<Value>,
+    #[serde(flatten)]
+    rest: Map<String, Value>,
+}
+
+/// Write the project's own launch configs and its settings into the
+/// workspace file. Entries with the same CLI and name and settings with
+/// the same key are replaced; everything else in the file is kept.
+pub fn export(conn: &rusqlite::Connection, root: &Path) -> Result<PathBuf, KodiqError> {
+    let root_str = root.to_string_lossy().trim_end_matches('/').to_string();
+    let project = db::projects::find(conn, &root_str)?
+        .ok_or_else(|| KodiqError::NotFound(format!("Project at {}", root_str)))?;
+    let path = config::path(root);
+    let mut doc = match std::fs::read_to_string(&path) {
+        Ok(text) => match serde_json::from_str(&text) {
+            Ok(Value::Object(doc)) => doc,
+            _ => {
+                return Err(KodiqError::Conflict(format!(
+                    "{} is not a valid JSON object; fix it before exporting",
+                    config::WORKSPACE_FILE
+                )))
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Map::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(version) =
+        doc.get("version").and_then(Value::as_u64).filter(|v| *v > config::VERSION)
+    {
+        return Err(KodiqError::Conflict(format!(
+            "{} is version {}, newer than this app supports",
+            config::WORKSPACE_FILE,
+            version
+        )));
+    }
+
+    let mut launch = match doc.remove("launchConfigs") {
+        Some(Value::Array(entries)) => entries,
+        _ => Vec::new(),
+    };
+    let own = db::launch_configs::list(conn, Some(&project.id))?;
+    for cfg in own.into_iter().filter(|c| c.project_id.is_some()) {
+        let config = serde_json::from_str(&cfg.config).unwrap_or(Value::String(cfg.config));
+        let shared = SharedLaunchConfig {
+            name: cfg.profile_name,
+            cli: cfg.cli_name,
+            default: cfg.is_default,
+            config,
+        };
+        let value = serde_json::to_value(&shared).map_err(|e| KodiqError::Other(e.to_string()))?;
+        match launch.iter_mut().find(|v| v["name"] == shared.name && v["cli"] == shared.cli) {
+            Some(slot) => *slot = value,
+            None => launch.push(value),
+        }
+    }
+
+    let mut settings = match doc.remove("settings") {
+        Some(Value::Object(settings)) => settings,
+        _ => Map::new(),
+    };
+    settings.extend(local_settings(project.settings.as_deref()));
+
+    let ordered = Ordered {
+        version: doc.remove("version").unwrap_or(config::VERSION.into()),
+        launch_configs: Some(Value::Array(launch))
+            .filter(|v| v.as_array().is_some_and(|a| !a.is_empty())),
+        tasks: doc.remove("tasks"),
+        preview_servers: doc.remove("previewServers"),
+        env_files: doc.remove("envFiles"),
+        settings: Some(Value::Object(settings))
+            .filter(|v| v.as_object().is_some_and(|s| !s.is_empty())),
+        rest: doc,
+    };
+    let text =
+        serde_json::to_string_pretty(&ordered).map_err(|e| KodiqError::Other(e.to_string()))?;
+    std::fs::create_dir_all(path.parent().unwrap_or(root))?;
+    std::fs::write(&path, format!("{}\n", text))?;
+    Ok(path)
+}
+
+// ── Watcher hook ─────────────────────────────────────────────────────
+
+/// Re-read the workspace file when it changes and emit `workspace-changed`.
+pub fn on_fs_events(app: &tauri::AppHandle, root: &Path, changes: &[FsChange]) {
+    let path = config::path(root);
+    let mut touched = changes.iter().flat_map(|c| std::iter::once(&c.path).chain(c.from.as_ref()));
+    if !touched.any(|p| Path::new(p) == path) {
+        return;
+    }
+    let db = app.state::<DbState>();
+    let Ok(conn) = db.connection.lock() else { return };
+    match view(&conn, root) {
+        Ok(view) => {
+            if !view.issues.is_empty() {
+                tracing::warn!("{} has {} issue(s)", path.display(), view.issues.len());
+            }
+            let _ = app.emit("workspace-changed", view);
+        }
+        Err(e) => tracing::warn!("Failed to reload {}: {}", path.display(), e),
+    }
+}
+
+// ── Tauri Commands ───────────────────────────────────────────────────
+
+/// Local launch configs and settings merged with the project's workspace
+/// file, plus anything wrong with the file.
+#[tauri::command]
+pub fn workspace_load(
+    webview: tauri::Webview,
+    root: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<WorkspaceView, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    let conn = db.connection.lock()?;
+    Ok(view(&conn, Path::new(&root))?)
+}
+
+/// Export the project's local launch configs and settings to
+/// `.kodiq/workspace.json` so the team can share them.
+#[tracing::instrument(skip(webview, db))]
+#[tauri::command]
+pub fn workspace_export(
+    webview: tauri::Webview,
+    root: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<WorkspaceView, KodiqError> {
+    sandbox::check(&webview, &root, None)?;
+    let conn = db.connection.lock()?;
+    let path = export(&conn, Path::new(&root))?;
+    tracing::info!("Exported local configs to {}", path.display());
+    Ok(view(&conn, Path::new(&root))?)
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::launch_configs::NewLaunchConfig;
+    use serde_json::json;
+
+    fn setup() -> (tempfile::TempDir, rusqlite::Connection, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = db::init_test().connection.into_inner().unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        let project = db::projects::create(&conn, "app", &root).unwrap();
+        let settings = json!({ "format": { "timeoutMs": 900 }, "files": { "showHidden": true } });
+        let patch = db::projects::ProjectPatch {
+            name: None,
+            default_cli: None,
+            settings: Some(settings.to_string()),
+        };
+        db::projects::update(&conn, &project.id, &patch).unwrap();
+        (dir, conn, project.id)
+    }
+
+    fn launch(conn: &rusqlite::Connection, name: &str, project_id: Option<&str>) {
+        let cfg = NewLaunchConfig {
+            cli_name: "claude".into(),
+            profile_name: name.into(),
+            config: r#"{"args":["--verbose"]}"#.into(),
+            is_default: None,
+            project_id: project_id.map(String::from),
+        };
+        db::launch_configs::create(conn, cfg).unwrap();
+    }
+
+    #[test]
+    fn test_view_merges_and_tags() {
+        let (dir, conn, project_id) = setup();
+        let root = dir.path();
+        launch(&conn, "review", Some(&project_id));
+        std::fs::create_dir_all(root.join(".kodiq")).unwrap();
+        std::fs::write(
+            config::path(root),
+            json!({
+                "version": 1,
+                "launchConfigs": [
+                    { "name": "review", "cli": "claude", "config": {} },
+                    { "name": "plan", "cli": "claude", "config": { "args": [] } }
+                ],
+                "previewServers": [{ "name": "web", "command": "npm", "cwd": "web" }],
+                "settings": { "format": { "enabled": false, "timeoutMs": 500 }, "lsp": {} }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let view = view(&conn, root).unwrap();
+        assert_eq!(view.version, Some(1));
+        let configs: Vec<(&str, ConfigSource)> = view
+            .launch_configs
+            .iter()
+            .map(|c| (c.config.profile_name.as_str(), c.source))
+            .collect();
+        assert_eq!(configs, vec![("review", ConfigSource::Db), ("plan", ConfigSource::Repo)]);
+        assert_eq!(view.launch_configs[1].config.id, "repo:claude/plan");
+        let found = find_launch_config(root, "repo:claude/plan").unwrap();
+        assert_eq!(found.config, r#"{"args":[]}"#);
+
+        assert_eq!(
+            view.preview_servers[0].server.cwd,
+            Some(root.join("web").to_string_lossy().to_string())
+        );
+
+        let settings: Vec<(&str, ConfigSource, bool)> =
+            view.settings.iter().map(|s| (s.key.as_str(), s.source, s.overrides_repo)).collect();
+        assert_eq!(
+            settings,
+            vec![
+                ("files", ConfigSource::Db, false),
+                ("format", ConfigSource::Db, true),
+                ("lsp", ConfigSource::Repo, false),
+            ]
+        );
+        assert_eq!(view.settings[1].value, json!({ "enabled": false, "timeoutMs": 900 }));
+    }
+
+    #[test]
+    fn test_export_upserts_and_keeps_the_rest() {
+        let (dir, conn, project_id) = setup();
+        let root = dir.path();
+        launch(&conn, "review", Some(&project_id));
+        launch(&conn, "global", None);
+        std::fs::create_dir_all(root.join(".kodiq")).unwrap();
+        std::fs::write(
+            config::path(root),
+            json!({
+                "version": 1,
+                "tasks": [{ "name": "seed", "command": ["make", "seed"] }],
+                "launchConfigs": [
+                    { "name": "review", "cli": "claude", "config": {} },
+                    { "name": "plan", "cli": "claude", "config": {} }
+                ],
+                "settings": { "format": { "enabled": false } }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        export(&conn, root).unwrap();
+        let text = std::fs::read_to_string(config::path(root)).unwrap();
+        assert!(text.starts_with("{\n  \"version\": 1,\n  \"launchConfigs\""));
+        let loaded = config::parse(&text, root);
+        assert!(loaded.issues.is_empty(), "{:?}", loaded.issues);
+        let file = loaded.file.unwrap();
+        let names: Vec<&str> = file.launch_configs.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["review", "plan"]);
+        assert_eq!(file.launch_configs[0].config, json!({ "args": ["--verbose"] }));
+        assert_eq!(file.tasks[0].name, "seed");
+        assert_eq!(file.settings["format"], json!({ "timeoutMs": 900 }));
+        assert_eq!(file.settings["files"], json!({ "showHidden": true }));
+
+        std::fs::write(config::path(root), "{ broken").unwrap();
+        assert!(matches!(export(&conn, root), Err(KodiqError::Conflict(_))));
+    }
+}
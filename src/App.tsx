@@ -142,6 +142,18 @@ export default function App() {
               sort_order: currentTabs.length,
             })
             .catch((e) => console.error("[DB] save session:", e));
+          // Feed the frecency-ranked history with where the command ran
+          if (command && !connectionId) {
+            db.history
+              .add({
+                project_id: projectId,
+                session_id: id,
+                command,
+                cli_name: null,
+                cwd: projectPath,
+              })
+              .catch((e) => console.error("[DB] add history:", e));
+          }
         }
         return id;
       } catch (e) {
@@ -182,10 +194,17 @@ export default function App() {
     useAppStore.getState().closeAllEditorTabs();
     destroyAllEditorViews();
     addRecent({ name, path });
-    loadFileTree(path);
 
-    // Start native filesystem watcher
-    fs.startWatching(path).catch((e) => console.warn("[Watcher] failed to start:", e));
+    // Filesystem commands only accept registered project roots, so register first
+    const registered = db.projects.getOrCreate(name, path);
+    registered.then(
+      () => {
+        loadFileTree(path);
+        // Start native filesystem watcher
+        fs.startWatching(path).catch((e) => console.warn("[Watcher] failed to start:", e));
+      },
+      (e) => toast.error(t("failedToOpenProject"), { description: String(e) }),
+    );
 
     // Capture initial git state for activity log diff
     useAppStore.getState().clearActivity();
@@ -201,7 +220,7 @@ export default function App() {
     // Async init: DB project → auto-config → restore sessions (proper await chain)
     (async () => {
       try {
-        const project = await db.projects.getOrCreate(name, path);
+        const project = await registered;
 
         // Auto-generate default launch configs for installed CLIs (first time only)
         try {
@@ -445,9 +464,10 @@ export default function App() {
 
   // ── Filesystem watcher events ─────────────────────────────────────────
   useEffect(() => {
-    const unlistenFs = listen<string>("fs-changed", () => {
+    const unlistenFs = listen<string>("fs-changed", (event) => {
       const path = useAppStore.getState().projectPath;
-      if (path) loadFileTree(path);
+      // Other open projects have their own watchers
+      if (path && event.payload === path) loadFileTree(path);
     });
     return () => {
       unlistenFs.then((fn) => fn());
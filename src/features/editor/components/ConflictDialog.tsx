@@ -0,0 +1,108 @@
+// ── Save Conflict Dialog ────────────────────────────────────────────────────
+// shadcn AlertDialog shown when a save hits a newer disk copy:
+// Reload / Overwrite / Compare (inline line diff) / Cancel.
+
+import { useMemo, useState } from "react";
+import {
+  AlertDialog,
+  AlertDialogContent,
+  AlertDialogHeader,
+  AlertDialogTitle,
+  AlertDialogDescription,
+  AlertDialogFooter,
+  AlertDialogAction,
+  AlertDialogCancel,
+} from "@/components/ui/alert-dialog";
+import { Button } from "@/components/ui/button";
+import { t } from "@/lib/i18n";
+import type { WriteConflict } from "@shared/lib/types";
+import { lineDiff } from "../lib/lineDiff";
+
+interface Props {
+  conflict: WriteConflict | null;
+  onReload: () => void;
+  onOverwrite: () => void;
+  onCancel: () => void;
+}
+
+const LINE_STYLE = {
+  same: "text-[var(--text-tertiary)]",
+  removed: "bg-red-500/10 text-red-400",
+  added: "bg-green-500/10 text-green-400",
+} as const;
+
+const LINE_MARK = { same: " ", removed: "-", added: "+" } as const;
+
+export function ConflictDialog({ conflict, onReload, onOverwrite, onCancel }: Props) {
+  const [comparing, setComparing] = useState(false);
+
+  const diff = useMemo(() => {
+    if (!conflict || !comparing) return [];
+    return lineDiff(conflict.disk?.content ?? "", conflict.ours.content);
+  }, [conflict, comparing]);
+
+  const close = () => {
+    setComparing(false);
+    onCancel();
+  };
+
+  const fileName = conflict?.path.split("/").pop() ?? "";
+
+  return (
+    <AlertDialog open={conflict !== null} onOpenChange={(v) => !v && close()}>
+      <AlertDialogContent>
+        <AlertDialogHeader>
+          <AlertDialogTitle className="text-sm">{t("saveConflict")}</AlertDialogTitle>
+          <AlertDialogDescription className="text-xs">
+            <span className="font-medium text-[var(--text-primary)]">{fileName}</span>{" "}
+            {t(conflict?.disk ? "saveConflictModified" : "saveConflictDeleted")}
+          </AlertDialogDescription>
+        </AlertDialogHeader>
+
+        {comparing && (
+          <div className="max-h-72 overflow-auto rounded border border-[var(--border-subtle)] font-mono text-[11px]">
+            <div className="flex gap-3 border-b border-[var(--border-subtle)] px-2 py-1 text-[var(--text-tertiary)]">
+              <span className="text-red-400">- {t("diskVersion")}</span>
+              <span className="text-green-400">+ {t("yourVersion")}</span>
+            </div>
+            {diff.map((line, i) => (
+              <div key={i} className={`px-2 whitespace-pre ${LINE_STYLE[line.kind]}`}>
+                {LINE_MARK[line.kind]} {line.text}
+              </div>
+            ))}
+          </div>
+        )}
+
+        <AlertDialogFooter>
+          <AlertDialogCancel size="sm" onClick={close}>
+            {t("cancelClose")}
+          </AlertDialogCancel>
+          <Button size="sm" variant="ghost" onClick={() => setComparing((v) => !v)}>
+            {t(comparing ? "hideChanges" : "compareChanges")}
+          </Button>
+          {conflict?.disk && (
+            <AlertDialogAction
+              size="sm"
+              variant="outline"
+              onClick={() => {
+                setComparing(false);
+                onReload();
+              }}
+            >
+              {t("reloadFromDisk")}
+            </AlertDialogAction>
+          )}
+          <AlertDialogAction
+            size="sm"
+            onClick={() => {
+              setComparing(false);
+              onOverwrite();
+            }}
+          >
+            {t("overwriteFile")}
+          </AlertDialogAction>
+        </AlertDialogFooter>
+      </AlertDialogContent>
+    </AlertDialog>
+  );
+}
@@ -1,5 +1,5 @@
 // ── Editor Panel ────────────────────────────────────────────────────────────
-// Combines EditorTabBar + CodeMirrorEditor + UnsavedDialog + EditorStatusBar.
+// Combines EditorTabBar + CodeMirrorEditor + UnsavedDialog + ConflictDialog + EditorStatusBar.
 // Integrates GoToLineDialog and FindReplacePanel with keyboard shortcuts.
 // Returns null when no editor tabs are open.
 
@@ -16,24 +16,24 @@ import {
   getSearchQuery,
 } from "@codemirror/search";
 import { useAppStore } from "@/lib/store";
-import { fs } from "@shared/lib/tauri";
-import { handleError } from "@shared/lib/errors";
 import { t } from "@/lib/i18n";
-import { toast } from "sonner";
 import { EditorTabBar } from "./EditorTabBar";
 import { EditorBreadcrumb } from "./EditorBreadcrumb";
 import { CodeMirrorEditor } from "./CodeMirrorEditor";
 import { GoToLineDialog } from "./GoToLineDialog";
 import { FindReplacePanel, type SearchParams } from "./FindReplacePanel";
 import { destroyEditorView, getViewEntry } from "../lib/viewCache";
+import { overwriteFromConflict, reloadFromConflict, saveTab } from "../lib/fileIO";
 import { UnsavedDialog } from "./UnsavedDialog";
+import { ConflictDialog } from "./ConflictDialog";
 
 export function EditorPanel() {
   const editorTabs = useAppStore((s) => s.editorTabs);
   const activeEditorTab = useAppStore((s) => s.activeEditorTab);
   const closeEditorTab = useAppStore((s) => s.closeEditorTab);
   const forceCloseEditorTab = useAppStore((s) => s.forceCloseEditorTab);
-  const markTabSaved = useAppStore((s) => s.markTabSaved);
+  const saveConflict = useAppStore((s) => s.saveConflict);
+  const setSaveConflict = useAppStore((s) => s.setSaveConflict);
 
   // -- Unsaved dialog state -------
   const [pendingClose, setPendingClose] = useState<string | null>(null);
@@ -70,18 +70,14 @@ export function EditorPanel() {
   const handleSaveAndClose = useCallback(async () => {
     if (!pendingClose || !pendingTab) return;
 
-    try {
-      await fs.writeFile(pendingTab.path, pendingTab.content);
-      markTabSaved(pendingTab.path, pendingTab.content);
-      toast.success(t("fileSaved"));
+    // On a conflict the tab stays open behind the conflict dialog
+    if (await saveTab(pendingTab.path)) {
       forceCloseEditorTab(pendingTab.path);
       destroyEditorView(pendingTab.path);
-    } catch (e) {
-      handleError(e, t("failedToSave"));
     }
 
     setPendingClose(null);
-  }, [pendingClose, pendingTab, markTabSaved, forceCloseEditorTab]);
+  }, [pendingClose, pendingTab, forceCloseEditorTab]);
 
   const handleDiscard = useCallback(() => {
     if (!pendingClose) return;
@@ -94,6 +90,21 @@ export function EditorPanel() {
     setPendingClose(null);
   }, []);
 
+  // -- Conflict dialog actions -------
+  const handleReload = useCallback(() => {
+    if (saveConflict) reloadFromConflict(saveConflict);
+    setSaveConflict(null);
+  }, [saveConflict, setSaveConflict]);
+
+  const handleOverwrite = useCallback(() => {
+    setSaveConflict(null);
+    if (saveConflict) overwriteFromConflict(saveConflict);
+  }, [saveConflict, setSaveConflict]);
+
+  const handleCancelConflict = useCallback(() => {
+    setSaveConflict(null);
+  }, [setSaveConflict]);
+
   // -- Go to Line handler -------
   const handleGoToLine = useCallback(
     (line: number) => {
@@ -288,6 +299,14 @@ export function EditorPanel() {
         onDiscard={handleDiscard}
         onCancel={handleCancelClose}
       />
+
+      {/* Save Conflict Dialog */}
+      <ConflictDialog
+        conflict={saveConflict}
+        onReload={handleReload}
+        onOverwrite={handleOverwrite}
+        onCancel={handleCancelConflict}
+      />
     </div>
   );
 }
@@ -1,6 +1,5 @@
 import { useState, useMemo, useCallback } from "react";
 import { toast } from "sonner";
-import { fs } from "@shared/lib/tauri";
 import {
   CommandDialog,
   CommandInput,
@@ -12,6 +11,7 @@ import {
 import { useAppStore, type FileEntry } from "@/lib/store";
 import { FileIcon } from "@/components/icons";
 import { t } from "@/lib/i18n";
+import { openInEditor } from "../lib/fileIO";
 
 /** Recursively flatten file tree into list of file paths */
 function flattenTree(entries: FileEntry[], acc: FileEntry[] = []): FileEntry[] {
@@ -26,7 +26,6 @@ export function FileSearch() {
   const open = useAppStore((s) => s.fileSearchOpen);
   const setOpen = useAppStore((s) => s.setFileSearchOpen);
   const fileTree = useAppStore((s) => s.fileTree);
-  const setOpenFile = useAppStore((s) => s.setOpenFile);
   const projectPath = useAppStore((s) => s.projectPath);
 
   const [query, setQuery] = useState("");
@@ -44,15 +43,14 @@ export function FileSearch() {
   const openFile = useCallback(
     async (entry: FileEntry) => {
       try {
-        const content = await fs.readFile(entry.path);
-        setOpenFile(entry.path, content);
+        await openInEditor(entry.path);
       } catch (e) {
         toast.error(t("failedToOpenFile"), { description: String(e) });
       }
       setOpen(false);
       setQuery("");
     },
-    [setOpenFile, setOpen],
+    [setOpen],
   );
 
   const getRelativePath = (fullPath: string) => {
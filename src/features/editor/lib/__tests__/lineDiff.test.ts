@@ -0,0 +1,29 @@
+import { describe, it, expect } from "vitest";
+import { lineDiff } from "../lineDiff";
+
+describe("lineDiff", () => {
+  it("marks identical text as unchanged", () => {
+    expect(lineDiff("a\nb", "a\nb")).toEqual([
+      { kind: "same", text: "a" },
+      { kind: "same", text: "b" },
+    ]);
+  });
+
+  it("finds changed lines between shared context", () => {
+    expect(lineDiff("a\nb\nc\nd", "a\nx\nc\nd\ne")).toEqual([
+      { kind: "same", text: "a" },
+      { kind: "removed", text: "b" },
+      { kind: "added", text: "x" },
+      { kind: "same", text: "c" },
+      { kind: "same", text: "d" },
+      { kind: "added", text: "e" },
+    ]);
+  });
+
+  it("handles an empty side", () => {
+    expect(lineDiff("", "a")).toEqual([
+      { kind: "removed", text: "" },
+      { kind: "added", text: "a" },
+    ]);
+  });
+});
@@ -0,0 +1,78 @@
+// ── Editor File I/O ─────────────────────────────────────────────────────────
+// Open and save tabs through one path so every tab remembers the disk version
+// it was loaded from, and every save is checked against it.
+
+import { useAppStore } from "@/lib/store";
+import { fs } from "@shared/lib/tauri";
+import { handleError } from "@shared/lib/errors";
+import { t } from "@/lib/i18n";
+import { toast } from "sonner";
+import type { FileVersion, WriteConflict } from "@shared/lib/types";
+import { replaceViewContent } from "./viewCache";
+
+export function isWriteConflict(e: unknown): e is WriteConflict {
+  return typeof e === "object" && e !== null && (e as { kind?: unknown }).kind === "writeConflict";
+}
+
+/** Open a file in a tab. Binaries and images are refused with a toast. */
+export async function openInEditor(path: string): Promise<boolean> {
+  const file = await fs.readFileVersioned(path);
+  if (file.kind !== "text") {
+    toast.info(t(file.kind === "image" ? "cannotOpenImage" : "cannotOpenBinary"), {
+      description: path,
+    });
+    return false;
+  }
+  const version = { mtime: file.mtime, hash: file.hash };
+  useAppStore.getState().openFile(path, file.content, version, file.encoding);
+  return true;
+}
+
+/**
+ * Save a tab's buffer. `expected` defaults to the tab's version; a newer disk
+ * copy parks the conflict in the store for the conflict dialog.
+ * Returns whether the file was written.
+ */
+export async function saveTab(path: string, expected?: FileVersion | null): Promise<boolean> {
+  const { editorTabs, markTabSaved, setSaveConflict } = useAppStore.getState();
+  const tab = editorTabs.find((tab) => tab.path === path);
+  if (!tab) return false;
+
+  const content = tab.content;
+  try {
+    const version = expected ?? tab.version;
+    const result = await fs.writeFile(path, content, null, version, tab.encoding);
+    if (result.formatted != null) replaceViewContent(path, result.formatted);
+    markTabSaved(path, result.formatted ?? content, { mtime: result.mtime, hash: result.hash });
+    const [problem] = result.diagnostics;
+    if (problem) {
+      toast.warning(t("formatFailed"), { description: problem.message });
+    } else {
+      toast.success(t("fileSaved"));
+    }
+    return true;
+  } catch (e) {
+    if (isWriteConflict(e)) {
+      setSaveConflict(e);
+    } else {
+      handleError(e, t("failedToSave"));
+    }
+    return false;
+  }
+}
+
+/** Drop the buffer and take the disk copy from a conflict. */
+export function reloadFromConflict(conflict: WriteConflict): void {
+  const { disk, path } = conflict;
+  if (!disk) return;
+  useAppStore.getState().markTabSaved(path, disk.content, { mtime: disk.mtime, hash: disk.hash });
+  replaceViewContent(path, disk.content);
+}
+
+/** Save over the disk copy from a conflict, still refusing anything newer. */
+export function overwriteFromConflict(conflict: WriteConflict): Promise<boolean> {
+  const { disk, path } = conflict;
+  // A deleted file has no version to check against
+  const expected = disk ? { mtime: disk.mtime, hash: disk.hash } : { mtime: null, hash: null };
+  return saveTab(path, expected);
+}
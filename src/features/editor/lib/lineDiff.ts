@@ -0,0 +1,67 @@
+// ── Line Diff ───────────────────────────────────────────────────────────────
+// Minimal LCS line diff for the save-conflict dialog. Quadratic, so inputs
+// past MAX_CELLS fall back to "all removed, all added".
+
+export interface DiffLine {
+  kind: "same" | "removed" | "added";
+  text: string;
+}
+
+const MAX_CELLS = 4_000_000;
+
+export function lineDiff(before: string, after: string): DiffLine[] {
+  const a = before.split("\n");
+  const b = after.split("\n");
+
+  // Trim the shared head and tail so the table only covers the changed middle
+  let head = 0;
+  while (head < a.length && head < b.length && a[head] === b[head]) head++;
+  let tail = 0;
+  while (
+    tail < a.length - head &&
+    tail < b.length - head &&
+    a[a.length - 1 - tail] === b[b.length - 1 - tail]
+  ) {
+    tail++;
+  }
+  const midA = a.slice(head, a.length - tail);
+  const midB = b.slice(head, b.length - tail);
+
+  const out: DiffLine[] = a.slice(0, head).map((text) => ({ kind: "same", text }));
+  const n = midA.length;
+  const m = midB.length;
+
+  if (n * m > MAX_CELLS) {
+    out.push(...midA.map((text): DiffLine => ({ kind: "removed", text })));
+    out.push(...midB.map((text): DiffLine => ({ kind: "added", text })));
+  } else {
+    // lcs[i][j] = LCS length of midA[i..] and midB[j..]
+    const lcs = Array.from({ length: n + 1 }, () => new Uint32Array(m + 1));
+    for (let i = n - 1; i >= 0; i--) {
+      for (let j = m - 1; j >= 0; j--) {
+        lcs[i]![j] =
+          midA[i] === midB[j]
+            ? lcs[i + 1]![j + 1]! + 1
+            : Math.max(lcs[i + 1]![j]!, lcs[i]![j + 1]!);
+      }
+    }
+    let i = 0;
+    let j = 0;
+    while (i < n || j < m) {
+      if (i < n && j < m && midA[i] === midB[j]) {
+        out.push({ kind: "same", text: midA[i]! });
+        i++;
+        j++;
+      } else if (i < n && (j === m || lcs[i + 1]![j]! >= lcs[i]![j + 1]!)) {
+        out.push({ kind: "removed", text: midA[i]! });
+        i++;
+      } else {
+        out.push({ kind: "added", text: midB[j]! });
+        j++;
+      }
+    }
+  }
+
+  out.push(...a.slice(a.length - tail).map((text): DiffLine => ({ kind: "same", text })));
+  return out;
+}
@@ -25,6 +25,13 @@ export function hasViewEntry(path: string): boolean {
   return viewCache.has(path);
 }
 
+/** Replace a cached view's document, e.g. with formatted content after a save. */
+export function replaceViewContent(path: string, content: string): void {
+  const view = viewCache.get(path)?.view;
+  if (!view || view.state.doc.toString() === content) return;
+  view.dispatch({ changes: { from: 0, to: view.state.doc.length, insert: content } });
+}
+
 export function destroyEditorView(path: string): void {
   const entry = viewCache.get(path);
   if (entry) {
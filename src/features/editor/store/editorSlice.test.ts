@@ -73,6 +73,29 @@ describe("editorSlice", () => {
     expect(tab.content === tab.savedContent).toBe(true);
   });
 
+  it("tracks the disk version through saves", () => {
+    store.getState().openFile("/src/file.ts", "original", { mtime: 1, hash: "a" });
+    expect(firstTab(store).version).toEqual({ mtime: 1, hash: "a" });
+    store.getState().markTabSaved("/src/file.ts", "modified", { mtime: 2, hash: "b" });
+    expect(firstTab(store).version).toEqual({ mtime: 2, hash: "b" });
+    // Saves that report no version keep the last known one
+    store.getState().markTabSaved("/src/file.ts", "again");
+    expect(firstTab(store).version).toEqual({ mtime: 2, hash: "b" });
+  });
+
+  it("opens without a version when none is given", () => {
+    store.getState().openFile("/src/file.ts", "x");
+    expect(firstTab(store).version).toBeNull();
+    expect(firstTab(store).encoding).toBeNull();
+  });
+
+  it("keeps the detected encoding for saving", () => {
+    const latin1 = { charset: "windows-1252", bom: false } as const;
+    store.getState().openFile("/src/file.txt", "café", null, latin1);
+    store.getState().markTabSaved("/src/file.txt", "crème", { mtime: 2, hash: "b" });
+    expect(firstTab(store).encoding).toEqual(latin1);
+  });
+
   it("closeEditorTab returns false when dirty (blocks close)", () => {
     store.getState().openFile("/src/file.ts", "original");
     store.getState().updateTabContent("/src/file.ts", "modified");
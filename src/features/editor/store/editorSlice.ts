@@ -4,6 +4,7 @@
 
 import type { StateCreator } from "zustand";
 import { trackEvent } from "@shared/lib/analytics";
+import type { FileVersion, TextEncoding, WriteConflict } from "@shared/lib/types";
 
 // -- Types -------
 export interface CursorInfo {
@@ -18,6 +19,8 @@ export interface EditorTab {
   savedContent: string; // content on disk at open/last save
   content: string; // live buffer content (synced from CM6)
   language: string; // file extension (for language loading)
+  version: FileVersion | null; // disk version savedContent came from (checked on save)
+  encoding: TextEncoding | null; // charset + BOM detected on read (kept on save)
   scrollPos?: { top: number; left: number };
 }
 
@@ -26,18 +29,25 @@ export interface EditorSlice {
   editorTabs: EditorTab[];
   activeEditorTab: string | null;
   cursorInfo: CursorInfo | null;
+  saveConflict: WriteConflict | null; // rejected save awaiting reload/overwrite
 
   // Actions
-  openFile: (path: string, content: string) => void;
+  openFile: (
+    path: string,
+    content: string,
+    version?: FileVersion | null,
+    encoding?: TextEncoding | null,
+  ) => void;
   closeEditorTab: (path: string) => boolean; // false if dirty (caller handles confirm)
   forceCloseEditorTab: (path: string) => void;
   setActiveEditorTab: (path: string) => void;
   updateTabContent: (path: string, content: string) => void;
-  markTabSaved: (path: string, content: string) => void;
+  markTabSaved: (path: string, content: string, version?: FileVersion | null) => void;
   updateTabScroll: (path: string, pos: { top: number; left: number }) => void;
   closeAllEditorTabs: () => void;
   reorderEditorTabs: (fromIndex: number, toIndex: number) => void;
   setCursorInfo: (info: CursorInfo | null) => void;
+  setSaveConflict: (conflict: WriteConflict | null) => void;
 
   // Backward compat (delegates to openFile)
   setOpenFile: (path: string | null, content?: string | null) => void;
@@ -62,12 +72,13 @@ export const createEditorSlice: StateCreator<EditorSlice, [], [], EditorSlice> =
   editorTabs: [],
   activeEditorTab: null,
   cursorInfo: null,
+  saveConflict: null,
 
   // Backward compat getters (computed from tabs)
   openFilePath: null,
   openFileContent: null,
 
-  openFile: (path, content) => {
+  openFile: (path, content, version = null, encoding = null) => {
     const { editorTabs } = get();
     const existing = editorTabs.find((t) => t.path === path);
 
@@ -86,6 +97,8 @@ export const createEditorSlice: StateCreator<EditorSlice, [], [], EditorSlice> =
         savedContent: content,
         content,
         language: extractLanguage(path),
+        version,
+        encoding,
       };
       set({
         editorTabs: [...editorTabs, tab],
@@ -156,10 +169,12 @@ export const createEditorSlice: StateCreator<EditorSlice, [], [], EditorSlice> =
     });
   },
 
-  markTabSaved: (path, content) => {
+  markTabSaved: (path, content, version) => {
     set({
       editorTabs: get().editorTabs.map((t) =>
-        t.path === path ? { ...t, savedContent: content, content } : t,
+        t.path === path
+          ? { ...t, savedContent: content, content, version: version ?? t.version }
+          : t,
       ),
     });
   },
@@ -172,6 +187,8 @@ export const createEditorSlice: StateCreator<EditorSlice, [], [], EditorSlice> =
 
   setCursorInfo: (info) => set({ cursorInfo: info }),
 
+  setSaveConflict: (conflict) => set({ saveConflict: conflict }),
+
   closeAllEditorTabs: () => {
     set({
       editorTabs: [],
@@ -1,6 +1,6 @@
 import { useState } from "react";
 import { toast } from "sonner";
-import { fs } from "@shared/lib/tauri";
+import { openInEditor } from "@features/editor/lib/fileIO";
 import { ChevronRight, Copy, FolderOpen, TerminalSquare } from "lucide-react";
 import { Loader } from "@/components/Loader";
 import { cn } from "@/lib/utils";
@@ -27,7 +27,6 @@ export function TreeItem({ entry, depth, onExpand }: TreeItemProps) {
   const [children, setChildren] = useState<FileEntry[]>(entry.children || []);
   const [loaded, setLoaded] = useState(false);
   const [loading, setLoading] = useState(false);
-  const setOpenFile = useAppStore((s) => s.setOpenFile);
   const openFilePath = useAppStore((s) => s.openFilePath);
   const pl = 12 + depth * 14;
 
@@ -48,8 +47,7 @@ export function TreeItem({ entry, depth, onExpand }: TreeItemProps) {
       setIsOpen(!isOpen);
     } else {
       try {
-        const content = await fs.readFile(entry.path);
-        setOpenFile(entry.path, content);
+        await openInEditor(entry.path);
       } catch (e) {
         toast.error(t("failedToReadFile"), { description: String(e) });
       }
@@ -1,6 +1,5 @@
 import { useEffect, useState } from "react";
-import { homeDir } from "@tauri-apps/api/path";
-import { fs } from "@shared/lib/tauri";
+import { ssh } from "@shared/lib/tauri";
 import { t } from "@/lib/i18n";
 import { Key } from "lucide-react";
 
@@ -16,22 +15,8 @@ export function SshKeyBrowser({ onSelect }: SshKeyBrowserProps) {
   useEffect(() => {
     (async () => {
       try {
-        const home = await homeDir();
-        const sshDir = `${home}.ssh`;
-
-        const entries = await fs.readDir(sshDir);
-        const keyFiles = entries
-          .filter(
-            (e) =>
-              !e.isDir &&
-              !e.name.endsWith(".pub") &&
-              !e.name.endsWith(".known_hosts") &&
-              !e.name.endsWith(".known_hosts.old") &&
-              e.name !== "config" &&
-              e.name !== "authorized_keys",
-          )
-          .map((e) => e.path);
-        setKeys(keyFiles);
+        // Listed by the backend: the webview gets no filesystem access to ~/.ssh
+        setKeys(await ssh.listKeys());
       } catch {
         setKeys([]);
       } finally {
@@ -32,6 +32,7 @@ export function QuickLaunch({ onSpawnTab }: QuickLaunchProps) {
   const defaultCli = useAppStore((s) => s.defaultCli);
   const setDefaultCli = useAppStore((s) => s.setDefaultCli);
   const projectId = useAppStore((s) => s.projectId);
+  const projectPath = useAppStore((s) => s.projectPath);
   const launchConfigs = useAppStore((s) => s.launchConfigs);
   const removeLaunchConfig = useAppStore((s) => s.removeLaunchConfig);
   const setLastLaunchConfigId = useAppStore((s) => s.setLastLaunchConfigId);
@@ -48,11 +49,12 @@ export function QuickLaunch({ onSpawnTab }: QuickLaunchProps) {
       setRecentCommands([]);
       return;
     }
+    // Quick launch tabs open in the project root
     db.history
-      .recent(projectId, 5)
+      .recent(projectId, 5, projectPath)
       .then(setRecentCommands)
       .catch(() => setRecentCommands([]));
-  }, [projectId]);
+  }, [projectId, projectPath]);
 
   // Load launch configs when project changes
   useEffect(() => {
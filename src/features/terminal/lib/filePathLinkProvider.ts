@@ -3,7 +3,7 @@
 // and makes them clickable → opens in file viewer.
 
 import type { ILinkProvider, ILink, IBufferRange, Terminal } from "@xterm/xterm";
-import { fs } from "@shared/lib/tauri";
+import { openInEditor } from "@features/editor/lib/fileIO";
 import { useAppStore } from "@/lib/store";
 
 const VALID_EXTENSIONS = new Set([
@@ -127,13 +127,9 @@ export function createFilePathLinkProvider(
               ? cleanPath
               : `${projectPath}/${cleanPath.replace(/^\.\//, "")}`;
 
-            fs.readFile(fullPath)
-              .then((content) => {
-                useAppStore.getState().setOpenFile(fullPath, content);
-              })
-              .catch(() => {
-                // File not found or unreadable — silently ignore
-              });
+            openInEditor(fullPath).catch(() => {
+              // File not found or unreadable — silently ignore
+            });
           },
         };
       });
@@ -1,10 +1,8 @@
 import { useHotkeys } from "react-hotkeys-hook";
 import { useAppStore } from "@/lib/store";
-import { fs } from "@shared/lib/tauri";
-import { handleError } from "@shared/lib/errors";
 import { destroyEditorView } from "@features/editor/lib/viewCache";
+import { saveTab } from "@features/editor/lib/fileIO";
 import { t } from "@/lib/i18n";
-import { toast } from "sonner";
 import type { LaunchConfigPayload } from "@shared/lib/types";
 
 interface ShortcutActions {
@@ -29,17 +27,11 @@ export function useKeyboardShortcuts({ spawnTab, closeTab, reopenTab }: Shortcut
     "mod+s",
     (e) => {
       e.preventDefault();
-      const { activeEditorTab, editorTabs, markTabSaved } = useAppStore.getState();
+      const { activeEditorTab, editorTabs } = useAppStore.getState();
       if (!activeEditorTab) return;
       const tab = editorTabs.find((t) => t.path === activeEditorTab);
       if (!tab || tab.content === tab.savedContent) return;
-      const contentToSave = tab.content;
-      fs.writeFile(tab.path, contentToSave)
-        .then(() => {
-          markTabSaved(tab.path, contentToSave);
-          toast.success(t("fileSaved"));
-        })
-        .catch((err) => handleError(err, t("failedToSave")));
+      void saveTab(tab.path);
     },
     { enableOnFormTags: true },
   );
@@ -10,7 +10,12 @@ import * as Sentry from "@sentry/browser";
  * @param context - Optional prefix (e.g., "Terminal spawn", "File read")
  */
 export function handleError(error: unknown, context?: string): void {
-  const message = error instanceof Error ? error.message : String(error);
+  const message =
+    error instanceof Error
+      ? error.message
+      : typeof error === "object" && error !== null && "message" in error
+        ? String(error.message) // structured backend errors (e.g. write conflicts)
+        : String(error);
   const prefix = context ? `${context}: ` : "";
 
   console.error(`[Kodiq] ${prefix}${message}`);
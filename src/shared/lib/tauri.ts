@@ -2,7 +2,7 @@
 // All Rust↔JS communication goes through this module.
 // No raw invoke() calls elsewhere in the codebase.
 
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
 import { listen as tauriListen } from "@tauri-apps/api/event";
 import type { EventCallback, UnlistenFn } from "@tauri-apps/api/event";
 
@@ -18,7 +18,36 @@ export function listen<T>(event: string, handler: EventCallback<T>): Promise<Unl
   return tauriListen(event, handler);
 }
 import type {
+  FileContent,
+  FileMatch,
+  DirPage,
+  ListOptions,
+  TrashedItem,
+  FileHistoryEntry,
+  ContentSearchOptions,
+  ReplacePreview,
+  ReplaceSelection,
+  ReplaceResult,
   FileEntry,
+  FileVersion,
+  DocumentSymbol,
+  WorkspaceSymbol,
+  FormatOutcome,
+  ReadOptions,
+  TextEncoding,
+  WriteResult,
+  LspEvent,
+  LspServerInfo,
+  LspServerSpec,
+  Diagnostic,
+  TestFramework,
+  TestReport,
+  TestScope,
+  Task,
+  TaskMode,
+  TaskJob,
+  TaskStarted,
+  RecentTask,
   GitInfo,
   ProjectStats,
   CliTool,
@@ -27,11 +56,22 @@ import type {
   NewSession,
   HistoryEntry,
   NewHistoryEntry,
+  CommandSuggestion,
+  SearchFilters,
+  SearchHit,
   Snippet,
   NewSnippet,
+  UpdateSnippet,
+  TagCount,
+  Placeholder,
+  TemplateContext,
+  LibraryEntry,
   LaunchConfig,
   NewLaunchConfig,
   UpdateLaunchConfig,
+  LaunchIssue,
+  LaunchRun,
+  WorkspaceView,
   PreviewBounds,
   ServerInfo,
   ServerLogEntry,
@@ -46,6 +86,8 @@ import type {
   ActiveForward,
   ChatMessage,
   NewChatMessage,
+  ChatExportFormat,
+  ChatImportResult,
 } from "./types";
 
 // -- Helpers ─────────────────────────────────────────────
@@ -111,12 +153,243 @@ export const terminal = {
 export const fs = {
   readDir: (path: string, connectionId?: string | null) =>
     invoke<FileEntry[]>("read_dir", { path, connectionId: connectionId ?? null }),
+  /** One page of a large directory; pass `nextCursor` back until it is null. */
+  readDirPage: (
+    path: string,
+    cursor?: string | null,
+    limit?: number | null,
+    connectionId?: string | null,
+    options?: ListOptions | null,
+  ) =>
+    invoke<DirPage>("read_dir_page", {
+      path,
+      connectionId: connectionId ?? null,
+      options: options ?? null,
+      cursor: cursor ?? null,
+      limit: limit ?? null,
+    }),
   readFile: (path: string, connectionId?: string | null) =>
-    invoke<string>("read_file", { path, connectionId: connectionId ?? null }),
-  writeFile: (path: string, content: string, connectionId?: string | null) =>
-    invoke<void>("write_file", { path, content, connectionId: connectionId ?? null }),
-  startWatching: (path: string) => invoke<void>("start_watching", { path }),
-  stopWatching: () => invoke<void>("stop_watching"),
+    invoke<FileContent>("read_file", { path, connectionId: connectionId ?? null }).then(
+      (file) => {
+        if (file.kind !== "text") throw new Error(`Cannot open ${file.kind} file as text`);
+        return file.content;
+      },
+    ),
+  readFileVersioned: (
+    path: string,
+    connectionId?: string | null,
+    options?: ReadOptions | null,
+  ) =>
+    invoke<FileContent>("read_file", {
+      path,
+      connectionId: connectionId ?? null,
+      options: options ?? null,
+    }),
+  /**
+   * Pass `expected` from `readFileVersioned` to reject saves over newer disk changes,
+   * and `encoding` to keep the file's charset and BOM. `format` overrides the
+   * project's format-on-save setting (local files only).
+   */
+  writeFile: (
+    path: string,
+    content: string,
+    connectionId?: string | null,
+    expected?: FileVersion | null,
+    encoding?: TextEncoding | null,
+    format?: boolean | null,
+  ) =>
+    invoke<WriteResult>("write_file", {
+      path,
+      content,
+      encoding: encoding ?? null,
+      expected: expected ?? null,
+      connectionId: connectionId ?? null,
+      format: format ?? null,
+    }),
+  // File operations stay inside `root`; each returns the resulting path(s).
+  createFile: (root: string, path: string, connectionId?: string | null) =>
+    invoke<string>("fs_create_file", { root, path, connectionId: connectionId ?? null }),
+  createDir: (root: string, path: string, connectionId?: string | null) =>
+    invoke<string>("fs_create_dir", { root, path, connectionId: connectionId ?? null }),
+  rename: (root: string, from: string, to: string, connectionId?: string | null) =>
+    invoke<string>("fs_rename", { root, from, to, connectionId: connectionId ?? null }),
+  move: (root: string, paths: string[], targetDir: string, connectionId?: string | null) =>
+    invoke<string[]>("fs_move", { root, paths, targetDir, connectionId: connectionId ?? null }),
+  /** Without `to`, duplicates the entry next to itself as `name copy.ext`. */
+  copy: (root: string, from: string, to?: string | null, connectionId?: string | null) =>
+    invoke<string>("fs_copy", { root, from, to: to ?? null, connectionId: connectionId ?? null }),
+  /** Local entries go to the trash unless `permanent`; remote ones are always deleted. */
+  delete: (root: string, paths: string[], permanent?: boolean, connectionId?: string | null) =>
+    invoke<TrashedItem[]>("fs_delete", {
+      root,
+      paths,
+      permanent: permanent ?? null,
+      connectionId: connectionId ?? null,
+    }),
+  trashList: (root: string) => invoke<TrashedItem[]>("fs_trash_list", { root }),
+  trashRestore: (root: string, ids: string[]) =>
+    invoke<string[]>("fs_trash_restore", { root, ids }),
+  /** Local file history, newest first. */
+  historyList: (path: string) => invoke<FileHistoryEntry[]>("fs_history_list", { path }),
+  /** Unified diff between two versions; omit `to` to compare against disk. */
+  historyDiff: (path: string, from: string, to?: string | null) =>
+    invoke<string>("fs_history_diff", { path, from, to: to ?? null }),
+  /** Write a version back; returns the new disk version for open editors. */
+  historyRestore: (path: string, hash: string) =>
+    invoke<FileVersion>("fs_history_restore", { path, hash }),
+  /** Outline of a file; pass `content` to parse an unsaved buffer. */
+  documentSymbols: (path: string, content?: string | null) =>
+    invoke<DocumentSymbol[]>("fs_document_symbols", { path, content: content ?? null }),
+  /** Fuzzy symbol search across watched projects, or just `root`. */
+  workspaceSymbols: (query: string, root?: string | null, limit?: number) =>
+    invoke<WorkspaceSymbol[]>("fs_workspace_symbols", {
+      query,
+      root: root ?? null,
+      limit: limit ?? null,
+    }),
+  /** Quick-open: fuzzy-find files in the watched project at `root`. */
+  findFiles: (root: string, query: string, limit?: number) =>
+    invoke<FileMatch[]>("fs_find_files", { root, query, limit: limit ?? null }),
+  /**
+   * Project-wide content search. Returns a search id at once; results stream as
+   * `search-result` events, one per file, followed by `search-done`.
+   */
+  searchContent: (
+    root: string,
+    query: string,
+    options?: ContentSearchOptions | null,
+    connectionId?: string | null,
+  ) =>
+    invoke<string>("fs_search_content", {
+      root,
+      query,
+      options: options ?? null,
+      connectionId: connectionId ?? null,
+    }),
+  cancelSearch: (searchId: string) => invoke<void>("fs_cancel_search", { searchId }),
+  /** What replacing `query` would change, per file, without writing. */
+  replacePreview: (
+    root: string,
+    query: string,
+    replacement: string,
+    options?: ContentSearchOptions | null,
+    connectionId?: string | null,
+  ) =>
+    invoke<ReplacePreview[]>("fs_replace_preview", {
+      root,
+      query,
+      replacement,
+      options: options ?? null,
+      connectionId: connectionId ?? null,
+    }),
+  /** Fails with a conflict, writing nothing, if a file changed since its preview. */
+  replaceApply: (
+    query: string,
+    replacement: string,
+    files: ReplaceSelection[],
+    options?: ContentSearchOptions | null,
+    connectionId?: string | null,
+  ) =>
+    invoke<ReplaceResult>("fs_replace_apply", {
+      query,
+      replacement,
+      options: options ?? null,
+      files,
+      connectionId: connectionId ?? null,
+    }),
+  /** Undo a replace batch, the latest when `batchId` is omitted. */
+  replaceUndo: (batchId?: string | null) =>
+    invoke<string[]>("fs_replace_undo", { batchId: batchId ?? null }),
+  /** Format `content` (or the file on disk) without saving. */
+  format: (path: string, content?: string | null) =>
+    invoke<FormatOutcome>("fs_format", { path, content: content ?? null }),
+  /**
+   * Ask the user for a file or folder in a native dialog and allow filesystem
+   * access under it for this session. Resolves to `null` when cancelled.
+   */
+  grantPath: (directory: boolean, title?: string | null) =>
+    invoke<string | null>("fs_grant_path", { directory, title: title ?? null }),
+  revokePath: (path: string) => invoke<void>("fs_revoke_path", { path }),
+  startWatching: (path: string, connectionId?: string | null) =>
+    invoke<void>("start_watching", { path, connectionId: connectionId ?? null }),
+  /** Stops one root, or every watched root when `path` is omitted. */
+  stopWatching: (path?: string | null, connectionId?: string | null) =>
+    invoke<void>("stop_watching", { path: path ?? null, connectionId: connectionId ?? null }),
+  /** Expanded explorer directories; remote roots without inotify poll these. */
+  setWatchedDirs: (path: string, dirs: string[], connectionId?: string | null) =>
+    invoke<void>("set_watched_dirs", { path, dirs, connectionId: connectionId ?? null }),
+};
+
+// ── LSP ──────────────────────────────────────────────────
+export const lsp = {
+  registry: (root: string) => invoke<LspServerSpec[]>("lsp_registry", { root }),
+  /** Starts (or reattaches to) the project's server for `language`. */
+  start: (root: string, language: string, onEvent: (event: LspEvent) => void) => {
+    const channel = new Channel<LspEvent>();
+    channel.onmessage = onEvent;
+    return invoke<LspServerInfo>("lsp_start", { root, language, channel });
+  },
+  /** Send a JSON-RPC message; responses arrive as `message` events. */
+  send: (id: string, message: Record<string, unknown>) =>
+    invoke<void>("lsp_send", { id, message }),
+  stop: (id: string) => invoke<void>("lsp_stop", { id }),
+  restart: (id: string) => invoke<LspServerInfo>("lsp_restart", { id }),
+  list: () => invoke<LspServerInfo[]>("lsp_list"),
+  /** Saves sync through `fs.writeFile`; only open and unsaved edits go here. */
+  openDocument: (path: string, content: string) =>
+    invoke<void>("lsp_open_document", { path, content }),
+  changeDocument: (path: string, content: string) =>
+    invoke<void>("lsp_change_document", { path, content }),
+  closeDocument: (path: string) => invoke<void>("lsp_close_document", { path }),
+};
+
+// ── Diagnostics ──────────────────────────────────────────
+export const diagnostics = {
+  list: (root: string) => invoke<Diagnostic[]>("diagnostics_list", { root }),
+  /** Dismiss a project's problems, or only those from one terminal or server. */
+  clear: (root: string, source?: string | null) =>
+    invoke<void>("diagnostics_clear", { root, source: source ?? null }),
+};
+
+// ── Tests ────────────────────────────────────────────────
+export const tests = {
+  detect: (root: string) => invoke<TestFramework[]>("tests_detect", { root }),
+  /** Resolves when the run ends; output streams as `tests-output` meanwhile. */
+  run: (root: string, scope?: TestScope | null, framework?: TestFramework | null) =>
+    invoke<TestReport>("tests_run", { root, scope: scope ?? null, framework: framework ?? null }),
+  cancel: (root: string) => invoke<boolean>("tests_cancel", { root }),
+  lastResults: (root: string) => invoke<TestReport[]>("tests_last_results", { root }),
+  clearResults: (root: string) => invoke<number>("tests_clear_results", { root }),
+};
+
+// ── Tasks ────────────────────────────────────────────────
+export const tasks = {
+  list: (root: string) => invoke<Task[]>("task_list", { root }),
+  /** Headless jobs stream `task-output` and end with `task-finished`. */
+  run: (root: string, taskId: string, mode?: TaskMode | null) =>
+    invoke<TaskStarted>("task_run", { root, taskId, mode: mode ?? null }),
+  stop: (jobId: string) => invoke<boolean>("task_stop", { jobId }),
+  jobs: (root: string) => invoke<TaskJob[]>("task_jobs", { root }),
+  recent: (root: string) => invoke<RecentTask[]>("task_recent", { root }),
+  clearRecent: (root: string) => invoke<number>("task_clear_recent", { root }),
+};
+
+// ── Compound launches ────────────────────────────────────
+export const launch = {
+  /** Empty when the compound config JSON is valid. */
+  validate: (config: string) => invoke<LaunchIssue[]>("launch_validate", { config }),
+  /** `root` is required for `repo:` configs from the workspace file. */
+  start: (configId: string, root?: string) =>
+    invoke<LaunchRun>("launch_start", { configId, root: root ?? null }),
+  stop: (id: string) => invoke<LaunchRun>("launch_stop", { id }),
+  list: () => invoke<LaunchRun[]>("launch_list"),
+};
+
+// ── Workspace config ─────────────────────────────────────
+export const workspace = {
+  load: (root: string) => invoke<WorkspaceView>("workspace_load", { root }),
+  /** Write this project's launch configs and settings to `.kodiq/workspace.json`. */
+  exportLocal: (root: string) => invoke<WorkspaceView>("workspace_export", { root }),
 };
 
 // ── Git ──────────────────────────────────────────────────
@@ -231,20 +504,51 @@ export const db = {
   history: {
     search: (query: string, projectId?: string | null) =>
       invoke<HistoryEntry[]>("db_search_history", { query, projectId: projectId ?? null }),
-    recent: (projectId?: string | null, limit?: number) =>
+    /** Ranked by frecency; runs from `cwd` rank higher. */
+    recent: (projectId?: string | null, limit?: number, cwd?: string | null) =>
       invoke<HistoryEntry[]>("db_recent_history", {
         projectId: projectId ?? null,
+        cwd: cwd ?? null,
         limit: limit ?? 20,
       }),
     add: (entry: NewHistoryEntry) => invoke<void>("db_add_history", { entry }),
+    suggest: (prefix: string, projectId?: string | null, cwd?: string | null, limit?: number) =>
+      invoke<CommandSuggestion[]>("db_suggest_commands", {
+        prefix,
+        projectId: projectId ?? null,
+        cwd: cwd ?? null,
+        limit: limit ?? 5,
+      }),
   },
 
+  // ── Database — Search ────────────────────────────────────
+  /** Full-text search across history, chat, snippets and transcripts. */
+  search: (query: string, filters?: SearchFilters) =>
+    invoke<SearchHit[]>("db_search", { query, filters: filters ?? null }),
+
   // ── Database — Snippets ──────────────────────────────────
   snippets: {
-    list: (cliName?: string | null) =>
-      invoke<Snippet[]>("db_list_snippets", { cliName: cliName ?? null }),
+    /** Global snippets plus the project's; every snippet without a project. */
+    list: (cliName?: string | null, projectId?: string | null, tag?: string | null) =>
+      invoke<Snippet[]>("db_list_snippets", {
+        cliName: cliName ?? null,
+        projectId: projectId ?? null,
+        tag: tag ?? null,
+      }),
+    get: (id: string) => invoke<Snippet>("db_get_snippet", { id }),
     create: (snippet: NewSnippet) => invoke<Snippet>("db_create_snippet", { snippet }),
+    update: (id: string, patch: UpdateSnippet) =>
+      invoke<Snippet>("db_update_snippet", { id, patch }),
+    delete: (id: string) => invoke<void>("db_delete_snippet", { id }),
     use: (id: string) => invoke<Snippet>("db_use_snippet", { id }),
+    tags: (projectId?: string | null) =>
+      invoke<TagCount[]>("db_list_snippet_tags", { projectId: projectId ?? null }),
+    /** JSON snippet pack for sharing; `importPack` reads it back. */
+    exportPack: (ids: string[], name?: string | null) =>
+      invoke<string>("db_export_snippet_pack", { ids, name: name ?? null }),
+    /** Returns the number of snippets added; duplicates are skipped. */
+    importPack: (data: string, projectId?: string | null) =>
+      invoke<number>("db_import_snippet_pack", { data, projectId: projectId ?? null }),
   },
 
   // ── Database — Launch Configs ─────────────────────────────
@@ -269,6 +573,37 @@ export const chat = {
   saveMessage: (message: NewChatMessage) =>
     invoke<ChatMessage>("db_save_chat_message", { message }),
   clearHistory: (projectId: string) => invoke<number>("db_clear_chat", { projectId }),
+  /** Markdown for PR descriptions, or JSON that `importChat` reads back. */
+  exportChat: (projectId: string, format: ChatExportFormat) =>
+    invoke<string>("db_export_chat", { projectId, format }),
+  exportTranscript: (conversationId: string, format: ChatExportFormat) =>
+    invoke<string>("db_export_transcript", { conversationId, format }),
+  importChat: (projectId: string, data: string) =>
+    invoke<ChatImportResult>("db_import_chat", { projectId, data }),
+};
+
+// ── Prompts ─────────────────────────────────────────────
+export const prompts = {
+  placeholders: (content: string) => invoke<Placeholder[]>("prompt_placeholders", { content }),
+  /** Fill `{{...}}` placeholders; branch and diff are resolved on the backend. */
+  render: (content: string, context: TemplateContext) =>
+    invoke<string>("prompt_render", { content, context }),
+  /** DB snippets followed by the project's `.kodiq/prompts` files. */
+  library: (
+    projectPath?: string | null,
+    projectId?: string | null,
+    cliName?: string | null,
+    tag?: string | null,
+  ) =>
+    invoke<LibraryEntry[]>("prompt_library_list", {
+      projectPath: projectPath ?? null,
+      projectId: projectId ?? null,
+      cliName: cliName ?? null,
+      tag: tag ?? null,
+    }),
+  /** Move a snippet into the repo library; `keep` leaves the DB copy. */
+  promote: (id: string, projectPath: string, keep?: boolean) =>
+    invoke<LibraryEntry>("prompt_promote_snippet", { id, projectPath, keep: keep ?? null }),
 };
 
 // ── Academy — WebView ───────────────────────────────────
@@ -292,6 +627,8 @@ export const ssh = {
     invoke<boolean>("ssh_test_connection", { config, password: password ?? null }),
   connectionStatus: (connectionId: string) =>
     invoke<SshActiveConnection>("ssh_connection_status", { connectionId }),
+  /** Private key files in `~/.ssh`. */
+  listKeys: () => invoke<string[]>("ssh_list_keys"),
 
   // Terminal (remote PTY)
   spawnTerminal: (connectionId: string, cols?: number, rows?: number) =>
@@ -48,9 +48,452 @@ export interface FileEntry {
   name: string;
   path: string;
   isDir: boolean;
+  hidden?: boolean;
+  ignored?: boolean;
+  size?: number | null;
+  modified?: number | null;
+  mode?: number | null;
+  executable?: boolean;
+  isSymlink?: boolean;
+  symlinkTarget?: string | null;
+  brokenLink?: boolean;
+  gitStatus?: string | null;
+  gitKind?: string | null;
+  kind?: "text" | "binary" | "image" | null;
   children?: FileEntry[];
 }
 
+export interface FileVersion {
+  mtime: number | null;
+  hash: string | null;
+}
+
+export type SymbolKind =
+  | "function"
+  | "method"
+  | "class"
+  | "interface"
+  | "struct"
+  | "enum"
+  | "trait"
+  | "impl"
+  | "module"
+  | "type"
+  | "constant";
+
+/** Zero-based; `column` counts chars. */
+export interface SymbolRange {
+  start: { line: number; column: number };
+  end: { line: number; column: number };
+}
+
+export interface DocumentSymbol {
+  name: string;
+  kind: SymbolKind;
+  range: SymbolRange;
+  selectionRange: SymbolRange;
+  container: string | null;
+  children: DocumentSymbol[];
+}
+
+export interface WorkspaceSymbol {
+  name: string;
+  kind: SymbolKind;
+  path: string;
+  relativePath: string;
+  range: SymbolRange;
+  selectionRange: SymbolRange;
+  container: string | null;
+  score: number;
+  /** Char indices into `name` to highlight. */
+  positions: number[];
+}
+
+/** Quick-open hit from the project file index. */
+export interface FileMatch {
+  path: string;
+  relativePath: string;
+  score: number;
+  /** Char indices into `relativePath` to highlight. */
+  positions: number[];
+}
+
+export interface ContentSearchOptions {
+  /** Treat the query as a regular expression (Rust syntax) instead of a literal. */
+  regex?: boolean;
+  caseSensitive?: boolean;
+  wholeWord?: boolean;
+  include?: string[];
+  exclude?: string[];
+  /** Also search files excluded by `.gitignore` / `.ignore`. */
+  includeIgnored?: boolean;
+  contextLines?: number;
+  maxResults?: number | null;
+}
+
+export interface ContextLine {
+  line: number;
+  text: string;
+}
+
+export interface LineMatch {
+  /** 1-based line number and char column of the first match. */
+  line: number;
+  column: number;
+  text: string;
+  /** Char offsets into `text`. */
+  submatches: { start: number; end: number }[];
+  before: ContextLine[];
+  after: ContextLine[];
+}
+
+/** Payload of `search-result`: every match in one file. */
+export interface SearchResultEvent {
+  searchId: string;
+  path: string;
+  relativePath: string;
+  matches: LineMatch[];
+}
+
+/** Payload of `search-done`. */
+export interface SearchSummary {
+  searchId: string;
+  files: number;
+  matches: number;
+  truncated: boolean;
+  cancelled: boolean;
+  error: string | null;
+}
+
+export interface ReplaceLineChange {
+  line: number;
+  before: string;
+  after: string;
+}
+
+/** What a replace would change in one file. */
+export interface ReplacePreview {
+  path: string;
+  relativePath: string;
+  /** Hash of the previewed bytes; pass back to `replaceApply`. */
+  hash: string;
+  replacements: number;
+  changes: ReplaceLineChange[];
+}
+
+export interface ReplaceSelection {
+  path: string;
+  hash: string;
+}
+
+export interface ReplaceResult {
+  batchId: string;
+  files: string[];
+  replacements: number;
+}
+
+/** A formatter failure from the save pipeline; located when the tool names a line. */
+export interface FormatDiagnostic {
+  formatter: string;
+  severity: "error" | "warning";
+  message: string;
+  line: number | null;
+  column: number | null;
+}
+
+export interface FormatOutcome {
+  content: string;
+  formatter: string | null;
+  changed: boolean;
+  diagnostics: FormatDiagnostic[];
+}
+
+/** `write_file` result; `formatted` is set when formatting changed the content. */
+export interface WriteResult extends FileVersion {
+  formatted: string | null;
+  diagnostics: FormatDiagnostic[];
+}
+
+// ── LSP ──────────────────────────────────────────────────
+
+/** A language server from the registry (built-in or project `lsp.servers`). */
+export interface LspServerSpec {
+  language: string;
+  command: string;
+  args: string[];
+  extensions: string[];
+  watchFiles: string[];
+  initializationOptions: unknown;
+  env: Record<string, string>;
+}
+
+export type LspServerStatus = "starting" | "running" | "restarting" | "stopped" | "failed";
+
+export interface LspServerInfo {
+  id: string;
+  language: string;
+  root: string;
+  status: LspServerStatus;
+  restarts: number;
+  error: string | null;
+  /** Server capabilities from `initialize`, once running. */
+  capabilities: Record<string, unknown> | null;
+}
+
+/** Delivered on the channel passed to `lsp.start`. */
+export type LspEvent =
+  | { type: "message"; message: Record<string, unknown> }
+  | { type: "status"; status: LspServerStatus; restarts: number; error: string | null }
+  | { type: "log"; line: string };
+
+// ── Diagnostics ──────────────────────────────────────────
+
+/** A problem matched in terminal or preview server output. */
+export interface Diagnostic {
+  /** Terminal or preview server id. */
+  source: string;
+  matcher: string;
+  /** The path as printed; `path` is resolved against the cwd. */
+  file: string;
+  path: string;
+  line: number;
+  column: number | null;
+  severity: "error" | "warning" | "info";
+  code: string | null;
+  message: string;
+}
+
+/** Payload of the `diagnostics-changed` event. */
+export interface DiagnosticsChanged {
+  root: string;
+  diagnostics: Diagnostic[];
+}
+
+// ── Tests ────────────────────────────────────────────────
+
+export type TestFramework = "cargo" | "vitest" | "jest" | "pytest" | "go";
+
+/** `path` is absolute; `id` is a test node's `id`. */
+export type TestScope =
+  | { kind: "all" }
+  | { kind: "file"; path: string }
+  | { kind: "test"; path: string; id: string };
+
+export type TestStatus = "passed" | "failed" | "skipped";
+
+export interface TestNode {
+  /** For tests, what the framework accepts to run just this one. */
+  id: string;
+  name: string;
+  kind: "suite" | "file" | "group" | "test";
+  status: TestStatus;
+  durationMs: number | null;
+  file: string | null;
+  /** 1-based; for failures, where the failure was raised if known. */
+  line: number | null;
+  message: string | null;
+  children: TestNode[];
+}
+
+/** Timestamps are Unix seconds. */
+export interface TestReport {
+  root: string;
+  framework: TestFramework;
+  scope: TestScope;
+  startedAt: number;
+  finishedAt: number;
+  durationMs: number;
+  exitCode: number | null;
+  cancelled: boolean;
+  passed: number;
+  failed: number;
+  skipped: number;
+  tree: TestNode[];
+  /** Set when no results could be read; `output` usually says why. */
+  error: string | null;
+  output: string;
+}
+
+export type TaskSource =
+  | "npm"
+  | "cargo"
+  | "make"
+  | "just"
+  | "pyproject"
+  | "compose"
+  | "workspace";
+
+/** A runnable task from one of the project's manifests. */
+export interface Task {
+  /** `<source>:<name>`, unique within the project. */
+  id: string;
+  name: string;
+  source: TaskSource;
+  /** Program and arguments; no shell involved. */
+  command: string[];
+  cwd: string;
+  /** The manifest the task was read from. */
+  file: string;
+  description: string | null;
+}
+
+export type TaskMode = "terminal" | "headless";
+
+/** A headless run of a task. Timestamps are Unix seconds. */
+export interface TaskJob {
+  id: string;
+  root: string;
+  task: Task;
+  startedAt: number;
+  /** `null` while running. */
+  finishedAt: number | null;
+  exitCode: number | null;
+  cancelled: boolean;
+  output: string[];
+}
+
+export interface TaskStarted {
+  task: Task;
+  terminalId: string | null;
+  job: TaskJob | null;
+}
+
+export interface RecentTask {
+  taskId: string;
+  name: string;
+  source: TaskSource;
+  runCount: number;
+  lastRunAt: number;
+}
+
+/** Payload of `tasks-changed`, emitted when a task manifest changes. */
+export interface TasksChanged {
+  root: string;
+  tasks: Task[];
+}
+
+export interface TextEncoding {
+  charset: "utf-8" | "utf-16le" | "utf-16be" | "windows-1252";
+  bom: boolean;
+}
+
+export type LineEnding = "lf" | "crlf" | "cr";
+
+/** Byte window of a partial read; `start`/`end` sit on character boundaries. */
+export interface ContentRange {
+  start: number;
+  end: number;
+  firstLine: number | null;
+  hasMore: boolean;
+}
+
+/** Partial read: a byte window or a 0-based line window. */
+export interface ReadOptions {
+  offset?: number;
+  length?: number;
+  startLine?: number;
+  lineCount?: number;
+}
+
+export interface FileContent extends FileVersion {
+  kind: "text" | "binary" | "image";
+  /** Decoded text, base64 for images, empty for other binaries. */
+  content: string;
+  size: number;
+  encoding: TextEncoding | null;
+  lineEnding: LineEnding | null;
+  mixedLineEndings: boolean;
+  mime: string | null;
+  range: ContentRange | null;
+}
+
+export interface FileSnapshot {
+  content: string;
+  mtime: number | null;
+  hash: string;
+}
+
+/** Error payload when `write_file` finds the file changed on disk. */
+export interface WriteConflict {
+  kind: "writeConflict";
+  message: string;
+  path: string;
+  disk: FileSnapshot | null;
+  ours: FileSnapshot;
+}
+
+export interface FsChange {
+  kind: "created" | "modified" | "removed" | "renamed";
+  path: string;
+  /** Previous path for renames and moves. */
+  from: string | null;
+  isDir: boolean;
+}
+
+/** Payload of `fs-events`: one debounced watcher batch. */
+export interface FsEventBatch {
+  root: string;
+  connectionId: string | null;
+  changes: FsChange[];
+  /** Events were lost; re-read the whole tree. */
+  rescan: boolean;
+}
+
+/** Payload of `fs-watch-warning`. */
+export interface WatchWarning {
+  root: string;
+  connectionId: string | null;
+  message: string;
+  polling: boolean;
+}
+
+/** Error payload when a filesystem path is outside every allowed root. */
+export interface OutsideRootError {
+  kind: "outsideRoot";
+  message: string;
+  path: string;
+}
+
+/** Error payload when a non-main webview calls a filesystem command. */
+export interface ForbiddenError {
+  kind: "forbidden";
+  message: string;
+  webview: string;
+}
+
+/** Per-call listing overrides, e.g. a "show ignored" toggle in the tree. */
+export interface ListOptions {
+  /** Project root the directory belongs to; enables nested gitignores. */
+  root?: string | null;
+  showHidden?: boolean | null;
+  showIgnored?: boolean | null;
+  /** Decorate entries with `git status`; on unless `false`. */
+  git?: boolean | null;
+}
+
+/** An entry in the trash; restore it by `id`. */
+export interface TrashedItem {
+  id: string;
+  originalPath: string;
+  deletedAt: string;
+  isDir: boolean;
+}
+
+export interface DirPage {
+  entries: FileEntry[];
+  nextCursor: string | null;
+  total: number;
+}
+
+/** One version in a local file's history; `hash` names it for diff/restore. */
+export interface FileHistoryEntry {
+  hash: string;
+  /** Milliseconds since the Unix epoch. */
+  timestamp: number;
+  size: number;
+  source: "save" | "external" | "restore";
+}
+
 // ── Git ──────────────────────────────────────────────────
 export interface ChangedFile {
   file: string;
@@ -144,6 +587,89 @@ export interface LaunchConfigPayload {
   shell: string | null;
 }
 
+/** `cli_name` of launch configs whose `config` is a `CompoundConfig`. */
+export const COMPOUND_CLI = "compound";
+
+export type LaunchReadiness =
+  | { kind: "port"; port: number; host?: string | null }
+  | { kind: "log"; pattern: string }
+  | { kind: "http"; url: string };
+
+interface LaunchStepBase {
+  name: string;
+  dependsOn?: string[];
+  /** When the step counts as started; right away if unset. */
+  ready?: LaunchReadiness | null;
+  timeoutMs?: number | null;
+}
+
+export type LaunchStep = LaunchStepBase &
+  (
+    | { type: "terminal"; command?: string | null; cwd?: string | null; env?: Record<string, string> }
+    | {
+        type: "server";
+        command: string;
+        args?: string[];
+        cwd?: string | null;
+        env?: Record<string, string>;
+      }
+    | {
+        type: "portForward";
+        connectionId: string;
+        localPort: number;
+        remoteHost?: string | null;
+        remotePort: number;
+      }
+  );
+
+/** The `config` JSON of a compound launch. */
+export interface CompoundConfig {
+  steps: LaunchStep[];
+}
+
+/** A validation error; `kind` names the problem, `message` describes it. */
+export type LaunchIssue = { message: string } & (
+  | { kind: "syntax"; reason: string; line: number; column: number }
+  | { kind: "noSteps" }
+  | { kind: "missingName"; index: number }
+  | { kind: "duplicateStep"; step: string }
+  | { kind: "unknownDependency"; step: string; dependency: string }
+  | { kind: "cycle"; steps: string[] }
+  | { kind: "missingCommand"; step: string }
+  | { kind: "invalidPort"; step: string }
+  | { kind: "invalidPattern"; step: string; reason: string }
+  | { kind: "invalidUrl"; step: string; url: string }
+  | { kind: "noOutput"; step: string }
+);
+
+export type LaunchStepStatus =
+  | "pending"
+  | "starting"
+  | "waiting"
+  | "ready"
+  | "failed"
+  | "skipped"
+  | "stopped";
+
+export interface LaunchStepRun {
+  name: string;
+  kind: LaunchStep["type"];
+  status: LaunchStepStatus;
+  /** The terminal, server or forward id once started. */
+  handle: string | null;
+  error: string | null;
+}
+
+/** A started compound launch; emitted as `launch-changed` on every change. */
+export interface LaunchRun {
+  id: string;
+  configId: string;
+  name: string;
+  status: "starting" | "running" | "failed" | "stopped";
+  steps: LaunchStepRun[];
+  startedAt: number;
+}
+
 export interface NewLaunchConfig {
   cli_name: string;
   profile_name: string;
@@ -158,6 +684,49 @@ export interface UpdateLaunchConfig {
   is_default?: boolean;
 }
 
+// ── Workspace Config (.kodiq/workspace.json) ─────────────
+export type ConfigSource = "db" | "repo";
+
+/** A launch config tagged with its source; `repo:` ids come from the file. */
+export interface SourcedLaunchConfig extends LaunchConfig {
+  source: ConfigSource;
+  read_only: boolean;
+}
+
+/** Ready for `preview.startServer`: absolute cwd, env files applied. */
+export interface SourcedServer extends ServerConfig {
+  source: ConfigSource;
+}
+
+export interface SourcedSetting {
+  key: string;
+  value: unknown;
+  /** `db` when set locally, even if merged over a shared value. */
+  source: ConfigSource;
+  overridesRepo: boolean;
+}
+
+export interface WorkspaceIssue {
+  /** Like `launchConfigs[1].config`; empty for the whole file. */
+  at: string;
+  message: string;
+  line: number | null;
+  column: number | null;
+}
+
+/** Local configs merged with the workspace file; emitted as `workspace-changed`. */
+export interface WorkspaceView {
+  root: string;
+  path: string;
+  exists: boolean;
+  version: number | null;
+  launchConfigs: SourcedLaunchConfig[];
+  previewServers: SourcedServer[];
+  envFiles: string[];
+  settings: SourcedSetting[];
+  issues: WorkspaceIssue[];
+}
+
 // ── History ──────────────────────────────────────────────
 export interface HistoryEntry {
   id: number;
@@ -168,6 +737,7 @@ export interface HistoryEntry {
   exit_code: number | null;
   duration_ms: number | null;
   timestamp: number;
+  cwd: string | null;
 }
 
 export interface NewHistoryEntry {
@@ -175,6 +745,50 @@ export interface NewHistoryEntry {
   session_id: string | null;
   command: string;
   cli_name: string | null;
+  exit_code?: number | null;
+  duration_ms?: number | null;
+  /** Where the command ran — runs from the current cwd rank higher. */
+  cwd?: string | null;
+}
+
+/** A deduplicated command ranked by frecency, for terminal autosuggestions. */
+export interface CommandSuggestion {
+  command: string;
+  score: number;
+  run_count: number;
+  last_used: number;
+  last_exit_code: number | null;
+}
+
+// ── Search ───────────────────────────────────────────────
+export type SearchKind = "history" | "chat" | "snippet" | "transcript";
+
+export interface SearchFilters {
+  project_id?: string | null;
+  /** Chat provider or CLI name. */
+  provider?: string | null;
+  /** Unix seconds, inclusive. */
+  since?: number | null;
+  until?: number | null;
+  /** History only — other kinds are skipped when set. */
+  exit_code?: number | null;
+  kinds?: SearchKind[] | null;
+  limit?: number | null;
+}
+
+export interface SearchHit {
+  kind: SearchKind;
+  /** Source row id (history/transcript ids are stringified integers). */
+  id: string;
+  project_id: string | null;
+  provider: string | null;
+  title: string | null;
+  /** HTML-escaped excerpt with matches wrapped in `<mark>`. */
+  snippet: string;
+  exit_code: number | null;
+  timestamp: number;
+  /** bm25 score — lower is a better match. */
+  rank: number;
 }
 
 // ── Snippets ─────────────────────────────────────────────
@@ -183,7 +797,8 @@ export interface Snippet {
   title: string;
   content: string;
   cli_name: string | null;
-  tags: string;
+  tags: string[];
+  project_id: string | null;
   usage_count: number;
   created_at: number;
   updated_at: number;
@@ -193,7 +808,42 @@ export interface NewSnippet {
   title: string;
   content: string;
   cli_name: string | null;
-  tags: string | null;
+  tags?: string[];
+  project_id?: string | null;
+}
+
+export interface UpdateSnippet {
+  title?: string | null;
+  content?: string | null;
+  cli_name?: string | null;
+  tags?: string[] | null;
+}
+
+export interface TagCount {
+  tag: string;
+  count: number;
+}
+
+/** A `{{...}}` variable in snippet content. */
+export type Placeholder =
+  | { kind: "file" | "selection" | "branch" | "diff" | "project" }
+  | { kind: "input"; label: string };
+
+export interface TemplateContext {
+  project_path?: string | null;
+  connection_id?: string | null;
+  file?: string | null;
+  selection?: string | null;
+  /** `{{input:Label}}` values keyed by label. */
+  inputs?: Record<string, string>;
+}
+
+/** A snippet in the prompt library, from the DB or a repo prompt file. */
+export interface LibraryEntry extends Snippet {
+  source: "db" | "repo";
+  /** Prompt file path relative to the project root (repo entries only). */
+  path: string | null;
+  read_only: boolean;
 }
 
 // ── Recent Projects (in-memory) ──────────────────────────
@@ -354,6 +1004,14 @@ export interface ChatDoneEvent {
   provider: string;
 }
 
+export type ChatExportFormat = "markdown" | "json";
+
+export interface ChatImportResult {
+  imported: number;
+  /** Exported message id → newly assigned id. */
+  id_map: Record<string, string>;
+}
+
 // ── UI Types ─────────────────────────────────────────────
 export type ColorScheme = "light" | "dark";
 export type Viewport = "desktop" | "tablet" | "mobile";